@@ -1,23 +1,42 @@
 use crate::{
-    actions::create_challenge::PublicChallengerData,
-    context::{Context, setup_progress_bar},
-    ui::{self, CHAIN, CHECK, GEAR, KEY, SHIELD},
-    util::{FEES, MIN_CHANGE, select_utxos},
+    actions::{create_challenge::PublicChallengerData, precheck::PreAcceptanceAck},
+    context::Context,
+    esplora::Utxo,
+    format::parse_output,
+    limits::{
+        BoundedMessage, InputError, MAX_PROOF_LEN, MAX_PSBT_LEN, MAX_SHORT_FIELD_LEN, MAX_VK_LEN,
+        MAX_WITNESS_SCRIPT_LEN, check_field, load_message,
+    },
+    proving::render_metrics,
+    render::Renderer,
+    store::{
+        ChallengeRecord, ContactRecord, MessageKind, PendingAcceptance, Role, Store, message_hash,
+    },
+    ui::{CHAIN, GEAR, KEY, SHIELD},
+    util::{
+        apply_coin_control, check_address_reuse, check_denomination, compute_change,
+        release_expired_reservations, select_utxos, window_deadline,
+    },
+    verification::{VerificationContext, render_report, verify_challenge},
 };
 use base64::{Engine as _, engine::general_purpose};
 use bitcoin::{
-    Address, Amount, CompressedPublicKey, OutPoint, Txid,
+    Address, Amount, CompressedPublicKey, EcdsaSighashType, OutPoint, ScriptBuf, TxOut, Txid,
     absolute::{Height, LockTime},
+    address::NetworkUnchecked,
     hashes::{Hash, ripemd160, sha256},
-    secp256k1::{Message, PublicKey, SecretKey},
+    secp256k1::Message,
 };
 use clap::Args;
-use color_eyre::eyre;
-use console::style;
-use op_rand_prover::{BarretenbergProver, OpRandProof, OpRandProver};
-use op_rand_types::ThirdRankCommitment;
+use color_eyre::{
+    eyre,
+    eyre::{OptionExt, bail, ensure},
+};
+use op_rand_prover::OpRandProverAsync;
+use op_rand_transaction_builder::{FeePolicy, SignatureProvider, TransactionKind};
+use op_rand_types::{Capabilities, ProtocolInfo, ThirdRankCommitment, parse_public_key_strict};
 use serde::{Deserialize, Serialize};
-use std::{fs, str::FromStr};
+use std::{collections::BTreeSet, fs, str::FromStr};
 
 #[derive(Args, Debug)]
 pub struct AcceptChallengeArgs {
@@ -32,6 +51,39 @@ pub struct AcceptChallengeArgs {
     /// Number of the commitment to accept
     #[clap(long)]
     pub selected_commitment: u32,
+
+    /// Change public key.
+    #[clap(long)]
+    pub change_pubkey: Option<String>,
+
+    /// Only fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "utxo")]
+    pub include_utxos: Vec<OutPoint>,
+
+    /// Never fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "exclude-utxo")]
+    pub exclude_utxos: Vec<OutPoint>,
+
+    /// Accept a challenge this wallet already accepted again, replacing the previous acceptance
+    /// and releasing the UTXOs reserved for it. Only the acceptance the challenger completes
+    /// counts, so make sure the previous one was not sent.
+    #[clap(long)]
+    pub replace: bool,
+
+    /// Number of blocks the challenger has to complete the challenge. After that the funding
+    /// UTXOs are released and the acceptance is void once they are spent.
+    #[clap(long)]
+    pub completion_window: Option<u32>,
+
+    /// Change output (`ADDRESS:SATS`) of a third party sponsoring the challenge transaction
+    /// fees. The sponsor adds its input after the challenger signs, see `sponsor-psbt`.
+    #[clap(long, value_parser = parse_output)]
+    pub sponsor_change: Option<(Address<NetworkUnchecked>, Amount)>,
+
+    /// Challenger's acknowledgement of the funding disclosed with `precheck disclose`. The
+    /// challenge is funded from the acknowledged UTXOs only.
+    #[clap(long, conflicts_with = "include_utxos")]
+    pub funding_ack: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -43,6 +95,41 @@ pub struct AcceptorData {
     pub challenge_output_witness_script: String,
     pub proof: String,
     pub vk: String,
+    /// Protocol version of the acceptor and capabilities agreed with the challenger
+    pub protocol: ProtocolInfo,
+    /// Block height from which the challenger must not complete the challenge anymore
+    #[serde(default)]
+    pub completion_deadline: Option<u32>,
+    /// SHA-256 of the bytecode of the circuit the proof was generated with, hex encoded
+    #[serde(default)]
+    pub circuit_hash: Option<String>,
+}
+
+impl BoundedMessage for AcceptorData {
+    fn check_sizes(&self) -> Result<(), InputError> {
+        check_field("id", &self.id, MAX_SHORT_FIELD_LEN)?;
+        check_field(
+            "acceptor_pubkey_hash",
+            &self.acceptor_pubkey_hash,
+            MAX_SHORT_FIELD_LEN,
+        )?;
+        for commitment in &self.third_rank_commitments {
+            check_field("third_rank_commitments", commitment, MAX_SHORT_FIELD_LEN)?;
+        }
+        check_field("psbt", &self.psbt, MAX_PSBT_LEN)?;
+        check_field(
+            "challenge_output_witness_script",
+            &self.challenge_output_witness_script,
+            MAX_WITNESS_SCRIPT_LEN,
+        )?;
+        check_field("proof", &self.proof, MAX_PROOF_LEN)?;
+        check_field("vk", &self.vk, MAX_VK_LEN)?;
+        if let Some(circuit_hash) = &self.circuit_hash {
+            check_field("circuit_hash", circuit_hash, MAX_SHORT_FIELD_LEN)?;
+        }
+
+        Ok(())
+    }
 }
 
 pub async fn run(
@@ -50,144 +137,264 @@ pub async fn run(
         challenge_file,
         output,
         selected_commitment,
+        change_pubkey,
+        mut include_utxos,
+        exclude_utxos,
+        replace,
+        completion_window,
+        sponsor_change,
+        funding_ack,
     }: AcceptChallengeArgs,
     mut ctx: Context,
 ) -> eyre::Result<()> {
-    println!(
-        "{}",
-        ui::header("                        🤝 ACCEPTING CHALLENGE 🤝")
-    );
+    let mut r = ctx.renderer();
+    r.header("🤝", "ACCEPTING CHALLENGE");
 
-    let challenge_json = fs::read_to_string(&challenge_file)?;
-    let challenge_data: PublicChallengerData = serde_json::from_str(&challenge_json)?;
-
-    println!(
-        "\n{} {} {}",
-        CHECK,
-        style("Challenge loaded:").bold().green(),
-        style(&challenge_data.id).bright().white()
-    );
-
-    println!(
-        "{} {} {}",
-        CHECK,
-        style("Challenge amount:").bold().yellow(),
-        ui::format_bitcoin_amount(challenge_data.amount)
-    );
+    let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
 
-    let prover = BarretenbergProver::default();
-    let pb = setup_progress_bar("Setting up challenge circuit...".into());
-    let prover_clone = prover.clone();
-    tokio::task::spawn_blocking(move || {
-        prover_clone
-            .setup_challenger_circuit()
-            .expect("Failed to setup challenger circuit")
-    })
-    .await?;
-    pb.finish_with_message("Challenger circuit is set up");
+    // The sponsor's input can only be added if the signatures don't commit to all inputs
+    let required = if sponsor_change.is_some() {
+        Capabilities::DETERMINISTIC_IDS.union(Capabilities::ANYONECANPAY)
+    } else {
+        Capabilities::DETERMINISTIC_IDS
+    };
+    let capabilities = ProtocolInfo::local().negotiate(&challenge_data.protocol, required)?;
+    challenge_data.check_id()?;
+
+    r.field("Challenge ID", &challenge_data.id);
+    r.field("Challenge amount", challenge_data.amount);
+
+    if let Some(funding_ack) = funding_ack {
+        let ack: PreAcceptanceAck = load_message(&funding_ack)?;
+        ensure!(
+            ack.id == challenge_data.id,
+            "Funding acknowledgement is for challenge {}, not {}",
+            ack.id,
+            challenge_data.id
+        );
+        ensure!(
+            ack.accepted,
+            "The challenger vetoed the funding: {}",
+            ack.reasons.join("; ")
+        );
+        r.done("Funding acknowledged by the challenger");
+        include_utxos = ack.funding_outpoints;
+    }
 
     let commitments: [ThirdRankCommitment; 2] = challenge_data
         .third_rank_commitments
         .iter()
-        .map(|s| ThirdRankCommitment::from_str(s))
+        .map(|s| ThirdRankCommitment::from_str_strict(s))
         .collect::<Result<Vec<_>, _>>()?
         .try_into()
         .map_err(|_| eyre::eyre!("Expected exactly 2 commitments"))?;
 
-    let challenger_pubkey = PublicKey::from_str(&challenge_data.challenger_pubkey)?;
-    let challenger_pubkey_hash = hex::decode(&challenge_data.challenger_pubkey_hash)?
-        .try_into()
-        .map_err(|_| eyre::eyre!("Failed to convert challenger public key hash to array"))?;
-    let proof = hex::decode(&challenge_data.proof)?;
-    let vk = hex::decode(&challenge_data.vk)?;
-    let proof_data = OpRandProof::new(proof, vk);
-
-    println!(
-        "\n{} {}",
-        SHIELD,
-        style("Verifying challenger proof...").bold().blue()
-    );
-
-    prover.verify_challenger_proof(
-        commitments.clone(),
-        &challenger_pubkey,
-        challenger_pubkey_hash,
-        &proof_data,
-    )?;
-
-    println!(
-        "{} {}",
-        CHECK,
-        style("Challenger proof verified successfully!")
-            .bold()
-            .green()
-    );
+    let challenger_pubkey =
+        parse_public_key_strict("challenger public key", &challenge_data.challenger_pubkey)?;
+    let vk_hash = sha256::Hash::hash(&hex::decode(&challenge_data.vk)?).to_string();
 
     let cfg = ctx.config()?;
+    check_denomination(r.as_mut(), &cfg.denominations, challenge_data.amount)?;
     let private_key = cfg.private_key;
     let esplora_client = ctx.esplora_client()?;
     let tx_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let prover = ctx.prover()?;
+    let fee_policy = ctx.fee_policy()?;
+
+    check_not_accepted(&mut store, &challenge_data.id, replace)?;
     let secp = ctx.secp_ctx();
+    // The proof binds the wallet key, as the challenge output is spent with it, so the identity
+    // key can't stand in for it
     let public_key = private_key.public_key(secp);
     let address = Address::p2wpkh(
         &CompressedPublicKey::from_private_key(secp, &private_key).unwrap(),
         cfg.network,
     );
 
-    println!(
-        "\n{} {}",
-        GEAR,
-        style("Preparing transaction inputs...").bold().blue()
+    // Every check of the challenge runs before failing, so that all its problems are reported
+    // at once. The acceptor circuit is set up after the challenger proof is verified, as setups
+    // replace the global SRS, and the wallet UTXOs are fetched meanwhile.
+    let verification_ctx = VerificationContext {
+        prover: prover.clone(),
+        esplora_client: Some(&esplora_client),
+        spv_network: cfg.spv.then_some(cfg.network),
+        acceptor_pubkey: Some(public_key.inner),
+    };
+
+    r.step(
+        SHIELD,
+        "Verifying challenger proof and checking the deposit...",
     );
 
-    let utxos = esplora_client.get_utxos(&address.to_string()).await?;
-    let selected_utxos = select_utxos(utxos, challenge_data.amount + FEES)?;
+    let pb = r.progress("Verifying the challenge, fetching UTXOs...");
+    ctx.track_proving(&pb);
+    let ((report, acceptor_circuit), utxos) = tokio::try_join!(
+        async {
+            let report = verify_challenge(&challenge_data, &verification_ctx).await;
+            let acceptor_circuit = prover.setup_acceptor_circuit().await?;
+            eyre::Ok((report, acceptor_circuit))
+        },
+        esplora_client.get_utxos(&address.to_string()),
+    )?;
+    pb.finish_with_message("Challenge verified, acceptor circuit is set up");
+
+    if !report.passed() {
+        render_report(r.as_mut(), &report);
+        r.finish();
+    }
+    report.ensure_passed()?;
+
+    r.done("Challenger proof verified successfully!");
+    if challenge_data.deposit_broadcast {
+        r.done("Deposit is confirmed and unspent!");
+    }
+
+    // Proofs bind the commitments and the deposit of each challenge, so they are verified every
+    // time. The record only tells whether the challenger changed since the last game.
+    let counterparty_pubkey = challenger_pubkey.to_string();
+    render_counterparty(
+        r.as_mut(),
+        &Counterparty {
+            contact: store.contacts.get(&counterparty_pubkey),
+            games: store.games_with(&counterparty_pubkey),
+            vk_hash: &vk_hash,
+            capabilities: challenge_data.protocol.capabilities,
+        },
+    );
+
+    r.step(GEAR, "Preparing transaction inputs...");
 
     let selected_commitment_index = selected_commitment as usize;
     let selected_commitment = &commitments[selected_commitment_index];
 
-    println!(
-        "{} {} {}",
-        CHECK,
-        style("Selected commitment:").bold().yellow(),
-        style((selected_commitment_index + 1).to_string())
-            .bright()
-            .cyan()
-    );
+    r.field("Selected commitment", selected_commitment_index + 1);
 
-    let inputs_sum = selected_utxos.iter().map(|utxo| utxo.value).sum::<u64>();
-    let change_amount = inputs_sum - challenge_data.amount - FEES;
-    let change = if change_amount < MIN_CHANGE {
-        None
-    } else {
-        Some(Amount::from_sat(change_amount))
-    };
-    let prevouts = selected_utxos
-        .iter()
-        .map(|utxo| {
-            Ok((
-                OutPoint::new(Txid::from_str(&utxo.txid)?, utxo.vout),
-                Amount::from_sat(utxo.value),
-            ))
-        })
-        .collect::<Result<Vec<_>, eyre::Error>>()?;
+    release_expired_reservations(r.as_mut(), &esplora_client, &mut store).await?;
+    let challenge_hash = message_hash(&serde_json::to_string(&challenge_data)?);
+    let wallet_pubkey = hex::encode(public_key.inner.serialize());
 
-    println!(
-        "\n{} {}",
-        CHAIN,
-        style("Building challenge transaction...").bold().blue()
-    );
-
-    let (challenge_script, psbt) = tx_builder.build_challenge_tx(
-        &challenger_pubkey.into(),
-        challenge_data.deposit_outpoint,
-        selected_commitment.to_owned(),
-        LockTime::Blocks(Height::from_consensus(challenge_data.locktime)?),
-        Amount::from_sat(challenge_data.amount),
-        prevouts,
-        change,
-        None,
-    )?;
+    let sponsor_change = sponsor_change
+        .map(|(address, value)| {
+            Ok::<_, eyre::Report>(TxOut {
+                value,
+                script_pubkey: address.require_network(cfg.network)?.script_pubkey(),
+            })
+        })
+        .transpose()?;
+    let excluded = exclude_utxos.iter().copied().collect::<BTreeSet<_>>();
+
+    let resumed = store
+        .pending_acceptances
+        .remove(&challenge_data.id)
+        .and_then(|pending| {
+            match check_resumable(&pending, &challenge_hash, selected_commitment_index, &utxos) {
+                Ok(()) => Some(pending),
+                Err(reason) => {
+                    r.warning(&format!("Discarding the unfinished acceptance: {}", reason));
+                    None
+                }
+            }
+        });
+
+    let pending = match resumed {
+        Some(pending) => {
+            check_same_options(
+                &pending,
+                change_pubkey.as_deref(),
+                &excluded,
+                sponsor_change.as_ref(),
+            )?;
+            r.done("Resuming the unfinished acceptance from the proving step");
+            pending
+        }
+        None => {
+            let utxos = apply_coin_control(utxos, &include_utxos, &exclude_utxos, &store)?;
+            let selected_utxos = select_funding(
+                r.as_mut(),
+                utxos,
+                !include_utxos.is_empty(),
+                challenge_data.amount,
+                &fee_policy,
+            )?;
+
+            let fee = fee_policy.fee(TransactionKind::Challenge {
+                inputs: selected_utxos.len(),
+            })?;
+            let change = compute_change(&selected_utxos, challenge_data.amount, fee, &fee_policy)?;
+            let change_key = change_pubkey
+                .as_deref()
+                .map(bitcoin::PublicKey::from_str)
+                .transpose()?;
+            let change_script = match change {
+                Some(_) => {
+                    let pubkey = change_key.unwrap_or(public_key);
+                    Some(ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()?).to_hex_string())
+                }
+                None => None,
+            };
+
+            check_address_reuse(
+                r.as_mut(),
+                cfg.address_reuse,
+                &store,
+                &wallet_pubkey,
+                change_script.as_deref(),
+            )?;
+
+            let prevouts = selected_utxos
+                .iter()
+                .map(|utxo| {
+                    Ok((
+                        OutPoint::new(Txid::from_str(&utxo.txid)?, utxo.vout),
+                        utxo.value,
+                    ))
+                })
+                .collect::<Result<Vec<_>, eyre::Error>>()?;
+
+            r.step(CHAIN, "Building challenge transaction...");
+
+            let (challenge_script, psbt) = tx_builder.build_challenge_tx(
+                &challenger_pubkey.into(),
+                challenge_data.deposit_outpoint,
+                selected_commitment.to_owned(),
+                LockTime::Blocks(Height::from_consensus(challenge_data.locktime)?),
+                challenge_data.amount,
+                prevouts,
+                change,
+                change_key,
+                sponsor_change.clone(),
+                if capabilities.contains(Capabilities::ANYONECANPAY) {
+                    EcdsaSighashType::AllPlusAnyoneCanPay
+                } else {
+                    EcdsaSighashType::All
+                },
+            )?;
+
+            // Proving may fail or take long enough to be interrupted, so the transaction is
+            // kept until the proof is generated
+            let pending = PendingAcceptance {
+                challenge_hash,
+                selected_commitment: selected_commitment_index,
+                change_script,
+                funding_outpoints: selected_utxos
+                    .iter()
+                    .map(Utxo::outpoint)
+                    .collect::<eyre::Result<_>>()?,
+                psbt: general_purpose::STANDARD.encode(psbt.serialize()),
+                witness_script: challenge_script.to_hex_string(),
+                change_pubkey,
+                exclude_utxos: excluded,
+                sponsor_change,
+            };
+            store
+                .pending_acceptances
+                .insert(challenge_data.id.clone(), pending.clone());
+            store.save()?;
+
+            pending
+        }
+    };
 
     let pk_combined = public_key.inner.combine(&selected_commitment.inner())?;
 
@@ -196,59 +403,322 @@ pub async fn run(
 
     let message =
         Message::from_digest(sha256::Hash::hash(ripemd160_hash.as_byte_array()).to_byte_array());
-    let sk = SecretKey::from_slice(&private_key.to_bytes())?;
-
-    let sig = secp.sign_ecdsa(&message, &sk);
-
-    let pb = setup_progress_bar("Setting up acceptor circuit...".into());
-    let prover_clone = prover.clone();
-    tokio::task::spawn_blocking(move || {
-        prover_clone
-            .setup_acceptor_circuit()
-            .expect("Failed to setup acceptor circuit")
-    })
-    .await?;
-    pb.finish_with_message("Acceptor circuit is set up");
-    let pb = setup_progress_bar("Generating acceptor proof...".into());
-    let proof = prover.generate_acceptor_proof(
-        &public_key.inner,
-        &sig,
-        ripemd160_hash.to_byte_array(),
-        commitments,
-    )?;
+    let sig = tx_builder.signer().sign_ecdsa(&message, None)?;
+
+    let pb = r.progress("Generating acceptor proof...");
+    ctx.track_proving(&pb);
+    let proof = prover
+        .generate_acceptor_proof(
+            &acceptor_circuit,
+            &public_key.inner,
+            &sig,
+            ripemd160_hash.to_byte_array(),
+            &commitments,
+        )
+        .await?;
     pb.finish_with_message("Acceptor proof generated");
-
-    println!(
-        "\n{} {}",
-        KEY,
-        style("Generating acceptor data...").bold().blue()
-    );
+    if let Some(metrics) = proof.metrics() {
+        render_metrics(r.as_mut(), metrics);
+    }
+
+    r.step(KEY, "Generating acceptor data...");
+
+    let completion_deadline = match completion_window {
+        Some(window) => Some(window_deadline(
+            esplora_client.get_tip_height().await?,
+            window,
+        )?),
+        None => None,
+    };
 
     let acceptor_output = AcceptorData {
+        protocol: ProtocolInfo {
+            capabilities,
+            ..ProtocolInfo::local()
+        },
         id: challenge_data.id.clone(),
         proof: hex::encode(proof.proof()),
         vk: hex::encode(proof.vk()),
         acceptor_pubkey_hash: hex::encode(ripemd160_hash),
         third_rank_commitments: challenge_data.third_rank_commitments,
-        psbt: general_purpose::STANDARD.encode(psbt.serialize()),
-        challenge_output_witness_script: challenge_script.to_hex_string(),
+        psbt: pending.psbt,
+        challenge_output_witness_script: pending.witness_script.clone(),
+        completion_deadline,
+        circuit_hash: proof.circuit_hash().map(|hash| hash.to_string()),
     };
 
     let acceptor_json = serde_json::to_string(&acceptor_output)?;
-    fs::write(&output, acceptor_json)?;
-
-    println!("{}", ui::success_footer("Challenge accepted successfully!"));
-    println!(
-        "   {} {} {}",
-        style("Acceptor data saved to:").dim(),
-        style(&output).bright().white().bold(),
-        style("📄").dim()
+    fs::write(&output, &acceptor_json)?;
+    store.queue_message(&challenge_data.id, MessageKind::Acceptance, &acceptor_json);
+
+    store.pending_acceptances.remove(&challenge_data.id);
+    store.challenges.insert(
+        challenge_data.id.clone(),
+        ChallengeRecord {
+            role: Role::Acceptor,
+            wallet_pubkey,
+            change_script: pending.change_script,
+            funding_outpoints: pending.funding_outpoints,
+            deposit_outpoint: Some(challenge_data.deposit_outpoint),
+            witness_script: Some(pending.witness_script),
+            completion_deadline,
+            metadata: Default::default(),
+            reoffer_of: None,
+            superseded_by: None,
+            counterparty_pubkey: Some(counterparty_pubkey.clone()),
+        },
     );
-    println!(
-        "   {} {}",
-        style("Challenge ID:").dim(),
-        style(&challenge_data.id).bright().cyan()
+    let contact = store.contacts.entry(counterparty_pubkey).or_default();
+    contact.vk_hash = Some(vk_hash);
+    contact.capabilities = challenge_data.protocol.capabilities;
+    store.save()?;
+
+    r.section("FILE OUTPUTS");
+    r.field("Acceptor data (share with challenger)", &output);
+    r.success("Challenge accepted successfully!");
+    r.finish();
+
+    Ok(())
+}
+
+/// Selects the UTXOs funding the challenge transaction of an acceptance. Unless the UTXOs were
+/// picked by hand, a stake UTXO of exactly the needed amount (see `prepare-stake`) funds it with a
+/// single input and no change.
+pub fn select_funding(
+    r: &mut dyn Renderer,
+    mut utxos: Vec<Utxo>,
+    picked: bool,
+    amount: Amount,
+    fee_policy: &FeePolicy,
+) -> eyre::Result<Vec<Utxo>> {
+    let stake_amount = amount
+        .checked_add(fee_policy.fee(TransactionKind::Challenge { inputs: 1 })?)
+        .ok_or_eyre("Amount overflow")?;
+
+    match utxos.iter().position(|utxo| utxo.value == stake_amount) {
+        Some(index) if !picked => {
+            r.done("Funding the challenge from a stake UTXO");
+            Ok(vec![utxos.swap_remove(index)])
+        }
+        _ => select_utxos(utxos, amount, fee_policy, |inputs| {
+            TransactionKind::Challenge { inputs }
+        }),
+    }
+}
+
+/// Challenger of a challenge being accepted, as known from earlier games
+struct Counterparty<'a> {
+    contact: Option<&'a ContactRecord>,
+    /// Challenges accepted from them before
+    games: usize,
+    /// SHA-256 of the verification key sent with the challenge
+    vk_hash: &'a str,
+    /// Capabilities advertised in the challenge
+    capabilities: Capabilities,
+}
+
+/// Renders the record of the challenger and flags what changed since the last game
+fn render_counterparty(r: &mut dyn Renderer, counterparty: &Counterparty) {
+    let Some(contact) = counterparty.contact else {
+        r.field("Counterparty", "new counterparty, no prior games");
+        return;
+    };
+
+    r.field(
+        "Counterparty",
+        format!(
+            "known counterparty, {} prior games, {} disputes",
+            counterparty.games, contact.disputes
+        ),
     );
+    if contact.disputes > 0 {
+        r.warning(&format!(
+            "The challenger let {} acceptance(s) expire without completing them",
+            contact.disputes
+        ));
+    }
+    if contact
+        .vk_hash
+        .as_deref()
+        .is_some_and(|vk_hash| vk_hash != counterparty.vk_hash)
+    {
+        r.warning("The challenger's verification key changed since the last game");
+    }
+    let dropped = contact.capabilities.difference(counterparty.capabilities);
+    if !dropped.is_empty() {
+        r.warning(&format!("The challenger no longer advertises: {}", dropped));
+    }
+}
+
+/// Checks that an unfinished acceptance can be resumed: it must be for the same challenge and
+/// commitment, and its funding UTXOs must still be unspent
+fn check_resumable(
+    pending: &PendingAcceptance,
+    challenge_hash: &str,
+    selected_commitment: usize,
+    utxos: &[Utxo],
+) -> Result<(), String> {
+    if pending.challenge_hash != challenge_hash {
+        return Err("the challenge changed".to_string());
+    }
+    if pending.selected_commitment != selected_commitment {
+        return Err(format!(
+            "commitment {} was selected",
+            pending.selected_commitment + 1
+        ));
+    }
+    for outpoint in &pending.funding_outpoints {
+        if !utxos
+            .iter()
+            .any(|utxo| utxo.outpoint().is_ok_and(|utxo| &utxo == outpoint))
+        {
+            return Err(format!("UTXO {} was spent", outpoint));
+        }
+    }
 
     Ok(())
 }
+
+/// Checks that an unfinished acceptance is resumed with the funding options it was built with.
+/// Its transaction is not built again, so other options would be silently ignored.
+fn check_same_options(
+    pending: &PendingAcceptance,
+    change_pubkey: Option<&str>,
+    exclude_utxos: &BTreeSet<OutPoint>,
+    sponsor_change: Option<&TxOut>,
+) -> eyre::Result<()> {
+    for (flag, same) in [
+        (
+            "--change-pubkey",
+            pending.change_pubkey.as_deref() == change_pubkey,
+        ),
+        ("--exclude-utxo", &pending.exclude_utxos == exclude_utxos),
+        (
+            "--sponsor-change",
+            pending.sponsor_change.as_ref() == sponsor_change,
+        ),
+    ] {
+        ensure!(
+            same,
+            "The unfinished acceptance of this challenge was built with other {} values, pass \
+             the same ones or --replace to start over",
+            flag
+        );
+    }
+
+    Ok(())
+}
+
+/// Refuses to accept a challenge this wallet created or already accepted. With `replace`, a
+/// previous acceptance is dropped instead, so its UTXOs can fund the new one. An unfinished
+/// acceptance is dropped too, so that the acceptance starts over.
+fn check_not_accepted(store: &mut Store, challenge_id: &str, replace: bool) -> eyre::Result<()> {
+    if replace {
+        store.pending_acceptances.remove(challenge_id);
+    }
+
+    match store.challenges.get(challenge_id).map(|record| record.role) {
+        None => Ok(()),
+        Some(Role::Challenger) => bail!("Challenge {} was created by this wallet", challenge_id),
+        Some(Role::Acceptor) if replace => {
+            store.challenges.remove(challenge_id);
+            Ok(())
+        }
+        Some(Role::Acceptor) => bail!(
+            "Challenge {} was already accepted by this wallet, pass --replace to accept it again",
+            challenge_id
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_counterparty() {
+        let contact = ContactRecord {
+            vk_hash: Some("aa".repeat(32)),
+            capabilities: Capabilities::ANYONECANPAY.union(Capabilities::DETERMINISTIC_IDS),
+            disputes: 1,
+        };
+        let vk_hash = "bb".repeat(32);
+
+        insta::assert_snapshot!(render_all(|r| render_counterparty(
+            r,
+            &Counterparty {
+                contact: Some(&contact),
+                games: 12,
+                vk_hash: &vk_hash,
+                capabilities: Capabilities::DETERMINISTIC_IDS,
+            },
+        )));
+    }
+
+    fn pending_acceptance() -> PendingAcceptance {
+        PendingAcceptance {
+            challenge_hash: "aa".repeat(32),
+            selected_commitment: 0,
+            change_script: None,
+            funding_outpoints: vec![],
+            psbt: String::new(),
+            witness_script: String::new(),
+            change_pubkey: Some("02".to_string() + &"11".repeat(32)),
+            exclude_utxos: BTreeSet::from([OutPoint::null()]),
+            sponsor_change: Some(TxOut {
+                value: Amount::from_sat(1_000),
+                script_pubkey: ScriptBuf::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn resumes_with_same_options() {
+        let pending = pending_acceptance();
+
+        check_same_options(
+            &pending,
+            pending.change_pubkey.as_deref(),
+            &pending.exclude_utxos,
+            pending.sponsor_change.as_ref(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn refuses_to_resume_with_other_options() {
+        let pending = pending_acceptance();
+        let other_sponsor_change = TxOut {
+            value: Amount::from_sat(2_000),
+            script_pubkey: ScriptBuf::new(),
+        };
+
+        let err = check_same_options(
+            &pending,
+            None,
+            &pending.exclude_utxos,
+            pending.sponsor_change.as_ref(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--change-pubkey"));
+
+        let err = check_same_options(
+            &pending,
+            pending.change_pubkey.as_deref(),
+            &BTreeSet::new(),
+            pending.sponsor_change.as_ref(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--exclude-utxo"));
+
+        let err = check_same_options(
+            &pending,
+            pending.change_pubkey.as_deref(),
+            &pending.exclude_utxos,
+            Some(&other_sponsor_change),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--sponsor-change"));
+    }
+}
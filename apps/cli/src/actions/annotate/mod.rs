@@ -0,0 +1,71 @@
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt};
+
+use crate::context::Context;
+
+#[derive(Args, Debug)]
+pub struct AnnotateArgs {
+    /// Challenge ID
+    pub id: String,
+
+    /// Short label. An empty label removes it.
+    #[clap(long)]
+    pub label: Option<String>,
+
+    /// Name of the other party. An empty name removes it.
+    #[clap(long)]
+    pub counterparty: Option<String>,
+
+    /// Free-form memo. An empty memo removes it.
+    #[clap(long)]
+    pub memo: Option<String>,
+
+    /// Add a tag. Can be repeated.
+    #[clap(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Remove a tag. Can be repeated.
+    #[clap(long = "untag")]
+    pub untags: Vec<String>,
+}
+
+pub async fn run(
+    AnnotateArgs {
+        id,
+        label,
+        counterparty,
+        memo,
+        tags,
+        untags,
+    }: AnnotateArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut store = ctx.store()?;
+    let metadata = &mut store
+        .challenges
+        .get_mut(&id)
+        .ok_or_eyre("Challenge not found in the local store")?
+        .metadata;
+
+    for (field, value) in [
+        (&mut metadata.label, label),
+        (&mut metadata.counterparty, counterparty),
+        (&mut metadata.memo, memo),
+    ] {
+        if let Some(value) = value {
+            *field = (!value.is_empty()).then_some(value);
+        }
+    }
+    metadata.tags.extend(tags);
+    for tag in &untags {
+        metadata.tags.remove(tag);
+    }
+
+    store.save()?;
+
+    let mut r = ctx.renderer();
+    r.success(&format!("Challenge {} annotated", id));
+    r.finish();
+
+    Ok(())
+}
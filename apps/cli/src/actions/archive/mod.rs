@@ -0,0 +1,170 @@
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt};
+
+use crate::{
+    archive::ArchivedChallenge,
+    context::Context,
+    render::Renderer,
+    store::{BroadcastKind, unix_time},
+    ui::{CHAIN, GEAR},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Args, Debug)]
+pub struct ArchiveArgs {
+    /// Archive challenges settled more than this many days ago, instead of `archive.after_days`
+    #[clap(long)]
+    pub older_than: Option<u64>,
+
+    /// Only list the challenges that would be archived
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+/// Challenges moved to the archive by a run
+struct ArchiveSummary<'a> {
+    path: &'a str,
+    dry_run: bool,
+    archived: Vec<(String, u64)>,
+    proofs_dropped: Vec<String>,
+    total: usize,
+}
+
+pub async fn run(
+    ArchiveArgs {
+        older_than,
+        dry_run,
+    }: ArchiveArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🗄️", "ARCHIVING SETTLED CHALLENGES");
+
+    let cfg = ctx.config()?;
+    let esplora_client = ctx.esplora_client()?;
+    let mut store = ctx.store()?;
+    let mut archive = ctx.archive()?;
+    let now = unix_time().ok_or_eyre("System clock is before the Unix epoch")?;
+    let cutoff = now.saturating_sub(older_than.unwrap_or(cfg.archive.after_days) * SECONDS_PER_DAY);
+
+    r.step(CHAIN, "Checking settling transactions...");
+
+    // Only a confirmed sweep or refund settles a challenge, so that nothing is archived while
+    // it can still be replaced
+    let mut settled = Vec::new();
+    for id in store.challenges.keys() {
+        let mut settled_at = None;
+        for (txid, record) in store.challenge_transactions(id) {
+            if !matches!(
+                record.kind,
+                Some(BroadcastKind::Sweep | BroadcastKind::Refund)
+            ) {
+                continue;
+            }
+            let Some(recorded_at) = record.recorded_at else {
+                continue;
+            };
+            let confirmed = record.block_height.is_some()
+                || (esplora_client.has_transaction(txid).await?
+                    && esplora_client.get_tx_status(txid).await?.confirmed);
+            if confirmed {
+                settled_at = Some(recorded_at);
+                break;
+            }
+        }
+
+        if let Some(settled_at) = settled_at.filter(|settled_at| *settled_at <= cutoff) {
+            settled.push((id.clone(), settled_at));
+        }
+    }
+
+    r.done("Settling transactions checked");
+
+    let mut proofs_dropped = Vec::new();
+    if !dry_run {
+        r.step(GEAR, "Moving settled challenges to the archive...");
+
+        for (id, settled_at) in &settled {
+            if let Some(archived) = ArchivedChallenge::take(&mut store, id, *settled_at) {
+                archive.challenges.insert(id.clone(), archived);
+            }
+        }
+
+        if let Some(days) = cfg.archive.drop_proofs_after_days {
+            let cutoff = now.saturating_sub(days * SECONDS_PER_DAY);
+            for (id, archived) in &mut archive.challenges {
+                if !archived.proofs_dropped && archived.settled_at <= cutoff {
+                    archived.drop_proofs()?;
+                    proofs_dropped.push(id.clone());
+                }
+            }
+        }
+
+        // The archive is saved first, so that an interrupted run leaves a challenge in both
+        // files rather than in neither
+        archive.save()?;
+        store.save()?;
+
+        r.done("Archive saved");
+    }
+
+    render_archive(
+        r.as_mut(),
+        &ArchiveSummary {
+            path: &archive.path().display().to_string(),
+            dry_run,
+            archived: settled,
+            proofs_dropped,
+            total: archive.challenges.len(),
+        },
+    );
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the challenges moved to the archive
+fn render_archive(r: &mut dyn Renderer, summary: &ArchiveSummary) {
+    r.field("Archive", summary.path);
+    r.field("Archived Challenges", summary.total);
+    r.field("Settled Challenges", summary.archived.len());
+
+    r.section(if summary.dry_run {
+        "CHALLENGES TO ARCHIVE"
+    } else {
+        "ARCHIVED"
+    });
+    for (id, settled_at) in &summary.archived {
+        r.entry(id);
+        r.field("Challenge ID", id);
+        r.field("Settled At", *settled_at);
+    }
+
+    if !summary.proofs_dropped.is_empty() {
+        r.section("PROOFS DROPPED");
+        for id in &summary.proofs_dropped {
+            r.entry(id);
+            r.field("Challenge ID", id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_archive() {
+        let summary = ArchiveSummary {
+            path: "op_rand_archive.json.gz",
+            dry_run: false,
+            archived: vec![("challenge-1".to_string(), 1_760_000_000)],
+            proofs_dropped: vec!["challenge-0".to_string()],
+            total: 2,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_archive(r, &summary)));
+    }
+}
@@ -1,25 +1,22 @@
-use bitcoin::{Address, CompressedPublicKey};
+use bitcoin::{Address, Amount, CompressedPublicKey, amount::CheckedSum};
+use color_eyre::eyre::OptionExt;
 
 use crate::{
     context::Context,
-    ui::{self, CHAIN, CHECK, GEAR},
+    esplora::Utxo,
+    render::Renderer,
+    store::Store,
+    ui::{CHAIN, GEAR},
 };
-use console::style;
 
 pub async fn run(mut ctx: Context) -> eyre::Result<()> {
-    println!(
-        "{}",
-        ui::header("                          💰 WALLET BALANCE 💰")
-    );
-
-    println!(
-        "\n{} {}",
-        GEAR,
-        style("Fetching wallet information...").bold().blue()
-    );
+    let mut r = ctx.renderer();
+    r.header("💰", "WALLET BALANCE");
+    r.step(GEAR, "Fetching wallet information...");
 
     let cfg = ctx.config()?;
     let esplora_client = ctx.esplora_client()?;
+    let store = ctx.store()?;
     let private_key = cfg.private_key;
     let secp = ctx.secp_ctx();
     let address = Address::p2wpkh(
@@ -27,146 +24,116 @@ pub async fn run(mut ctx: Context) -> eyre::Result<()> {
         cfg.network,
     );
 
-    println!(
-        "{} {} {}",
-        CHECK,
-        style("Wallet address:").bold().yellow(),
-        style(&address.to_string()).bright().white()
-    );
-
-    println!(
-        "\n{} {}",
-        CHAIN,
-        style("Querying UTXOs from Esplora...").bold().blue()
-    );
+    r.field("Wallet address", address.to_string());
+    r.step(CHAIN, "Querying UTXOs from Esplora...");
 
     let utxos = esplora_client.get_utxos(&address.to_string()).await?;
 
-    println!(
-        "{} {} UTXOs found",
-        CHECK,
-        style(utxos.len().to_string()).bold().green()
-    );
-
-    // Calculate confirmed and unconfirmed balances
-    let mut confirmed_balance: u64 = 0;
-    let mut unconfirmed_balance: u64 = 0;
-
-    for utxo in &utxos {
-        if utxo.status.confirmed {
-            confirmed_balance += utxo.value;
-        } else {
-            unconfirmed_balance += utxo.value;
-        }
-    }
+    r.done(&format!("{} UTXOs found", utxos.len()));
 
-    let total_balance = confirmed_balance + unconfirmed_balance;
+    render_balance(r.as_mut(), &utxos, &store)?;
+    r.finish();
 
-    // Display balance information
-    println!("\n{}", style("┌─ BALANCE SUMMARY").bold().blue());
-    println!("│");
-
-    if confirmed_balance > 0 {
-        println!(
-            "│ 💎 {} {}",
-            style("Confirmed Balance:").bold().green(),
-            ui::format_bitcoin_amount(confirmed_balance)
-        );
-    } else {
-        println!(
-            "│ 💎 {} {}",
-            style("Confirmed Balance:").bold().green(),
-            style("0 satoshis (0.00000000 BTC)").dim()
-        );
-    }
-
-    if unconfirmed_balance > 0 {
-        println!(
-            "│ ⏳ {} {}",
-            style("Unconfirmed Balance:").bold().yellow(),
-            ui::format_bitcoin_amount(unconfirmed_balance)
-        );
-    } else {
-        println!(
-            "│ ⏳ {} {}",
-            style("Unconfirmed Balance:").bold().yellow(),
-            style("0 satoshis (0.00000000 BTC)").dim()
-        );
-    }
+    Ok(())
+}
 
-    println!("│");
-    println!(
-        "│ 🏆 {} {}",
-        style("Total Balance:").bold().cyan(),
-        ui::format_bitcoin_amount(total_balance)
-    );
+/// Renders the balance summary and the details of every UTXO
+fn render_balance(r: &mut dyn Renderer, utxos: &[Utxo], store: &Store) -> eyre::Result<()> {
+    // Calculate confirmed and unconfirmed balances
+    let confirmed_balance = utxos
+        .iter()
+        .filter(|utxo| utxo.status.confirmed)
+        .map(|utxo| utxo.value)
+        .checked_sum()
+        .ok_or_eyre("Balance overflow")?;
+    let unconfirmed_balance = utxos
+        .iter()
+        .filter(|utxo| !utxo.status.confirmed)
+        .map(|utxo| utxo.value)
+        .checked_sum()
+        .ok_or_eyre("Balance overflow")?;
+
+    let total_balance = confirmed_balance
+        .checked_add(unconfirmed_balance)
+        .ok_or_eyre("Balance overflow")?;
+
+    r.section("BALANCE SUMMARY");
+    r.field("Confirmed Balance", confirmed_balance);
+    r.field("Unconfirmed Balance", unconfirmed_balance);
+    r.field("Total Balance", total_balance);
 
     if !utxos.is_empty() {
-        println!("\n{}", style("┌─ UTXO DETAILS").bold().blue());
-        println!("│");
+        r.section("UTXO DETAILS");
 
         for (i, utxo) in utxos.iter().enumerate() {
-            let status_icon = if utxo.status.confirmed { "✅" } else { "⏳" };
-            let status_text = if utxo.status.confirmed {
-                "confirmed"
-            } else {
-                "unconfirmed"
-            };
-
-            println!(
-                "│ {} {} {}",
-                status_icon,
-                style(format!("UTXO {}:", i + 1)).bold(),
-                style(ui::format_bitcoin_amount(utxo.value))
-                    .bright()
-                    .white()
-            );
-            println!(
-                "│   {} {}:{}",
-                style("Outpoint:").dim(),
-                style(&utxo.txid[..16]).dim(),
-                style(utxo.vout.to_string()).dim()
-            );
-            println!(
-                "│   {} {}",
-                style("Status:").dim(),
+            r.entry(&format!("UTXO {}", i + 1));
+            r.field("Outpoint", format!("{}:{}", utxo.txid, utxo.vout));
+            r.field("Amount", utxo.value);
+
+            let record = utxo.outpoint().ok().and_then(|o| store.utxos.get(&o));
+            if let Some(label) = record.and_then(|record| record.label.as_ref()) {
+                r.field("Label", label);
+            }
+            r.field("Frozen", record.is_some_and(|record| record.frozen));
+            r.field(
+                "Status",
                 if utxo.status.confirmed {
-                    style(status_text).green()
+                    "confirmed"
                 } else {
-                    style(status_text).yellow()
-                }
+                    "unconfirmed"
+                },
             );
-
             if let Some(block_height) = utxo.status.block_height {
-                println!(
-                    "│   {} {}",
-                    style("Block Height:").dim(),
-                    style(block_height.to_string()).dim()
-                );
+                r.field("Block Height", block_height);
             }
+        }
+    }
 
-            if i < utxos.len() - 1 {
-                println!("│");
-            }
+    if total_balance == Amount::ZERO {
+        r.warning("Wallet is empty, send some funds to this address to see your balance");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{esplora::UtxoStatus, render::render_all, store::UtxoRecord};
+
+    fn utxo(vout: u32, sats: u64, block_height: Option<u64>) -> Utxo {
+        Utxo {
+            txid: "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d".to_string(),
+            vout,
+            status: UtxoStatus {
+                confirmed: block_height.is_some(),
+                block_height,
+                block_hash: None,
+                block_time: None,
+            },
+            value: Amount::from_sat(sats),
         }
     }
 
-    if total_balance == 0 {
-        println!("\n{}", style("┌─ WALLET STATUS").bold().blue());
-        println!("│");
-        println!(
-            "│ {} {}",
-            style("Status:").dim(),
-            style("Wallet is empty - no UTXOs found").yellow()
+    #[test]
+    fn renders_balance() {
+        let utxos = [utxo(0, 50_000, Some(840_000)), utxo(1, 1_500, None)];
+        let mut store = Store::default();
+        store.utxos.insert(
+            utxos[0].outpoint().unwrap(),
+            UtxoRecord {
+                label: Some("savings".to_string()),
+                frozen: true,
+            },
         );
-        println!(
-            "│ {} {}",
-            style("Tip:").dim(),
-            style("Send some funds to this address to see your balance").dim()
-        );
-    }
 
-    println!("\n{}", "═".repeat(80));
+        insta::assert_snapshot!(render_all(|r| render_balance(r, &utxos, &store).unwrap()));
+    }
 
-    Ok(())
+    #[test]
+    fn renders_empty_wallet() {
+        insta::assert_snapshot!(render_all(|r| {
+            render_balance(r, &[], &Store::default()).unwrap()
+        }));
+    }
 }
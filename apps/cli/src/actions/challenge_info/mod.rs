@@ -1,9 +1,12 @@
-use std::fs;
-
 use clap::Args;
-use console::{Emoji, style};
 
-use crate::actions::create_challenge::PublicChallengerData;
+use crate::{
+    actions::{create_challenge::PublicChallengerData, list_challenges::render_metadata},
+    context::Context,
+    limits::load_message,
+    render::Renderer,
+    store::ChallengeMetadata,
+};
 
 #[derive(Args, Debug)]
 pub struct ChallengeInfoArgs {
@@ -12,156 +15,118 @@ pub struct ChallengeInfoArgs {
     pub challenge_file: String,
 }
 
-static ROCKET: Emoji<'_, '_> = Emoji("🚀 ", "");
-static KEY: Emoji<'_, '_> = Emoji("🔑 ", "");
-static LOCK: Emoji<'_, '_> = Emoji("🔒 ", "");
-static SHIELD: Emoji<'_, '_> = Emoji("🛡️ ", "");
-static CLOCK: Emoji<'_, '_> = Emoji("⏰ ", "");
-static CHAIN: Emoji<'_, '_> = Emoji("⛓️ ", "");
-
-pub async fn run(ChallengeInfoArgs { challenge_file }: ChallengeInfoArgs) -> eyre::Result<()> {
-    let challenge_json = fs::read_to_string(&challenge_file)?;
-    let challenge_data: PublicChallengerData = serde_json::from_str(&challenge_json)?;
-
-    println!("\n{}", "═".repeat(80));
-    println!(
-        "{}",
-        style("                           🎯 CHALLENGE INFORMATION 🎯")
-            .bold()
-            .cyan()
-    );
-    println!("{}", "═".repeat(80));
-
-    println!(
-        "\n{} {}",
-        style("Challenge ID:").bold().yellow(),
-        style(&challenge_data.id).bright().white()
-    );
-
-    let btc_amount = challenge_data.amount as f64 / 100_000_000.0;
-    println!(
-        "{} {} {} satoshis ({} BTC)",
-        CHAIN,
-        style("Amount:").bold().yellow(),
-        style(challenge_data.amount.to_string())
-            .bright()
-            .green()
-            .bold(),
-        style(format!("{:.8}", btc_amount)).bright().green()
-    );
-
-    // Deposit information
-    println!("\n{}", style("┌─ DEPOSIT INFORMATION").bold().blue());
-    println!("│");
-    println!("│ {} {}", CHAIN, style("Outpoint:").bold().yellow());
-    println!(
-        "│   {} {}",
-        style("TXID:").dim(),
-        style(challenge_data.deposit_outpoint.txid.to_string())
-            .bright()
-            .white()
-    );
-    println!(
-        "│   {} {}",
-        style("VOUT:").dim(),
-        style(challenge_data.deposit_outpoint.vout.to_string())
-            .bright()
-            .white()
-    );
-
-    // Locktime information
-    println!("│");
-    println!(
-        "│ {} {} {} blocks",
-        CLOCK,
-        style("Locktime:").bold().yellow(),
-        style(challenge_data.locktime.to_string()).bright().cyan()
-    );
-
-    // Public key information
-    println!("\n{}", style("┌─ CRYPTOGRAPHIC DATA").bold().magenta());
-    println!("│");
-    println!(
-        "│ {} {}",
-        KEY,
-        style("Challenger Public Key:").bold().yellow()
-    );
-    println!("│   {}", style(&challenge_data.challenger_pubkey).dim());
-    println!("│");
-    println!("│ {} {}", LOCK, style("Public Key Hash:").bold().yellow());
-    println!(
-        "│   {}",
-        style(&challenge_data.challenger_pubkey_hash).dim()
-    );
-
-    // Third rank commitments
-    println!("\n{}", style("┌─ THIRD RANK COMMITMENTS").bold().green());
-    println!("│");
+pub async fn run(
+    ChallengeInfoArgs { challenge_file }: ChallengeInfoArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+
+    // Notes are shown when the challenge is in the local store, the file alone is enough otherwise
+    let metadata = ctx.store().ok().and_then(|mut store| {
+        store
+            .challenges
+            .remove(&challenge_data.id)
+            .map(|record| record.metadata)
+    });
+
+    let mut r = ctx.renderer();
+    render_challenge(r.as_mut(), &challenge_data, metadata.as_ref());
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the public data of a challenge, with the user notes about it if any
+fn render_challenge(
+    r: &mut dyn Renderer,
+    challenge_data: &PublicChallengerData,
+    metadata: Option<&ChallengeMetadata>,
+) {
+    r.header("🎯", "CHALLENGE INFORMATION");
+    r.field("Challenge ID", &challenge_data.id);
+    r.field("Amount", challenge_data.amount);
+
+    if let Some(metadata) = metadata {
+        render_metadata(r, metadata);
+    }
+
+    r.section("DEPOSIT INFORMATION");
+    r.field("TXID", challenge_data.deposit_outpoint.txid.to_string());
+    r.field("VOUT", challenge_data.deposit_outpoint.vout);
+    r.field("Broadcast", challenge_data.deposit_broadcast);
+    r.field("Locktime", challenge_data.locktime);
+    if let Some(deadline) = challenge_data.acceptance_deadline {
+        r.field("Acceptance Deadline", deadline);
+    }
+    if let Some(beacon) = &challenge_data.beacon {
+        r.field("Selection Beacon", beacon.source.to_string());
+        r.field("Salt Commitment", &beacon.salt_commitment);
+    }
+
+    r.section("CRYPTOGRAPHIC DATA");
+    r.field("Challenger Public Key", &challenge_data.challenger_pubkey);
+    r.field("Public Key Hash", &challenge_data.challenger_pubkey_hash);
+
+    r.section("THIRD RANK COMMITMENTS");
     for (i, commitment) in challenge_data.third_rank_commitments.iter().enumerate() {
-        println!(
-            "│ {}",
-            style(format!("Commitment {}:", i + 1)).bold().yellow(),
-        );
-        println!("│   {}", style(commitment).dim());
-        if i < challenge_data.third_rank_commitments.len() - 1 {
-            println!("│");
-        }
+        r.entry(&format!("Commitment {}", i + 1));
+        r.field("Public Key", commitment);
     }
 
-    // Zero-knowledge proof information
-    println!("\n{}", style("┌─ ZERO-KNOWLEDGE PROOF").bold().red());
-    println!("│");
-    println!("│ {} {}", SHIELD, style("Proof:").bold().yellow());
-    let proof_preview = if challenge_data.proof.len() > 64 {
-        format!(
-            "{}...{}",
-            &challenge_data.proof[..32],
-            &challenge_data.proof[challenge_data.proof.len() - 32..]
-        )
-    } else {
-        challenge_data.proof.clone()
-    };
-    println!("│   {}", style(proof_preview).dim());
-    println!(
-        "│   {} {} bytes",
-        style("Size:").dim(),
-        style((challenge_data.proof.len() / 2).to_string())
-            .bright()
-            .white()
-    );
-    println!("│");
-    println!(
-        "│ {} {}",
-        style("🔍").bold(),
-        style("Verification Key:").bold().yellow()
-    );
-    let vk_preview = if challenge_data.vk.len() > 64 {
-        format!(
-            "{}...{}",
-            &challenge_data.vk[..32],
-            &challenge_data.vk[challenge_data.vk.len() - 32..]
-        )
+    r.section("ZERO-KNOWLEDGE PROOF");
+    r.field("Proof", preview(&challenge_data.proof));
+    r.field("Proof Size", challenge_data.proof.len() / 2);
+    r.field("Verification Key", preview(&challenge_data.vk));
+    r.field("Verification Key Size", challenge_data.vk.len() / 2);
+
+    r.success("Challenge ready for acceptance!");
+}
+
+/// Shortens a long hex string to its first and last 32 characters
+fn preview(hex: &str) -> String {
+    if hex.len() > 64 {
+        format!("{}...{}", &hex[..32], &hex[hex.len() - 32..])
     } else {
-        challenge_data.vk.clone()
-    };
-    println!("│   {}", style(vk_preview).dim());
-    println!(
-        "│   {} {} bytes",
-        style("Size:").dim(),
-        style((challenge_data.vk.len() / 2).to_string())
-            .bright()
-            .white()
-    );
-
-    // Footer
-    println!("\n{}", "═".repeat(80));
-    println!(
-        "{} {}",
-        ROCKET,
-        style("Challenge ready for acceptance!").bold().green()
-    );
-    println!("{}", "═".repeat(80));
-    println!();
+        hex.to_string()
+    }
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Amount, OutPoint};
+    use op_rand_types::ProtocolInfo;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_challenge() {
+        let challenge_data = PublicChallengerData {
+            id: "0f9e5a4c-7d2b-4c1e-9a8f-3b6d2e1c0a9b".to_string(),
+            amount: Amount::from_sat(10_000),
+            deposit_outpoint: OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            third_rank_commitments: [
+                "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5".to_string(),
+                "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9".to_string(),
+            ],
+            challenger_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .to_string(),
+            challenger_pubkey_hash: "751e76e8199196d454941c45d1b3a323f1433bd6".to_string(),
+            proof: "ab".repeat(100),
+            vk: "cd".repeat(20),
+            locktime: 144,
+            deposit_broadcast: true,
+            protocol: ProtocolInfo::local(),
+            acceptance_deadline: Some(840_144),
+            beacon: None,
+            circuit_hash: None,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_challenge(r, &challenge_data, None)));
+    }
 }
@@ -5,17 +5,29 @@ use crate::{
         accept_challenge::AcceptorData,
         create_challenge::{PrivateChallengerData, PublicChallengerData},
     },
-    context::{Context, setup_progress_bar},
-    ui::{self, CHAIN, CHECK, GEAR, RADIO, SHIELD},
+    context::Context,
+    esplora::EsploraClient,
+    limits::load_message,
+    proving::envelope_proof,
+    render::Renderer,
+    store::{BroadcastKind, Store},
+    ui::{CHAIN, GEAR, RADIO, SHIELD},
+    util::{InputConflict, apply_coin_control, find_input_conflict},
 };
 use base64::{Engine as _, engine::general_purpose};
-use bitcoin::{Amount, Psbt, consensus::Encodable};
+use bitcoin::{
+    Address, Amount, CompressedPublicKey, OutPoint, Psbt, Transaction, TxIn,
+    absolute::{Height, LockTime},
+    consensus::{self, Encodable},
+};
 use clap::Args;
 use color_eyre::eyre;
-use color_eyre::eyre::ensure;
-use console::style;
-use op_rand_prover::{BarretenbergProver, OpRandProof, OpRandProver};
-use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+use color_eyre::eyre::{OptionExt, bail, ensure};
+use op_rand_prover::OpRandProverAsync;
+use op_rand_transaction_builder::{Challenge, RelayPolicy, parse_challenge_witness_script};
+use op_rand_types::{
+    Capabilities, FirstRankCommitment, ProtocolInfo, ThirdRankCommitment, parse_public_key_strict,
+};
 
 #[derive(Args, Debug)]
 pub struct CompleteChallengeArgs {
@@ -28,6 +40,14 @@ pub struct CompleteChallengeArgs {
     /// Path to the acceptor JSON file
     #[clap(long, default_value = "acceptor.json")]
     pub acceptor_file: String,
+    /// Add this wallet UTXO (`txid:vout`) to the challenge transaction to pay extra fees.
+    /// Can be repeated. Requires the acceptor to sign with SIGHASH_ALL|ANYONECANPAY.
+    #[clap(long = "fee-utxo")]
+    pub fee_utxos: Vec<OutPoint>,
+    /// Write the challenge PSBT signed for a fee sponsor to this file instead of broadcasting
+    /// the challenge transaction. The sponsor finalizes it with `sponsor-psbt`.
+    #[clap(long, conflicts_with = "fee_utxos")]
+    pub sponsor_psbt: Option<String>,
 }
 
 pub async fn run(
@@ -35,194 +55,556 @@ pub async fn run(
         challenger_file,
         challenger_private_file,
         acceptor_file,
+        fee_utxos,
+        sponsor_psbt,
     }: CompleteChallengeArgs,
     mut ctx: Context,
 ) -> eyre::Result<()> {
-    println!(
-        "{}",
-        ui::header("                      🏁 COMPLETING CHALLENGE 🏁")
-    );
-
-    println!(
-        "\n{} {}",
-        GEAR,
-        style("Loading challenge data...").bold().blue()
-    );
+    let mut r = ctx.renderer();
+    r.header("🏁", "COMPLETING CHALLENGE");
+    r.step(GEAR, "Loading challenge data...");
 
-    let challenger_json = fs::read_to_string(&challenger_file)?;
-    let challenger_data: PublicChallengerData = serde_json::from_str(&challenger_json)?;
+    let challenger_data: PublicChallengerData = load_message(&challenger_file)?;
 
     let challenger_private_json = fs::read_to_string(&challenger_private_file)?;
     let challenger_private_data: PrivateChallengerData =
         serde_json::from_str(&challenger_private_json)?;
 
-    let acceptor_json = fs::read_to_string(&acceptor_file)?;
-    let acceptor_data: AcceptorData = serde_json::from_str(&acceptor_json)?;
+    let acceptor_data: AcceptorData = load_message(&acceptor_file)?;
 
-    println!(
-        "{} {} {}",
-        CHECK,
-        style("Challenge ID:").bold().yellow(),
-        style(&challenger_data.id).bright().white()
-    );
+    r.field("Challenge ID", &challenger_data.id);
 
     ensure!(
         challenger_data.id == acceptor_data.id,
         "Challenger and acceptor IDs do not match"
     );
 
-    println!(
-        "{} {}",
-        CHECK,
-        style("Challenge and acceptor IDs match!").bold().green()
-    );
+    // Fee and sponsor inputs can only be added if the acceptor's signatures don't commit to all
+    // inputs
+    let required = if fee_utxos.is_empty() && sponsor_psbt.is_none() {
+        Capabilities::DETERMINISTIC_IDS
+    } else {
+        Capabilities::DETERMINISTIC_IDS.union(Capabilities::ANYONECANPAY)
+    };
+    ProtocolInfo::local().negotiate(&acceptor_data.protocol, required)?;
+    challenger_data.check_id()?;
+
+    r.done("Challenge and acceptor IDs match!");
 
-    let prover = BarretenbergProver::default();
+    let prover = ctx.prover()?;
     let acceptor_pubkey_hash = hex::decode(&acceptor_data.acceptor_pubkey_hash)?;
 
-    println!(
-        "\n{} {}",
-        SHIELD,
-        style("Validating commitments...").bold().blue()
-    );
+    r.step(SHIELD, "Validating commitments...");
 
     let challenger_commitments = challenger_data
         .third_rank_commitments
         .iter()
-        .map(|s| ThirdRankCommitment::from_str(s))
+        .map(|s| ThirdRankCommitment::from_str_strict(s))
         .collect::<Result<Vec<_>, _>>()?;
 
     let acceptor_commitments = acceptor_data
         .third_rank_commitments
         .iter()
-        .map(|s| ThirdRankCommitment::from_str(s))
+        .map(|s| ThirdRankCommitment::from_str_strict(s))
         .collect::<Result<Vec<_>, _>>()?;
 
     ensure!(
         challenger_commitments
             .iter()
             .zip(acceptor_commitments.iter())
-            .all(|(a, b)| a.inner() == b.inner()),
+            .all(|(a, b)| a.ct_eq(b)),
         "Third rank commitments do not match between challenger and acceptor"
     );
 
-    println!(
-        "{} {}",
-        CHECK,
-        style("Third rank commitments validated!").bold().green()
-    );
+    r.done("Third rank commitments validated!");
 
-    let proof = hex::decode(&acceptor_data.proof)?;
-    let vk = hex::decode(&acceptor_data.vk)?;
-    let proof_data = OpRandProof::new(proof, vk);
-
-    let pb = setup_progress_bar("Setting up acceptor circuit...".into());
-    let prover_clone = prover.clone();
-    tokio::task::spawn_blocking(move || {
-        prover_clone
-            .setup_acceptor_circuit()
-            .expect("Failed to setup acceptor circuit")
-    })
-    .await?;
-    pb.finish_with_message("Acceptor circuit is set up");
+    let proof_data = envelope_proof(
+        &acceptor_data.proof,
+        &acceptor_data.vk,
+        acceptor_data.circuit_hash.as_deref(),
+    )?;
 
-    println!(
-        "\n{} {}",
-        SHIELD,
-        style("Verifying acceptor proof...").bold().blue()
-    );
+    let pb = r.progress("Setting up acceptor circuit...");
+    ctx.track_proving(&pb);
+    let acceptor_circuit = prover.setup_acceptor_circuit().await?;
+    pb.finish_with_message("Acceptor circuit is set up");
 
-    prover.verify_acceptor_proof(
-        acceptor_pubkey_hash
-            .try_into()
-            .map_err(|_| eyre::eyre!("Failed to convert pubkey hash to array"))?,
-        challenger_commitments
-            .try_into()
-            .map_err(|_| eyre::eyre!("Failed to convert commitments to array"))?,
-        &proof_data,
-    )?;
+    r.step(SHIELD, "Verifying acceptor proof...");
+
+    prover
+        .verify_acceptor_proof(
+            &acceptor_circuit,
+            acceptor_pubkey_hash
+                .try_into()
+                .map_err(|_| eyre::eyre!("Failed to convert pubkey hash to array"))?,
+            &challenger_commitments,
+            &proof_data,
+        )
+        .await?;
 
-    println!(
-        "{} {}",
-        CHECK,
-        style("Acceptor proof verified successfully!")
-            .bold()
-            .green()
-    );
+    r.done("Acceptor proof verified successfully!");
 
     // TODO: cosign the PSBT and broadcast the transaction
+    let cfg = ctx.config()?;
     let esplora_client = ctx.esplora_client()?;
     let transaction_builder = ctx.transaction_builder()?;
-    let psbt_bytes = general_purpose::STANDARD.decode(&acceptor_data.psbt)?;
+    let mut store = ctx.store()?;
+    let relay_policy = cfg.relay_policy();
+
+    if let Some(new_id) = store
+        .challenges
+        .get(&challenger_data.id)
+        .and_then(|record| record.superseded_by.as_ref())
+    {
+        bail!(
+            "Challenge was re-offered as {}, its acceptance can't be completed",
+            new_id
+        );
+    }
+
+    if let Some(deadline) = acceptor_data.completion_deadline {
+        let tip_height = esplora_client.get_tip_height().await?;
+        ensure!(
+            tip_height < u64::from(deadline),
+            "Acceptance expired at block {}, the acceptor may have spent the funding UTXOs",
+            deadline
+        );
+    }
 
+    let psbt_bytes = general_purpose::STANDARD.decode(&acceptor_data.psbt)?;
     let psbt = Psbt::deserialize(&psbt_bytes)?;
+
+    // The acceptor can still spend their inputs until the challenge transaction confirms
+    r.step(CHAIN, "Checking the acceptor's inputs...");
+    let acceptor_inputs = psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .filter(|outpoint| *outpoint != challenger_data.deposit_outpoint)
+        .collect::<Vec<_>>();
+    if let Some(conflict) = find_input_conflict(
+        &esplora_client,
+        &store,
+        &challenger_data.id,
+        acceptor_inputs.iter().copied(),
+    )
+    .await?
+    {
+        return abort_on_conflict(
+            r.as_mut(),
+            &esplora_client,
+            &mut store,
+            &challenger_data,
+            conflict,
+        )
+        .await;
+    }
+    r.done("Acceptor's inputs are unspent");
+
+    let fee_inputs = if fee_utxos.is_empty() {
+        Vec::new()
+    } else {
+        let address = Address::p2wpkh(
+            &CompressedPublicKey::from_private_key(ctx.secp_ctx(), &cfg.private_key).unwrap(),
+            cfg.network,
+        );
+        let utxos = esplora_client.get_utxos(&address.to_string()).await?;
+
+        apply_coin_control(utxos, &fee_utxos, &[], &store)?
+            .iter()
+            .map(|utxo| Ok((utxo.outpoint()?, utxo.value)))
+            .collect::<eyre::Result<Vec<_>>>()?
+    };
     let selected_first_rank_commitment =
         FirstRankCommitment::from_str(&challenger_private_data.selected_first_rank_commitment)?;
-
-    println!(
-        "\n{} {}",
-        GEAR,
-        style("Finalizing challenge transaction...").bold().blue()
+    // Value spent by the challenge transaction, for the relay policy check of its fee. The
+    // deposit input is signed, and its UTXO set, by the challenger only.
+    let input_value = psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .zip(&psbt.inputs)
+        .filter(|(input, _)| input.previous_output != challenger_data.deposit_outpoint)
+        .filter_map(|(_, input)| input.witness_utxo.as_ref())
+        .map(|utxo| utxo.value)
+        .chain(fee_inputs.iter().map(|(_, value)| *value))
+        .chain([challenger_data.amount])
+        .sum::<Amount>();
+
+    r.step(
+        SHIELD,
+        "Checking earlier versions of the challenge transaction...",
     );
 
-    let signed_challenge_transaction = transaction_builder.complete_challenge_tx(
+    // Signatures aside, the transaction about to be signed. A sponsor adds an input of its own
+    // later, so the fee of a sponsored version isn't known yet.
+    let mut candidate = psbt.unsigned_tx.clone();
+    candidate
+        .input
+        .extend(fee_inputs.iter().map(|(outpoint, _)| TxIn {
+            previous_output: *outpoint,
+            ..Default::default()
+        }));
+    let candidate_fee = match sponsor_psbt {
+        Some(_) => None,
+        None => Some(
+            input_value
+                .checked_sub(candidate.output.iter().map(|output| output.value).sum())
+                .ok_or_eyre("The challenge transaction spends more than its inputs")?,
+        ),
+    };
+    check_earlier_versions(
+        &esplora_client,
+        &store,
+        &relay_policy,
+        &challenger_data.id,
+        &candidate,
+        candidate_fee,
+    )
+    .await?;
+
+    r.done("No conflicting version was signed");
+
+    r.step(GEAR, "Finalizing challenge transaction...");
+
+    let challenge = Challenge::new(
+        consensus::encode::deserialize_hex(&challenger_private_data.deposit_transaction)?,
+        parse_public_key_strict("challenger public key", &challenger_data.challenger_pubkey)?
+            .into(),
+        LockTime::Blocks(Height::from_consensus(challenger_data.locktime)?),
+    )
+    .accept(
         psbt,
-        Amount::from_sat(challenger_data.amount),
-        0,
+        parse_challenge_witness_script(&acceptor_data.challenge_output_witness_script)?,
+    )?;
+    let deposit_transaction = challenger_private_data.deposit_transaction;
+
+    if let Some(sponsor_psbt) = sponsor_psbt {
+        let psbt =
+            challenge.sign_for_sponsor(&transaction_builder, selected_first_rank_commitment)?;
+
+        // The signed version is recorded before it is shared, so that a conflicting version is
+        // never signed after it. It lacks the input of the sponsor, which is added later.
+        if !challenger_data.deposit_broadcast {
+            store.record_transaction(
+                &challenger_data.id,
+                BroadcastKind::Deposit,
+                &deposit_transaction,
+            )?;
+        }
+        store.record_transaction(
+            &challenger_data.id,
+            BroadcastKind::Challenge,
+            &consensus::encode::serialize_hex(&psbt.unsigned_tx),
+        )?;
+        if let Some(record) = store.challenges.get_mut(&challenger_data.id) {
+            record.witness_script = Some(acceptor_data.challenge_output_witness_script.clone());
+        }
+        store.save()?;
+
+        fs::write(
+            &sponsor_psbt,
+            general_purpose::STANDARD.encode(psbt.serialize()),
+        )?;
+
+        r.step(RADIO, "Broadcasting deposit transaction...");
+
+        if challenger_data.deposit_broadcast {
+            r.done("Deposit transaction was broadcasted at creation");
+        } else {
+            esplora_client
+                .broadcast_transaction(&deposit_transaction)
+                .await?;
+
+            r.done("Deposit transaction broadcasted!");
+        }
+
+        r.section("FILE OUTPUTS");
+        r.field("Sponsor PSBT (share with the sponsor)", &sponsor_psbt);
+        r.success("CHALLENGE SIGNED FOR THE SPONSOR!");
+        r.finish();
+
+        return Ok(());
+    }
+
+    let challenge = challenge.complete(
+        &transaction_builder,
         selected_first_rank_commitment,
+        fee_inputs,
     )?;
+    let signed_challenge_transaction = challenge.challenge_transaction();
+    let fee = input_value
+        .checked_sub(
+            signed_challenge_transaction
+                .output
+                .iter()
+                .map(|output| output.value)
+                .sum(),
+        )
+        .ok_or_eyre("The challenge transaction spends more than its inputs")?;
+    relay_policy.check_standard(signed_challenge_transaction, fee)?;
 
-    let deposit_transaction = challenger_private_data.deposit_transaction;
     let mut challenge_transaction_bytes = Vec::new();
     signed_challenge_transaction.consensus_encode(&mut challenge_transaction_bytes)?;
     let challenge_transaction = hex::encode(challenge_transaction_bytes);
 
-    println!(
-        "\n{} {}",
-        RADIO,
-        style("Broadcasting transactions...").bold().blue()
+    r.step(RADIO, "Broadcasting transactions...");
+
+    if !challenger_data.deposit_broadcast {
+        store.record_transaction(
+            &challenger_data.id,
+            BroadcastKind::Deposit,
+            &deposit_transaction,
+        )?;
+    }
+    store.record_transaction(
+        &challenger_data.id,
+        BroadcastKind::Challenge,
+        &challenge_transaction,
+    )?;
+    if let Some(record) = store.challenges.get_mut(&challenger_data.id) {
+        record.witness_script = Some(acceptor_data.challenge_output_witness_script.clone());
+    }
+    store.save()?;
+
+    if challenger_data.deposit_broadcast {
+        r.done("Deposit transaction was broadcasted at creation");
+    } else {
+        esplora_client
+            .broadcast_transaction(&deposit_transaction)
+            .await?;
+
+        r.done("Deposit transaction broadcasted!");
+    }
+
+    // A conflict may have appeared since the check, the rejection alone doesn't tell
+    if let Err(err) = esplora_client
+        .broadcast_transaction(&challenge_transaction)
+        .await
+    {
+        if let Some(conflict) = find_input_conflict(
+            &esplora_client,
+            &store,
+            &challenger_data.id,
+            acceptor_inputs.iter().copied(),
+        )
+        .await?
+        {
+            return abort_on_conflict(
+                r.as_mut(),
+                &esplora_client,
+                &mut store,
+                &challenger_data,
+                conflict,
+            )
+            .await;
+        }
+        return Err(err);
+    }
+
+    r.done("Challenge transaction broadcasted!");
+
+    r.section("TRANSACTION DETAILS");
+    r.field(
+        "Challenge TXID",
+        signed_challenge_transaction.compute_txid().to_string(),
     );
+    r.field("Challenge Transaction", &challenge_transaction);
+    r.success("CHALLENGE COMPLETED SUCCESSFULLY!");
+    r.finish();
 
-    esplora_client
-        .broadcast_transaction(&deposit_transaction)
-        .await?;
+    Ok(())
+}
 
-    println!(
-        "{} {}",
-        CHECK,
-        style("Deposit transaction broadcasted!").bold().green()
-    );
+/// Refuses to sign a version of the challenge transaction conflicting with one recorded before,
+/// unless the earlier version is unconfirmed and `fee` is enough for the new version to replace
+/// it. Every signed version is a valid alternative for the acceptor to broadcast, so signing
+/// another one must not leave them a choice. Signing the same transaction again is allowed.
+async fn check_earlier_versions(
+    esplora_client: &EsploraClient,
+    store: &Store,
+    relay_policy: &RelayPolicy,
+    challenge_id: &str,
+    candidate: &Transaction,
+    fee: Option<Amount>,
+) -> eyre::Result<()> {
+    let ntxid = candidate.compute_ntxid();
+
+    for (txid, record) in store.challenge_transactions(challenge_id) {
+        if record.kind != Some(BroadcastKind::Challenge) {
+            continue;
+        }
+        let earlier: Transaction = consensus::encode::deserialize_hex(&record.raw_tx)?;
+        if earlier.compute_ntxid() == ntxid {
+            continue;
+        }
+
+        if esplora_client.has_transaction(txid).await?
+            && esplora_client.get_tx_status(txid).await?.confirmed
+        {
+            bail!(
+                "Challenge transaction {} is confirmed, another version of it can't be signed",
+                txid
+            );
+        }
+
+        let Some(fee) = fee else {
+            bail!(
+                "Challenge transaction {} was signed already, a sponsored version can't be shown to replace it",
+                txid
+            );
+        };
+
+        // The replacement pays for its own relay on top of the fee of the replaced version. A
+        // version signed for a sponsor lacks the sponsor input, so its fee isn't known.
+        let Ok(earlier_fee) = transaction_fee(esplora_client, &earlier).await else {
+            bail!(
+                "Challenge transaction {} was signed for a sponsor already, its fee is unknown so no version can be shown to replace it",
+                txid
+            );
+        };
+        let min_fee = relay_policy
+            .min_relay_fee
+            .fee_wu(earlier.weight())
+            .and_then(|relay_fee| earlier_fee.checked_add(relay_fee))
+            .ok_or_eyre("Fee of the earlier challenge transaction overflows")?;
+        ensure!(
+            fee >= min_fee,
+            "Challenge transaction {} was signed already. A version replacing it must pay at least {} in fees, this one pays {}",
+            txid,
+            min_fee,
+            fee
+        );
+    }
 
-    esplora_client
-        .broadcast_transaction(&challenge_transaction)
-        .await?;
+    Ok(())
+}
 
-    println!(
-        "{} {}",
-        CHECK,
-        style("Challenge transaction broadcasted!").bold().green()
-    );
+/// Fee paid by `tx`, from the outputs it spends
+async fn transaction_fee(esplora_client: &EsploraClient, tx: &Transaction) -> eyre::Result<Amount> {
+    let mut input_value = Amount::ZERO;
+    for input in &tx.input {
+        let outpoint = input.previous_output;
+        let previous = esplora_client.get_transaction(&outpoint.txid).await?;
+        input_value += previous
+            .output
+            .get(outpoint.vout as usize)
+            .ok_or_eyre("Spent output not found")?
+            .value;
+    }
+
+    input_value
+        .checked_sub(tx.output.iter().map(|output| output.value).sum())
+        .ok_or_eyre("Transaction spends more than its inputs")
+}
+
+/// Completion aborted because the acceptor double-spent one of their inputs
+struct Abort<'a> {
+    challenge_id: &'a str,
+    conflict: &'a InputConflict,
+    deposit_broadcast: bool,
+    /// Block from which the deposit can be refunded, if the acceptance window is still open
+    refund_from: Option<u32>,
+    released_utxos: usize,
+}
+
+/// Aborts a completion the acceptor made impossible by double-spending one of their inputs.
+/// The UTXOs reserved for the deposit are released and the refund or re-offer flow is shown.
+async fn abort_on_conflict(
+    r: &mut dyn Renderer,
+    esplora_client: &EsploraClient,
+    store: &mut Store,
+    challenger_data: &PublicChallengerData,
+    conflict: InputConflict,
+) -> eyre::Result<()> {
+    let released_utxos = store.release(&challenger_data.id).len();
+    store.save()?;
 
-    // Success message
-    println!(
-        "\n{}",
-        ui::success_footer("CHALLENGE COMPLETED SUCCESSFULLY!")
+    let deposit_broadcast = esplora_client
+        .has_transaction(&challenger_data.deposit_outpoint.txid)
+        .await?;
+    let tip_height = esplora_client.get_tip_height().await?;
+    let refund_from = challenger_data
+        .acceptance_deadline
+        .filter(|deadline| tip_height < u64::from(*deadline));
+
+    render_abort(
+        r,
+        &Abort {
+            challenge_id: &challenger_data.id,
+            conflict: &conflict,
+            deposit_broadcast,
+            refund_from,
+            released_utxos,
+        },
     );
-    println!("{}", ui::section_header("TRANSACTION DETAILS"));
-    println!("│");
-    println!(
-        "│ {} {}",
-        CHAIN,
-        style("Challenge Transaction:").bold().yellow()
+    r.finish();
+
+    Err(conflict.into())
+}
+
+/// Renders the conflict and the ways to recover the deposit
+fn render_abort(r: &mut dyn Renderer, abort: &Abort) {
+    r.warning("The acceptor double-spent an input of the challenge transaction");
+
+    r.section("CONFLICT");
+    r.field("Conflicting Input", abort.conflict.outpoint.to_string());
+    r.field(
+        "Conflicting Transaction",
+        abort.conflict.spending_txid.to_string(),
     );
-    println!("│   {}", style(&challenge_transaction).dim());
-    println!(
-        "│   {} {}",
-        style("TXID:").dim(),
-        style(&signed_challenge_transaction.compute_txid().to_string())
-            .bright()
-            .white()
+    r.field("Deposit Broadcast", abort.deposit_broadcast);
+    r.field("Released UTXOs", abort.released_utxos);
+
+    r.section("NEXT STEPS");
+    if abort.deposit_broadcast {
+        match abort.refund_from {
+            Some(height) => r.field(
+                "Refund",
+                format!("op-rand-cli refund, from block {}", height),
+            ),
+            None => r.field("Refund", "op-rand-cli refund"),
+        }
+    } else {
+        r.field(
+            "Refund",
+            "nothing to refund, the deposit was never broadcast",
+        );
+    }
+    r.field(
+        "Re-offer",
+        format!("op-rand-cli reoffer {}", abort.challenge_id),
     );
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use bitcoin::Txid;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_abort() {
+        let conflict = InputConflict {
+            outpoint: OutPoint::from_str(
+                "0707070707070707070707070707070707070707070707070707070707070707:1",
+            )
+            .unwrap(),
+            spending_txid: Txid::from_str(
+                "0808080808080808080808080808080808080808080808080808080808080808",
+            )
+            .unwrap(),
+        };
+        let abort = Abort {
+            challenge_id: "a1b2c3",
+            conflict: &conflict,
+            deposit_broadcast: true,
+            refund_from: Some(850_144),
+            released_utxos: 0,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_abort(r, &abort)));
+    }
 }
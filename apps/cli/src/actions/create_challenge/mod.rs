@@ -1,33 +1,52 @@
 use bitcoin::{
-    Address, Amount, CompressedPublicKey, OutPoint, PublicKey, Txid,
-    consensus::Encodable,
-    hashes::{Hash, ripemd160, sha256},
-    secp256k1::rand::thread_rng,
+    Address, Amount, CompressedPublicKey, OutPoint, PublicKey, ScriptBuf, Txid,
+    consensus::{self, Encodable},
+    hashes::{Hash, HashEngine, ripemd160, sha256},
+    secp256k1::{
+        self,
+        rand::{RngCore, thread_rng},
+    },
 };
 use clap::Args;
 use color_eyre::{
     eyre,
-    eyre::{OptionExt, ensure},
+    eyre::{Context as _, OptionExt, ensure},
+};
+use op_rand_prover::{OpRandProverAsync, OpRandProverSpawn};
+use op_rand_transaction_builder::TransactionKind;
+use op_rand_types::{
+    BeaconParams, BeaconSource, Commitments, EntropyMixer, EntropyRecord, MIXING, ProtocolInfo,
+    ThirdRankCommitment, beacon_anchor, beacon_salt_commitment, beacon_selection,
+    parse_public_key_strict,
 };
-use console::style;
-use op_rand_prover::{BarretenbergProver, OpRandProver};
-use op_rand_types::Commitments;
 use serde::{Deserialize, Serialize};
-use std::{fs, str::FromStr};
+use std::{fs, str::FromStr, time::Duration};
 
 use crate::{
-    context::{Context, setup_progress_bar},
-    ui::{self, CHAIN, CHECK, CLOCK, GEAR, KEY, SPARKLES, TARGET},
-    util::{FEES, MIN_CHANGE, select_utxos},
+    context::Context,
+    drand,
+    format::parse_sats,
+    limits::{
+        BoundedMessage, InputError, MAX_PROOF_LEN, MAX_SHORT_FIELD_LEN, MAX_VK_LEN, check_field,
+    },
+    proving::render_metrics,
+    render::Renderer,
+    store::{BroadcastKind, ChallengeRecord, MessageKind, Role, unix_time},
+    ui::{CHAIN, GEAR, KEY, RADIO, SPARKLES},
+    util::{
+        BLOCK_TIME_TOLERANCE, apply_coin_control, check_address_reuse, check_denomination,
+        compute_change, confirmed_before_beacon, fetch_beacon_value, release_expired_reservations,
+        select_utxos, window_deadline,
+    },
 };
 
 #[derive(Args, Debug)]
 pub struct CreateChallengeArgs {
     /// Challenge amount in satoshis.
-    #[clap(long)]
-    pub amount: u64,
+    #[clap(long, value_parser = parse_sats)]
+    pub amount: Amount,
 
-    /// Number of commitments to create.s
+    /// Number of commitments to create.
     #[clap(long, default_value = "2")]
     pub commitments_count: u32,
 
@@ -46,12 +65,57 @@ pub struct CreateChallengeArgs {
     /// Locktime for the challenge transaction.
     #[clap(long)]
     pub locktime: u32,
+
+    /// Broadcast the deposit transaction now instead of at completion.
+    #[clap(long)]
+    pub broadcast_deposit_now: bool,
+
+    /// Number of blocks the acceptor has to accept the challenge. After that the deposit can be refunded.
+    #[clap(long)]
+    pub acceptance_window: Option<u32>,
+
+    /// Only fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "utxo")]
+    pub include_utxos: Vec<OutPoint>,
+
+    /// Never fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "exclude-utxo")]
+    pub exclude_utxos: Vec<OutPoint>,
+
+    /// Mix this text, e.g. dice rolls, into the OS entropy the commitments are generated from.
+    /// Can be repeated.
+    #[clap(long)]
+    pub entropy: Vec<String>,
+
+    /// Mix the contents of this file into the OS entropy. Can be repeated.
+    #[clap(long)]
+    pub entropy_file: Vec<String>,
+
+    /// Mix this drand beacon round, or `latest`, into the OS entropy.
+    #[clap(long)]
+    pub drand: Option<String>,
+
+    /// Select the first-rank commitment with this future drand round instead of at random.
+    #[clap(long, conflicts_with = "beacon_block_height")]
+    pub beacon_drand_round: Option<u64>,
+
+    /// Select the first-rank commitment with the hash of the block at this future height instead
+    /// of at random.
+    #[clap(long)]
+    pub beacon_block_height: Option<u64>,
 }
 
+/// How often to check whether the selection beacon is published
+const BEACON_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often to check whether the beacon anchor is confirmed
+const ANCHOR_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Serialize, Deserialize)]
 pub struct PublicChallengerData {
     pub id: String,
-    pub amount: u64,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub amount: Amount,
     pub deposit_outpoint: OutPoint,
     pub third_rank_commitments: [String; 2],
     pub challenger_pubkey: String,
@@ -59,15 +123,148 @@ pub struct PublicChallengerData {
     pub proof: String,
     pub vk: String,
     pub locktime: u32,
+    /// Whether the deposit transaction was broadcast at creation
+    #[serde(default)]
+    pub deposit_broadcast: bool,
+    /// Protocol version and capabilities offered by the challenger
+    pub protocol: ProtocolInfo,
+    /// Block height after which the challenger may refund the deposit
+    #[serde(default)]
+    pub acceptance_deadline: Option<u32>,
+    /// Public beacon the deposit commitment was selected with, if not selected at random
+    #[serde(default)]
+    pub beacon: Option<BeaconParams>,
+    /// SHA-256 of the bytecode of the circuit the proof was generated with, hex encoded
+    #[serde(default)]
+    pub circuit_hash: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PrivateChallengerData {
     pub id: String,
-    pub amount: u64,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub amount: Amount,
     pub deposit_transaction: String,
     pub first_rank_commitments: [String; 2],
     pub selected_first_rank_commitment: String,
+    /// Entropy sources the commitments were generated from and how they were mixed
+    #[serde(default)]
+    pub randomness: Option<RandomnessTranscript>,
+    /// Salt mixed into the beacon selection, hex encoded. Reveal it after settlement so that
+    /// the selection can be verified.
+    #[serde(default)]
+    pub beacon_salt: Option<String>,
+}
+
+/// Audit record of the randomness a challenge was created with
+#[derive(Serialize, Deserialize)]
+pub struct RandomnessTranscript {
+    pub mixing: String,
+    pub sources: Vec<EntropyRecord>,
+}
+
+/// Domain separation tag of challenge IDs
+pub const CHALLENGE_ID_TAG: &[u8] = b"op_rand/challenge-id";
+
+/// Derives the challenge ID from the challenge parameters, so that both parties compute it
+/// independently and an ID can't be presented along with other parameters.
+pub fn challenge_id(
+    amount: Amount,
+    deposit_outpoint: &OutPoint,
+    third_rank_commitments: &[secp256k1::PublicKey; 2],
+    challenger_pubkey: &secp256k1::PublicKey,
+    challenger_pubkey_hash: &[u8; 20],
+    locktime: u32,
+) -> String {
+    let mut engine = sha256::Hash::engine();
+    engine.input(CHALLENGE_ID_TAG);
+    deposit_outpoint
+        .consensus_encode(&mut engine)
+        .expect("hash engines don't fail");
+    engine.input(&amount.to_sat().to_le_bytes());
+    engine.input(&locktime.to_le_bytes());
+    engine.input(&challenger_pubkey.serialize());
+    engine.input(challenger_pubkey_hash);
+    for commitment in third_rank_commitments {
+        engine.input(&commitment.serialize());
+    }
+
+    sha256::Hash::from_engine(engine).to_string()
+}
+
+impl PublicChallengerData {
+    /// Derives the challenge ID from the challenge parameters, see [`challenge_id`].
+    pub fn derive_id(&self) -> eyre::Result<String> {
+        let [first, second] = &self.third_rank_commitments;
+        let third_rank_commitments = [
+            ThirdRankCommitment::from_str_strict(first)?.inner(),
+            ThirdRankCommitment::from_str_strict(second)?.inner(),
+        ];
+        let challenger_pubkey_hash = hex::decode(&self.challenger_pubkey_hash)?
+            .try_into()
+            .map_err(|_| eyre::eyre!("Failed to convert challenger public key hash to array"))?;
+
+        Ok(challenge_id(
+            self.amount,
+            &self.deposit_outpoint,
+            &third_rank_commitments,
+            &parse_public_key_strict("challenger public key", &self.challenger_pubkey)?,
+            &challenger_pubkey_hash,
+            self.locktime,
+        ))
+    }
+
+    /// Checks that the challenge ID is the one derived from the challenge parameters, so that
+    /// an ID can't be presented along with other parameters.
+    pub fn check_id(&self) -> eyre::Result<()> {
+        ensure!(
+            self.id == self.derive_id()?,
+            "Challenge ID does not match the challenge parameters"
+        );
+
+        Ok(())
+    }
+}
+
+impl BoundedMessage for PublicChallengerData {
+    fn check_sizes(&self) -> Result<(), InputError> {
+        check_field("id", &self.id, MAX_SHORT_FIELD_LEN)?;
+        for commitment in &self.third_rank_commitments {
+            check_field("third_rank_commitments", commitment, MAX_SHORT_FIELD_LEN)?;
+        }
+        check_field(
+            "challenger_pubkey",
+            &self.challenger_pubkey,
+            MAX_SHORT_FIELD_LEN,
+        )?;
+        check_field(
+            "challenger_pubkey_hash",
+            &self.challenger_pubkey_hash,
+            MAX_SHORT_FIELD_LEN,
+        )?;
+        check_field("proof", &self.proof, MAX_PROOF_LEN)?;
+        check_field("vk", &self.vk, MAX_VK_LEN)?;
+        if let Some(circuit_hash) = &self.circuit_hash {
+            check_field("circuit_hash", circuit_hash, MAX_SHORT_FIELD_LEN)?;
+        }
+        if let Some(beacon) = &self.beacon {
+            check_field(
+                "beacon.salt_commitment",
+                &beacon.salt_commitment,
+                MAX_SHORT_FIELD_LEN,
+            )?;
+            check_field("beacon.selection", &beacon.selection, MAX_SHORT_FIELD_LEN)?;
+            if let Some(anchor_txid) = &beacon.anchor_txid {
+                check_field(
+                    "beacon.anchor_txid",
+                    &anchor_txid.to_string(),
+                    MAX_SHORT_FIELD_LEN,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 pub async fn run(
@@ -78,186 +275,349 @@ pub async fn run(
         private_output,
         change_pubkey,
         locktime,
+        broadcast_deposit_now,
+        acceptance_window,
+        include_utxos,
+        exclude_utxos,
+        entropy,
+        entropy_file,
+        drand,
+        beacon_drand_round,
+        beacon_block_height,
     }: CreateChallengeArgs,
     mut ctx: Context,
 ) -> eyre::Result<()> {
-    println!(
-        "{}",
-        ui::header("                        🎯 CREATING CHALLENGE 🎯")
-    );
+    let mut r = ctx.renderer();
+    r.header("🎯", "CREATING CHALLENGE");
 
     ensure!(
         commitments_count == 2,
         "OP_RAND currently only supports 2 commitments"
     );
 
-    println!(
-        "\n{} {} {}",
-        CHECK,
-        style("Challenge amount:").bold().yellow(),
-        ui::format_bitcoin_amount(amount)
-    );
-
-    println!(
-        "{} {} {} blocks",
-        CLOCK,
-        style("Locktime:").bold().yellow(),
-        style(locktime.to_string()).bright().cyan()
-    );
+    r.field("Challenge amount", amount);
+    r.field("Locktime", locktime);
 
     let cfg = ctx.config()?;
+    check_denomination(r.as_mut(), &cfg.denominations, amount)?;
     let esplora_client = ctx.esplora_client()?;
     let transaction_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let prover = ctx.prover()?;
+    let fee_policy = ctx.fee_policy()?;
+    // The proof binds the wallet key, as the deposit is spent with it, so the identity key
+    // can't stand in for it
     let private_key = cfg.private_key;
     let secp = ctx.secp_ctx();
     let public_key = private_key.public_key(secp).inner;
     let address = Address::p2wpkh(
-        &CompressedPublicKey::from_private_key(secp, &private_key).unwrap(),
+        &CompressedPublicKey::from_private_key(secp, &private_key)?,
         cfg.network,
     );
 
-    println!(
-        "\n{} {}",
-        GEAR,
-        style("Preparing transaction inputs...").bold().blue()
-    );
+    let beacon_source = match (beacon_drand_round, beacon_block_height) {
+        (Some(round), _) => Some(BeaconSource::Drand { round }),
+        (None, Some(height)) => Some(BeaconSource::Block { height }),
+        (None, None) => None,
+    };
+    if let Some(source) = beacon_source {
+        ensure!(
+            fetch_beacon_value(&cfg.drand_url, &cfg.drand_chain, &esplora_client, source)
+                .await?
+                .is_none(),
+            "{} is already published, the selection beacon must be in the future",
+            source
+        );
+        // The anchor must be confirmed in a block timestamped this long before the round
+        if let BeaconSource::Drand { round } = source {
+            ensure!(
+                unix_time().unwrap_or_default() + BLOCK_TIME_TOLERANCE
+                    < cfg.drand_chain.round_time(round),
+                "{} is too close for the beacon anchor to confirm before it, use a later round",
+                source
+            );
+        }
+    }
+
+    r.step(GEAR, "Preparing transaction inputs...");
 
     let utxos = esplora_client.get_utxos(&address.to_string()).await?;
-    let selected_utxos = select_utxos(utxos, amount + FEES)?;
-
-    println!(
-        "{} {} UTXOs selected for funding",
-        CHECK,
-        style(selected_utxos.len().to_string()).bold().green()
-    );
-
-    let prover = BarretenbergProver::default();
+    release_expired_reservations(r.as_mut(), &esplora_client, &mut store).await?;
+    let utxos = apply_coin_control(utxos, &include_utxos, &exclude_utxos, &store)?;
+    // With a beacon, the wallet UTXOs fund the beacon anchor, whose first output then funds the
+    // deposit alone
+    let deposit_fee = fee_policy.fee(TransactionKind::Deposit { inputs: 1 })?;
+    let funding_amount = match beacon_source {
+        Some(_) => amount
+            .checked_add(deposit_fee)
+            .ok_or_eyre("Challenge amount is too large")?,
+        None => amount,
+    };
+    let funding_kind = |inputs| match beacon_source {
+        Some(_) => TransactionKind::BeaconAnchor { inputs },
+        None => TransactionKind::Deposit { inputs },
+    };
+    let selected_utxos = select_utxos(utxos, funding_amount, &fee_policy, funding_kind)?;
+
+    r.done(&format!(
+        "{} UTXOs selected for funding",
+        selected_utxos.len()
+    ));
+
+    let fee = fee_policy.fee(funding_kind(selected_utxos.len()))?;
+    let change = compute_change(&selected_utxos, funding_amount, fee, &fee_policy)?;
+    let change_pubkey = change_pubkey
+        .map(|pk| PublicKey::from_str(&pk))
+        .transpose()?;
+    let change_script = match change {
+        Some(_) => {
+            let pubkey = change_pubkey.unwrap_or(PublicKey::new(public_key));
+            Some(ScriptBuf::new_p2wpkh(&pubkey.wpubkey_hash()?).to_hex_string())
+        }
+        None => None,
+    };
+    let wallet_pubkey = hex::encode(public_key.serialize());
+
+    check_address_reuse(
+        r.as_mut(),
+        cfg.address_reuse,
+        &store,
+        &wallet_pubkey,
+        change_script.as_deref(),
+    )?;
 
-    let pb = setup_progress_bar("Setting up the challenger circuit...".into());
-    let prover_clone = prover.clone();
-    tokio::task::spawn_blocking(move || {
-        prover_clone
-            .setup_challenger_circuit()
-            .expect("Failed to setup challenger circuit")
-    })
-    .await?;
+    let pb = r.progress("Setting up the challenger circuit...");
+    ctx.track_proving(&pb);
+    let challenger_circuit = prover.setup_challenger_circuit().await?;
     pb.finish_with_message("Challenger circuit is set up");
 
-    println!(
-        "\n{} {}",
-        KEY,
-        style("Generating cryptographic commitments...")
-            .bold()
-            .blue()
-    );
-
-    let commitments = Commitments::generate(secp, &mut thread_rng())?;
+    r.step(KEY, "Generating cryptographic commitments...");
+
+    let mut mixer = EntropyMixer::new(&mut thread_rng());
+    for text in &entropy {
+        mixer.mix("user", text.as_bytes());
+    }
+    for path in &entropy_file {
+        mixer.mix(&format!("file:{}", path), &fs::read(path)?);
+    }
+    if let Some(round) = drand {
+        let round = match round.as_str() {
+            "latest" => None,
+            round => Some(round.parse()?),
+        };
+        let beacon = drand::fetch_beacon(&cfg.drand_url, &cfg.drand_chain, round).await?;
+        mixer.mix(
+            &format!("drand:{}", beacon.round),
+            &hex::decode(&beacon.randomness)?,
+        );
+    }
+    let mut rng = mixer.finish();
+    r.field("Entropy sources", rng.records().len());
+
+    let commitments = Commitments::generate(secp, &mut rng)?;
 
     let first_rank_commitments = commitments.first_rank_commitments();
-    let random_first_rank_commitment = commitments
-        .pick_random_first_rank_commitment(&mut thread_rng())
-        .ok_or_eyre("No first rank commitments available")?;
-
-    let (_commitment_sk, commitment_pk) = random_first_rank_commitment.inner();
-    let tweaked_pk = public_key.combine(&commitment_pk)?;
-
     let third_rank_commitments = commitments.third_rank_commitments();
 
-    let sha256_hash = sha256::Hash::hash(&tweaked_pk.serialize());
-    let ripemd160_hash = ripemd160::Hash::hash(sha256_hash.as_byte_array());
-
-    println!(
-        "{} {} third-rank commitments generated",
-        CHECK,
-        style("2").bold().green()
-    );
-
-    let pb = setup_progress_bar("Generating the challenger proof...".into());
-    let proof = prover.generate_challenger_proof(
-        first_rank_commitments.to_owned(),
-        third_rank_commitments.to_owned(),
-        &public_key,
-        ripemd160_hash.to_byte_array(),
-    )?;
-    pb.finish_with_message("Challenger proof generated");
-
-    let inputs_sum = selected_utxos.iter().map(|utxo| utxo.value).sum::<u64>();
-    let change_amount = inputs_sum - amount - FEES;
-    let change = if change_amount < MIN_CHANGE {
-        None
-    } else {
-        Some(Amount::from_sat(change_amount))
-    };
     let prevouts = selected_utxos
         .iter()
         .map(|utxo| {
             Ok((
                 OutPoint::new(Txid::from_str(&utxo.txid)?, utxo.vout),
-                Amount::from_sat(utxo.value),
+                utxo.value,
             ))
         })
         .collect::<Result<Vec<_>, eyre::Error>>()?;
 
-    println!(
-        "\n{} {}",
-        CHAIN,
-        style("Creating deposit transaction...").bold().blue()
-    );
+    let (random_first_rank_commitment, beacon, (prevouts, change, fee)) = match beacon_source {
+        Some(source) => {
+            let mut salt = [0u8; 32];
+            rng.fill_bytes(&mut salt);
+
+            // The commitments and the salt are anchored on chain before the beacon is
+            // published, so that they can't be ground against the beacon value
+            r.step(CHAIN, "Anchoring the commitments before the beacon...");
+
+            let anchor = beacon_anchor(
+                &source,
+                &beacon_salt_commitment(&salt),
+                third_rank_commitments,
+            );
+            let anchor_tx = transaction_builder.build_beacon_anchor_transaction(
+                prevouts,
+                funding_amount,
+                &anchor,
+                change,
+                change_pubkey,
+            )?;
+            fee_policy.relay.check_standard(&anchor_tx, fee)?;
+            let anchor_tx_hex = consensus::encode::serialize_hex(&anchor_tx);
+
+            // Recorded before the challenge has an ID, and assigned to it once it has one
+            let anchor_txid =
+                store.record_transaction("", BroadcastKind::BeaconAnchor, &anchor_tx_hex)?;
+            store.save()?;
+            esplora_client.broadcast_transaction(&anchor_tx_hex).await?;
+            r.field("Anchor TXID", anchor_txid.to_string());
+
+            let pb = r.progress("Waiting for the beacon anchor to confirm...");
+            let status = loop {
+                let status = esplora_client.get_tx_status(&anchor_txid).await?;
+                if status.confirmed {
+                    break status;
+                }
+                tokio::time::sleep(ANCHOR_POLL_INTERVAL).await;
+            };
+            pb.finish_with_message("Beacon anchor confirmed");
+            ensure!(
+                confirmed_before_beacon(&status, &cfg.drand_chain, source)?,
+                "The beacon anchor {} confirmed too late for {}, its outputs are back in the \
+                 wallet. Create the challenge with a later beacon",
+                anchor_txid,
+                source
+            );
+
+            let pb = r.progress(&format!("Waiting for {}...", source));
+            let value = loop {
+                match fetch_beacon_value(&cfg.drand_url, &cfg.drand_chain, &esplora_client, source)
+                    .await?
+                {
+                    Some(value) => break value,
+                    None => tokio::time::sleep(BEACON_POLL_INTERVAL).await,
+                }
+            };
+            pb.finish_with_message(format!("{} published", source));
+
+            let mut params = BeaconParams::new(source, &salt);
+            params.anchor_txid = Some(anchor_txid);
+
+            (
+                commitments.pick_first_rank_commitment(beacon_selection(&salt, &value)),
+                Some((params, salt)),
+                (
+                    vec![(OutPoint::new(anchor_txid, 0), funding_amount)],
+                    None,
+                    deposit_fee,
+                ),
+            )
+        }
+        None => (
+            commitments.pick_random_first_rank_commitment(&mut rng),
+            None,
+            (prevouts, change, fee),
+        ),
+    };
+    let random_first_rank_commitment =
+        random_first_rank_commitment.ok_or_eyre("No first rank commitments available")?;
 
-    let pb = setup_progress_bar("Creating a deposit transaction...".into());
+    let (_commitment_sk, commitment_pk) = random_first_rank_commitment.inner();
+    let tweaked_pk = public_key.combine(&commitment_pk)?;
+
+    let sha256_hash = sha256::Hash::hash(&tweaked_pk.serialize());
+    let ripemd160_hash = ripemd160::Hash::hash(sha256_hash.as_byte_array());
+
+    r.done("2 third-rank commitments generated");
+
+    let funding_outpoints = prevouts
+        .iter()
+        .map(|(outpoint, _)| *outpoint)
+        .collect::<Vec<_>>();
+
+    r.step(CHAIN, "Creating deposit transaction...");
+
+    let pb = r.progress("Creating a deposit transaction...");
     let deposit_tx = transaction_builder.build_deposit_transaction(
         random_first_rank_commitment.to_owned(),
         prevouts,
-        Amount::from_sat(amount),
+        amount,
         change,
-        change_pubkey.and_then(|pk| PublicKey::from_str(&pk).ok()),
+        change_pubkey,
     )?;
+    fee_policy.relay.check_standard(&deposit_tx, fee)?;
 
     pb.finish_with_message("Deposit transaction created");
 
-    println!(
-        "{} {} {}",
-        CHECK,
-        style("Deposit TXID:").bold().yellow(),
-        style(&deposit_tx.compute_txid().to_string())
-            .bright()
-            .white()
+    r.field("Deposit TXID", deposit_tx.compute_txid().to_string());
+
+    let deposit_outpoint = OutPoint::new(deposit_tx.compute_txid(), 0);
+
+    // The proof commits to the deposit outpoint, so it can't be reused with another deposit
+    let pb = r.progress("Generating the challenger proof...");
+    ctx.track_proving(&pb);
+    let proof = prover
+        .spawn_challenger_proof(
+            &challenger_circuit,
+            first_rank_commitments,
+            third_rank_commitments,
+            &public_key,
+            ripemd160_hash.to_byte_array(),
+            &deposit_outpoint,
+        )
+        .await?;
+    pb.finish_with_message("Challenger proof generated");
+    if let Some(metrics) = proof.metrics() {
+        render_metrics(r.as_mut(), metrics);
+    }
+
+    let id = challenge_id(
+        amount,
+        &deposit_outpoint,
+        &[
+            third_rank_commitments[0].inner(),
+            third_rank_commitments[1].inner(),
+        ],
+        &public_key,
+        &ripemd160_hash.to_byte_array(),
+        locktime,
     );
+    let mut tx_bytes = Vec::new();
+    deposit_tx.consensus_encode(&mut tx_bytes)?;
+    let deposit_tx_hex = hex::encode(&tx_bytes);
 
-    let pb = setup_progress_bar("Assembling the challenger data...".into());
-    let id = uuid::Uuid::new_v4().to_string();
+    let pb = r.progress("Assembling the challenger data...");
 
-    println!(
-        "\n{} {}",
-        SPARKLES,
-        style("Finalizing challenge data...").bold().blue()
-    );
+    r.step(SPARKLES, "Finalizing challenge data...");
+
+    let acceptance_deadline = match acceptance_window {
+        Some(window) => Some(window_deadline(
+            esplora_client.get_tip_height().await?,
+            window,
+        )?),
+        None => None,
+    };
 
     let public_challenge_output = PublicChallengerData {
         id: id.clone(),
         amount,
-        deposit_outpoint: OutPoint::new(deposit_tx.compute_txid(), 0),
+        deposit_outpoint,
         third_rank_commitments: [
             hex::encode(third_rank_commitments[0].inner().serialize()),
             hex::encode(third_rank_commitments[1].inner().serialize()),
         ],
-        challenger_pubkey: hex::encode(public_key.serialize()),
+        challenger_pubkey: wallet_pubkey.clone(),
         challenger_pubkey_hash: hex::encode(ripemd160_hash.to_byte_array()),
         proof: hex::encode(proof.proof()),
         vk: hex::encode(proof.vk()),
         locktime,
+        deposit_broadcast: broadcast_deposit_now,
+        protocol: ProtocolInfo::local(),
+        acceptance_deadline,
+        beacon: beacon.as_ref().map(|(params, _)| params.clone()),
+        circuit_hash: proof.circuit_hash().map(|hash| hash.to_string()),
     };
 
+    let beacon_anchor_txid = public_challenge_output
+        .beacon
+        .as_ref()
+        .and_then(|params| params.anchor_txid);
     let json_output = serde_json::to_string_pretty(&public_challenge_output)?;
-    fs::write(&public_output, json_output)?;
-
-    let mut tx_bytes = Vec::new();
-    deposit_tx.consensus_encode(&mut tx_bytes)?;
+    fs::write(&public_output, &json_output)?;
+    store.queue_message(&id, MessageKind::Challenge, &json_output);
 
     let private_challenge_output = PrivateChallengerData {
         id: id.clone(),
         amount,
-        deposit_transaction: hex::encode(tx_bytes),
+        deposit_transaction: deposit_tx_hex.clone(),
         first_rank_commitments: [
             hex::encode(first_rank_commitments[0].inner().0.secret_bytes()),
             hex::encode(first_rank_commitments[1].inner().0.secret_bytes()),
@@ -265,44 +625,209 @@ pub async fn run(
         selected_first_rank_commitment: hex::encode(
             random_first_rank_commitment.inner().0.secret_bytes(),
         ),
+        randomness: Some(RandomnessTranscript {
+            mixing: MIXING.to_string(),
+            sources: rng.records().to_vec(),
+        }),
+        beacon_salt: beacon.map(|(_, salt)| hex::encode(salt)),
     };
 
     let private_json_output = serde_json::to_string_pretty(&private_challenge_output)?;
     fs::write(&private_output, private_json_output)?;
 
+    store.challenges.insert(
+        id.clone(),
+        ChallengeRecord {
+            role: Role::Challenger,
+            wallet_pubkey,
+            change_script,
+            funding_outpoints,
+            deposit_outpoint: Some(public_challenge_output.deposit_outpoint),
+            witness_script: None,
+            completion_deadline: None,
+            metadata: Default::default(),
+            reoffer_of: None,
+            superseded_by: None,
+            counterparty_pubkey: None,
+        },
+    );
+    if let Some(anchor_txid) = beacon_anchor_txid {
+        store.assign_transaction(&anchor_txid, &id);
+    }
+    store.save()?;
+
     pb.finish_with_message("Challenge data assembled");
 
-    // Success message
-    println!("{}", ui::success_footer("CHALLENGE CREATED SUCCESSFULLY!"));
-    println!("{}", ui::section_header("CHALLENGE DETAILS"));
-    println!("│");
-    println!(
-        "│ {} {} {}",
-        TARGET,
-        style("Challenge ID:").bold().yellow(),
-        style(&id).bright().white()
-    );
-    println!(
-        "│ {} {} {}",
-        TARGET,
-        style("Amount:").bold().yellow(),
-        ui::format_bitcoin_amount(amount)
-    );
-    println!("│");
-    println!("{}", ui::section_header("FILE OUTPUTS"));
-    println!("│");
-    println!(
-        "│ {} {} {}",
-        style("📤").bold(),
-        style("Public data (share with acceptor):").bold().green(),
-        style(&public_output).bright().white()
-    );
-    println!(
-        "│ {} {} {}",
-        style("🔒").bold(),
-        style("Private data (keep secure):").bold().red(),
-        style(&private_output).bright().white()
+    // Broadcast last, once the first rank commitment the deposit is locked with is saved, so
+    // that no failure in between loses the deposit
+    if broadcast_deposit_now {
+        r.step(RADIO, "Broadcasting deposit transaction...");
+
+        store.record_transaction(&id, BroadcastKind::Deposit, &deposit_tx_hex)?;
+        store.save()?;
+
+        esplora_client
+            .broadcast_transaction(&deposit_tx_hex)
+            .await
+            .wrap_err_with(|| {
+                format!(
+                    "The challenge was saved but its deposit wasn't broadcast, run `rebroadcast {}` \
+                     before sharing {}",
+                    deposit_tx.compute_txid(),
+                    public_output
+                )
+            })?;
+
+        r.done("Deposit transaction broadcasted!");
+    }
+
+    render_summary(
+        r.as_mut(),
+        &public_challenge_output,
+        &public_output,
+        &private_output,
     );
+    r.success("CHALLENGE CREATED SUCCESSFULLY!");
+    r.finish();
 
     Ok(())
 }
+
+/// Renders the created challenge and where its data was saved
+pub fn render_summary(
+    r: &mut dyn Renderer,
+    challenge: &PublicChallengerData,
+    public_output: &str,
+    private_output: &str,
+) {
+    r.section("CHALLENGE DETAILS");
+    r.field("Challenge ID", &challenge.id);
+    r.field("Amount", challenge.amount);
+    r.field("Deposit Outpoint", challenge.deposit_outpoint.to_string());
+    r.field("Deposit Broadcast", challenge.deposit_broadcast);
+    if let Some(deadline) = challenge.acceptance_deadline {
+        r.field("Acceptance Deadline", deadline);
+    }
+    if let Some(beacon) = &challenge.beacon {
+        r.field("Selection Beacon", beacon.source.to_string());
+        if let Some(anchor_txid) = beacon.anchor_txid {
+            r.field("Beacon Anchor", anchor_txid.to_string());
+        }
+    }
+
+    r.section("FILE OUTPUTS");
+    r.field("Public data (share with acceptor)", public_output);
+    r.field("Private data (keep secure)", private_output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_summary() {
+        let challenge = PublicChallengerData {
+            id: "0f9e5a4c-7d2b-4c1e-9a8f-3b6d2e1c0a9b".to_string(),
+            amount: Amount::from_sat(10_000),
+            deposit_outpoint: OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            third_rank_commitments: [String::new(), String::new()],
+            challenger_pubkey: String::new(),
+            challenger_pubkey_hash: String::new(),
+            proof: String::new(),
+            vk: String::new(),
+            locktime: 144,
+            deposit_broadcast: false,
+            protocol: ProtocolInfo::local(),
+            acceptance_deadline: None,
+            beacon: None,
+            circuit_hash: None,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_summary(
+            r,
+            &challenge,
+            "challenger.json",
+            "private_challenger.json"
+        )));
+    }
+
+    fn public_key(byte: u8) -> String {
+        secp256k1::SecretKey::from_slice(&[byte; 32])
+            .unwrap()
+            .public_key(&secp256k1::Secp256k1::new())
+            .to_string()
+    }
+
+    /// Challenge whose ID is derived from its parameters
+    fn challenge() -> PublicChallengerData {
+        let mut challenge = PublicChallengerData {
+            id: String::new(),
+            amount: Amount::from_sat(10_000),
+            deposit_outpoint: OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            third_rank_commitments: [public_key(2), public_key(3)],
+            challenger_pubkey: public_key(1),
+            challenger_pubkey_hash: "11".repeat(20),
+            proof: String::new(),
+            vk: String::new(),
+            locktime: 840_144,
+            deposit_broadcast: false,
+            protocol: ProtocolInfo::local(),
+            acceptance_deadline: None,
+            beacon: None,
+            circuit_hash: None,
+        };
+        challenge.id = challenge.derive_id().unwrap();
+
+        challenge
+    }
+
+    #[test]
+    fn accepts_derived_id() {
+        let challenge = challenge();
+
+        challenge.check_id().unwrap();
+        assert_eq!(challenge.id.len(), 64);
+        assert_eq!(challenge.derive_id().unwrap(), challenge.id);
+    }
+
+    #[test]
+    fn rejects_id_of_other_parameters() {
+        let mut other_amount = challenge();
+        other_amount.amount = Amount::from_sat(20_000);
+        assert!(other_amount.check_id().is_err());
+
+        let mut other_locktime = challenge();
+        other_locktime.locktime += 1;
+        assert!(other_locktime.check_id().is_err());
+
+        let mut swapped_commitments = challenge();
+        swapped_commitments.third_rank_commitments.swap(0, 1);
+        assert!(swapped_commitments.check_id().is_err());
+
+        let mut other_id = challenge();
+        other_id.id = "0f9e5a4c-7d2b-4c1e-9a8f-3b6d2e1c0a9b".to_string();
+        assert!(other_id.check_id().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_parameters() {
+        let mut malformed_commitment = challenge();
+        malformed_commitment.third_rank_commitments[0] = "05".repeat(33);
+        assert!(malformed_commitment.derive_id().is_err());
+
+        let mut short_hash = challenge();
+        short_hash.challenger_pubkey_hash = "11".repeat(19);
+        assert!(short_hash.derive_id().is_err());
+    }
+}
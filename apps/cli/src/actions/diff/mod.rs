@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+
+use bitcoin::hashes::{Hash, sha256};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, ensure};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::{
+    actions::{
+        accept_challenge::AcceptorData,
+        create_challenge::PublicChallengerData,
+        message::classify,
+        precheck::{PreAcceptanceAck, PreAcceptanceData},
+    },
+    context::Context,
+    limits::{MAX_MESSAGE_SIZE, read_bounded},
+    render::Renderer,
+    store::MessageKind,
+    ui::GEAR,
+};
+
+/// Values longer than this are shown as their length and hash
+const MAX_SHOWN_LEN: usize = 80;
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Path to the earlier version of the message
+    pub file_a: String,
+
+    /// Path to the later version of the message
+    pub file_b: String,
+}
+
+/// A field whose value differs between the two messages. A missing value means the field is
+/// only in the other message.
+#[derive(Debug)]
+struct FieldDiff {
+    path: String,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+pub async fn run(DiffArgs { file_a, file_b }: DiffArgs, ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🔀", "MESSAGE DIFF");
+    r.step(GEAR, "Canonicalizing the messages...");
+
+    let (kind_a, a) = canonicalize(&read_bounded(&file_a, MAX_MESSAGE_SIZE)?)?;
+    let (kind_b, b) = canonicalize(&read_bounded(&file_b, MAX_MESSAGE_SIZE)?)?;
+    ensure!(
+        kind_a == kind_b,
+        "Can't compare a {} message with a {} message",
+        kind_a.as_str(),
+        kind_b.as_str()
+    );
+
+    r.done("Messages canonicalized");
+
+    let diffs = diff_values(&a, &b);
+    render_diff(r.as_mut(), kind_a, &diffs);
+
+    if diffs.is_empty() {
+        r.success("The messages are identical");
+    } else {
+        r.warning("Verify the later message again before using it");
+    }
+    r.finish();
+
+    Ok(())
+}
+
+/// Parses a protocol message into its type and serializes it back, so that formatting, field
+/// order and omitted defaults don't show up as differences
+fn canonicalize(content: &str) -> eyre::Result<(MessageKind, Value)> {
+    let (kind, _) = classify(content).ok_or_eyre("The file is not a protocol message")??;
+
+    let value = match kind {
+        MessageKind::Challenge => roundtrip::<PublicChallengerData>(content)?,
+        MessageKind::Acceptance => roundtrip::<AcceptorData>(content)?,
+        MessageKind::PreAcceptance => roundtrip::<PreAcceptanceData>(content)?,
+        MessageKind::PreAcceptanceAck => roundtrip::<PreAcceptanceAck>(content)?,
+    };
+
+    Ok((kind, value))
+}
+
+/// Deserializes a message as `T` and serializes it back
+fn roundtrip<T: DeserializeOwned + Serialize>(content: &str) -> eyre::Result<Value> {
+    Ok(serde_json::to_value(serde_json::from_str::<T>(content)?)?)
+}
+
+/// Returns the leaf fields whose values differ, ordered by path
+fn diff_values(a: &Value, b: &Value) -> Vec<FieldDiff> {
+    let mut fields_a = BTreeMap::new();
+    let mut fields_b = BTreeMap::new();
+    flatten(a, String::new(), &mut fields_a);
+    flatten(b, String::new(), &mut fields_b);
+
+    let mut paths: Vec<&String> = fields_a.keys().chain(fields_b.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let before = fields_a.get(path);
+            let after = fields_b.get(path);
+            (before != after).then(|| FieldDiff {
+                path: path.clone(),
+                before: before.cloned(),
+                after: after.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Collects the leaf values of `value` keyed by their path, such as `protocol.version` or
+/// `third_rank_commitments[1]`
+fn flatten(value: &Value, path: String, fields: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten(value, path, fields);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (i, value) in array.iter().enumerate() {
+                flatten(value, format!("{}[{}]", path, i), fields);
+            }
+        }
+        _ => {
+            fields.insert(path, value.clone());
+        }
+    }
+}
+
+/// Shows a value, replacing long ones such as proofs by their length and SHA-256
+fn show(value: Option<&Value>) -> String {
+    let text = match value {
+        None => return "(missing)".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(value) => value.to_string(),
+    };
+
+    if text.len() > MAX_SHOWN_LEN {
+        format!(
+            "{} chars, sha256 {}",
+            text.len(),
+            sha256::Hash::hash(text.as_bytes())
+        )
+    } else {
+        text
+    }
+}
+
+/// Renders the fields that differ between the two messages
+fn render_diff(r: &mut dyn Renderer, kind: MessageKind, diffs: &[FieldDiff]) {
+    r.field("Kind", kind.as_str());
+    r.field("Changed Fields", diffs.len());
+
+    r.section("CHANGES");
+    for diff in diffs {
+        r.entry(&diff.path);
+        r.field("Field", &diff.path);
+        r.field("Before", show(diff.before.as_ref()));
+        r.field("After", show(diff.after.as_ref()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_diff() {
+        let a = serde_json::json!({
+            "id": "challenge-1",
+            "locktime": 840_144,
+            "proof": "ab".repeat(64),
+            "third_rank_commitments": ["02aa", "03bb"],
+        });
+        let b = serde_json::json!({
+            "id": "challenge-1",
+            "locktime": 840_288,
+            "proof": "cd".repeat(64),
+            "third_rank_commitments": ["02aa", "03bb"],
+            "circuit_hash": "1f",
+        });
+        let diffs = diff_values(&a, &b);
+
+        insta::assert_snapshot!(render_all(|r| render_diff(
+            r,
+            MessageKind::Challenge,
+            &diffs
+        )));
+    }
+
+    const ACK: &str = r#"{"id":"challenge-1","funding_outpoints":[],"accepted":true,"protocol":{"version":2,"capabilities":32}}"#;
+
+    #[test]
+    fn ignores_formatting_order_and_omitted_defaults() {
+        let reformatted = r#"{
+            "protocol": { "capabilities": 32, "version": 2 },
+            "accepted": true,
+            "reasons": [],
+            "funding_outpoints": [],
+            "id": "challenge-1"
+        }"#;
+
+        let (kind_a, a) = canonicalize(ACK).unwrap();
+        let (kind_b, b) = canonicalize(reformatted).unwrap();
+
+        assert_eq!(kind_a, MessageKind::PreAcceptanceAck);
+        assert_eq!(kind_a, kind_b);
+        assert!(diff_values(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn rejects_non_protocol_messages() {
+        assert!(canonicalize(r#"{"id":"challenge-1"}"#).is_err());
+        assert!(canonicalize("not json").is_err());
+    }
+
+    #[test]
+    fn diffs_nested_and_missing_fields() {
+        let a = serde_json::json!({
+            "protocol": { "version": 2, "capabilities": 32 },
+            "reasons": ["spent"],
+        });
+        let b = serde_json::json!({
+            "protocol": { "version": 2, "capabilities": 96 },
+            "reasons": ["spent", "unconfirmed"],
+        });
+
+        let diffs = diff_values(&a, &b);
+        let paths = diffs
+            .iter()
+            .map(|diff| diff.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["protocol.capabilities", "reasons[1]"]);
+        assert_eq!(diffs[1].before, None);
+        assert_eq!(diffs[1].after, Some(Value::from("unconfirmed")));
+    }
+
+    #[test]
+    fn shows_long_values_as_their_hash() {
+        assert_eq!(show(None), "(missing)");
+        assert_eq!(show(Some(&Value::from("02aa"))), "02aa");
+        assert!(show(Some(&Value::from("ab".repeat(64)))).starts_with("128 chars, sha256 "));
+    }
+}
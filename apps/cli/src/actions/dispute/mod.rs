@@ -0,0 +1,725 @@
+use std::str::FromStr;
+
+use base64::{Engine as _, engine::general_purpose};
+use bitcoin::{Psbt, ScriptBuf, Txid};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt};
+use op_rand_transaction_builder::ChallengeSpend;
+
+use crate::{
+    actions::{
+        accept_challenge::AcceptorData,
+        create_challenge::PublicChallengerData,
+        refund::{self, RefundArgs},
+        sweep::{self, SweepArgs},
+    },
+    context::Context,
+    esplora::EsploraClient,
+    render::Renderer,
+    store::{ChallengeRecord, MessageKind, Role, Store},
+    ui::{CHAIN, GEAR},
+    util::{InputConflict, find_input_conflict, release_expired_reservations},
+};
+
+#[derive(Args, Debug)]
+pub struct DisputeArgs {
+    /// Challenge ID
+    pub id: String,
+
+    /// Run the recovery available now, a refund, a sweep or the release of reserved UTXOs,
+    /// instead of only describing it
+    #[clap(long)]
+    pub recover: bool,
+
+    /// Path to the challenge JSON file, used by refunds
+    #[clap(long, default_value = "challenger.json")]
+    pub challenger_file: String,
+
+    /// Path to the challenger's private key file, used by refunds
+    #[clap(long, default_value = "private_challenger.json")]
+    pub challenger_private_file: String,
+}
+
+/// What went wrong with a challenge, as seen from the local store and the chain
+#[derive(Debug)]
+enum Diagnosis {
+    /// The deposit was never broadcast, so no funds are locked
+    DepositNotBroadcast,
+    /// Nobody accepted the challenge and the deposit is still locked
+    NotAccepted {
+        acceptance_deadline: Option<u32>,
+        refund: RefundWindow,
+    },
+    /// The acceptor double-spent an input of the challenge transaction
+    InputConflict {
+        conflict: InputConflict,
+        refund: RefundWindow,
+    },
+    /// An acceptance was received but never completed
+    NotCompleted {
+        completion_deadline: Option<u32>,
+        expired: bool,
+        refund: RefundWindow,
+    },
+    /// The challenger never completed the acceptance of this wallet
+    CounterpartyNeverBroadcast {
+        completion_deadline: Option<u32>,
+        expired: bool,
+    },
+    /// The challenger refunded the deposit instead of completing the challenge
+    Refunded { txid: Txid },
+    /// The challenge transaction was broadcast but is not confirmed
+    ChallengeUnconfirmed { txid: Txid },
+    /// The challenge transaction is confirmed and its output unspent
+    AwaitingSweep { lock_time: u32, unlocked: bool },
+    /// This wallet swept the challenge output
+    Settled { settlement_txid: Txid },
+    /// The counterparty swept the challenge output
+    SweptByCounterparty {
+        settlement_txid: Txid,
+        after_timeout: bool,
+        lock_time: u32,
+    },
+}
+
+/// When the challenger can refund the deposit
+#[derive(Debug, Clone, Copy)]
+enum RefundWindow {
+    /// The deposit was never broadcast
+    Unavailable,
+    /// The acceptance deadline didn't pass yet
+    From(u32),
+    /// The deposit can be refunded now
+    Open,
+}
+
+impl RefundWindow {
+    /// Returns when a deposit with `acceptance_deadline` can be refunded at `tip_height`
+    fn new(deposit_broadcast: bool, acceptance_deadline: Option<u32>, tip_height: u64) -> Self {
+        match acceptance_deadline {
+            _ if !deposit_broadcast => RefundWindow::Unavailable,
+            Some(deadline) if tip_height < u64::from(deadline) => RefundWindow::From(deadline),
+            _ => RefundWindow::Open,
+        }
+    }
+}
+
+/// Recovery the command can run itself
+#[derive(Debug, PartialEq, Eq)]
+enum Recovery {
+    Refund,
+    Sweep,
+    Release,
+}
+
+/// A step the user can take to recover
+struct RecoveryOption {
+    action: String,
+    command: Option<String>,
+}
+
+impl RecoveryOption {
+    fn new(action: impl Into<String>, command: Option<String>) -> Self {
+        Self {
+            action: action.into(),
+            command,
+        }
+    }
+}
+
+pub async fn run(
+    DisputeArgs {
+        id,
+        recover,
+        challenger_file,
+        challenger_private_file,
+    }: DisputeArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("⚖️ ", "DISPUTE WALKTHROUGH");
+
+    let esplora_client = ctx.esplora_client()?;
+    let mut store = ctx.store()?;
+    let record = store
+        .challenges
+        .get(&id)
+        .ok_or_eyre("Challenge not found in the local store")?
+        .clone();
+
+    r.step(CHAIN, "Inspecting the transcript and the chain...");
+    let diagnosis = diagnose(&esplora_client, &store, &id, &record).await?;
+    r.done("Challenge state reconstructed");
+
+    render_dispute(r.as_mut(), &id, record.role, &diagnosis);
+
+    let recovery = diagnosis.recovery(record.role);
+    if !recover {
+        r.finish();
+        return Ok(());
+    }
+    let Some(recovery) = recovery else {
+        r.warning("No recovery can be run for this challenge now");
+        r.finish();
+        return Ok(());
+    };
+
+    match recovery {
+        Recovery::Release => {
+            r.step(GEAR, "Releasing the UTXOs reserved for the acceptance...");
+            release_expired_reservations(r.as_mut(), &esplora_client, &mut store).await?;
+            store.save()?;
+            r.success("Reserved UTXOs released");
+            r.finish();
+
+            Ok(())
+        }
+        Recovery::Refund => {
+            r.finish();
+            refund::run(
+                RefundArgs {
+                    challenger_file,
+                    challenger_private_file,
+                    recipient: None,
+                    recipient_pubkey: None,
+                },
+                ctx,
+            )
+            .await
+        }
+        Recovery::Sweep => {
+            r.finish();
+            sweep::run(
+                SweepArgs {
+                    id: Some(id),
+                    challenge_tx: None,
+                    recipient: None,
+                    recipient_pubkey: None,
+                    payouts: Vec::new(),
+                    challenge_file: None,
+                    acceptor_file: None,
+                    challenger: false,
+                    acceptor: false,
+                },
+                ctx,
+            )
+            .await
+        }
+    }
+}
+
+/// Reconstructs what happened to a challenge from its messages and the chain
+async fn diagnose(
+    esplora_client: &EsploraClient,
+    store: &Store,
+    id: &str,
+    record: &ChallengeRecord,
+) -> eyre::Result<Diagnosis> {
+    let challenge: PublicChallengerData = serde_json::from_str(
+        &store
+            .find_message(id, MessageKind::Challenge)
+            .ok_or_eyre("The challenge message was not recorded in the local store")?
+            .content,
+    )?;
+    let acceptance = store
+        .find_message(id, MessageKind::Acceptance)
+        .map(|message| serde_json::from_str::<AcceptorData>(&message.content))
+        .transpose()?;
+    let tip_height = esplora_client.get_tip_height().await?;
+    let passed = |deadline: Option<u32>| deadline.is_some_and(|d| tip_height >= u64::from(d));
+
+    let deposit = challenge.deposit_outpoint;
+    let deposit_broadcast = esplora_client.has_transaction(&deposit.txid).await?;
+    let deposit_spend = if deposit_broadcast {
+        let outspend = esplora_client
+            .get_outspend(&deposit.txid, deposit.vout)
+            .await?;
+        outspend
+            .txid
+            .filter(|_| outspend.spent)
+            .map(|txid| Txid::from_str(&txid))
+            .transpose()?
+    } else {
+        None
+    };
+
+    let refund = RefundWindow::new(deposit_broadcast, challenge.acceptance_deadline, tip_height);
+
+    let Some(spending_txid) = deposit_spend else {
+        return Ok(match (record.role, acceptance) {
+            (Role::Challenger, Some(acceptance)) => {
+                let psbt = Psbt::deserialize(&general_purpose::STANDARD.decode(&acceptance.psbt)?)?;
+                let acceptor_inputs = psbt
+                    .unsigned_tx
+                    .input
+                    .iter()
+                    .map(|input| input.previous_output)
+                    .filter(|outpoint| *outpoint != deposit);
+
+                match find_input_conflict(esplora_client, store, id, acceptor_inputs).await? {
+                    Some(conflict) => Diagnosis::InputConflict { conflict, refund },
+                    None => Diagnosis::NotCompleted {
+                        completion_deadline: acceptance.completion_deadline,
+                        expired: passed(acceptance.completion_deadline),
+                        refund,
+                    },
+                }
+            }
+            (Role::Challenger, None) if !deposit_broadcast => Diagnosis::DepositNotBroadcast,
+            (Role::Challenger, None) => Diagnosis::NotAccepted {
+                acceptance_deadline: challenge.acceptance_deadline,
+                refund,
+            },
+            (Role::Acceptor, _) => Diagnosis::CounterpartyNeverBroadcast {
+                completion_deadline: record.completion_deadline,
+                expired: passed(record.completion_deadline),
+            },
+        });
+    };
+
+    // Without a challenge output, the deposit was refunded
+    let challenge_transaction = esplora_client.get_transaction(&spending_txid).await?;
+    let challenge_output = record
+        .witness_script
+        .as_deref()
+        .map(ScriptBuf::from_hex)
+        .transpose()?
+        .map(|witness_script| ScriptBuf::new_p2wsh(&witness_script.wscript_hash()));
+    let is_challenge = challenge_transaction
+        .output
+        .first()
+        .is_some_and(|output| Some(&output.script_pubkey) == challenge_output.as_ref());
+    if !is_challenge {
+        return Ok(Diagnosis::Refunded {
+            txid: spending_txid,
+        });
+    }
+
+    if !esplora_client
+        .get_tx_status(&spending_txid)
+        .await?
+        .confirmed
+    {
+        return Ok(Diagnosis::ChallengeUnconfirmed {
+            txid: spending_txid,
+        });
+    }
+
+    let outspend = esplora_client.get_outspend(&spending_txid, 0).await?;
+    let Some(settlement_txid) = outspend.txid.filter(|_| outspend.spent) else {
+        return Ok(Diagnosis::AwaitingSweep {
+            lock_time: challenge.locktime,
+            unlocked: tip_height >= u64::from(challenge.locktime),
+        });
+    };
+    let settlement_txid = Txid::from_str(&settlement_txid)?;
+    let settlement_transaction = esplora_client.get_transaction(&settlement_txid).await?;
+
+    let spend = ChallengeSpend::classify(&challenge_transaction, &settlement_transaction)?;
+    let after_timeout = matches!(spend, ChallengeSpend::ChallengerTimeout { .. });
+    let swept_by_us = match record.role {
+        Role::Challenger => after_timeout,
+        Role::Acceptor => !after_timeout,
+    };
+
+    Ok(if swept_by_us {
+        Diagnosis::Settled { settlement_txid }
+    } else {
+        Diagnosis::SweptByCounterparty {
+            settlement_txid,
+            after_timeout,
+            lock_time: challenge.locktime,
+        }
+    })
+}
+
+impl Diagnosis {
+    /// Short description of the problem
+    fn problem(&self) -> &'static str {
+        match self {
+            Diagnosis::DepositNotBroadcast => "deposit never broadcast",
+            Diagnosis::NotAccepted { .. } => "challenge never accepted",
+            Diagnosis::InputConflict { .. } => "acceptor double-spent an input",
+            Diagnosis::NotCompleted { .. } => "acceptance never completed",
+            Diagnosis::CounterpartyNeverBroadcast { .. } => "challenger never broadcast",
+            Diagnosis::Refunded { .. } => "deposit refunded",
+            Diagnosis::ChallengeUnconfirmed { .. } => "challenge transaction unconfirmed",
+            Diagnosis::AwaitingSweep { .. } => "challenge output unspent",
+            Diagnosis::Settled { .. } => "none, settled",
+            Diagnosis::SweptByCounterparty {
+                after_timeout: true,
+                ..
+            } => "sweep race lost",
+            Diagnosis::SweptByCounterparty { .. } => "counterparty won",
+        }
+    }
+
+    /// What happened, in the user's terms
+    fn details(&self, role: Role) -> String {
+        match self {
+            Diagnosis::DepositNotBroadcast => {
+                "The deposit transaction was never broadcast, so no funds are locked".to_string()
+            }
+            Diagnosis::NotAccepted {
+                acceptance_deadline,
+                ..
+            } => match acceptance_deadline {
+                Some(deadline) => format!(
+                    "No acceptance was received and the deposit is locked until block {}",
+                    deadline
+                ),
+                None => "No acceptance was received and the deposit is locked".to_string(),
+            },
+            Diagnosis::InputConflict { conflict, .. } => {
+                format!("{}, the acceptance can't be completed anymore", conflict)
+            }
+            Diagnosis::NotCompleted {
+                completion_deadline,
+                expired,
+                ..
+            } => match (completion_deadline, expired) {
+                (Some(deadline), true) => format!(
+                    "The acceptance expired at block {} without being completed",
+                    deadline
+                ),
+                (Some(deadline), false) => format!(
+                    "The acceptance can still be completed until block {}",
+                    deadline
+                ),
+                (None, _) => "The acceptance was received but not completed".to_string(),
+            },
+            Diagnosis::CounterpartyNeverBroadcast {
+                completion_deadline,
+                expired,
+            } => match (completion_deadline, expired) {
+                (Some(deadline), true) => format!(
+                    "The challenger didn't complete the acceptance before block {}, it expired",
+                    deadline
+                ),
+                (Some(deadline), false) => format!(
+                    "The challenger has until block {} to complete the acceptance",
+                    deadline
+                ),
+                (None, _) => {
+                    "The challenger didn't broadcast the challenge transaction".to_string()
+                }
+            },
+            Diagnosis::Refunded { txid } => match role {
+                Role::Challenger => format!("The deposit was refunded by {}", txid),
+                Role::Acceptor => format!(
+                    "The challenger refunded the deposit with {}, the acceptance is void and \
+                     its UTXOs were never spent",
+                    txid
+                ),
+            },
+            Diagnosis::ChallengeUnconfirmed { txid } => format!(
+                "Challenge transaction {} is not confirmed and may have dropped out of the mempool",
+                txid
+            ),
+            Diagnosis::AwaitingSweep {
+                lock_time,
+                unlocked,
+                ..
+            } => match (role, unlocked) {
+                (Role::Challenger, true) => format!(
+                    "The acceptor didn't sweep the output before block {}, the challenger can \
+                     sweep it now",
+                    lock_time
+                ),
+                (Role::Challenger, false) => format!(
+                    "The acceptor can sweep the output if they won, the challenger from block {}",
+                    lock_time
+                ),
+                (Role::Acceptor, true) => format!(
+                    "The lock time expired at block {}, the challenger can sweep the output too",
+                    lock_time
+                ),
+                (Role::Acceptor, false) => format!(
+                    "The output can be swept if the acceptor won, until block {}",
+                    lock_time
+                ),
+            },
+            Diagnosis::Settled { settlement_txid } => {
+                format!(
+                    "This wallet swept the challenge output with {}",
+                    settlement_txid
+                )
+            }
+            Diagnosis::SweptByCounterparty {
+                settlement_txid,
+                after_timeout,
+                lock_time,
+            } => match (role, after_timeout) {
+                (Role::Acceptor, true) => format!(
+                    "The challenger swept the output with {} after the lock time expired at \
+                     block {}. If the acceptor won, their sweep didn't confirm in time",
+                    settlement_txid, lock_time
+                ),
+                _ => format!(
+                    "The acceptor won and swept the output with {}",
+                    settlement_txid
+                ),
+            },
+        }
+    }
+
+    /// Steps the user can take to recover
+    fn options(&self, role: Role, id: &str) -> Vec<RecoveryOption> {
+        let refund = |window: &RefundWindow| match window {
+            RefundWindow::Unavailable => None,
+            RefundWindow::From(deadline) => Some(RecoveryOption::new(
+                format!("Refund the deposit from block {}", deadline),
+                None,
+            )),
+            RefundWindow::Open => Some(RecoveryOption::new(
+                "Refund the deposit",
+                Some(format!("op-rand-cli dispute {} --recover", id)),
+            )),
+        };
+        let reoffer = || {
+            RecoveryOption::new(
+                "Re-offer the challenge to another acceptor",
+                Some(format!("op-rand-cli reoffer {}", id)),
+            )
+        };
+
+        match self {
+            Diagnosis::DepositNotBroadcast => vec![reoffer()],
+            Diagnosis::NotAccepted { refund: window, .. }
+            | Diagnosis::InputConflict { refund: window, .. } => [Some(reoffer()), refund(window)]
+                .into_iter()
+                .flatten()
+                .collect(),
+            Diagnosis::NotCompleted {
+                expired,
+                refund: window,
+                ..
+            } => {
+                let complete = (!expired).then(|| {
+                    RecoveryOption::new(
+                        "Complete the acceptance",
+                        Some("op-rand-cli complete-challenge".to_string()),
+                    )
+                });
+                // Refunding would void an acceptance that can still be completed
+                [
+                    complete,
+                    Some(reoffer()),
+                    refund(window).filter(|_| *expired),
+                ]
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+            Diagnosis::CounterpartyNeverBroadcast { expired: true, .. } => {
+                vec![RecoveryOption::new(
+                    "Release the UTXOs reserved for the acceptance",
+                    Some(format!("op-rand-cli dispute {} --recover", id)),
+                )]
+            }
+            Diagnosis::CounterpartyNeverBroadcast { .. } => vec![RecoveryOption::new(
+                "Wait for the challenger, or spend a funding UTXO to void the acceptance",
+                None,
+            )],
+            Diagnosis::Refunded { .. } => Vec::new(),
+            Diagnosis::ChallengeUnconfirmed { txid } => vec![RecoveryOption::new(
+                "Rebroadcast the challenge transaction",
+                (role == Role::Challenger).then(|| format!("op-rand-cli rebroadcast {}", txid)),
+            )],
+            Diagnosis::AwaitingSweep {
+                unlocked,
+                lock_time,
+            } => match (role, unlocked) {
+                (Role::Challenger, true) => vec![RecoveryOption::new(
+                    "Sweep the challenge output",
+                    Some(format!("op-rand-cli sweep {}", id)),
+                )],
+                (Role::Challenger, false) => vec![RecoveryOption::new(
+                    format!("Sweep the challenge output from block {}", lock_time),
+                    None,
+                )],
+                (Role::Acceptor, _) => vec![RecoveryOption::new(
+                    "Sweep the challenge output if the acceptor won",
+                    Some(format!("op-rand-cli sweep {}", id)),
+                )],
+            },
+            Diagnosis::Settled { .. } => vec![RecoveryOption::new(
+                "Show the outcome certificate",
+                Some(format!("op-rand-cli outcome {}", id)),
+            )],
+            Diagnosis::SweptByCounterparty { .. } => Vec::new(),
+        }
+    }
+
+    /// Recovery `--recover` runs, if one is available now
+    fn recovery(&self, role: Role) -> Option<Recovery> {
+        match self {
+            Diagnosis::NotAccepted {
+                refund: RefundWindow::Open,
+                ..
+            }
+            | Diagnosis::InputConflict {
+                refund: RefundWindow::Open,
+                ..
+            }
+            | Diagnosis::NotCompleted {
+                expired: true,
+                refund: RefundWindow::Open,
+                ..
+            } => Some(Recovery::Refund),
+            Diagnosis::CounterpartyNeverBroadcast { expired: true, .. } => Some(Recovery::Release),
+            Diagnosis::AwaitingSweep { unlocked, .. } => match role {
+                Role::Challenger if !unlocked => None,
+                _ => Some(Recovery::Sweep),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Renders the diagnosis of a challenge with the recovery options of the user's role
+fn render_dispute(r: &mut dyn Renderer, id: &str, role: Role, diagnosis: &Diagnosis) {
+    r.field("Challenge ID", id);
+    r.field(
+        "Role",
+        match role {
+            Role::Challenger => "challenger",
+            Role::Acceptor => "acceptor",
+        },
+    );
+
+    r.section("DIAGNOSIS");
+    r.field("Problem", diagnosis.problem());
+    r.field("Details", diagnosis.details(role));
+
+    let options = diagnosis.options(role, id);
+    if options.is_empty() {
+        r.section("RECOVERY");
+        r.field("Action", "Nothing left to recover on chain");
+        return;
+    }
+
+    r.section("RECOVERY OPTIONS");
+    for (i, option) in options.iter().enumerate() {
+        r.entry(&format!("{}.", i + 1));
+        r.field("Action", &option.action);
+        if let Some(command) = &option.command {
+            r.field("Command", command);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_dispute() {
+        let diagnosis = Diagnosis::NotCompleted {
+            completion_deadline: Some(840_144),
+            expired: true,
+            refund: RefundWindow::Open,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_dispute(
+            r,
+            "challenge-1",
+            Role::Challenger,
+            &diagnosis
+        )));
+    }
+
+    #[test]
+    fn opens_refund_window_at_acceptance_deadline() {
+        assert!(matches!(
+            RefundWindow::new(false, Some(100), 200),
+            RefundWindow::Unavailable
+        ));
+        assert!(matches!(
+            RefundWindow::new(true, Some(100), 99),
+            RefundWindow::From(100)
+        ));
+        assert!(matches!(
+            RefundWindow::new(true, Some(100), 100),
+            RefundWindow::Open
+        ));
+        assert!(matches!(
+            RefundWindow::new(true, None, 0),
+            RefundWindow::Open
+        ));
+    }
+
+    #[test]
+    fn refunds_only_expired_acceptances() {
+        let pending = Diagnosis::NotCompleted {
+            completion_deadline: Some(100),
+            expired: false,
+            refund: RefundWindow::Open,
+        };
+        assert_eq!(pending.recovery(Role::Challenger), None);
+        assert!(
+            pending
+                .options(Role::Challenger, "challenge-1")
+                .iter()
+                .all(|option| !option.action.starts_with("Refund"))
+        );
+
+        let expired = Diagnosis::NotCompleted {
+            completion_deadline: Some(100),
+            expired: true,
+            refund: RefundWindow::Open,
+        };
+        assert_eq!(expired.recovery(Role::Challenger), Some(Recovery::Refund));
+
+        let before_deadline = Diagnosis::NotAccepted {
+            acceptance_deadline: Some(100),
+            refund: RefundWindow::From(100),
+        };
+        assert_eq!(before_deadline.recovery(Role::Challenger), None);
+    }
+
+    #[test]
+    fn sweeps_challenger_timeout_only_after_lock_time() {
+        let locked = Diagnosis::AwaitingSweep {
+            lock_time: 100,
+            unlocked: false,
+        };
+        assert_eq!(locked.recovery(Role::Challenger), None);
+        assert_eq!(locked.recovery(Role::Acceptor), Some(Recovery::Sweep));
+
+        let unlocked = Diagnosis::AwaitingSweep {
+            lock_time: 100,
+            unlocked: true,
+        };
+        assert_eq!(unlocked.recovery(Role::Challenger), Some(Recovery::Sweep));
+    }
+
+    #[test]
+    fn releases_reservations_of_expired_acceptances() {
+        let expired = Diagnosis::CounterpartyNeverBroadcast {
+            completion_deadline: Some(100),
+            expired: true,
+        };
+        assert_eq!(expired.recovery(Role::Acceptor), Some(Recovery::Release));
+
+        let pending = Diagnosis::CounterpartyNeverBroadcast {
+            completion_deadline: Some(100),
+            expired: false,
+        };
+        assert_eq!(pending.recovery(Role::Acceptor), None);
+    }
+
+    #[test]
+    fn leaves_nothing_to_recover_after_settlement() {
+        let lost = Diagnosis::SweptByCounterparty {
+            settlement_txid: Txid::from_str(&"11".repeat(32)).unwrap(),
+            after_timeout: true,
+            lock_time: 100,
+        };
+
+        assert_eq!(lost.problem(), "sweep race lost");
+        assert!(lost.options(Role::Acceptor, "challenge-1").is_empty());
+        assert_eq!(lost.recovery(Role::Acceptor), None);
+    }
+}
@@ -0,0 +1,356 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::{NetworkKind, blockdata::constants::genesis_block};
+use color_eyre::eyre::{self, bail};
+use op_rand_prover::{DaemonProver, circuit_artifacts};
+
+use crate::{context::Context, render::Renderer, ui::GEAR};
+
+/// Tip older than this is reported as stale.
+const STALE_TIP_SECS: u64 = 2 * 60 * 60;
+
+/// Free space below which the store can't be saved reliably, in bytes
+const MIN_FREE_SPACE: u64 = 64 * 1024 * 1024;
+
+/// Free space below which a downloaded SRS or circuit may not fit, in bytes
+const LOW_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Outcome of a single diagnostic check
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+}
+
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+        }
+    }
+}
+
+pub async fn run(mut ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🩺", "OP_RAND DOCTOR");
+    r.step(GEAR, "Running diagnostics...");
+
+    let mut checks = Vec::new();
+
+    for artifact in circuit_artifacts() {
+        checks.push(match artifact.bytecode_hash {
+            Some(hash) => Check::pass(
+                "Circuit artifact",
+                format!("{} circuit loaded, bytecode sha256 {}", artifact.name, hash),
+            ),
+            None => Check::fail(
+                "Circuit artifact",
+                format!(
+                    "{} circuit missing or invalid at {}",
+                    artifact.name,
+                    artifact.path.display()
+                ),
+            ),
+        });
+    }
+
+    checks.push(check_srs(&ctx.dirs().srs_path()));
+
+    match ctx.config() {
+        Ok(cfg) => {
+            checks.push(Check::pass("Config", "configuration loaded"));
+
+            // The store may not exist yet, the space is checked where it will be written
+            let store_dir = cfg
+                .store_path
+                .parent()
+                .unwrap_or(ctx.dirs().data_dir())
+                .to_path_buf();
+            checks.push(check_disk_space(&store_dir, free_space(&store_dir)));
+
+            if cfg.private_key.network == NetworkKind::from(cfg.network) {
+                checks.push(Check::pass(
+                    "Key/network",
+                    format!("private key matches network {}", cfg.network),
+                ));
+            } else {
+                checks.push(Check::fail(
+                    "Key/network",
+                    format!("private key was not encoded for network {}", cfg.network),
+                ));
+            }
+
+            checks.push(match cfg.identity_key() {
+                Ok(identity_key) => Check::pass(
+                    "Identity key",
+                    format!(
+                        "{} identity key {}, separate from the wallet key",
+                        if cfg.identity_key.is_some() {
+                            "configured"
+                        } else {
+                            "derived"
+                        },
+                        identity_key.public_key(ctx.secp_ctx())
+                    ),
+                ),
+                Err(e) => Check::fail("Identity key", format!("{:#}", e)),
+            });
+
+            checks.extend(check_esplora(&mut ctx, cfg.network).await);
+
+            checks.push(match DaemonProver::connect(&cfg.prover_socket) {
+                Ok(_) => Check::pass(
+                    "Prover daemon",
+                    format!("listening on {}", cfg.prover_socket.display()),
+                ),
+                Err(_) => Check::pass("Prover daemon", "not running, proving in-process"),
+            });
+        }
+        Err(e) => checks.push(Check::fail("Config", format!("{:#}", e))),
+    }
+
+    render_report(r.as_mut(), &checks);
+
+    let failed = checks
+        .iter()
+        .filter(|check| matches!(check.status, Status::Fail))
+        .count();
+
+    if failed > 0 {
+        r.finish();
+        bail!("{} diagnostic check(s) failed", failed);
+    }
+
+    r.success("All critical checks passed");
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the result of every check
+fn render_report(r: &mut dyn Renderer, checks: &[Check]) {
+    r.section("DIAGNOSTIC REPORT");
+
+    for check in checks {
+        r.entry(check.name);
+        r.field("Check", check.name);
+        r.field("Status", check.status.as_str());
+        r.field("Detail", &check.detail);
+    }
+}
+
+/// Checks the local SRS file the in-process prover loads instead of downloading the SRS
+fn check_srs(path: &Path) -> Check {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() && metadata.len() > 0 => Check::pass(
+            "SRS cache",
+            format!(
+                "{} MiB SRS cached at {}",
+                metadata.len() / MIB,
+                path.display()
+            ),
+        ),
+        Ok(_) => Check::fail(
+            "SRS cache",
+            format!("{} is not a non-empty SRS file", path.display()),
+        ),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Check::warn(
+            "SRS cache",
+            format!(
+                "no SRS cached at {}, the SRS is downloaded on every circuit setup",
+                path.display()
+            ),
+        ),
+        Err(e) => Check::fail(
+            "SRS cache",
+            format!("failed to read {}: {}", path.display(), e),
+        ),
+    }
+}
+
+/// Checks that the file system holding the store at `dir` has `free` bytes left to save it
+fn check_disk_space(dir: &Path, free: io::Result<u64>) -> Check {
+    let free = match free {
+        Ok(free) => free,
+        Err(e) => {
+            return Check::warn(
+                "Store disk space",
+                format!("failed to get the free space of {}: {}", dir.display(), e),
+            );
+        }
+    };
+
+    let detail = format!("{} MiB free for the store in {}", free / MIB, dir.display());
+    if free < MIN_FREE_SPACE {
+        Check::fail("Store disk space", detail)
+    } else if free < LOW_FREE_SPACE {
+        Check::warn("Store disk space", detail)
+    } else {
+        Check::pass("Store disk space", detail)
+    }
+}
+
+/// Returns the space available to the user on the file system holding `path`, in bytes
+#[cfg(unix)]
+fn free_space(path: &Path) -> io::Result<u64> {
+    let stat = rustix::fs::statvfs(path)?;
+
+    Ok(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+#[cfg(not(unix))]
+fn free_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "free space is only checked on Unix",
+    ))
+}
+
+/// Checks Esplora connectivity, that it serves the configured network and that its tip is fresh
+async fn check_esplora(ctx: &mut Context, network: bitcoin::Network) -> Vec<Check> {
+    let esplora_client = match ctx.esplora_client() {
+        Ok(client) => client,
+        Err(e) => return vec![Check::fail("Esplora", format!("{:#}", e))],
+    };
+
+    let mut checks = Vec::new();
+
+    match esplora_client.get_block_hash(0).await {
+        Ok(hash) if hash == genesis_block(network).block_hash().to_string() => {
+            checks.push(Check::pass(
+                "Esplora network",
+                format!("server follows {}", network),
+            ));
+        }
+        Ok(hash) => checks.push(Check::fail(
+            "Esplora network",
+            format!("server genesis {} does not match {}", hash, network),
+        )),
+        Err(e) => {
+            checks.push(Check::fail("Esplora connectivity", format!("{:#}", e)));
+            return checks;
+        }
+    }
+
+    let tip = match esplora_client.get_tip_hash().await {
+        Ok(hash) => esplora_client.get_block(&hash).await,
+        Err(e) => Err(e),
+    };
+
+    match tip {
+        Ok(block) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let age = now.saturating_sub(block.timestamp);
+            let detail = format!("tip height {} is {} minutes old", block.height, age / 60);
+
+            checks.push(if age > STALE_TIP_SECS {
+                Check::warn("Esplora tip", detail)
+            } else {
+                Check::pass("Esplora tip", detail)
+            });
+        }
+        Err(e) => checks.push(Check::fail("Esplora tip", format!("{:#}", e))),
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_report() {
+        let checks = [
+            Check::pass(
+                "Circuit artifact",
+                "challenger circuit loaded, bytecode sha256 00ff",
+            ),
+            Check::fail(
+                "Circuit artifact",
+                "acceptor circuit missing or invalid at circuits/acceptor.json",
+            ),
+            Check::pass("Config", "configuration loaded"),
+            Check::warn("Esplora tip", "tip height 840000 is 180 minutes old"),
+        ];
+
+        insta::assert_snapshot!(render_all(|r| render_report(r, &checks)));
+    }
+
+    #[test]
+    fn checks_disk_space() {
+        let dir = Path::new("/data");
+        let status = |free| check_disk_space(dir, free).status.as_str();
+
+        assert_eq!(status(Ok(MIN_FREE_SPACE - 1)), "fail");
+        assert_eq!(status(Ok(MIN_FREE_SPACE)), "warn");
+        assert_eq!(status(Ok(LOW_FREE_SPACE)), "pass");
+        assert_eq!(
+            status(Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "unsupported"
+            ))),
+            "warn"
+        );
+    }
+
+    #[test]
+    fn checks_srs_file() {
+        let dir = std::env::temp_dir().join(format!("op-rand-doctor-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let srs = dir.join("srs.dat");
+
+        assert_eq!(check_srs(&srs).status.as_str(), "warn");
+        fs::write(&srs, b"").unwrap();
+        assert_eq!(check_srs(&srs).status.as_str(), "fail");
+        fs::write(&srs, [0; 64]).unwrap();
+        assert_eq!(check_srs(&srs).status.as_str(), "pass");
+        assert_eq!(check_srs(&dir).status.as_str(), "fail");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
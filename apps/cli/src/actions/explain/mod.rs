@@ -0,0 +1,261 @@
+use bitcoin::Txid;
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt};
+
+use crate::{context::Context, render::Renderer, store::Role};
+
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// Challenge ID
+    pub id: String,
+}
+
+/// Width of a lifeline column in the sequence diagram
+const COLUMN_WIDTH: usize = 26;
+
+/// Participant of the sequence diagram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Party {
+    Challenger,
+    Acceptor,
+    Bitcoin,
+}
+
+impl Party {
+    const ALL: [Party; 3] = [Party::Challenger, Party::Acceptor, Party::Bitcoin];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Party::Challenger => "Challenger",
+            Party::Acceptor => "Acceptor",
+            Party::Bitcoin => "Bitcoin",
+        }
+    }
+
+    /// Column of the party's lifeline
+    fn column(&self) -> usize {
+        match self {
+            Party::Challenger => 0,
+            Party::Acceptor => COLUMN_WIDTH,
+            Party::Bitcoin => 2 * COLUMN_WIDTH,
+        }
+    }
+}
+
+impl From<Role> for Party {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Challenger => Party::Challenger,
+            Role::Acceptor => Party::Acceptor,
+        }
+    }
+}
+
+/// A message exchanged between the parties or a transaction broadcast to the network
+struct Event {
+    from: Party,
+    to: Party,
+    /// Message or transaction kind
+    kind: &'static str,
+    txid: Option<Txid>,
+    block_height: Option<u64>,
+    recorded_at: Option<u64>,
+}
+
+impl Event {
+    fn label(&self) -> String {
+        match self.txid {
+            Some(txid) => format!("{} {}", self.kind, &txid.to_string()[..8]),
+            None => self.kind.to_string(),
+        }
+    }
+}
+
+pub async fn run(ExplainArgs { id }: ExplainArgs, mut ctx: Context) -> eyre::Result<()> {
+    let store = ctx.store()?;
+    let role = store
+        .challenges
+        .get(&id)
+        .ok_or_eyre("Challenge not found in the local store")?
+        .role;
+
+    let us = Party::from(role);
+    let counterparty = match role {
+        Role::Challenger => Party::Acceptor,
+        Role::Acceptor => Party::Challenger,
+    };
+
+    let sent = store
+        .outbox
+        .values()
+        .filter(|message| message.challenge_id == id)
+        .map(|message| (us, counterparty, message));
+    let received = store
+        .inbox
+        .values()
+        .filter(|message| message.challenge_id == id)
+        .map(|message| (counterparty, us, message));
+    let mut events = sent
+        .chain(received)
+        .map(|(from, to, message)| Event {
+            from,
+            to,
+            kind: message.kind.as_str(),
+            txid: None,
+            block_height: None,
+            recorded_at: message.recorded_at,
+        })
+        .chain(
+            store
+                .challenge_transactions(&id)
+                .into_iter()
+                .map(|(txid, record)| Event {
+                    from: us,
+                    to: Party::Bitcoin,
+                    kind: record.kind.map_or("unknown", |kind| kind.as_str()),
+                    txid: Some(*txid),
+                    block_height: record.block_height,
+                    recorded_at: record.recorded_at,
+                }),
+        )
+        .collect::<Vec<_>>();
+    // Stable, so that events recorded by older versions keep their order
+    events.sort_by_key(|event| event.recorded_at);
+
+    let mut r = ctx.renderer();
+    render_explanation(r.as_mut(), &id, role, &events);
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the message and transaction flow of a challenge
+fn render_explanation(r: &mut dyn Renderer, id: &str, role: Role, events: &[Event]) {
+    r.header("🧭", "CHALLENGE FLOW");
+    r.field("Challenge ID", id);
+    r.field(
+        "Role",
+        match role {
+            Role::Challenger => "challenger",
+            Role::Acceptor => "acceptor",
+        },
+    );
+    r.field("Events", events.len());
+
+    if events.is_empty() {
+        r.warning("No messages or transactions were recorded for this challenge");
+        return;
+    }
+
+    r.text(&sequence_diagram(events));
+
+    r.section("EVENTS");
+    for (i, event) in events.iter().enumerate() {
+        r.entry(&format!("{}. {}", i + 1, event.label()));
+        r.field("From", event.from.as_str());
+        r.field("To", event.to.as_str());
+        r.field("Kind", event.kind);
+        if let Some(txid) = event.txid {
+            r.field("TXID", txid.to_string());
+        }
+        if let Some(height) = event.block_height {
+            r.field("Block Height", height);
+        }
+        if let Some(recorded_at) = event.recorded_at {
+            r.field("Recorded At", recorded_at);
+        }
+    }
+}
+
+/// Draws the events as an ASCII sequence diagram with a lifeline per party
+fn sequence_diagram(events: &[Event]) -> String {
+    let width = Party::Bitcoin.column() + 1;
+    let lifelines = || {
+        let mut line = vec![' '; width];
+        for party in Party::ALL {
+            line[party.column()] = '|';
+        }
+        line
+    };
+
+    let mut lines = vec![
+        Party::ALL
+            .iter()
+            .map(|party| format!("{:<COLUMN_WIDTH$}", party.as_str()))
+            .collect::<String>()
+            .trim_end()
+            .to_string(),
+    ];
+    lines.push(lifelines().into_iter().collect());
+
+    for event in events {
+        let (from, to) = (event.from.column(), event.to.column());
+        let (left, right) = (from.min(to), from.max(to));
+
+        let mut line = lifelines();
+        line[left + 1..right].fill('-');
+        if to > from {
+            line[right - 1] = '>';
+        } else {
+            line[left + 1] = '<';
+        }
+        let label = format!(" {} ", event.label());
+        for (i, c) in label.chars().take(right - left - 5).enumerate() {
+            line[left + 3 + i] = c;
+        }
+
+        let mut line = line.into_iter().collect::<String>();
+        if let Some(height) = event.block_height {
+            line.push_str(&format!("  height {}", height));
+        }
+        lines.push(line);
+        lines.push(lifelines().into_iter().collect());
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_explanation() {
+        let events = [
+            Event {
+                from: Party::Challenger,
+                to: Party::Acceptor,
+                kind: "challenge",
+                txid: None,
+                block_height: None,
+                recorded_at: Some(1_760_000_000),
+            },
+            Event {
+                from: Party::Acceptor,
+                to: Party::Challenger,
+                kind: "acceptance",
+                txid: None,
+                block_height: None,
+                recorded_at: Some(1_760_000_600),
+            },
+            Event {
+                from: Party::Challenger,
+                to: Party::Bitcoin,
+                kind: "challenge",
+                txid: Some(Txid::from_byte_array([7; 32])),
+                block_height: Some(840_000),
+                recorded_at: Some(1_760_001_200),
+            },
+        ];
+
+        insta::assert_snapshot!(render_all(|r| render_explanation(
+            r,
+            "challenge-1",
+            Role::Challenger,
+            &events
+        )));
+    }
+}
@@ -0,0 +1,174 @@
+use std::fs;
+
+use bitcoin::{PublicKey, ScriptBuf, Transaction, consensus::Decodable};
+use clap::{ArgGroup, Args};
+use color_eyre::eyre;
+use op_rand_transaction_builder::{
+    SpendingBranch, SpendingInstructions, parse_challenge_witness_script,
+};
+
+use crate::{
+    actions::accept_challenge::AcceptorData, context::Context, limits::load_message,
+    render::Renderer, ui::GEAR,
+};
+
+#[derive(Args, Debug)]
+#[clap(group(
+    ArgGroup::new("export_spend")
+        .required(true)
+        .args(&["challenger", "acceptor"])
+        .multiple(false),
+))]
+pub struct ExportSpendArgs {
+    /// Challenge transaction hex.
+    #[clap(long)]
+    pub challenge_tx: String,
+
+    /// Path to the acceptor JSON file
+    #[clap(long, default_value = "acceptor.json")]
+    pub acceptor_file: String,
+
+    /// Write the instructions to this file instead of printing them
+    #[clap(long)]
+    pub output: Option<String>,
+
+    #[clap(long, group = "export_spend")]
+    pub challenger: bool,
+
+    #[clap(long, group = "export_spend")]
+    pub acceptor: bool,
+}
+
+pub async fn run(
+    ExportSpendArgs {
+        challenge_tx,
+        acceptor_file,
+        output,
+        challenger,
+        acceptor: _,
+    }: ExportSpendArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("📤", "EXPORT SPENDING INSTRUCTIONS");
+    r.step(GEAR, "Loading challenge data...");
+
+    let acceptor_data: AcceptorData = load_message(&acceptor_file)?;
+    let witness_script =
+        parse_challenge_witness_script(&acceptor_data.challenge_output_witness_script)?;
+
+    let challenge_tx_bytes = hex::decode(&challenge_tx)?;
+    let challenge_transaction = Transaction::consensus_decode(&mut challenge_tx_bytes.as_slice())?;
+
+    let branch = if challenger {
+        SpendingBranch::Challenger
+    } else {
+        SpendingBranch::Acceptor
+    };
+
+    let instructions = SpendingInstructions::new(&challenge_transaction, &witness_script, branch)?;
+
+    let private_key = ctx.config()?.private_key;
+    let wallet_pubkey = private_key.public_key(ctx.secp_ctx());
+
+    let owns_signing_key = match instructions.tweak {
+        Some(tweak) => wallet_pubkey
+            .inner
+            .combine(&tweak.public_key(ctx.secp_ctx()))
+            .is_ok_and(|key| PublicKey::new(key) == instructions.signing_pubkey),
+        None => wallet_pubkey == instructions.signing_pubkey,
+    };
+
+    if !owns_signing_key {
+        r.warning("The configured wallet key can't sign for this branch");
+    }
+
+    render_instructions(r.as_mut(), &instructions);
+
+    if let Some(path) = output {
+        fs::write(&path, instructions.to_string())?;
+        r.done(&format!("Spending instructions saved to {}", path));
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the spending instructions of a challenge output
+fn render_instructions(r: &mut dyn Renderer, instructions: &SpendingInstructions) {
+    r.section("SPENDING INSTRUCTIONS");
+    r.field("Outpoint", instructions.outpoint.to_string());
+    r.field("Amount", instructions.amount);
+    r.field(
+        "Descriptor",
+        match &instructions.descriptor {
+            Some(descriptor) => descriptor.to_string(),
+            None => "none, the output script predates protocol version 2".to_string(),
+        },
+    );
+    r.field(
+        "Witness Script",
+        instructions.witness_script.to_hex_string(),
+    );
+
+    r.section("SIGNING");
+    match instructions.branch {
+        SpendingBranch::Acceptor => {
+            r.field("Branch", "acceptor");
+            r.field("Signing Key", instructions.signing_pubkey.to_string());
+            if let Some(tweak) = instructions.tweak {
+                r.field("Key Tweak", tweak.display_secret().to_string());
+            }
+            r.field("Witness", "<signature> 01 <witness script>");
+        }
+        SpendingBranch::Challenger => {
+            r.field("Branch", "challenger");
+            r.field("Signing Key", instructions.signing_pubkey.to_string());
+            if let Some(lock_time) = instructions.lock_time {
+                r.field("Lock Time", lock_time.to_consensus_u32());
+            }
+            r.field("Witness", "<signature> <> <witness script>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Amount, OutPoint, absolute::LockTime};
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_challenger_instructions() {
+        let mut instructions = SpendingInstructions {
+            branch: SpendingBranch::Challenger,
+            outpoint: OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            amount: Amount::from_sat(20_000),
+            witness_script: ScriptBuf::new(),
+            descriptor: Some("wsh(or_i(pk(02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5),\
+                and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),after(840144))))"
+                .parse()
+                .unwrap()),
+            signing_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .parse()
+                .unwrap(),
+            tweak: None,
+            lock_time: Some(LockTime::from_consensus(840_144)),
+        };
+        instructions.witness_script = instructions
+            .descriptor
+            .as_ref()
+            .unwrap()
+            .explicit_script()
+            .unwrap();
+
+        insta::assert_snapshot!(render_all(|r| render_instructions(r, &instructions)));
+    }
+}
@@ -0,0 +1,223 @@
+use std::{fs, str::FromStr};
+
+use base64::{Engine as _, engine::general_purpose};
+use bitcoin::{Address, PrivateKey, ScriptBuf, Txid, address::NetworkUnchecked};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, bail, ensure};
+use op_rand_transaction_builder::{SpendingBranch, SpendingInstructions, TransactionKind};
+use op_rand_types::ct_eq;
+use serde::Serialize;
+
+use crate::{context::Context, render::Renderer, store::Role, ui::CHAIN, util::recipient_script};
+
+#[derive(Args, Debug)]
+pub struct ExportWinningsArgs {
+    /// Challenge ID
+    pub id: String,
+
+    /// Recipient address, e.g. P2TR, P2WSH or legacy. Must belong to the configured network
+    #[clap(long, conflicts_with = "recipient_pubkey")]
+    pub recipient: Option<Address<NetworkUnchecked>>,
+
+    /// Recipient public key, paid to with P2WPKH. The wallet key by default
+    #[clap(long)]
+    pub recipient_pubkey: Option<String>,
+
+    /// Embed the signing private key into the descriptor instead of only printing the tweak
+    #[clap(long)]
+    pub include_private_key: bool,
+
+    /// Write the export to this JSON file
+    #[clap(long)]
+    pub output: Option<String>,
+}
+
+/// Wallet import bundle for the winnings of a challenge
+#[derive(Debug, Serialize)]
+pub struct WinningsExport {
+    pub id: String,
+    pub descriptor: String,
+    pub psbt: String,
+    pub signing_pubkey: String,
+    pub tweak: Option<String>,
+    pub lock_time: Option<u32>,
+}
+
+pub async fn run(
+    ExportWinningsArgs {
+        id,
+        recipient,
+        recipient_pubkey,
+        include_private_key,
+        output,
+    }: ExportWinningsArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🏆", "EXPORT WINNINGS");
+
+    let store = ctx.store()?;
+    let esplora_client = ctx.esplora_client()?;
+    let cfg = ctx.config()?;
+    let fee_policy = ctx.fee_policy()?;
+    let secp = ctx.secp_ctx();
+
+    let record = store
+        .challenges
+        .get(&id)
+        .ok_or_eyre("Challenge not found in the local store")?;
+    let deposit_outpoint = record
+        .deposit_outpoint
+        .ok_or_eyre("Deposit outpoint of the challenge is unknown")?;
+    let witness_script = ScriptBuf::from_hex(
+        record
+            .witness_script
+            .as_deref()
+            .ok_or_eyre("Challenge was not accepted yet")?,
+    )?;
+
+    r.step(CHAIN, "Looking up challenge transaction...");
+
+    let outspend = esplora_client
+        .get_outspend(&deposit_outpoint.txid, deposit_outpoint.vout)
+        .await?;
+    ensure!(
+        outspend.spent,
+        "Challenge transaction was not broadcast yet"
+    );
+    let challenge_txid = Txid::from_str(
+        outspend
+            .txid
+            .as_deref()
+            .ok_or_eyre("Esplora didn't return the spending transaction")?,
+    )?;
+    let challenge_transaction = esplora_client.get_transaction(&challenge_txid).await?;
+
+    r.field("Challenge TXID", challenge_txid.to_string());
+
+    let branch = match record.role {
+        Role::Challenger => SpendingBranch::Challenger,
+        Role::Acceptor => SpendingBranch::Acceptor,
+    };
+    let instructions = SpendingInstructions::new(&challenge_transaction, &witness_script, branch)?;
+    let Some(plain_descriptor) = &instructions.descriptor else {
+        bail!(
+            "The challenge output was created by a protocol version 1 client and has no descriptor, spend it with `sweep` or `export-spend`"
+        );
+    };
+
+    // Signing key is the wallet key, tweaked by the revealed second rank commitment for the Acceptor
+    let signing_key = match instructions.tweak {
+        Some(tweak) => {
+            PrivateKey::new(cfg.private_key.inner.add_tweak(&tweak.into())?, cfg.network)
+        }
+        None => cfg.private_key,
+    };
+
+    if !ct_eq(
+        &signing_key.public_key(secp).to_bytes(),
+        &instructions.signing_pubkey.to_bytes(),
+    ) {
+        bail!("This wallet didn't win the challenge: its key can't sign for the challenge output");
+    }
+
+    if let Some(lock_time) = instructions.lock_time {
+        let tip_height = esplora_client.get_tip_height().await?;
+        if u64::from(lock_time.to_consensus_u32()) > tip_height {
+            r.warning(&format!(
+                "The output is spendable only after block {}",
+                lock_time
+            ));
+        }
+    }
+
+    let recipient = match recipient_script(recipient, recipient_pubkey.as_deref(), cfg.network)? {
+        Some(script) => script,
+        None => ScriptBuf::new_p2wpkh(&cfg.private_key.public_key(secp).wpubkey_hash()?),
+    };
+    let psbt = instructions.to_psbt(
+        recipient,
+        fee_policy.fee(TransactionKind::Sweep { outputs: 1 })?,
+    )?;
+
+    let descriptor = if include_private_key {
+        r.warning("The descriptor contains a private key, keep it secret");
+        instructions.descriptor_with_secret(secp, &signing_key)?
+    } else {
+        plain_descriptor.to_string()
+    };
+
+    let export = WinningsExport {
+        id,
+        descriptor,
+        psbt: general_purpose::STANDARD.encode(psbt.serialize()),
+        signing_pubkey: instructions.signing_pubkey.to_string(),
+        tweak: instructions
+            .tweak
+            .map(|tweak| tweak.display_secret().to_string()),
+        lock_time: instructions.lock_time.map(|lt| lt.to_consensus_u32()),
+    };
+
+    render_export(
+        r.as_mut(),
+        &cfg.private_key.public_key(secp).to_string(),
+        &export,
+    );
+
+    if let Some(path) = output {
+        fs::write(&path, serde_json::to_string_pretty(&export)?)?;
+        r.done(&format!("Winnings export saved to {}", path));
+    }
+
+    r.success("Winnings exported successfully!");
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders how the signing key is derived and what to import into the wallet
+fn render_export(r: &mut dyn Renderer, wallet_pubkey: &str, export: &WinningsExport) {
+    r.field("Challenge ID", &export.id);
+
+    r.section("KEY DERIVATION");
+    r.field("Wallet Key", wallet_pubkey);
+    if let Some(tweak) = &export.tweak {
+        // sha256 of the revealed second rank commitment
+        r.field("Tweak", tweak);
+    }
+    r.field("Signing Key", &export.signing_pubkey);
+    if let Some(lock_time) = export.lock_time {
+        r.field("Lock Time", lock_time);
+    }
+
+    r.section("WALLET IMPORT");
+    r.field("Descriptor", &export.descriptor);
+    r.field("PSBT", &export.psbt);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_acceptor_export() {
+        let export = WinningsExport {
+            id: "0f9e5a4c-7d2b-4c1e-9a8f-3b6d2e1c0a9b".to_string(),
+            descriptor: "wsh(or_i(pk(02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5),and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),after(840144))))".to_string(),
+            psbt: "cHNidP8BAFICAAAAAQ==".to_string(),
+            signing_pubkey: "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5"
+                .to_string(),
+            tweak: Some(
+                "0000000000000000000000000000000000000000000000000000000000000001".to_string(),
+            ),
+            lock_time: None,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_export(
+            r,
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            &export
+        )));
+    }
+}
@@ -0,0 +1,135 @@
+use clap::Args;
+use color_eyre::eyre;
+
+use crate::{
+    context::Context,
+    render::Renderer,
+    store::{ChallengeMetadata, ChallengeRecord, Role},
+};
+
+#[derive(Args, Debug)]
+pub struct ListChallengesArgs {
+    /// Only list challenges with this tag. Can be repeated to require several tags.
+    #[clap(long = "tag")]
+    pub tags: Vec<String>,
+}
+
+pub async fn run(
+    ListChallengesArgs { tags }: ListChallengesArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let store = ctx.store()?;
+
+    let challenges = store
+        .challenges
+        .iter()
+        .filter(|(_, record)| tags.iter().all(|tag| record.metadata.tags.contains(tag)))
+        .collect::<Vec<_>>();
+
+    let mut r = ctx.renderer();
+    render_challenges(r.as_mut(), &challenges);
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the challenges recorded in the local store
+fn render_challenges(r: &mut dyn Renderer, challenges: &[(&String, &ChallengeRecord)]) {
+    r.header("📋", "CHALLENGES");
+    r.field("Challenges", challenges.len());
+
+    if challenges.is_empty() {
+        r.warning("No challenges found");
+        return;
+    }
+
+    r.section("CHALLENGE DETAILS");
+    for (id, record) in challenges {
+        r.entry(id);
+        r.field("Challenge ID", *id);
+        r.field(
+            "Role",
+            match record.role {
+                Role::Challenger => "challenger",
+                Role::Acceptor => "acceptor",
+            },
+        );
+        if let Some(deposit_outpoint) = record.deposit_outpoint {
+            r.field("Deposit", deposit_outpoint.to_string());
+        }
+        if let Some(reoffer_of) = &record.reoffer_of {
+            r.field("Re-offer Of", reoffer_of);
+        }
+        if let Some(superseded_by) = &record.superseded_by {
+            r.field("Superseded By", superseded_by);
+        }
+        render_metadata(r, &record.metadata);
+    }
+}
+
+/// Renders the user notes about a challenge, skipping unset fields
+pub fn render_metadata(r: &mut dyn Renderer, metadata: &ChallengeMetadata) {
+    if let Some(label) = &metadata.label {
+        r.field("Label", label);
+    }
+    if let Some(counterparty) = &metadata.counterparty {
+        r.field("Counterparty", counterparty);
+    }
+    if !metadata.tags.is_empty() {
+        r.field(
+            "Tags",
+            metadata.tags.iter().cloned().collect::<Vec<_>>().join(", "),
+        );
+    }
+    if let Some(memo) = &metadata.memo {
+        r.field("Memo", memo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::OutPoint;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_challenges() {
+        let tagged = ChallengeRecord {
+            role: Role::Challenger,
+            wallet_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .to_string(),
+            change_script: None,
+            funding_outpoints: Vec::new(),
+            deposit_outpoint: Some(OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            )),
+            witness_script: None,
+            completion_deadline: None,
+            metadata: ChallengeMetadata {
+                label: Some("Friday game".to_string()),
+                counterparty: Some("Alice".to_string()),
+                memo: Some("Loser buys pizza".to_string()),
+                tags: ["friends".to_string(), "weekly".to_string()].into(),
+            },
+            reoffer_of: None,
+            superseded_by: None,
+            counterparty_pubkey: None,
+        };
+        let untagged = ChallengeRecord {
+            role: Role::Acceptor,
+            deposit_outpoint: None,
+            metadata: ChallengeMetadata::default(),
+            ..tagged.clone()
+        };
+        let ids = ["challenge-1".to_string(), "challenge-2".to_string()];
+
+        insta::assert_snapshot!(render_all(|r| render_challenges(
+            r,
+            &[(&ids[0], &tagged), (&ids[1], &untagged)]
+        )));
+    }
+}
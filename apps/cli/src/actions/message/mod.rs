@@ -0,0 +1,214 @@
+use std::{fs, path::PathBuf};
+
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{self, Context as _};
+
+use crate::{
+    actions::{
+        accept_challenge::AcceptorData,
+        create_challenge::PublicChallengerData,
+        precheck::{PreAcceptanceAck, PreAcceptanceData},
+    },
+    context::Context,
+    limits::{BoundedMessage, InputError, MAX_MESSAGE_SIZE, read_bounded},
+    render::Renderer,
+    store::{MessageKind, Store, message_hash},
+    ui::{GEAR, RADIO},
+};
+
+#[derive(Args, Debug)]
+pub struct MessageArgs {
+    #[command(subcommand)]
+    pub command: MessageCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MessageCommand {
+    /// List queued and received protocol messages
+    List,
+
+    /// Deliver queued messages into a directory shared with the counterparty
+    Flush {
+        /// Directory to deliver messages to, e.g. a removable drive or a synced folder
+        #[clap(long)]
+        to: PathBuf,
+
+        /// Deliver messages that were already delivered again
+        #[clap(long)]
+        all: bool,
+    },
+
+    /// Receive messages from a directory shared with the counterparty
+    Fetch {
+        /// Directory to read messages from
+        #[clap(long)]
+        from: PathBuf,
+
+        /// Directory to write newly received messages to
+        #[clap(long, default_value = ".")]
+        output_dir: PathBuf,
+    },
+}
+
+pub async fn run(MessageArgs { command }: MessageArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut store = ctx.store()?;
+    let mut r = ctx.renderer();
+
+    match command {
+        MessageCommand::List => {
+            r.header("📬", "PROTOCOL MESSAGES");
+            render_messages(r.as_mut(), &store);
+        }
+        MessageCommand::Flush { to, all } => {
+            r.header("📤", "FLUSHING OUTBOX");
+            r.step(RADIO, "Delivering queued messages...");
+
+            fs::create_dir_all(&to)
+                .wrap_err_with(|| format!("Failed to create {}", to.display()))?;
+
+            let mut delivered = 0;
+            for (hash, message) in store.outbox.iter_mut() {
+                if message.delivered && !all {
+                    continue;
+                }
+
+                let path = to.join(format!("{}.json", hash));
+                fs::write(&path, &message.content)
+                    .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                message.delivered = true;
+                delivered += 1;
+
+                r.done(&format!(
+                    "{} of challenge {} delivered",
+                    message.kind.as_str(),
+                    message.challenge_id
+                ));
+            }
+            store.save()?;
+
+            r.success(&format!(
+                "{} message(s) delivered to {}",
+                delivered,
+                to.display()
+            ));
+        }
+        MessageCommand::Fetch { from, output_dir } => {
+            r.header("📥", "FETCHING MESSAGES");
+            r.step(GEAR, "Reading messages...");
+
+            let mut paths = fs::read_dir(&from)
+                .wrap_err_with(|| format!("Failed to read {}", from.display()))?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<eyre::Result<Vec<_>>>()?;
+            paths.retain(|path| path.extension().is_some_and(|ext| ext == "json"));
+            paths.sort();
+
+            let mut received = 0;
+            for path in paths {
+                let content = match read_bounded(&path, MAX_MESSAGE_SIZE) {
+                    Ok(content) => content,
+                    Err(e @ InputError::FileTooLarge { .. }) => {
+                        r.warning(&e.to_string());
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                // Messages this wallet delivered itself are not addressed to it
+                if store.outbox.contains_key(&message_hash(&content)) {
+                    continue;
+                }
+
+                let (kind, challenge_id) = match classify(&content) {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => {
+                        r.warning(&format!("{}: {}", path.display(), e));
+                        continue;
+                    }
+                    None => {
+                        r.warning(&format!("{} is not a protocol message", path.display()));
+                        continue;
+                    }
+                };
+
+                if !store.receive_message(&challenge_id, kind, &content) {
+                    continue;
+                }
+
+                let output = output_dir.join(format!("{}-{}.json", kind.as_str(), challenge_id));
+                fs::write(&output, &content)
+                    .wrap_err_with(|| format!("Failed to write {}", output.display()))?;
+                received += 1;
+
+                r.done(&format!(
+                    "{} of challenge {} saved to {}",
+                    kind.as_str(),
+                    challenge_id,
+                    output.display()
+                ));
+            }
+            store.save()?;
+
+            r.success(&format!("{} new message(s) received", received));
+        }
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+/// Detects the kind and the challenge ID of a protocol message. Fails if a field of the message
+/// is over its size limit.
+pub fn classify(content: &str) -> Option<Result<(MessageKind, String), InputError>> {
+    if let Ok(challenge) = serde_json::from_str::<PublicChallengerData>(content) {
+        return Some(
+            challenge
+                .check_sizes()
+                .map(|()| (MessageKind::Challenge, challenge.id)),
+        );
+    }
+    if let Ok(acceptance) = serde_json::from_str::<AcceptorData>(content) {
+        return Some(
+            acceptance
+                .check_sizes()
+                .map(|()| (MessageKind::Acceptance, acceptance.id)),
+        );
+    }
+    // An acknowledgement also parses as the pre-acceptance it answers
+    if let Ok(ack) = serde_json::from_str::<PreAcceptanceAck>(content) {
+        return Some(
+            ack.check_sizes()
+                .map(|()| (MessageKind::PreAcceptanceAck, ack.id)),
+        );
+    }
+    if let Ok(pre_acceptance) = serde_json::from_str::<PreAcceptanceData>(content) {
+        return Some(
+            pre_acceptance
+                .check_sizes()
+                .map(|()| (MessageKind::PreAcceptance, pre_acceptance.id)),
+        );
+    }
+
+    None
+}
+
+/// Renders the outbox and the inbox of the store
+fn render_messages(r: &mut dyn Renderer, store: &Store) {
+    r.section("OUTBOX");
+    for (hash, message) in &store.outbox {
+        r.entry(hash);
+        r.field("Hash", hash);
+        r.field("Kind", message.kind.as_str());
+        r.field("Challenge ID", &message.challenge_id);
+        r.field("Delivered", message.delivered);
+    }
+
+    r.section("INBOX");
+    for (hash, message) in &store.inbox {
+        r.entry(hash);
+        r.field("Hash", hash);
+        r.field("Kind", message.kind.as_str());
+        r.field("Challenge ID", &message.challenge_id);
+    }
+}
@@ -6,18 +6,53 @@ use tracing_log::AsTrace;
 
 use crate::{
     actions::{
-        accept_challenge::AcceptChallengeArgs, challenge_info::ChallengeInfoArgs,
-        complete_challenge::CompleteChallengeArgs, create_challenge::CreateChallengeArgs,
-        try_spend::TrySpendArgs,
+        accept_challenge::AcceptChallengeArgs, annotate::AnnotateArgs, archive::ArchiveArgs,
+        challenge_info::ChallengeInfoArgs, complete_challenge::CompleteChallengeArgs,
+        create_challenge::CreateChallengeArgs, diff::DiffArgs, dispute::DisputeArgs,
+        explain::ExplainArgs, export_spend::ExportSpendArgs, export_winnings::ExportWinningsArgs,
+        list_challenges::ListChallengesArgs, message::MessageArgs, outcome::OutcomeArgs,
+        precheck::PrecheckArgs, prepare_stake::PrepareStakeArgs, prover_daemon::ProverDaemonArgs,
+        rebroadcast::RebroadcastArgs, refund::RefundArgs, reoffer::ReofferArgs, spec::SpecArgs,
+        sponsor_psbt::SponsorPsbtArgs, sweep::SweepArgs, txlog::TxlogArgs,
+        unarchive::UnarchiveArgs, utxo::UtxoArgs, verify::VerifyArgs,
+        verify_beacon::VerifyBeaconArgs, version::VersionArgs,
     },
     context::Context,
+    platform::Dirs,
+    render::OutputFormat,
+    telemetry,
 };
 mod accept_challenge;
+mod annotate;
+mod archive;
 mod balance;
 mod challenge_info;
 mod complete_challenge;
 mod create_challenge;
-mod try_spend;
+mod diff;
+mod dispute;
+mod doctor;
+mod explain;
+mod export_spend;
+mod export_winnings;
+mod list_challenges;
+mod message;
+mod outcome;
+mod precheck;
+mod prepare_stake;
+mod prover_daemon;
+mod rebroadcast;
+mod refund;
+mod reoffer;
+mod spec;
+mod sponsor_psbt;
+mod sweep;
+mod txlog;
+mod unarchive;
+mod utxo;
+mod verify;
+mod verify_beacon;
+mod version;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -28,8 +63,19 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    #[clap(short, long, default_value = "config.toml")]
-    pub config: PathBuf,
+    /// Configuration file. Defaults to `config.toml` in the working directory if it exists, or
+    /// in the platform configuration directory otherwise.
+    #[clap(short, long)]
+    pub config: Option<PathBuf>,
+
+    /// Directory for the configuration, the state store and the caches, instead of the platform
+    /// directories
+    #[clap(long, global = true)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Output format
+    #[clap(long, global = true, value_enum, default_value_t)]
+    pub format: OutputFormat,
 }
 
 #[derive(Subcommand, Debug)]
@@ -37,20 +83,95 @@ pub enum Commands {
     /// Create a challenge
     CreateChallenge(CreateChallengeArgs),
 
+    /// Run every check on a challenge before accepting it and report all problems at once
+    Verify(VerifyArgs),
+
+    /// Disclose the funding of an acceptance before proving, or acknowledge a disclosed one
+    Precheck(PrecheckArgs),
+
     /// Accept a challenge
     AcceptChallenge(AcceptChallengeArgs),
 
+    /// Add the sponsor's fee input to a challenge transaction and finalize it
+    SponsorPsbt(SponsorPsbtArgs),
+
+    /// Consolidate wallet UTXOs into a single stake UTXO to accept a challenge with
+    PrepareStake(PrepareStakeArgs),
+
     /// Complete a challenge
     CompleteChallenge(CompleteChallengeArgs),
 
-    /// Try to spend a challenge
-    TrySpend(TrySpendArgs),
+    /// Sweep the challenge output with the branch of this wallet's role
+    Sweep(SweepArgs),
+
+    /// Export spending instructions for a challenge output to use with external wallets
+    ExportSpend(ExportSpendArgs),
+
+    /// Export a descriptor and a PSBT to claim challenge winnings with another wallet
+    ExportWinnings(ExportWinningsArgs),
 
     /// Info about a challenge
     Info(ChallengeInfoArgs),
 
+    /// List the challenges in the local store
+    ListChallenges(ListChallengesArgs),
+
+    /// Label, tag and take notes on a challenge
+    Annotate(AnnotateArgs),
+
+    /// Move challenges settled long ago from the local store to the archive
+    Archive(ArchiveArgs),
+
+    /// Move an archived challenge back to the local store
+    Unarchive(UnarchiveArgs),
+
     /// Get wallet balance
     Balance,
+
+    /// Show a certificate of who won a settled challenge
+    Outcome(OutcomeArgs),
+
+    /// Verify that the commitment of a challenge was selected by its public beacon
+    VerifyBeacon(VerifyBeaconArgs),
+
+    /// Refund the deposit of a challenge that was never accepted
+    Refund(RefundArgs),
+
+    /// Re-offer a challenge that was rejected or expired, replacing its envelope
+    Reoffer(ReofferArgs),
+
+    /// Compare two versions of a protocol message field by field
+    Diff(DiffArgs),
+
+    /// Diagnose a stuck challenge and walk through the recovery options
+    Dispute(DisputeArgs),
+
+    /// Rebroadcast a protocol transaction that dropped out of the mempool
+    Rebroadcast(RebroadcastArgs),
+
+    /// Show every transaction broadcast for a challenge with its raw hex and inclusion status
+    Txlog(TxlogArgs),
+
+    /// Show the messages and transactions of a challenge as a sequence diagram
+    Explain(ExplainArgs),
+
+    /// Label and freeze wallet UTXOs
+    Utxo(UtxoArgs),
+
+    /// Queue, deliver and receive protocol messages through a shared directory
+    Message(MessageArgs),
+
+    /// Export the byte-level protocol definitions of this build for other implementations
+    Spec(SpecArgs),
+
+    /// Run self-diagnostics
+    Doctor,
+
+    /// Show the version and protocol version of this build, and the hashes of its circuits
+    Version(VersionArgs),
+
+    /// Keep the circuits set up and serve proving requests of other commands over a local socket
+    ProverDaemon(ProverDaemonArgs),
 }
 
 impl Cli {
@@ -59,8 +180,19 @@ impl Cli {
             .with_max_level(self.verbosity.log_level_filter().as_trace())
             .init();
 
-        let context = Context::new(self.config);
-        execute_command(self.command, context).await
+        let dirs = Dirs::new(self.data_dir)?;
+        op_rand_prover::set_circuit_dir(dirs.circuit_dir());
+
+        let config_path = dirs.config_path(self.config);
+        let context = Context::new(config_path.clone(), dirs.clone(), self.format);
+        let result = execute_command(self.command, context).await;
+
+        if let Err(err) = &result {
+            let mut context = Context::new(config_path, dirs, self.format);
+            telemetry::report_failure(&mut context, err).await;
+        }
+
+        result
     }
 }
 
@@ -68,10 +200,35 @@ async fn execute_command(command: Commands, context: Context) -> eyre::Result<()
     use Commands as Cmd;
     match command {
         Cmd::CreateChallenge(cmd) => create_challenge::run(cmd, context).await,
+        Cmd::Verify(cmd) => verify::run(cmd, context).await,
+        Cmd::Precheck(cmd) => precheck::run(cmd, context).await,
         Cmd::AcceptChallenge(cmd) => accept_challenge::run(cmd, context).await,
+        Cmd::SponsorPsbt(cmd) => sponsor_psbt::run(cmd, context).await,
+        Cmd::PrepareStake(cmd) => prepare_stake::run(cmd, context).await,
         Cmd::CompleteChallenge(cmd) => complete_challenge::run(cmd, context).await,
-        Cmd::TrySpend(cmd) => try_spend::run(cmd, context).await,
-        Cmd::Info(cmd) => challenge_info::run(cmd).await,
+        Cmd::Sweep(cmd) => sweep::run(cmd, context).await,
+        Cmd::ExportSpend(cmd) => export_spend::run(cmd, context).await,
+        Cmd::ExportWinnings(cmd) => export_winnings::run(cmd, context).await,
+        Cmd::Info(cmd) => challenge_info::run(cmd, context).await,
+        Cmd::ListChallenges(cmd) => list_challenges::run(cmd, context).await,
+        Cmd::Annotate(cmd) => annotate::run(cmd, context).await,
+        Cmd::Archive(cmd) => archive::run(cmd, context).await,
+        Cmd::Unarchive(cmd) => unarchive::run(cmd, context).await,
         Cmd::Balance => balance::run(context).await,
+        Cmd::Outcome(cmd) => outcome::run(cmd, context).await,
+        Cmd::VerifyBeacon(cmd) => verify_beacon::run(cmd, context).await,
+        Cmd::Refund(cmd) => refund::run(cmd, context).await,
+        Cmd::Reoffer(cmd) => reoffer::run(cmd, context).await,
+        Cmd::Rebroadcast(cmd) => rebroadcast::run(cmd, context).await,
+        Cmd::Diff(cmd) => diff::run(cmd, context).await,
+        Cmd::Dispute(cmd) => dispute::run(cmd, context).await,
+        Cmd::Txlog(cmd) => txlog::run(cmd, context).await,
+        Cmd::Explain(cmd) => explain::run(cmd, context).await,
+        Cmd::Utxo(cmd) => utxo::run(cmd, context).await,
+        Cmd::Message(cmd) => message::run(cmd, context).await,
+        Cmd::Spec(cmd) => spec::run(cmd, context).await,
+        Cmd::Doctor => doctor::run(context).await,
+        Cmd::Version(cmd) => version::run(cmd, context).await,
+        Cmd::ProverDaemon(cmd) => prover_daemon::run(cmd, context).await,
     }
 }
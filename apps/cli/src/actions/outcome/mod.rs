@@ -0,0 +1,206 @@
+use std::{fs, pin::pin, str::FromStr};
+
+use bitcoin::{Amount, OutPoint, Transaction, Txid};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, ensure};
+use futures_util::StreamExt;
+use op_rand_transaction_builder::{ChainBackend, Outcome, OutpointEvent, SpendingBranch};
+use serde::{Deserialize, Serialize};
+
+use crate::{context::Context, esplora::EsploraClient, render::Renderer, ui::CHAIN};
+
+#[derive(Args, Debug)]
+pub struct OutcomeArgs {
+    /// Challenge ID
+    pub id: String,
+
+    /// Write the certificate to this JSON file
+    #[clap(long)]
+    pub output: Option<String>,
+
+    /// Wait for the challenge output to be spent if the challenge is not settled yet
+    #[clap(long)]
+    pub watch: bool,
+}
+
+/// Outcome certificate of a settled challenge. Every field can be reconstructed from chain data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutcomeCertificate {
+    pub id: String,
+    pub winner: String,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub amount: Amount,
+    pub challenge_txid: String,
+    pub settlement_txid: String,
+    pub settlement_height: Option<u64>,
+    pub challenger_pubkey: String,
+    pub revealed_deposit_pubkey: String,
+    pub tweaked_acceptor_pubkey: String,
+    pub locktime: u32,
+}
+
+pub async fn run(
+    OutcomeArgs { id, output, watch }: OutcomeArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("📜", "OUTCOME CERTIFICATE");
+
+    let store = ctx.store()?;
+    let esplora_client = ctx.esplora_client()?;
+
+    let record = store
+        .challenges
+        .get(&id)
+        .ok_or_eyre("Challenge not found in the local store")?;
+    let deposit_outpoint = record
+        .deposit_outpoint
+        .ok_or_eyre("Deposit outpoint of the challenge is unknown")?;
+
+    r.step(CHAIN, "Reconstructing the outcome from chain data...");
+
+    let (challenge_transaction, _) = get_spending_transaction(
+        &esplora_client,
+        &deposit_outpoint.txid,
+        deposit_outpoint.vout,
+    )
+    .await
+    .map_err(|e| e.wrap_err("Challenge transaction was not broadcast yet"))?;
+    let (settlement_transaction, settlement_height) = if watch {
+        r.step(CHAIN, "Waiting for the challenge output to be spent...");
+        watch_settlement(
+            r.as_mut(),
+            &esplora_client,
+            OutPoint::new(challenge_transaction.compute_txid(), 0),
+        )
+        .await?
+    } else {
+        get_spending_transaction(&esplora_client, &challenge_transaction.compute_txid(), 0)
+            .await
+            .map_err(|e| e.wrap_err("Challenge is not settled yet"))?
+    };
+
+    let outcome = Outcome::from_settlement(&challenge_transaction, &settlement_transaction)?;
+
+    r.done("Outcome reconstructed");
+
+    let certificate = OutcomeCertificate {
+        id,
+        winner: match outcome.winner {
+            SpendingBranch::Acceptor => "acceptor".to_string(),
+            SpendingBranch::Challenger => "challenger".to_string(),
+        },
+        amount: outcome.amount,
+        challenge_txid: outcome.challenge_txid.to_string(),
+        settlement_txid: outcome.settlement_txid.to_string(),
+        settlement_height,
+        challenger_pubkey: outcome.challenger_pubkey.to_string(),
+        revealed_deposit_pubkey: outcome.revealed_deposit_pubkey.to_string(),
+        tweaked_acceptor_pubkey: outcome.tweaked_acceptor_pubkey.to_string(),
+        locktime: outcome.lock_time.to_consensus_u32(),
+    };
+
+    render_certificate(r.as_mut(), &certificate);
+
+    if let Some(path) = output {
+        fs::write(&path, serde_json::to_string_pretty(&certificate)?)?;
+        r.done(&format!("Certificate saved to {}", path));
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the outcome certificate
+fn render_certificate(r: &mut dyn Renderer, certificate: &OutcomeCertificate) {
+    r.field("Challenge ID", &certificate.id);
+    r.field("Winner", &certificate.winner);
+    r.field("Amount", certificate.amount);
+
+    r.section("TRANSACTIONS");
+    r.field("Challenge TXID", &certificate.challenge_txid);
+    r.field("Settlement TXID", &certificate.settlement_txid);
+    match certificate.settlement_height {
+        Some(height) => r.field("Settlement Height", height),
+        None => r.field("Settlement Height", "unconfirmed"),
+    }
+
+    r.section("EVIDENCE");
+    r.field("Challenger Public Key", &certificate.challenger_pubkey);
+    r.field("Revealed Deposit Key", &certificate.revealed_deposit_pubkey);
+    r.field("Tweaked Acceptor Key", &certificate.tweaked_acceptor_pubkey);
+    r.field("Locktime", certificate.locktime);
+}
+
+/// Waits for `challenge_output` to be spent and returns the spending transaction, with its
+/// confirmation height if any. Failed polls are warned about and retried.
+async fn watch_settlement(
+    r: &mut dyn Renderer,
+    esplora_client: &EsploraClient,
+    challenge_output: OutPoint,
+) -> eyre::Result<(Transaction, Option<u64>)> {
+    let mut events = pin!(esplora_client.watch_outpoint(challenge_output));
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(OutpointEvent::Spent {
+                transaction,
+                block_height,
+            }) => return Ok((transaction, block_height.map(u64::from))),
+            Ok(OutpointEvent::Unspent) => {}
+            Err(err) => r.warning(&format!("Failed to check the challenge output: {}", err)),
+        }
+    }
+
+    eyre::bail!("Stopped watching the challenge output")
+}
+
+/// Fetches the transaction spending the given output, with its confirmation height if any
+async fn get_spending_transaction(
+    esplora_client: &EsploraClient,
+    txid: &Txid,
+    vout: u32,
+) -> eyre::Result<(bitcoin::Transaction, Option<u64>)> {
+    let outspend = esplora_client.get_outspend(txid, vout).await?;
+    ensure!(outspend.spent, "Output {}:{} is unspent", txid, vout);
+
+    let spending_txid = Txid::from_str(
+        outspend
+            .txid
+            .as_deref()
+            .ok_or_eyre("Esplora didn't return the spending transaction")?,
+    )?;
+    let transaction = esplora_client.get_transaction(&spending_txid).await?;
+    let height = outspend.status.and_then(|status| status.block_height);
+
+    Ok((transaction, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_certificate() {
+        let certificate = OutcomeCertificate {
+            id: "0f9e5a4c-7d2b-4c1e-9a8f-3b6d2e1c0a9b".to_string(),
+            winner: "acceptor".to_string(),
+            amount: Amount::from_sat(20_000),
+            challenge_txid: "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                .to_string(),
+            settlement_txid: "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+                .to_string(),
+            settlement_height: Some(840_200),
+            challenger_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                .to_string(),
+            revealed_deposit_pubkey:
+                "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5".to_string(),
+            tweaked_acceptor_pubkey:
+                "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9".to_string(),
+            locktime: 840_144,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_certificate(r, &certificate)));
+    }
+}
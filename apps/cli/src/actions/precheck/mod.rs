@@ -0,0 +1,393 @@
+use std::fs;
+
+use bitcoin::{Address, Amount, CompressedPublicKey, OutPoint};
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{self, OptionExt, ensure};
+use op_rand_transaction_builder::TransactionKind;
+use op_rand_types::{Capabilities, ProtocolInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actions::{accept_challenge::select_funding, create_challenge::PublicChallengerData},
+    context::Context,
+    esplora::{EsploraClient, Utxo},
+    format::parse_sats,
+    limits::{BoundedMessage, InputError, MAX_SHORT_FIELD_LEN, check_field, load_message},
+    render::Renderer,
+    store::MessageKind,
+    ui::{GEAR, SHIELD},
+    util::{apply_coin_control, release_expired_reservations},
+};
+
+#[derive(Args, Debug)]
+pub struct PrecheckArgs {
+    #[command(subcommand)]
+    pub command: PrecheckCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PrecheckCommand {
+    /// Disclose the UTXOs that will fund an acceptance, before spending time on the proof
+    Disclose {
+        /// Path to the challenge JSON file
+        #[clap(long, default_value = "challenger.json")]
+        challenge_file: String,
+
+        /// Output file path for the pre-acceptance JSON
+        #[clap(long, default_value = "pre_acceptance.json")]
+        output: String,
+
+        /// Only fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+        #[clap(long = "utxo")]
+        include_utxos: Vec<OutPoint>,
+
+        /// Never fund the challenge from this UTXO (`txid:vout`). Can be repeated.
+        #[clap(long = "exclude-utxo")]
+        exclude_utxos: Vec<OutPoint>,
+    },
+
+    /// Check the funding UTXOs disclosed by an acceptor and acknowledge or veto them
+    Review {
+        /// Path to the challenge JSON file
+        #[clap(long, default_value = "challenger.json")]
+        challenge_file: String,
+
+        /// Path to the pre-acceptance JSON file
+        #[clap(long, default_value = "pre_acceptance.json")]
+        pre_acceptance_file: String,
+
+        /// Output file path for the acknowledgement JSON
+        #[clap(long, default_value = "pre_acceptance_ack.json")]
+        output: String,
+
+        /// Confirmations every funding UTXO needs
+        #[clap(long, default_value_t = 1)]
+        min_confirmations: u32,
+
+        /// Smallest funding UTXO value in sats. Defaults to the dust limit of its script.
+        #[clap(long, value_parser = parse_sats)]
+        min_value: Option<Amount>,
+    },
+}
+
+/// UTXOs the acceptor intends to fund the challenge transaction with, sent before the proof
+#[derive(Serialize, Deserialize)]
+pub struct PreAcceptanceData {
+    pub id: String,
+    pub funding_outpoints: Vec<OutPoint>,
+    pub protocol: ProtocolInfo,
+}
+
+impl BoundedMessage for PreAcceptanceData {
+    fn check_sizes(&self) -> Result<(), InputError> {
+        check_field("id", &self.id, MAX_SHORT_FIELD_LEN)
+    }
+}
+
+/// Challenger's answer to a pre-acceptance. The acceptor funds the acceptance from the
+/// acknowledged UTXOs only if `accepted` is set.
+#[derive(Serialize, Deserialize)]
+pub struct PreAcceptanceAck {
+    pub id: String,
+    pub funding_outpoints: Vec<OutPoint>,
+    pub accepted: bool,
+    /// Why the funding was vetoed, one entry per rejected UTXO
+    #[serde(default)]
+    pub reasons: Vec<String>,
+    pub protocol: ProtocolInfo,
+}
+
+impl BoundedMessage for PreAcceptanceAck {
+    fn check_sizes(&self) -> Result<(), InputError> {
+        check_field("id", &self.id, MAX_SHORT_FIELD_LEN)?;
+        for reason in &self.reasons {
+            check_field("reasons", reason, MAX_SHORT_FIELD_LEN)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Result of checking a single disclosed funding UTXO
+struct FundingCheck {
+    outpoint: OutPoint,
+    value: Option<Amount>,
+    confirmations: u32,
+    /// Why the UTXO is vetoed, if it is
+    veto: Option<String>,
+}
+
+pub async fn run(PrecheckArgs { command }: PrecheckArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+
+    match command {
+        PrecheckCommand::Disclose {
+            challenge_file,
+            output,
+            include_utxos,
+            exclude_utxos,
+        } => {
+            r.header("📋", "DISCLOSING FUNDING");
+
+            let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+            let capabilities = ProtocolInfo::local()
+                .negotiate(&challenge_data.protocol, Capabilities::FUNDING_PRECHECK)?;
+            r.field("Challenge ID", &challenge_data.id);
+
+            let cfg = ctx.config()?;
+            let esplora_client = ctx.esplora_client()?;
+            let mut store = ctx.store()?;
+            let fee_policy = ctx.fee_policy()?;
+            let address = Address::p2wpkh(
+                &CompressedPublicKey::from_private_key(ctx.secp_ctx(), &cfg.private_key).unwrap(),
+                cfg.network,
+            );
+
+            r.step(GEAR, "Selecting funding UTXOs...");
+            release_expired_reservations(r.as_mut(), &esplora_client, &mut store).await?;
+            let utxos = esplora_client.get_utxos(&address.to_string()).await?;
+            let utxos = apply_coin_control(utxos, &include_utxos, &exclude_utxos, &store)?;
+            let selected_utxos = select_funding(
+                r.as_mut(),
+                utxos,
+                !include_utxos.is_empty(),
+                challenge_data.amount,
+                &fee_policy,
+            )?;
+            if selected_utxos.iter().any(|utxo| !utxo.status.confirmed) {
+                r.warning("Some funding UTXOs are unconfirmed, the challenger may veto them");
+            }
+
+            let pre_acceptance = PreAcceptanceData {
+                id: challenge_data.id.clone(),
+                funding_outpoints: selected_utxos
+                    .iter()
+                    .map(Utxo::outpoint)
+                    .collect::<eyre::Result<_>>()?,
+                protocol: ProtocolInfo {
+                    capabilities,
+                    ..ProtocolInfo::local()
+                },
+            };
+            for outpoint in &pre_acceptance.funding_outpoints {
+                r.field("Funding UTXO", outpoint.to_string());
+            }
+
+            let json = serde_json::to_string(&pre_acceptance)?;
+            fs::write(&output, &json)?;
+            store.queue_message(&challenge_data.id, MessageKind::PreAcceptance, &json);
+            store.save()?;
+
+            r.section("FILE OUTPUTS");
+            r.field("Pre-acceptance (share with challenger)", &output);
+            r.success("Funding disclosed successfully!");
+        }
+        PrecheckCommand::Review {
+            challenge_file,
+            pre_acceptance_file,
+            output,
+            min_confirmations,
+            min_value,
+        } => {
+            r.header("📋", "REVIEWING FUNDING");
+
+            let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+            let pre_acceptance: PreAcceptanceData = load_message(&pre_acceptance_file)?;
+            ensure!(
+                pre_acceptance.id == challenge_data.id,
+                "Pre-acceptance is for challenge {}, not {}",
+                pre_acceptance.id,
+                challenge_data.id
+            );
+            let capabilities = ProtocolInfo::local()
+                .negotiate(&pre_acceptance.protocol, Capabilities::FUNDING_PRECHECK)?;
+            r.field("Challenge ID", &challenge_data.id);
+
+            let esplora_client = ctx.esplora_client()?;
+            let mut store = ctx.store()?;
+
+            r.step(SHIELD, "Checking the funding UTXOs...");
+            let tip_height = u32::try_from(esplora_client.get_tip_height().await?)?;
+            let mut checks = Vec::with_capacity(pre_acceptance.funding_outpoints.len());
+            for outpoint in &pre_acceptance.funding_outpoints {
+                checks.push(
+                    check_funding(
+                        &esplora_client,
+                        *outpoint,
+                        tip_height,
+                        min_confirmations,
+                        min_value,
+                    )
+                    .await?,
+                );
+            }
+
+            let total = checks
+                .iter()
+                .filter_map(|check| check.value)
+                .try_fold(Amount::ZERO, Amount::checked_add)
+                .ok_or_eyre("UTXO values overflow")?;
+            let mut reasons = checks
+                .iter()
+                .filter_map(|check| {
+                    check
+                        .veto
+                        .as_ref()
+                        .map(|veto| format!("{}: {}", check.outpoint, veto))
+                })
+                .collect::<Vec<_>>();
+            let needed = challenge_data
+                .amount
+                .checked_add(ctx.fee_policy()?.fee(TransactionKind::Challenge {
+                    inputs: checks.len(),
+                })?)
+                .ok_or_eyre("Amount overflow")?;
+            if total < needed {
+                reasons.push(format!(
+                    "funding of {} does not cover the stake and fee of {}",
+                    total, needed
+                ));
+            }
+
+            render_checks(r.as_mut(), &checks);
+
+            let ack = PreAcceptanceAck {
+                id: challenge_data.id.clone(),
+                funding_outpoints: pre_acceptance.funding_outpoints,
+                accepted: reasons.is_empty(),
+                reasons,
+                protocol: ProtocolInfo {
+                    capabilities,
+                    ..ProtocolInfo::local()
+                },
+            };
+
+            let json = serde_json::to_string(&ack)?;
+            fs::write(&output, &json)?;
+            store.queue_message(&challenge_data.id, MessageKind::PreAcceptanceAck, &json);
+            store.save()?;
+
+            r.section("FILE OUTPUTS");
+            r.field("Acknowledgement (share with acceptor)", &output);
+            if ack.accepted {
+                r.success("Funding acknowledged!");
+            } else {
+                for reason in &ack.reasons {
+                    r.warning(reason);
+                }
+                r.success("Funding vetoed, the acceptor has to disclose other UTXOs");
+            }
+        }
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+/// Checks that a funding UTXO exists, is unspent, has enough confirmations and is not dust.
+async fn check_funding(
+    esplora_client: &EsploraClient,
+    outpoint: OutPoint,
+    tip_height: u32,
+    min_confirmations: u32,
+    min_value: Option<Amount>,
+) -> eyre::Result<FundingCheck> {
+    let mut check = FundingCheck {
+        outpoint,
+        value: None,
+        confirmations: 0,
+        veto: None,
+    };
+
+    if !esplora_client.has_transaction(&outpoint.txid).await? {
+        check.veto = Some("transaction not found".to_string());
+        return Ok(check);
+    }
+
+    let tx = esplora_client.get_transaction(&outpoint.txid).await?;
+    let Some(output) = tx.output.get(outpoint.vout as usize) else {
+        check.veto = Some("output does not exist".to_string());
+        return Ok(check);
+    };
+    check.value = Some(output.value);
+
+    let status = esplora_client.get_tx_status(&outpoint.txid).await?;
+    check.confirmations = status
+        .block_height
+        .filter(|_| status.confirmed)
+        .map_or(0, |height| (tip_height + 1).saturating_sub(height as u32));
+
+    let min_value = min_value.unwrap_or_else(|| output.script_pubkey.minimal_non_dust());
+    check.veto = if esplora_client
+        .get_outspend(&outpoint.txid, outpoint.vout)
+        .await?
+        .spent
+    {
+        Some("already spent".to_string())
+    } else if check.confirmations < min_confirmations {
+        Some(format!(
+            "{} confirmations, {} required",
+            check.confirmations, min_confirmations
+        ))
+    } else if output.value < min_value {
+        Some(format!(
+            "{} is below the minimum of {}",
+            output.value, min_value
+        ))
+    } else {
+        None
+    };
+
+    Ok(check)
+}
+
+/// Renders the result of checking every disclosed funding UTXO
+fn render_checks(r: &mut dyn Renderer, checks: &[FundingCheck]) {
+    r.section("FUNDING");
+    for (i, check) in checks.iter().enumerate() {
+        r.entry(&format!("UTXO {}", i + 1));
+        r.field("Outpoint", check.outpoint.to_string());
+        if let Some(value) = check.value {
+            r.field("Amount", value);
+        }
+        r.field("Confirmations", check.confirmations);
+        match &check.veto {
+            Some(veto) => {
+                r.field("Status", "vetoed");
+                r.field("Reason", veto);
+            }
+            None => r.field("Status", "acknowledged"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+    use bitcoin::{Txid, hashes::Hash};
+
+    #[test]
+    fn renders_checks() {
+        let txid = Txid::all_zeros();
+
+        insta::assert_snapshot!(render_all(|r| render_checks(
+            r,
+            &[
+                FundingCheck {
+                    outpoint: OutPoint::new(txid, 0),
+                    value: Some(Amount::from_sat(150_000)),
+                    confirmations: 6,
+                    veto: None,
+                },
+                FundingCheck {
+                    outpoint: OutPoint::new(txid, 1),
+                    value: Some(Amount::from_sat(300)),
+                    confirmations: 0,
+                    veto: Some("0 confirmations, 1 required".to_string()),
+                },
+            ],
+        )));
+    }
+}
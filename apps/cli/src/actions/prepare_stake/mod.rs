@@ -0,0 +1,140 @@
+use std::str::FromStr;
+
+use bitcoin::{Address, CompressedPublicKey, OutPoint, PublicKey, consensus};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt};
+use op_rand_transaction_builder::TransactionKind;
+
+use crate::{
+    actions::create_challenge::PublicChallengerData,
+    context::Context,
+    limits::load_message,
+    store::{BroadcastKind, UtxoRecord},
+    ui::{CHAIN, GEAR, RADIO},
+    util::{apply_coin_control, compute_change, release_expired_reservations, select_utxos},
+};
+
+#[derive(Args, Debug)]
+pub struct PrepareStakeArgs {
+    /// Path to the challenge JSON file
+    #[clap(long, default_value = "challenger.json")]
+    pub challenge_file: String,
+
+    /// Change public key.
+    #[clap(long)]
+    pub change_pubkey: Option<String>,
+
+    /// Only fund the stake from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "utxo")]
+    pub include_utxos: Vec<OutPoint>,
+
+    /// Never fund the stake from this UTXO (`txid:vout`). Can be repeated.
+    #[clap(long = "exclude-utxo")]
+    pub exclude_utxos: Vec<OutPoint>,
+}
+
+pub async fn run(
+    PrepareStakeArgs {
+        challenge_file,
+        change_pubkey,
+        include_utxos,
+        exclude_utxos,
+    }: PrepareStakeArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🪙", "PREPARING STAKE");
+
+    let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+
+    let cfg = ctx.config()?;
+    let esplora_client = ctx.esplora_client()?;
+    let transaction_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let fee_policy = ctx.fee_policy()?;
+
+    // The challenge transaction spends the challenge amount plus the fee of a challenge
+    // transaction with a single input from the acceptor
+    let stake_amount = challenge_data
+        .amount
+        .checked_add(fee_policy.fee(TransactionKind::Challenge { inputs: 1 })?)
+        .ok_or_eyre("Amount overflow")?;
+
+    r.field("Challenge ID", &challenge_data.id);
+    r.field("Stake amount", stake_amount);
+
+    let secp = ctx.secp_ctx();
+    let address = Address::p2wpkh(
+        &CompressedPublicKey::from_private_key(secp, &cfg.private_key).unwrap(),
+        cfg.network,
+    );
+
+    r.step(GEAR, "Preparing transaction inputs...");
+
+    let utxos = esplora_client.get_utxos(&address.to_string()).await?;
+    release_expired_reservations(r.as_mut(), &esplora_client, &mut store).await?;
+    let utxos = apply_coin_control(utxos, &include_utxos, &exclude_utxos, &store)?;
+    let selected_utxos = select_utxos(utxos, stake_amount, &fee_policy, |inputs| {
+        TransactionKind::Stake { inputs }
+    })?;
+
+    r.done(&format!(
+        "{} UTXOs selected for funding",
+        selected_utxos.len()
+    ));
+
+    let fee = fee_policy.fee(TransactionKind::Stake {
+        inputs: selected_utxos.len(),
+    })?;
+    let change = compute_change(&selected_utxos, stake_amount, fee, &fee_policy)?;
+    let change_pubkey = change_pubkey
+        .map(|pk| PublicKey::from_str(&pk))
+        .transpose()?;
+    let prevouts = selected_utxos
+        .iter()
+        .map(|utxo| Ok((utxo.outpoint()?, utxo.value)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let consolidated = prevouts
+        .iter()
+        .map(|(outpoint, _)| outpoint.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    r.step(CHAIN, "Creating stake transaction...");
+
+    let stake_tx = transaction_builder.build_stake_transaction(
+        prevouts,
+        stake_amount,
+        change,
+        change_pubkey,
+    )?;
+    fee_policy.relay.check_standard(&stake_tx, fee)?;
+    let stake_tx_hex = consensus::encode::serialize_hex(&stake_tx);
+    let stake_outpoint = OutPoint::new(stake_tx.compute_txid(), 0);
+
+    r.step(RADIO, "Broadcasting stake transaction...");
+
+    store.record_transaction(&challenge_data.id, BroadcastKind::Stake, &stake_tx_hex)?;
+    store.utxos.insert(
+        stake_outpoint,
+        UtxoRecord {
+            label: Some(format!("stake for challenge {}", challenge_data.id)),
+            frozen: false,
+        },
+    );
+    store.save()?;
+
+    esplora_client.broadcast_transaction(&stake_tx_hex).await?;
+
+    r.section("STAKE");
+    r.field("Outpoint", stake_outpoint.to_string());
+    r.field("Amount", stake_amount);
+    r.field("Consolidated UTXOs", consolidated);
+    r.success(&format!(
+        "Stake prepared! Accept with --utxo {}",
+        stake_outpoint
+    ));
+    r.finish();
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use color_eyre::eyre;
+use op_rand_prover::ProverDaemon;
+
+use crate::{context::Context, ui::RADIO};
+
+#[derive(Args, Debug)]
+pub struct ProverDaemonArgs {
+    /// Socket to listen on. Defaults to `prover_socket` from the config.
+    #[clap(long)]
+    pub socket: Option<PathBuf>,
+}
+
+pub async fn run(
+    ProverDaemonArgs { socket }: ProverDaemonArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🧮", "PROVER DAEMON");
+
+    let socket = match socket {
+        Some(socket) => socket,
+        None => ctx.config()?.prover_socket,
+    };
+
+    let pb = r.progress("Setting up the challenger and acceptor circuits...");
+    let prover = ctx.in_process_prover()?;
+    let daemon = tokio::task::spawn_blocking(|| ProverDaemon::new(prover)).await??;
+    pb.finish_with_message("Circuits are set up");
+
+    r.step(RADIO, "Listening for proving requests...");
+    r.field("Socket", socket.display().to_string());
+
+    r.section("CIRCUITS");
+    for circuit in daemon.circuits() {
+        r.entry(&circuit.circuit().to_string());
+        r.field("Circuit Hash", circuit.circuit_hash().to_string());
+        r.field("SRS Size", circuit.srs_size());
+    }
+    r.finish();
+
+    tokio::task::spawn_blocking(move || daemon.serve(&socket)).await??;
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use bitcoin::Txid;
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, bail};
+
+use crate::{context::Context, ui::RADIO};
+
+#[derive(Args, Debug)]
+pub struct RebroadcastArgs {
+    /// Transaction ID of a protocol transaction broadcast by this wallet
+    pub txid: Txid,
+
+    /// Maximum number of broadcast attempts
+    #[clap(long, default_value = "5")]
+    pub max_attempts: u32,
+
+    /// Delay before the second attempt in seconds, doubled after every failure
+    #[clap(long, default_value = "10")]
+    pub backoff: u64,
+}
+
+pub async fn run(
+    RebroadcastArgs {
+        txid,
+        max_attempts,
+        backoff,
+    }: RebroadcastArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("📡", "REBROADCAST TRANSACTION");
+
+    let store = ctx.store()?;
+    let esplora_client = ctx.esplora_client()?;
+
+    let record = store
+        .transactions
+        .get(&txid)
+        .ok_or_eyre("Transaction was not broadcast by this wallet")?;
+
+    r.field("Challenge ID", &record.challenge_id);
+
+    if esplora_client.has_transaction(&txid).await? {
+        let status = esplora_client.get_tx_status(&txid).await?;
+        let state = if status.confirmed {
+            "confirmed"
+        } else {
+            "in the mempool"
+        };
+
+        r.field("Status", state);
+        r.success(&format!("Transaction is already {}", state));
+        r.finish();
+
+        return Ok(());
+    }
+
+    let mut delay = Duration::from_secs(backoff);
+
+    for attempt in 1..=max_attempts {
+        r.step(
+            RADIO,
+            &format!("Broadcast attempt {}/{}...", attempt, max_attempts),
+        );
+
+        match esplora_client.broadcast_transaction(&record.raw_tx).await {
+            Ok(_) => {
+                r.field("Status", "rebroadcasted");
+                r.field("Attempts", attempt);
+                r.success("Transaction rebroadcasted!");
+                r.finish();
+
+                return Ok(());
+            }
+            Err(e) => r.warning(&format!("{:#}", e)),
+        }
+
+        if attempt < max_attempts {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    bail!(
+        "Failed to rebroadcast {} after {} attempts",
+        txid,
+        max_attempts
+    )
+}
@@ -0,0 +1,128 @@
+use std::{fs, str::FromStr};
+
+use bitcoin::{Address, Transaction, address::NetworkUnchecked, consensus};
+use clap::Args;
+use color_eyre::eyre::{self, bail, ensure};
+use op_rand_transaction_builder::TransactionKind;
+use op_rand_types::FirstRankCommitment;
+
+use crate::{
+    actions::create_challenge::{PrivateChallengerData, PublicChallengerData},
+    context::Context,
+    limits::load_message,
+    store::BroadcastKind,
+    ui::{CHAIN, GEAR, RADIO},
+    util::recipient_script,
+};
+
+#[derive(Args, Debug)]
+pub struct RefundArgs {
+    /// Path to the challenge JSON file
+    #[clap(long, default_value = "challenger.json")]
+    pub challenger_file: String,
+
+    /// Path to the challenger's private key file
+    #[clap(long, default_value = "private_challenger.json")]
+    pub challenger_private_file: String,
+
+    /// Recipient address, e.g. P2TR, P2WSH or legacy. Must belong to the configured network
+    #[clap(long, conflicts_with = "recipient_pubkey")]
+    pub recipient: Option<Address<NetworkUnchecked>>,
+
+    /// Recipient public key, paid to with P2WPKH. The wallet key by default
+    #[clap(long)]
+    pub recipient_pubkey: Option<String>,
+}
+
+pub async fn run(
+    RefundArgs {
+        challenger_file,
+        challenger_private_file,
+        recipient,
+        recipient_pubkey,
+    }: RefundArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("↩️ ", "REFUNDING DEPOSIT");
+    r.step(GEAR, "Loading challenge data...");
+
+    let challenger_data: PublicChallengerData = load_message(&challenger_file)?;
+
+    let challenger_private_json = fs::read_to_string(&challenger_private_file)?;
+    let challenger_private_data: PrivateChallengerData =
+        serde_json::from_str(&challenger_private_json)?;
+
+    ensure!(
+        challenger_data.id == challenger_private_data.id,
+        "Public and private challenge IDs do not match"
+    );
+
+    r.field("Challenge ID", &challenger_data.id);
+
+    let network = ctx.config()?.network;
+    let esplora_client = ctx.esplora_client()?;
+    let transaction_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let fee_policy = ctx.fee_policy()?;
+
+    r.step(CHAIN, "Checking the deposit...");
+
+    if let Some(deadline) = challenger_data.acceptance_deadline {
+        let tip_height = esplora_client.get_tip_height().await?;
+        ensure!(
+            tip_height >= u64::from(deadline),
+            "The acceptor has until block {} to accept the challenge (current height {})",
+            deadline,
+            tip_height
+        );
+    }
+
+    let deposit_outpoint = challenger_data.deposit_outpoint;
+    if !esplora_client
+        .has_transaction(&deposit_outpoint.txid)
+        .await?
+    {
+        bail!("Deposit transaction was never broadcast, there's nothing to refund");
+    }
+
+    let outspend = esplora_client
+        .get_outspend(&deposit_outpoint.txid, deposit_outpoint.vout)
+        .await?;
+    ensure!(
+        !outspend.spent,
+        "Deposit was already spent by {}",
+        outspend.txid.unwrap_or_default()
+    );
+
+    r.step(GEAR, "Creating refund transaction...");
+
+    let deposit_transaction: Transaction =
+        consensus::encode::deserialize_hex(&challenger_private_data.deposit_transaction)?;
+    let selected_first_rank_commitment =
+        FirstRankCommitment::from_str(&challenger_private_data.selected_first_rank_commitment)?;
+    let recipient = recipient_script(recipient, recipient_pubkey.as_deref(), network)?;
+
+    let fee = fee_policy.fee(TransactionKind::Refund)?;
+    let refund_tx = transaction_builder.build_deposit_refund_tx(
+        &deposit_transaction,
+        selected_first_rank_commitment,
+        recipient,
+        fee,
+    )?;
+    fee_policy.relay.check_standard(&refund_tx, fee)?;
+    let refund_tx_hex = consensus::encode::serialize_hex(&refund_tx);
+
+    r.step(RADIO, "Broadcasting refund transaction...");
+
+    store.record_transaction(&challenger_data.id, BroadcastKind::Refund, &refund_tx_hex)?;
+    store.save()?;
+
+    let txid = esplora_client.broadcast_transaction(&refund_tx_hex).await?;
+
+    r.field("Refund TXID", &txid);
+    r.success("Deposit refunded successfully!");
+    r.finish();
+
+    Ok(())
+}
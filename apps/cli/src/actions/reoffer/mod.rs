@@ -0,0 +1,433 @@
+use std::{fs, str::FromStr};
+
+use bitcoin::{
+    OutPoint, PublicKey, Transaction,
+    consensus::{self, Encodable},
+    hashes::{Hash, ripemd160, sha256},
+    secp256k1::{self, rand::thread_rng},
+};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, bail, ensure};
+use op_rand_prover::{OpRandProverAsync, OpRandProverSpawn};
+use op_rand_types::{Commitments, EntropyMixer, MIXING, ProtocolInfo};
+
+use crate::{
+    actions::create_challenge::{
+        PrivateChallengerData, PublicChallengerData, RandomnessTranscript, challenge_id,
+        render_summary,
+    },
+    context::Context,
+    limits::load_message,
+    proving::render_metrics,
+    render::Renderer,
+    store::{ChallengeRecord, MessageKind, Role},
+    ui::{CHAIN, GEAR, KEY, SPARKLES},
+    util::window_deadline,
+};
+
+#[derive(Args, Debug)]
+pub struct ReofferArgs {
+    /// ID of the challenge to re-offer
+    pub id: String,
+
+    /// Path to the challenge JSON file
+    #[clap(long, default_value = "challenger.json")]
+    pub challenger_file: String,
+
+    /// Path to the challenger's private key file
+    #[clap(long, default_value = "private_challenger.json")]
+    pub challenger_private_file: String,
+
+    /// Output file path for the re-offered challenge JSON
+    #[clap(long, default_value = "challenger.json")]
+    pub public_output: String,
+
+    /// Output file path for the re-offered private challenge JSON
+    #[clap(long, default_value = "private_challenger.json")]
+    pub private_output: String,
+
+    /// Locktime of the re-offer. The locktime of the challenge by default.
+    #[clap(long)]
+    pub locktime: Option<u32>,
+
+    /// Number of blocks the acceptor has to accept the re-offer. After that the deposit can be
+    /// refunded.
+    #[clap(long)]
+    pub acceptance_window: Option<u32>,
+
+    /// Generate new commitments and a new deposit spending the same UTXOs. Only possible while
+    /// the deposit is unbroadcast.
+    #[clap(long)]
+    pub new_commitments: bool,
+
+    /// Change public key of the new deposit. The wallet key by default.
+    #[clap(long, requires = "new_commitments")]
+    pub change_pubkey: Option<String>,
+}
+
+/// Commitments and deposit of a re-offer
+struct Envelope {
+    deposit_transaction: Transaction,
+    third_rank_commitments: [String; 2],
+    challenger_pubkey_hash: String,
+    proof: String,
+    vk: String,
+    circuit_hash: Option<String>,
+    private: PrivateChallengerData,
+}
+
+pub async fn run(
+    ReofferArgs {
+        id,
+        challenger_file,
+        challenger_private_file,
+        public_output,
+        private_output,
+        locktime,
+        acceptance_window,
+        new_commitments,
+        change_pubkey,
+    }: ReofferArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🔁", "RE-OFFERING CHALLENGE");
+    r.step(GEAR, "Loading challenge data...");
+
+    let challenger_data: PublicChallengerData = load_message(&challenger_file)?;
+
+    let challenger_private_json = fs::read_to_string(&challenger_private_file)?;
+    let challenger_private_data: PrivateChallengerData =
+        serde_json::from_str(&challenger_private_json)?;
+
+    ensure!(
+        challenger_data.id == id && challenger_private_data.id == id,
+        "Challenge files don't belong to challenge {}",
+        id
+    );
+
+    let private_key = ctx.config()?.private_key;
+    let esplora_client = ctx.esplora_client()?;
+    let transaction_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let prover = ctx.prover()?;
+    let secp = ctx.secp_ctx();
+
+    let record = store
+        .challenges
+        .get(&id)
+        .ok_or_eyre("Challenge not found in the local store")?
+        .clone();
+    ensure!(
+        record.role == Role::Challenger,
+        "Only the challenger can re-offer a challenge"
+    );
+    if let Some(new_id) = &record.superseded_by {
+        bail!("Challenge was already re-offered as {}", new_id);
+    }
+    ensure!(
+        !store.is_accepted(&id),
+        "Challenge was already accepted, complete it instead"
+    );
+
+    r.step(CHAIN, "Checking the deposit...");
+
+    let deposit_outpoint = challenger_data.deposit_outpoint;
+    let deposit_broadcast = esplora_client
+        .has_transaction(&deposit_outpoint.txid)
+        .await?;
+    if deposit_broadcast {
+        let outspend = esplora_client
+            .get_outspend(&deposit_outpoint.txid, deposit_outpoint.vout)
+            .await?;
+        ensure!(
+            !outspend.spent,
+            "Deposit was already spent by {}",
+            outspend.txid.unwrap_or_default()
+        );
+    }
+    r.field("Deposit Broadcast", deposit_broadcast);
+
+    let old_deposit: Transaction =
+        consensus::encode::deserialize_hex(&challenger_private_data.deposit_transaction)?;
+
+    let envelope = if new_commitments {
+        // The selected commitment is bound to the deposit output, so new commitments need a new
+        // deposit. It spends the same UTXOs, so at most one of the two deposits can confirm.
+        ensure!(
+            !deposit_broadcast,
+            "Deposit was broadcast, its commitments can't be replaced. Refund it and create a new challenge instead"
+        );
+        ensure!(
+            challenger_data.beacon.is_none(),
+            "Re-offering a beacon-selected challenge with new commitments is not supported"
+        );
+
+        r.step(KEY, "Generating new commitments...");
+
+        let mut prevouts = Vec::with_capacity(old_deposit.input.len());
+        for input in &old_deposit.input {
+            let outpoint = input.previous_output;
+            let previous = esplora_client.get_transaction(&outpoint.txid).await?;
+            let value = previous
+                .output
+                .get(outpoint.vout as usize)
+                .ok_or_eyre("Funding output not found")?
+                .value;
+            prevouts.push((outpoint, value));
+        }
+        let change = old_deposit.output.get(1).map(|output| output.value);
+        let change_pubkey = change_pubkey
+            .map(|pk| PublicKey::from_str(&pk))
+            .transpose()?;
+
+        let mut rng = EntropyMixer::new(&mut thread_rng()).finish();
+        let commitments = Commitments::generate(secp, &mut rng)?;
+        let selected = commitments
+            .pick_random_first_rank_commitment(&mut rng)
+            .ok_or_eyre("No first rank commitments available")?;
+
+        let public_key = private_key.public_key(secp).inner;
+        let tweaked_pk = public_key.combine(&selected.inner().1)?;
+        let sha256_hash = sha256::Hash::hash(&tweaked_pk.serialize());
+        let ripemd160_hash = ripemd160::Hash::hash(sha256_hash.as_byte_array());
+
+        let deposit_transaction = transaction_builder.build_deposit_transaction(
+            selected.to_owned(),
+            prevouts,
+            challenger_data.amount,
+            change,
+            change_pubkey,
+        )?;
+        r.field(
+            "Deposit TXID",
+            deposit_transaction.compute_txid().to_string(),
+        );
+
+        let pb = r.progress("Generating the challenger proof...");
+        ctx.track_proving(&pb);
+        let challenger_circuit = prover.setup_challenger_circuit().await?;
+        let proof = prover
+            .spawn_challenger_proof(
+                &challenger_circuit,
+                commitments.first_rank_commitments(),
+                commitments.third_rank_commitments(),
+                &public_key,
+                ripemd160_hash.to_byte_array(),
+                &OutPoint::new(deposit_transaction.compute_txid(), 0),
+            )
+            .await?;
+        pb.finish_with_message("Challenger proof generated");
+        if let Some(metrics) = proof.metrics() {
+            render_metrics(r.as_mut(), metrics);
+        }
+
+        let [first, second] = commitments.first_rank_commitments();
+        let [third_first, third_second] = commitments.third_rank_commitments();
+        let mut tx_bytes = Vec::new();
+        deposit_transaction.consensus_encode(&mut tx_bytes)?;
+
+        Envelope {
+            third_rank_commitments: [
+                hex::encode(third_first.inner().serialize()),
+                hex::encode(third_second.inner().serialize()),
+            ],
+            challenger_pubkey_hash: hex::encode(ripemd160_hash.to_byte_array()),
+            proof: hex::encode(proof.proof()),
+            vk: hex::encode(proof.vk()),
+            circuit_hash: proof.circuit_hash().map(|hash| hash.to_string()),
+            private: PrivateChallengerData {
+                id: String::new(),
+                amount: challenger_data.amount,
+                deposit_transaction: hex::encode(tx_bytes),
+                first_rank_commitments: [
+                    hex::encode(first.inner().0.secret_bytes()),
+                    hex::encode(second.inner().0.secret_bytes()),
+                ],
+                selected_first_rank_commitment: hex::encode(selected.inner().0.secret_bytes()),
+                randomness: Some(RandomnessTranscript {
+                    mixing: MIXING.to_string(),
+                    sources: rng.records().to_vec(),
+                }),
+                beacon_salt: None,
+            },
+            deposit_transaction,
+        }
+    } else {
+        // The proof only commits to the commitments and the deposit, so it stays valid
+        Envelope {
+            deposit_transaction: old_deposit,
+            third_rank_commitments: challenger_data.third_rank_commitments.clone(),
+            challenger_pubkey_hash: challenger_data.challenger_pubkey_hash.clone(),
+            proof: challenger_data.proof.clone(),
+            vk: challenger_data.vk.clone(),
+            circuit_hash: challenger_data.circuit_hash.clone(),
+            private: PrivateChallengerData {
+                id: String::new(),
+                ..challenger_private_data
+            },
+        }
+    };
+
+    r.step(SPARKLES, "Finalizing the re-offer...");
+
+    let locktime = locktime.unwrap_or(challenger_data.locktime);
+    let deposit_outpoint = OutPoint::new(envelope.deposit_transaction.compute_txid(), 0);
+    let challenger_pubkey = secp256k1::PublicKey::from_str(&challenger_data.challenger_pubkey)?;
+    let challenger_pubkey_hash: [u8; 20] = hex::decode(&envelope.challenger_pubkey_hash)?
+        .try_into()
+        .map_err(|_| eyre::eyre!("Failed to convert challenger public key hash to array"))?;
+    let [first, second] = &envelope.third_rank_commitments;
+    let new_id = challenge_id(
+        challenger_data.amount,
+        &deposit_outpoint,
+        &[
+            secp256k1::PublicKey::from_str(first)?,
+            secp256k1::PublicKey::from_str(second)?,
+        ],
+        &challenger_pubkey,
+        &challenger_pubkey_hash,
+        locktime,
+    );
+    ensure!(
+        new_id != id,
+        "The re-offer must change the locktime or the commitments"
+    );
+
+    let acceptance_deadline = match acceptance_window {
+        Some(window) => Some(window_deadline(
+            esplora_client.get_tip_height().await?,
+            window,
+        )?),
+        None => None,
+    };
+
+    let public_challenge_output = PublicChallengerData {
+        id: new_id.clone(),
+        amount: challenger_data.amount,
+        deposit_outpoint,
+        third_rank_commitments: envelope.third_rank_commitments,
+        challenger_pubkey: challenger_data.challenger_pubkey,
+        challenger_pubkey_hash: envelope.challenger_pubkey_hash,
+        proof: envelope.proof,
+        vk: envelope.vk,
+        locktime,
+        deposit_broadcast,
+        protocol: ProtocolInfo::local(),
+        acceptance_deadline,
+        beacon: challenger_data.beacon.filter(|_| !new_commitments),
+        circuit_hash: envelope.circuit_hash,
+    };
+    let private_challenge_output = PrivateChallengerData {
+        id: new_id.clone(),
+        ..envelope.private
+    };
+
+    let json_output = serde_json::to_string_pretty(&public_challenge_output)?;
+    fs::write(&public_output, &json_output)?;
+    fs::write(
+        &private_output,
+        serde_json::to_string_pretty(&private_challenge_output)?,
+    )?;
+
+    store.supersede(
+        &id,
+        &new_id,
+        ChallengeRecord {
+            change_script: if new_commitments {
+                envelope
+                    .deposit_transaction
+                    .output
+                    .get(1)
+                    .map(|output| output.script_pubkey.to_hex_string())
+            } else {
+                record.change_script.clone()
+            },
+            funding_outpoints: Vec::new(),
+            deposit_outpoint: Some(deposit_outpoint),
+            reoffer_of: None,
+            superseded_by: None,
+            counterparty_pubkey: None,
+            ..record
+        },
+    );
+    store.queue_message(&new_id, MessageKind::Challenge, &json_output);
+    store.save()?;
+
+    render_reoffer(
+        r.as_mut(),
+        &id,
+        new_commitments,
+        &public_challenge_output,
+        &public_output,
+        &private_output,
+    );
+    r.success("CHALLENGE RE-OFFERED SUCCESSFULLY!");
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the re-offer of a challenge and where its data was saved
+fn render_reoffer(
+    r: &mut dyn Renderer,
+    old_id: &str,
+    new_commitments: bool,
+    challenge: &PublicChallengerData,
+    public_output: &str,
+    private_output: &str,
+) {
+    r.section("LINEAGE");
+    r.field("Re-offer Of", old_id);
+    r.field(
+        "Commitments",
+        if new_commitments { "new" } else { "unchanged" },
+    );
+    if !new_commitments {
+        r.warning("The re-offer spends the same deposit, only complete one of the two challenges");
+    }
+
+    render_summary(r, challenge, public_output, private_output);
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Amount;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_reoffer() {
+        let challenge = PublicChallengerData {
+            id: "5d1c0e9b7a3f4e2d8c6b1a0f9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a2f1e0d".to_string(),
+            amount: Amount::from_sat(100_000),
+            deposit_outpoint: OutPoint::new(
+                "a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d"
+                    .parse()
+                    .unwrap(),
+                0,
+            ),
+            third_rank_commitments: [String::new(), String::new()],
+            challenger_pubkey: String::new(),
+            challenger_pubkey_hash: String::new(),
+            proof: String::new(),
+            vk: String::new(),
+            locktime: 288,
+            deposit_broadcast: true,
+            protocol: ProtocolInfo::local(),
+            acceptance_deadline: Some(840_144),
+            beacon: None,
+            circuit_hash: None,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_reoffer(
+            r,
+            "challenge-1",
+            false,
+            &challenge,
+            "challenger.json",
+            "private_challenger.json"
+        )));
+    }
+}
@@ -0,0 +1,468 @@
+use std::{fmt::Write as _, fs, path::PathBuf};
+
+use bitcoin::{Amount, EcdsaSighashType, OutPoint, Txid, hashes::Hash};
+use clap::{Args, Subcommand};
+use color_eyre::eyre::{self, Context as _, bail};
+use op_rand_prover::{
+    Circuit, CircuitAbi, PublicInput, acceptor_circuit_abi, challenger_circuit_abi,
+};
+use op_rand_transaction_builder::{SpendingBranch, challenge_script_template};
+use op_rand_types::{
+    BeaconParams, BeaconSource, Capabilities, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, ProtocolInfo,
+};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    actions::{
+        accept_challenge::AcceptorData,
+        create_challenge::{CHALLENGE_ID_TAG, PublicChallengerData},
+    },
+    context::Context,
+    limits::MAX_MESSAGE_SIZE,
+    ui::GEAR,
+};
+
+#[derive(Args, Debug)]
+pub struct SpecArgs {
+    #[command(subcommand)]
+    pub command: SpecCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SpecCommand {
+    /// Write the protocol specification of this build as `spec.json` and `spec.md`
+    Export {
+        /// Directory to write the specification to
+        #[clap(long, default_value = "spec")]
+        output_dir: PathBuf,
+    },
+}
+
+/// Byte-level definitions of the protocol as implemented by this build. Everything is taken
+/// from the code that builds and checks the protocol messages, scripts and proofs.
+#[derive(Serialize)]
+struct Spec {
+    implementation: String,
+    protocol: ProtocolSpec,
+    messages: Vec<MessageSpec>,
+    challenge_id: ChallengeIdSpec,
+    challenge_output: ChallengeOutputSpec,
+    witness_stacks: Vec<WitnessStackSpec>,
+    sighashes: Vec<SighashSpec>,
+    public_inputs: Vec<PublicInputsSpec>,
+}
+
+#[derive(Serialize)]
+struct ProtocolSpec {
+    version: u32,
+    min_version: u32,
+    capabilities: Vec<CapabilitySpec>,
+    /// Capabilities this build supports
+    supported: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct CapabilitySpec {
+    name: &'static str,
+    bit: u32,
+}
+
+#[derive(Serialize)]
+struct MessageSpec {
+    kind: &'static str,
+    file: &'static str,
+    /// Largest accepted file, in bytes
+    max_size: u64,
+    /// JSON schema derived from the message types
+    schema: Value,
+}
+
+#[derive(Serialize)]
+struct ChallengeIdSpec {
+    hash: &'static str,
+    /// Fields hashed after the tag, in order
+    preimage: Vec<&'static str>,
+    tag: String,
+}
+
+#[derive(Serialize)]
+struct ChallengeOutputSpec {
+    output: &'static str,
+    miniscript: String,
+    witness_script: String,
+}
+
+#[derive(Serialize)]
+struct WitnessStackSpec {
+    input: &'static str,
+    /// Witness items, bottom first
+    stack: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+struct SighashSpec {
+    transaction: &'static str,
+    input: &'static str,
+    sighash: String,
+}
+
+#[derive(Serialize)]
+struct PublicInputsSpec {
+    circuit: Circuit,
+    /// Public inputs in the order they lead the proof, one byte per field element
+    inputs: Vec<PublicInput>,
+}
+
+pub async fn run(SpecArgs { command }: SpecArgs, ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+
+    match command {
+        SpecCommand::Export { output_dir } => {
+            r.header("📐", "EXPORTING PROTOCOL SPECIFICATION");
+            r.step(GEAR, "Collecting the definitions of this build...");
+
+            let spec = spec()?;
+            fs::create_dir_all(&output_dir)
+                .wrap_err_with(|| format!("Failed to create {}", output_dir.display()))?;
+
+            let json_path = output_dir.join("spec.json");
+            fs::write(&json_path, serde_json::to_string_pretty(&spec)?)
+                .wrap_err_with(|| format!("Failed to write {}", json_path.display()))?;
+            let markdown_path = output_dir.join("spec.md");
+            fs::write(&markdown_path, markdown(&spec))
+                .wrap_err_with(|| format!("Failed to write {}", markdown_path.display()))?;
+
+            r.section("FILE OUTPUTS");
+            r.field("JSON", json_path.display().to_string());
+            r.field("Markdown", markdown_path.display().to_string());
+            r.success("SPECIFICATION EXPORTED SUCCESSFULLY!");
+        }
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+fn spec() -> eyre::Result<Spec> {
+    let script = challenge_script_template()?;
+    let all_or_anyonecanpay = format!(
+        "{}, or {} if the `anyonecanpay` capability was agreed",
+        EcdsaSighashType::All,
+        EcdsaSighashType::AllPlusAnyoneCanPay
+    );
+
+    Ok(Spec {
+        implementation: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        protocol: ProtocolSpec {
+            version: PROTOCOL_VERSION,
+            min_version: MIN_PROTOCOL_VERSION,
+            capabilities: Capabilities::NAMES
+                .iter()
+                .map(|(capability, name)| CapabilitySpec {
+                    name,
+                    bit: capability.bits().trailing_zeros(),
+                })
+                .collect(),
+            supported: Capabilities::NAMES
+                .iter()
+                .filter(|(capability, _)| Capabilities::SUPPORTED.contains(*capability))
+                .map(|(_, name)| *name)
+                .collect(),
+        },
+        messages: vec![
+            MessageSpec {
+                kind: "challenge",
+                file: "challenger.json",
+                max_size: MAX_MESSAGE_SIZE,
+                schema: message_schema(&sample_challenge())?,
+            },
+            MessageSpec {
+                kind: "acceptance",
+                file: "acceptor.json",
+                max_size: MAX_MESSAGE_SIZE,
+                schema: message_schema(&sample_acceptance())?,
+            },
+        ],
+        challenge_id: ChallengeIdSpec {
+            hash: "SHA-256",
+            preimage: vec![
+                "deposit outpoint, consensus encoded (36 bytes)",
+                "amount in sats (u64 little-endian)",
+                "locktime (u32 little-endian)",
+                "challenger public key (33 bytes compressed)",
+                "challenger public key hash (20 bytes)",
+                "third rank commitments (2 x 33 bytes compressed)",
+            ],
+            tag: String::from_utf8_lossy(CHALLENGE_ID_TAG).into_owned(),
+        },
+        challenge_output: ChallengeOutputSpec {
+            output: "output 0 of the challenge transaction, P2WSH of the witness script",
+            miniscript: script.miniscript,
+            witness_script: script.asm,
+        },
+        witness_stacks: vec![
+            WitnessStackSpec {
+                input: "P2WPKH inputs",
+                stack: vec!["<signature>", "<public key>"],
+            },
+            WitnessStackSpec {
+                input: "challenge output, acceptor branch",
+                stack: SpendingBranch::Acceptor.witness_stack_template().to_vec(),
+            },
+            WitnessStackSpec {
+                input: "challenge output, challenger branch",
+                stack: SpendingBranch::Challenger.witness_stack_template().to_vec(),
+            },
+        ],
+        sighashes: vec![
+            SighashSpec {
+                transaction: "deposit",
+                input: "challenger's inputs",
+                sighash: EcdsaSighashType::All.to_string(),
+            },
+            SighashSpec {
+                transaction: "challenge",
+                input: "acceptor's inputs",
+                sighash: all_or_anyonecanpay,
+            },
+            SighashSpec {
+                transaction: "challenge",
+                input: "deposit, signed with P_c + A",
+                sighash: format!(
+                    "{}, or {} when signed for a fee sponsor",
+                    EcdsaSighashType::All,
+                    EcdsaSighashType::AllPlusAnyoneCanPay
+                ),
+            },
+            SighashSpec {
+                transaction: "challenge",
+                input: "challenger's fee inputs and the sponsor's input",
+                sighash: EcdsaSighashType::All.to_string(),
+            },
+            SighashSpec {
+                transaction: "refund",
+                input: "deposit, signed with P_c + A",
+                sighash: EcdsaSighashType::All.to_string(),
+            },
+            SighashSpec {
+                transaction: "sweep",
+                input: "challenge output",
+                sighash: EcdsaSighashType::All.to_string(),
+            },
+        ],
+        public_inputs: vec![
+            public_inputs_spec(&challenger_circuit_abi()),
+            public_inputs_spec(&acceptor_circuit_abi()),
+        ],
+    })
+}
+
+fn public_inputs_spec(abi: &CircuitAbi) -> PublicInputsSpec {
+    PublicInputsSpec {
+        circuit: abi.circuit(),
+        inputs: abi.inputs().to_vec(),
+    }
+}
+
+/// Challenge message with every optional field set
+fn sample_challenge() -> PublicChallengerData {
+    PublicChallengerData {
+        id: String::new(),
+        amount: Amount::ZERO,
+        deposit_outpoint: OutPoint::new(Txid::all_zeros(), 0),
+        third_rank_commitments: [String::new(), String::new()],
+        challenger_pubkey: String::new(),
+        challenger_pubkey_hash: String::new(),
+        proof: String::new(),
+        vk: String::new(),
+        locktime: 0,
+        deposit_broadcast: false,
+        protocol: ProtocolInfo::local(),
+        acceptance_deadline: Some(0),
+        beacon: Some(BeaconParams {
+            anchor_txid: Some(Txid::all_zeros()),
+            ..BeaconParams::new(BeaconSource::Drand { round: 0 }, &[0; 32])
+        }),
+        circuit_hash: Some(String::new()),
+    }
+}
+
+/// Acceptance message with every optional field set
+fn sample_acceptance() -> AcceptorData {
+    AcceptorData {
+        id: String::new(),
+        acceptor_pubkey_hash: String::new(),
+        third_rank_commitments: [String::new(), String::new()],
+        psbt: String::new(),
+        challenge_output_witness_script: String::new(),
+        proof: String::new(),
+        vk: String::new(),
+        protocol: ProtocolInfo::local(),
+        completion_deadline: Some(0),
+        circuit_hash: Some(String::new()),
+    }
+}
+
+/// Derives the JSON schema of a message from a sample with every optional field set. A field
+/// is required if the message can't be parsed without it.
+fn message_schema<T: Serialize + DeserializeOwned>(sample: &T) -> eyre::Result<Value> {
+    let Value::Object(fields) = serde_json::to_value(sample)? else {
+        bail!("Protocol messages are JSON objects");
+    };
+
+    let required = fields
+        .keys()
+        .filter(|field| {
+            let mut partial = fields.clone();
+            partial.remove(*field);
+            serde_json::from_value::<T>(Value::Object(partial)).is_err()
+        })
+        .collect::<Vec<_>>();
+
+    let mut schema = value_schema(&Value::Object(fields.clone()));
+    schema["required"] = json!(required);
+
+    Ok(schema)
+}
+
+/// Returns the schema of a sample value. Arrays in protocol messages have a fixed length.
+fn value_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_u64() => json!({ "type": "integer", "minimum": 0 }),
+        Value::Number(number) if number.is_i64() => json!({ "type": "integer" }),
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => json!({
+            "type": "array",
+            "items": items.first().map(value_schema).unwrap_or_default(),
+            "minItems": items.len(),
+            "maxItems": items.len(),
+        }),
+        Value::Object(fields) => json!({
+            "type": "object",
+            "properties": fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value_schema(value)))
+                .collect::<Map<_, _>>(),
+            "required": fields.keys().collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Renders the specification as a Markdown document
+fn markdown(spec: &Spec) -> String {
+    let mut out = String::new();
+
+    // Writing to a `String` can't fail
+    let _ = writeln!(out, "# op_rand protocol specification\n");
+    let _ = writeln!(out, "Generated by {}.\n", spec.implementation);
+
+    let _ = writeln!(out, "## Protocol\n");
+    let _ = writeln!(
+        out,
+        "Version {}, compatible down to version {}.\n",
+        spec.protocol.version, spec.protocol.min_version
+    );
+    let _ = writeln!(out, "| Capability | Bit | Supported |\n|---|---|---|");
+    for capability in &spec.protocol.capabilities {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} |",
+            capability.name,
+            capability.bit,
+            spec.protocol.supported.contains(&capability.name)
+        );
+    }
+
+    let _ = writeln!(out, "\n## Messages");
+    for message in &spec.messages {
+        let _ = writeln!(
+            out,
+            "\n### {} (`{}`, at most {} bytes)\n",
+            message.kind, message.file, message.max_size
+        );
+        let _ = writeln!(
+            out,
+            "```json\n{}\n```",
+            serde_json::to_string_pretty(&message.schema).unwrap_or_default()
+        );
+    }
+
+    let _ = writeln!(out, "\n## Challenge ID\n");
+    let _ = writeln!(
+        out,
+        "Hex encoded {} of the tag `{}` followed by:\n",
+        spec.challenge_id.hash, spec.challenge_id.tag
+    );
+    for field in &spec.challenge_id.preimage {
+        let _ = writeln!(out, "- {}", field);
+    }
+
+    let _ = writeln!(out, "\n## Challenge output\n");
+    let _ = writeln!(
+        out,
+        "The challenge output is {}.\n",
+        spec.challenge_output.output
+    );
+    let _ = writeln!(
+        out,
+        "- Miniscript: `{}`\n- Witness script: `{}`",
+        spec.challenge_output.miniscript, spec.challenge_output.witness_script
+    );
+
+    let _ = writeln!(out, "\n## Witness stacks\n");
+    let _ = writeln!(out, "| Input | Stack (bottom first) |\n|---|---|");
+    for witness in &spec.witness_stacks {
+        let _ = writeln!(out, "| {} | `{}` |", witness.input, witness.stack.join(" "));
+    }
+
+    let _ = writeln!(out, "\n## Signature hashes\n");
+    let _ = writeln!(
+        out,
+        "All inputs are segwit v0 and signed with BIP 143 signature hashes.\n"
+    );
+    let _ = writeln!(out, "| Transaction | Input | Sighash type |\n|---|---|---|");
+    for sighash in &spec.sighashes {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} |",
+            sighash.transaction,
+            sighash.input,
+            sighash.sighash.replace('|', "\\|")
+        );
+    }
+
+    let _ = writeln!(out, "\n## Circuit public inputs\n");
+    let _ = writeln!(
+        out,
+        "Every byte is a separate public input, encoded as a 32-byte big-endian field element. \
+         The public inputs lead the proof in order."
+    );
+    for circuit in &spec.public_inputs {
+        let _ = writeln!(out, "\n### {}\n", circuit.circuit);
+        let _ = writeln!(out, "| Input | Offset | Size |\n|---|---|---|");
+        for input in &circuit.inputs {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                input.name, input.offset, input.size
+            );
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_spec() {
+        insta::assert_snapshot!(markdown(&spec().unwrap()));
+    }
+}
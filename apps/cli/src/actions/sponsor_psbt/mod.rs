@@ -0,0 +1,201 @@
+use base64::{Engine as _, engine::general_purpose};
+use bitcoin::{
+    Address, Amount, CompressedPublicKey, OutPoint, Psbt, TxOut, Txid, address::NetworkUnchecked,
+    consensus,
+};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, ensure};
+use op_rand_transaction_builder::Sponsorship;
+
+use crate::{
+    context::Context,
+    format::{parse_output, parse_sats},
+    limits::{MAX_PSBT_LEN, read_bounded},
+    render::Renderer,
+    store::BroadcastKind,
+    ui::{CHAIN, GEAR, RADIO},
+};
+
+#[derive(Args, Debug)]
+pub struct SponsorPsbtArgs {
+    /// Path to the challenge PSBT signed for the sponsor by `complete-challenge --sponsor-psbt`
+    #[clap(long)]
+    pub psbt: String,
+
+    /// Wallet UTXO (`txid:vout`) paying the fees
+    #[clap(long)]
+    pub utxo: OutPoint,
+
+    /// Change output (`ADDRESS:SATS`) the acceptor added for the sponsor
+    #[clap(long, value_parser = parse_output)]
+    pub change: (Address<NetworkUnchecked>, Amount),
+
+    /// Refuse to pay more fees than this. The maximum fee of the fee policy by default.
+    #[clap(long, value_parser = parse_sats)]
+    pub max_fee: Option<Amount>,
+
+    /// Broadcast the challenge transaction
+    #[clap(long)]
+    pub broadcast: bool,
+}
+
+/// Challenge transaction finalized by the sponsor
+struct Sponsored {
+    txid: Txid,
+    sponsor_fee: Amount,
+    /// Fee of the whole transaction, if the PSBT has the values of all other inputs
+    total_fee: Option<Amount>,
+    raw_tx: String,
+    broadcast: bool,
+}
+
+pub async fn run(
+    SponsorPsbtArgs {
+        psbt,
+        utxo,
+        change,
+        max_fee,
+        broadcast,
+    }: SponsorPsbtArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🎁", "SPONSORING CHALLENGE");
+    r.step(GEAR, "Loading the challenge PSBT...");
+
+    let psbt_base64 = read_bounded(&psbt, MAX_PSBT_LEN as u64)?;
+    let psbt = Psbt::deserialize(&general_purpose::STANDARD.decode(psbt_base64.trim())?)?;
+
+    let cfg = ctx.config()?;
+    let esplora_client = ctx.esplora_client()?;
+    let transaction_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let fee_policy = ctx.fee_policy()?;
+    let address = Address::p2wpkh(
+        &CompressedPublicKey::from_private_key(ctx.secp_ctx(), &cfg.private_key)?,
+        cfg.network,
+    );
+
+    r.step(CHAIN, "Checking the sponsor UTXO...");
+
+    let funding = esplora_client
+        .get_transaction(&utxo.txid)
+        .await?
+        .output
+        .get(utxo.vout as usize)
+        .cloned()
+        .ok_or_eyre("Sponsor UTXO not found")?;
+    ensure!(
+        funding.script_pubkey == address.script_pubkey(),
+        "Sponsor UTXO doesn't belong to the wallet"
+    );
+
+    let (change_address, change_value) = change;
+    let sponsorship = Sponsorship::new(
+        utxo,
+        funding.value,
+        TxOut {
+            value: change_value,
+            script_pubkey: change_address.require_network(cfg.network)?.script_pubkey(),
+        },
+    );
+    let sponsor_fee = sponsorship.fee()?;
+    let max_fee = max_fee.unwrap_or(fee_policy.max_fee);
+    ensure!(
+        sponsor_fee <= max_fee,
+        "Sponsoring costs {}, more than the maximum fee of {}",
+        sponsor_fee,
+        max_fee
+    );
+
+    r.step(GEAR, "Signing the sponsor input...");
+
+    let input_total = psbt
+        .inputs
+        .iter()
+        .map(|input| input.witness_utxo.as_ref().map(|utxo| utxo.value))
+        .sum::<Option<Amount>>();
+    let transaction = transaction_builder.sponsor_challenge_tx(psbt, &sponsorship)?;
+    let output_total = transaction
+        .output
+        .iter()
+        .map(|output| output.value)
+        .sum::<Amount>();
+    let total_fee = input_total
+        .and_then(|total| total.checked_add(funding.value))
+        .and_then(|total| total.checked_sub(output_total));
+
+    let raw_tx = consensus::encode::serialize_hex(&transaction);
+    if broadcast {
+        r.step(RADIO, "Broadcasting challenge transaction...");
+
+        // Recorded before it is broadcast, so that it can be rebroadcast. The challenge is only
+        // known when the sponsor is also one of its parties.
+        let deposit_outpoint = transaction.input.first().map(|input| input.previous_output);
+        let challenge_id = store
+            .challenges
+            .iter()
+            .find(|(_, record)| {
+                deposit_outpoint.is_some() && record.deposit_outpoint == deposit_outpoint
+            })
+            .map(|(id, _)| id.clone())
+            .unwrap_or_default();
+        store.record_transaction(&challenge_id, BroadcastKind::Challenge, &raw_tx)?;
+        store.save()?;
+
+        esplora_client.broadcast_transaction(&raw_tx).await?;
+        r.done("Challenge transaction broadcasted!");
+    }
+
+    render_sponsorship(
+        r.as_mut(),
+        &Sponsored {
+            txid: transaction.compute_txid(),
+            sponsor_fee,
+            total_fee,
+            raw_tx,
+            broadcast,
+        },
+    );
+    r.success("CHALLENGE SPONSORED SUCCESSFULLY!");
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the sponsored challenge transaction
+fn render_sponsorship(r: &mut dyn Renderer, sponsored: &Sponsored) {
+    r.section("TRANSACTION DETAILS");
+    r.field("Challenge TXID", sponsored.txid.to_string());
+    r.field("Sponsor Fee", sponsored.sponsor_fee);
+    if let Some(total_fee) = sponsored.total_fee {
+        r.field("Total Fee", total_fee);
+    }
+    r.field("Broadcast", sponsored.broadcast);
+    r.field("Challenge Transaction", &sponsored.raw_tx);
+
+    if !sponsored.broadcast {
+        r.warning("Share the challenge transaction with both parties or broadcast it");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_sponsorship() {
+        let sponsored = Sponsored {
+            txid: Txid::from_byte_array([7; 32]),
+            sponsor_fee: Amount::from_sat(1_500),
+            total_fee: Some(Amount::from_sat(1_800)),
+            raw_tx: "0200000000".to_string(),
+            broadcast: false,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_sponsorship(r, &sponsored)));
+    }
+}
@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use bitcoin::{
+    Address, PrivateKey, Transaction, Txid,
+    absolute::{Height, LockTime},
+    address::NetworkUnchecked,
+    consensus::Decodable,
+    secp256k1::{All, Secp256k1},
+};
+use clap::{ArgGroup, Args};
+use color_eyre::eyre::{self, OptionExt, bail, ensure};
+use op_rand_transaction_builder::{
+    Payout, PayoutShare, SpendingBranch, SpendingInstructions, TransactionKind,
+    parse_challenge_witness_script,
+};
+use op_rand_types::{ct_eq, parse_public_key_strict};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    actions::{accept_challenge::AcceptorData, create_challenge::PublicChallengerData},
+    context::Context,
+    format::parse_payout,
+    limits::{BoundedMessage, load_message},
+    store::{BroadcastKind, MessageKind, Role, Store},
+    ui::{CHAIN, GEAR, RADIO},
+    util::recipient_script,
+};
+
+#[derive(Args, Debug)]
+#[clap(group(
+    ArgGroup::new("role")
+        .args(&["challenger", "acceptor"])
+        .multiple(false),
+))]
+pub struct SweepArgs {
+    /// Challenge ID. Can be omitted when the challenge file is given
+    #[clap(required_unless_present = "challenge_file")]
+    pub id: Option<String>,
+
+    /// Challenge transaction hex, instead of looking it up on chain
+    #[clap(long)]
+    pub challenge_tx: Option<String>,
+
+    /// Recipient address, e.g. P2TR, P2WSH or legacy. Must belong to the configured network
+    #[clap(long, conflicts_with = "recipient_pubkey")]
+    pub recipient: Option<Address<NetworkUnchecked>>,
+
+    /// Recipient public key, paid to with P2WPKH. The wallet key by default
+    #[clap(long)]
+    pub recipient_pubkey: Option<String>,
+
+    /// Pay part of the sweep to `ADDRESS:SATS` or `ADDRESS:PERCENT%`, the percentage being of the
+    /// swept amount after fees. Can be repeated. The rest goes to the recipient.
+    #[clap(long = "payout", value_parser = parse_payout)]
+    pub payouts: Vec<(Address<NetworkUnchecked>, PayoutShare)>,
+
+    /// Path to the challenge JSON file, instead of the challenge message in the local store
+    #[clap(long)]
+    pub challenge_file: Option<String>,
+
+    /// Path to the acceptor JSON file, instead of the acceptance message in the local store
+    #[clap(long)]
+    pub acceptor_file: Option<String>,
+
+    /// Sweep as the challenger, overriding the role recorded in the local store
+    #[clap(long)]
+    pub challenger: bool,
+
+    /// Sweep as the acceptor, overriding the role recorded in the local store
+    #[clap(long)]
+    pub acceptor: bool,
+}
+
+pub async fn run(
+    SweepArgs {
+        id,
+        challenge_tx,
+        recipient,
+        recipient_pubkey,
+        payouts,
+        challenge_file,
+        acceptor_file,
+        challenger,
+        acceptor,
+    }: SweepArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🧹", "SWEEP");
+    r.step(GEAR, "Loading challenge data...");
+
+    let cfg = ctx.config()?;
+    let network = cfg.network;
+    let esplora_client = ctx.esplora_client()?;
+    let tx_builder = ctx.transaction_builder()?;
+    let mut store = ctx.store()?;
+    let fee_policy = ctx.fee_policy()?;
+    let secp = ctx.secp_ctx();
+
+    let challenger_data: PublicChallengerData = load_challenge_message(
+        &store,
+        id.as_deref(),
+        MessageKind::Challenge,
+        challenge_file.as_deref(),
+    )?;
+    if let Some(id) = &id {
+        ensure!(
+            *id == challenger_data.id,
+            "Challenge file is for challenge {}, not {}",
+            challenger_data.id,
+            id
+        );
+    }
+    let id = challenger_data.id.clone();
+    let acceptor_data: AcceptorData = load_challenge_message(
+        &store,
+        Some(&id),
+        MessageKind::Acceptance,
+        acceptor_file.as_deref(),
+    )?;
+
+    r.field("Challenge ID", &id);
+
+    r.step(CHAIN, "Looking up challenge transaction...");
+
+    let challenge_transaction = match challenge_tx {
+        Some(challenge_tx) => {
+            let challenge_tx_bytes = hex::decode(&challenge_tx)?;
+            Transaction::consensus_decode(&mut challenge_tx_bytes.as_slice())?
+        }
+        None => {
+            let deposit = challenger_data.deposit_outpoint;
+            let outspend = esplora_client
+                .get_outspend(&deposit.txid, deposit.vout)
+                .await?;
+            ensure!(
+                outspend.spent,
+                "Challenge transaction was not broadcast yet"
+            );
+            let challenge_txid = Txid::from_str(
+                outspend
+                    .txid
+                    .as_deref()
+                    .ok_or_eyre("Esplora didn't return the spending transaction")?,
+            )?;
+            esplora_client.get_transaction(&challenge_txid).await?
+        }
+    };
+    let challenge_txid = challenge_transaction.compute_txid();
+
+    r.field("Challenge TXID", challenge_txid.to_string());
+
+    if esplora_client.has_transaction(&challenge_txid).await? {
+        let outspend = esplora_client.get_outspend(&challenge_txid, 0).await?;
+        if outspend.spent {
+            bail!(
+                "Challenge output was already swept by {}",
+                outspend.txid.as_deref().unwrap_or("an unknown transaction")
+            );
+        }
+    }
+
+    // An explicit flag wins over the recorded role. Without either, the role is the branch the
+    // wallet key can sign for.
+    let witness_script =
+        parse_challenge_witness_script(&acceptor_data.challenge_output_witness_script)?;
+    let recorded_role = store.challenges.get(&id).map(|record| record.role);
+    let role = if challenger {
+        Role::Challenger
+    } else if acceptor {
+        Role::Acceptor
+    } else if let Some(role) = recorded_role {
+        role
+    } else if can_sign(
+        secp,
+        &cfg.private_key,
+        &SpendingInstructions::new(
+            &challenge_transaction,
+            &witness_script,
+            SpendingBranch::Challenger,
+        )?,
+    )? {
+        Role::Challenger
+    } else {
+        Role::Acceptor
+    };
+    if recorded_role.is_some_and(|recorded| recorded != role) {
+        r.warning("Sweeping with the other role than the one recorded in the local store");
+    }
+
+    let branch = match role {
+        Role::Challenger => SpendingBranch::Challenger,
+        Role::Acceptor => SpendingBranch::Acceptor,
+    };
+    let instructions = SpendingInstructions::new(&challenge_transaction, &witness_script, branch)?;
+    let lock_time = LockTime::Blocks(Height::from_consensus(challenger_data.locktime)?);
+
+    r.field(
+        "Role",
+        match role {
+            Role::Challenger => "challenger",
+            Role::Acceptor => "acceptor",
+        },
+    );
+
+    match role {
+        Role::Challenger => {
+            ensure!(
+                can_sign(secp, &cfg.private_key, &instructions)?,
+                "This wallet's key is not the challenger key of the challenge"
+            );
+            let tip_height = esplora_client.get_tip_height().await?;
+            ensure!(
+                tip_height >= u64::from(challenger_data.locktime),
+                "The challenger can sweep the output only from block {}, the tip is at block {}",
+                challenger_data.locktime,
+                tip_height
+            );
+        }
+        Role::Acceptor => {
+            ensure!(
+                can_sign(secp, &cfg.private_key, &instructions)?,
+                "This wallet didn't win the challenge, the challenger can sweep the output from \
+                 block {}",
+                challenger_data.locktime
+            );
+        }
+    }
+
+    let payouts = payouts
+        .into_iter()
+        .map(|(address, share)| {
+            Ok(Payout::new(
+                address.require_network(network)?.script_pubkey(),
+                share,
+            ))
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    let fee_amount = fee_policy.fee(TransactionKind::Sweep {
+        outputs: payouts.len() + 1,
+    })?;
+
+    let recipient = recipient_script(recipient, recipient_pubkey.as_deref(), network)?;
+
+    r.step(GEAR, "Creating sweep transaction...");
+
+    let sweep_tx = match role {
+        Role::Challenger => tx_builder.sweep_challenge_output_challenger_with_payouts(
+            &challenge_transaction,
+            &witness_script,
+            lock_time,
+            &payouts,
+            recipient,
+            fee_amount,
+        )?,
+        Role::Acceptor => {
+            let challenger_pubkey = bitcoin::PublicKey::new(parse_public_key_strict(
+                "challenger public key",
+                &challenger_data.challenger_pubkey,
+            )?);
+
+            tx_builder.sweep_challenge_output_acceptor_with_payouts(
+                &challenge_transaction,
+                &challenger_pubkey,
+                &witness_script,
+                &payouts,
+                recipient,
+                fee_amount,
+            )?
+        }
+    };
+    fee_policy.relay.check_standard(&sweep_tx, fee_amount)?;
+
+    r.done("Sweep transaction created!");
+    r.field("Sweep TXID", sweep_tx.compute_txid().to_string());
+    r.step(RADIO, "Broadcasting sweep transaction...");
+
+    let sweep_tx_hex = bitcoin::consensus::encode::serialize_hex(&sweep_tx);
+    store.record_transaction(&id, BroadcastKind::Sweep, &sweep_tx_hex)?;
+    store.save()?;
+
+    esplora_client.broadcast_transaction(&sweep_tx_hex).await?;
+
+    r.success("Sweep transaction broadcasted successfully!");
+    r.finish();
+
+    Ok(())
+}
+
+/// Loads a protocol message of the challenge from `file` if given, from the local store otherwise
+fn load_challenge_message<T: DeserializeOwned + BoundedMessage>(
+    store: &Store,
+    challenge_id: Option<&str>,
+    kind: MessageKind,
+    file: Option<&str>,
+) -> eyre::Result<T> {
+    if let Some(file) = file {
+        return load_message(file);
+    }
+
+    let Some(message) = challenge_id.and_then(|id| store.find_message(id, kind)) else {
+        bail!(
+            "No {} message of the challenge in the local store, pass its file instead",
+            kind.as_str()
+        );
+    };
+
+    Ok(serde_json::from_str(&message.content)?)
+}
+
+/// Returns whether the wallet key, tweaked for the Acceptor, is the key the challenge output
+/// must be signed with
+fn can_sign(
+    secp: &Secp256k1<All>,
+    private_key: &PrivateKey,
+    instructions: &SpendingInstructions,
+) -> eyre::Result<bool> {
+    let signing_key = match instructions.tweak {
+        Some(tweak) => PrivateKey::new(
+            private_key.inner.add_tweak(&tweak.into())?,
+            private_key.network,
+        ),
+        None => *private_key,
+    };
+
+    Ok(ct_eq(
+        &signing_key.public_key(secp).to_bytes(),
+        &instructions.signing_pubkey.to_bytes(),
+    ))
+}
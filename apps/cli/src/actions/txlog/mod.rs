@@ -0,0 +1,154 @@
+use bitcoin::{Transaction, Txid, consensus};
+use clap::Args;
+use color_eyre::eyre::{self, ensure};
+
+use crate::{context::Context, render::Renderer, store::BroadcastKind, ui::CHAIN};
+
+#[derive(Args, Debug)]
+pub struct TxlogArgs {
+    /// Challenge ID
+    pub id: String,
+}
+
+/// Inclusion status of a logged transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inclusion {
+    Confirmed(u64),
+    Mempool,
+    Unknown,
+}
+
+/// A logged transaction with its live status
+struct LogEntry {
+    txid: Txid,
+    kind: Option<BroadcastKind>,
+    recorded_at: Option<u64>,
+    raw_tx: String,
+    /// Whether the raw hex hashes to the recorded txid
+    verified: bool,
+    inclusion: Inclusion,
+}
+
+pub async fn run(TxlogArgs { id }: TxlogArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    let mut store = ctx.store()?;
+    let esplora_client = ctx.esplora_client()?;
+
+    let transactions = store.challenge_transactions(&id);
+    ensure!(
+        !transactions.is_empty(),
+        "No transactions were broadcast for this challenge"
+    );
+
+    r.step(CHAIN, "Checking transaction status...");
+
+    let mut entries = Vec::with_capacity(transactions.len());
+    for (txid, record) in transactions {
+        let verified = consensus::encode::deserialize_hex::<Transaction>(&record.raw_tx)
+            .is_ok_and(|tx| tx.compute_txid() == *txid);
+
+        let inclusion = if esplora_client.has_transaction(txid).await? {
+            match esplora_client.get_tx_status(txid).await?.block_height {
+                Some(height) => Inclusion::Confirmed(height),
+                None => Inclusion::Mempool,
+            }
+        } else {
+            Inclusion::Unknown
+        };
+
+        entries.push(LogEntry {
+            txid: *txid,
+            kind: record.kind,
+            recorded_at: record.recorded_at,
+            raw_tx: record.raw_tx.clone(),
+            verified,
+            inclusion,
+        });
+    }
+
+    // Keep the last seen inclusion height, so the log stays useful offline
+    for entry in &entries {
+        if let Some(record) = store.transactions.get_mut(&entry.txid) {
+            record.block_height = match entry.inclusion {
+                Inclusion::Confirmed(height) => Some(height),
+                Inclusion::Mempool | Inclusion::Unknown => None,
+            };
+        }
+    }
+    store.save()?;
+
+    render_txlog(r.as_mut(), &id, &entries);
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the transactions broadcast for a challenge
+fn render_txlog(r: &mut dyn Renderer, id: &str, entries: &[LogEntry]) {
+    r.header("🧾", "TRANSACTION LOG");
+    r.field("Challenge ID", id);
+    r.field("Transactions", entries.len());
+
+    r.section("TRANSACTIONS");
+    for entry in entries {
+        r.entry(&entry.txid.to_string());
+        r.field("TXID", entry.txid.to_string());
+        r.field("Kind", entry.kind.map_or("unknown", |kind| kind.as_str()));
+        if let Some(recorded_at) = entry.recorded_at {
+            r.field("Recorded At", recorded_at);
+        }
+        match entry.inclusion {
+            Inclusion::Confirmed(height) => {
+                r.field("Status", "confirmed");
+                r.field("Block Height", height);
+            }
+            Inclusion::Mempool => r.field("Status", "in the mempool"),
+            Inclusion::Unknown => r.field("Status", "not found"),
+        }
+        r.field("Verified", entry.verified);
+        r.field("Raw Transaction", &entry.raw_tx);
+    }
+
+    for entry in entries.iter().filter(|entry| !entry.verified) {
+        r.warning(&format!(
+            "Raw transaction of {} doesn't match its txid",
+            entry.txid
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_txlog() {
+        let raw_tx = "0200000001a1075db55d416d3ca199f55b6084e2115b9345e16c5cf302fc80e9d5fbf5d48d0000000000fdffffff0110270000000000001600147e5ae6c2a2b0e4e2c1c26fdb1a8e8b3a0b4e7f4c00000000";
+        let txid = consensus::encode::deserialize_hex::<Transaction>(raw_tx)
+            .unwrap()
+            .compute_txid();
+        let entries = [
+            LogEntry {
+                txid,
+                kind: Some(BroadcastKind::Deposit),
+                recorded_at: Some(1_760_000_000),
+                raw_tx: raw_tx.to_string(),
+                verified: true,
+                inclusion: Inclusion::Confirmed(840_000),
+            },
+            LogEntry {
+                txid: Txid::from_byte_array([7; 32]),
+                kind: None,
+                recorded_at: None,
+                raw_tx: raw_tx.to_string(),
+                verified: false,
+                inclusion: Inclusion::Unknown,
+            },
+        ];
+
+        insta::assert_snapshot!(render_all(|r| render_txlog(r, "challenge-1", &entries)));
+    }
+}
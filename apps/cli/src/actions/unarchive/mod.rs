@@ -0,0 +1,40 @@
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, ensure};
+
+use crate::context::Context;
+
+#[derive(Args, Debug)]
+pub struct UnarchiveArgs {
+    /// Challenge ID
+    pub id: String,
+}
+
+pub async fn run(UnarchiveArgs { id }: UnarchiveArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut store = ctx.store()?;
+    let mut archive = ctx.archive()?;
+
+    ensure!(
+        !store.challenges.contains_key(&id),
+        "Challenge is already in the local store"
+    );
+    let archived = archive
+        .challenges
+        .remove(&id)
+        .ok_or_eyre("Challenge not found in the archive")?;
+    let proofs_dropped = archived.proofs_dropped;
+    archived.restore(&mut store, &id);
+
+    // The store is saved first, so that an interrupted run leaves the challenge in both files
+    // rather than in neither
+    store.save()?;
+    archive.save()?;
+
+    let mut r = ctx.renderer();
+    if proofs_dropped {
+        r.warning("The proofs of the challenge were dropped from its messages when archived");
+    }
+    r.success(&format!("Challenge {} moved back to the local store", id));
+    r.finish();
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use bitcoin::OutPoint;
+use clap::{Args, Subcommand};
+use color_eyre::eyre;
+
+use crate::context::Context;
+
+#[derive(Args, Debug)]
+pub struct UtxoArgs {
+    #[command(subcommand)]
+    pub command: UtxoCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UtxoCommand {
+    /// Attach a label to a UTXO
+    Label {
+        /// UTXO outpoint (`txid:vout`)
+        outpoint: OutPoint,
+
+        /// Label text
+        label: String,
+    },
+
+    /// Freeze a UTXO so it's never used to fund a challenge
+    Freeze {
+        /// UTXO outpoint (`txid:vout`)
+        outpoint: OutPoint,
+    },
+
+    /// Unfreeze a previously frozen UTXO
+    Unfreeze {
+        /// UTXO outpoint (`txid:vout`)
+        outpoint: OutPoint,
+    },
+}
+
+pub async fn run(UtxoArgs { command }: UtxoArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut store = ctx.store()?;
+
+    let message = match command {
+        UtxoCommand::Label { outpoint, label } => {
+            store.utxos.entry(outpoint).or_default().label = Some(label.clone());
+            format!("UTXO {} labeled \"{}\"", outpoint, label)
+        }
+        UtxoCommand::Freeze { outpoint } => {
+            store.utxos.entry(outpoint).or_default().frozen = true;
+            format!("UTXO {} frozen", outpoint)
+        }
+        UtxoCommand::Unfreeze { outpoint } => {
+            if let Some(record) = store.utxos.get_mut(&outpoint) {
+                record.frozen = false;
+                if record.label.is_none() {
+                    store.utxos.remove(&outpoint);
+                }
+            }
+            format!("UTXO {} unfrozen", outpoint)
+        }
+    };
+
+    store.save()?;
+
+    let mut r = ctx.renderer();
+    r.success(&message);
+    r.finish();
+
+    Ok(())
+}
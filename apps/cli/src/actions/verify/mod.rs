@@ -0,0 +1,68 @@
+use clap::Args;
+use color_eyre::eyre;
+
+use crate::{
+    actions::create_challenge::PublicChallengerData,
+    context::Context,
+    limits::load_message,
+    render::Renderer,
+    ui::SHIELD,
+    verification::{VerificationContext, render_report, verify_challenge},
+};
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to the challenge JSON file
+    #[clap(long, default_value = "challenger.json")]
+    pub challenge_file: String,
+
+    /// Only run the checks that don't query the chain
+    #[clap(long)]
+    pub offline: bool,
+}
+
+pub async fn run(
+    VerifyArgs {
+        challenge_file,
+        offline,
+    }: VerifyArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🔍", "CHALLENGE VERIFICATION");
+
+    let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+    r.field("Challenge ID", &challenge_data.id);
+
+    let cfg = ctx.config()?;
+    let esplora_client = if offline {
+        None
+    } else {
+        Some(ctx.esplora_client()?)
+    };
+    let prover = ctx.prover()?;
+    let public_key = cfg.private_key.public_key(ctx.secp_ctx());
+
+    r.step(SHIELD, "Running every check on the challenge...");
+
+    let pb = r.progress("Verifying the challenge...");
+    let report = verify_challenge(
+        &challenge_data,
+        &VerificationContext {
+            prover,
+            esplora_client: esplora_client.as_ref(),
+            spv_network: cfg.spv.then_some(cfg.network),
+            acceptor_pubkey: Some(public_key.inner),
+        },
+    )
+    .await;
+    pb.finish_with_message("Challenge checked");
+
+    render_report(r.as_mut(), &report);
+    if report.passed() {
+        r.success("Challenge passed every check");
+    }
+    r.finish();
+
+    report.ensure_passed()
+}
@@ -0,0 +1,232 @@
+use std::str::FromStr;
+
+use bitcoin::{PublicKey, Txid, hashes::sha256};
+use clap::Args;
+use color_eyre::eyre::{self, OptionExt, ensure};
+use op_rand_transaction_builder::beacon_anchor_script;
+use op_rand_types::{
+    BEACON_SELECTION, BeaconSource, ThirdRankCommitment, beacon_anchor, beacon_salt_commitment,
+    beacon_selection, parse_public_key_strict,
+};
+
+use crate::{
+    actions::create_challenge::PublicChallengerData,
+    context::Context,
+    limits::load_message,
+    render::Renderer,
+    ui::CHAIN,
+    util::{confirmed_before_beacon, fetch_beacon_value},
+};
+
+#[derive(Args, Debug)]
+pub struct VerifyBeaconArgs {
+    /// Path to the challenge JSON file
+    #[clap(long, default_value = "challenger.json")]
+    pub challenge_file: String,
+
+    /// Beacon salt revealed by the challenger, hex encoded
+    #[clap(long)]
+    pub salt: String,
+}
+
+/// Result of checking a beacon selection against chain data
+struct BeaconVerification {
+    source: BeaconSource,
+    beacon: String,
+    salt_matches: bool,
+    /// Whether the anchor transaction commits to the challenge commitments and funds the deposit
+    anchor_matches: bool,
+    /// Whether the anchor transaction was confirmed before the beacon was published
+    anchored_before_beacon: bool,
+    /// Commitment the beacon selects
+    expected_index: usize,
+    /// Commitment the challenger revealed in the challenge transaction
+    revealed_index: Option<usize>,
+}
+
+impl BeaconVerification {
+    fn passed(&self) -> bool {
+        self.salt_matches
+            && self.anchor_matches
+            && self.anchored_before_beacon
+            && self.revealed_index == Some(self.expected_index)
+    }
+}
+
+pub async fn run(
+    VerifyBeaconArgs {
+        challenge_file,
+        salt,
+    }: VerifyBeaconArgs,
+    mut ctx: Context,
+) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🎲", "BEACON VERIFICATION");
+
+    let challenge_data: PublicChallengerData = load_message(&challenge_file)?;
+    let params = challenge_data
+        .beacon
+        .as_ref()
+        .ok_or_eyre("Challenge commitment wasn't selected with a beacon")?;
+    ensure!(
+        params.selection == BEACON_SELECTION,
+        "Unknown beacon selection rule: {}",
+        params.selection
+    );
+    let anchor_txid = params.anchor_txid.ok_or_eyre(
+        "Challenge doesn't name its beacon anchor, so the selection can't be audited",
+    )?;
+    let salt: [u8; 32] = hex::decode(&salt)?
+        .try_into()
+        .map_err(|_| eyre::eyre!("Beacon salt must be 32 bytes long"))?;
+
+    let cfg = ctx.config()?;
+    let esplora_client = ctx.esplora_client()?;
+    let secp = ctx.secp_ctx();
+
+    r.step(
+        CHAIN,
+        "Fetching the beacon and the challenge transaction...",
+    );
+
+    let beacon = fetch_beacon_value(
+        &cfg.drand_url,
+        &cfg.drand_chain,
+        &esplora_client,
+        params.source,
+    )
+    .await?
+    .ok_or_eyre("Beacon is not published yet")?;
+
+    // The anchor must commit to the challenge commitments and salt commitment, and be confirmed
+    // before the beacon, so that neither was chosen knowing the beacon value
+    let anchor = beacon_anchor(
+        &params.source,
+        &sha256::Hash::from_str(&params.salt_commitment)?,
+        &[
+            ThirdRankCommitment::from_str_strict(&challenge_data.third_rank_commitments[0])?,
+            ThirdRankCommitment::from_str_strict(&challenge_data.third_rank_commitments[1])?,
+        ],
+    );
+    let anchor_transaction = esplora_client.get_transaction(&anchor_txid).await?;
+    let deposit_transaction = esplora_client
+        .get_transaction(&challenge_data.deposit_outpoint.txid)
+        .await?;
+    let anchor_matches = anchor_transaction
+        .output
+        .iter()
+        .any(|output| output.script_pubkey == beacon_anchor_script(&anchor))
+        && deposit_transaction
+            .input
+            .iter()
+            .any(|input| input.previous_output.txid == anchor_txid);
+    let anchor_status = esplora_client.get_tx_status(&anchor_txid).await?;
+    let anchored_before_beacon = anchor_status.confirmed
+        && confirmed_before_beacon(&anchor_status, &cfg.drand_chain, params.source)?;
+
+    let outspend = esplora_client
+        .get_outspend(
+            &challenge_data.deposit_outpoint.txid,
+            challenge_data.deposit_outpoint.vout,
+        )
+        .await?;
+    ensure!(
+        outspend.spent,
+        "Challenge transaction was not broadcast yet"
+    );
+    let challenge_transaction = esplora_client
+        .get_transaction(&Txid::from_str(
+            outspend
+                .txid
+                .as_deref()
+                .ok_or_eyre("Esplora didn't return the spending transaction")?,
+        )?)
+        .await?;
+
+    // The deposit input reveals `P_c + A_i`, so `A_i` and its third rank commitment follow
+    let revealed_deposit_pubkey = challenge_transaction
+        .input
+        .first()
+        .and_then(|input| input.witness.nth(1))
+        .and_then(|pubkey| PublicKey::from_slice(pubkey).ok())
+        .ok_or_eyre("Challenge transaction doesn't reveal the deposit key")?;
+    let challenger_pubkey =
+        parse_public_key_strict("challenger public key", &challenge_data.challenger_pubkey)?;
+    let first_rank_pk = revealed_deposit_pubkey
+        .inner
+        .combine(&challenger_pubkey.negate(secp))?;
+    let third_rank_pk = hex::encode(
+        ThirdRankCommitment::from_first_rank_public_key(secp, &first_rank_pk)?
+            .inner()
+            .serialize(),
+    );
+
+    r.done("Challenge transaction found");
+
+    let verification = BeaconVerification {
+        source: params.source,
+        beacon: hex::encode(&beacon),
+        salt_matches: beacon_salt_commitment(&salt).to_string() == params.salt_commitment,
+        anchor_matches,
+        anchored_before_beacon,
+        expected_index: beacon_selection(&salt, &beacon),
+        revealed_index: challenge_data
+            .third_rank_commitments
+            .iter()
+            .position(|commitment| *commitment == third_rank_pk),
+    };
+
+    render_verification(r.as_mut(), &verification);
+    r.finish();
+
+    ensure!(
+        verification.passed(),
+        "Commitment selection doesn't follow the beacon"
+    );
+
+    Ok(())
+}
+
+/// Renders the beacon, the commitment it selects and the one the challenger used
+fn render_verification(r: &mut dyn Renderer, verification: &BeaconVerification) {
+    r.field("Beacon", verification.source.to_string());
+    r.field("Beacon Value", &verification.beacon);
+    r.field("Salt Matches Commitment", verification.salt_matches);
+    r.field("Anchor Matches Commitments", verification.anchor_matches);
+    r.field(
+        "Anchored Before Beacon",
+        verification.anchored_before_beacon,
+    );
+    r.field("Selected Commitment", verification.expected_index + 1);
+    match verification.revealed_index {
+        Some(index) => r.field("Revealed Commitment", index + 1),
+        None => r.field("Revealed Commitment", "none of the challenge commitments"),
+    }
+
+    if verification.passed() {
+        r.success("Commitment selection follows the beacon");
+    } else {
+        r.warning("Commitment selection doesn't follow the beacon");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn renders_verification() {
+        let verification = BeaconVerification {
+            source: BeaconSource::Drand { round: 5_000_000 },
+            beacon: "8f".repeat(32),
+            salt_matches: true,
+            anchor_matches: true,
+            anchored_before_beacon: false,
+            expected_index: 1,
+            revealed_index: Some(0),
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_verification(r, &verification)));
+    }
+}
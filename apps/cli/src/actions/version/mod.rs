@@ -0,0 +1,84 @@
+use clap::Args;
+use color_eyre::eyre;
+use op_rand_prover::{BuildInfo, CircuitFingerprint, OpRandProverAsync};
+use op_rand_types::{MIN_PROTOCOL_VERSION, ProtocolInfo};
+
+use crate::{context::Context, render::Renderer, ui::GEAR};
+
+#[derive(Args, Debug)]
+pub struct VersionArgs {
+    /// Also set up the circuits and show the hashes of their bytecode and verification keys,
+    /// to compare with the counterparty's before staking funds
+    #[clap(long)]
+    pub artifacts: bool,
+}
+
+pub async fn run(VersionArgs { artifacts }: VersionArgs, mut ctx: Context) -> eyre::Result<()> {
+    let mut r = ctx.renderer();
+    r.header("🏷️", "OP_RAND VERSION");
+
+    let protocol = ProtocolInfo::local();
+    r.field("Version", env!("CARGO_PKG_VERSION"));
+    r.field("Protocol version", protocol.version);
+    r.field("Min protocol version", MIN_PROTOCOL_VERSION);
+    r.field("Capabilities", protocol.capabilities.to_string());
+
+    if artifacts {
+        r.step(GEAR, "Setting up the circuits...");
+
+        let prover = ctx.prover()?;
+        let pb = r.progress("Setting up the challenger and acceptor circuits...");
+        let challenger = prover.setup_challenger_circuit().await?;
+        let acceptor = prover.setup_acceptor_circuit().await?;
+        pb.finish_with_message("Circuits are set up");
+
+        render_build_info(r.as_mut(), &BuildInfo::new(&challenger, &acceptor)?);
+    }
+
+    r.finish();
+
+    Ok(())
+}
+
+/// Renders the circuit fingerprints of the build
+fn render_build_info(r: &mut dyn Renderer, info: &BuildInfo) {
+    r.section("CIRCUITS");
+    for fingerprint in [&info.challenger, &info.acceptor] {
+        render_fingerprint(r, fingerprint);
+    }
+}
+
+fn render_fingerprint(r: &mut dyn Renderer, fingerprint: &CircuitFingerprint) {
+    r.entry(&format!("{} circuit", fingerprint.circuit));
+    r.field("Circuit", fingerprint.circuit.to_string());
+    r.field("Bytecode sha256", fingerprint.bytecode_hash.to_string());
+    r.field("VK sha256", fingerprint.vk_hash.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+    use bitcoin::hashes::{Hash, sha256};
+    use op_rand_prover::Circuit;
+
+    #[test]
+    fn renders_build_info() {
+        let info = BuildInfo {
+            protocol_version: 2,
+            min_protocol_version: 2,
+            challenger: CircuitFingerprint {
+                circuit: Circuit::Challenger,
+                bytecode_hash: sha256::Hash::hash(b"challenger circuit"),
+                vk_hash: sha256::Hash::hash(b"challenger vk"),
+            },
+            acceptor: CircuitFingerprint {
+                circuit: Circuit::Acceptor,
+                bytecode_hash: sha256::Hash::hash(b"modified acceptor"),
+                vk_hash: sha256::Hash::hash(b"acceptor vk"),
+            },
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_build_info(r, &info)));
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bitcoin::Txid;
+use color_eyre::{eyre, eyre::Context as _};
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{ChallengeRecord, MessageRecord, Store, TransactionRecord};
+
+/// Settled challenges moved out of the state store, persisted as a single gzip-compressed JSON
+/// file. Keeping them apart keeps the store small for wallets that played many games.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Archive {
+    /// Path the archive is loaded from and saved to.
+    #[serde(skip)]
+    path: PathBuf,
+
+    /// Archived challenges keyed by challenge ID.
+    #[serde(default)]
+    pub challenges: BTreeMap<String, ArchivedChallenge>,
+}
+
+/// A challenge with everything the state store recorded for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedChallenge {
+    pub record: ChallengeRecord,
+
+    /// Protocol transactions of the challenge, keyed by txid.
+    #[serde(default)]
+    pub transactions: BTreeMap<Txid, TransactionRecord>,
+
+    /// Messages created for the challenge, keyed by message hash.
+    #[serde(default)]
+    pub outbox: BTreeMap<String, MessageRecord>,
+
+    /// Messages received for the challenge, keyed by message hash.
+    #[serde(default)]
+    pub inbox: BTreeMap<String, MessageRecord>,
+
+    /// Unix time the settling transaction of the challenge was recorded at.
+    pub settled_at: u64,
+
+    /// Whether the proofs were dropped from the messages. Their content no longer matches
+    /// their hash then.
+    #[serde(default)]
+    pub proofs_dropped: bool,
+}
+
+impl Archive {
+    /// Loads the archive from `path`, returning an empty archive if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+
+        let mut archive = if path.exists() {
+            let mut content = String::new();
+            GzDecoder::new(
+                fs::File::open(path)
+                    .wrap_err_with(|| format!("Failed to open archive {}", path.display()))?,
+            )
+            .read_to_string(&mut content)
+            .wrap_err_with(|| format!("Failed to read archive {}", path.display()))?;
+            serde_json::from_str::<Archive>(&content)
+                .wrap_err_with(|| format!("Failed to parse archive {}", path.display()))?
+        } else {
+            Archive::default()
+        };
+
+        archive.path = path.to_path_buf();
+
+        Ok(archive)
+    }
+
+    /// Saves the archive, replacing the file atomically.
+    pub fn save(&self) -> eyre::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&serde_json::to_vec(self)?)?;
+        fs::write(&tmp_path, encoder.finish()?)
+            .wrap_err_with(|| format!("Failed to write archive {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .wrap_err_with(|| format!("Failed to replace archive {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    /// Returns the path the archive is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ArchivedChallenge {
+    /// Moves the challenge and its transactions and messages out of `store`.
+    pub fn take(store: &mut Store, challenge_id: &str, settled_at: u64) -> Option<Self> {
+        let record = store.challenges.remove(challenge_id)?;
+
+        Some(Self {
+            record,
+            transactions: take_where(&mut store.transactions, |transaction| {
+                transaction.challenge_id == challenge_id
+            }),
+            outbox: take_where(&mut store.outbox, |message| {
+                message.challenge_id == challenge_id
+            }),
+            inbox: take_where(&mut store.inbox, |message| {
+                message.challenge_id == challenge_id
+            }),
+            settled_at,
+            proofs_dropped: false,
+        })
+    }
+
+    /// Moves the challenge back into `store`.
+    pub fn restore(self, store: &mut Store, challenge_id: &str) {
+        store
+            .challenges
+            .insert(challenge_id.to_string(), self.record);
+        store.transactions.extend(self.transactions);
+        store.outbox.extend(self.outbox);
+        store.inbox.extend(self.inbox);
+    }
+
+    /// Drops the proofs from the messages of the challenge, keeping the rest of the transcript.
+    pub fn drop_proofs(&mut self) -> eyre::Result<()> {
+        for message in self.outbox.values_mut().chain(self.inbox.values_mut()) {
+            let mut content: serde_json::Value = serde_json::from_str(&message.content)?;
+            if let Some(fields) = content.as_object_mut() {
+                fields.remove("proof");
+            }
+            message.content = serde_json::to_string_pretty(&content)?;
+        }
+        self.proofs_dropped = true;
+
+        Ok(())
+    }
+}
+
+/// Removes the entries of `map` whose value matches `predicate` and returns them.
+fn take_where<K: Ord, V>(
+    map: &mut BTreeMap<K, V>,
+    predicate: impl Fn(&V) -> bool,
+) -> BTreeMap<K, V> {
+    let (taken, kept) = std::mem::take(map)
+        .into_iter()
+        .partition(|(_, value)| predicate(value));
+    *map = kept;
+
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MessageKind;
+
+    #[test]
+    fn drops_only_proofs() {
+        let mut archived = ArchivedChallenge {
+            record: serde_json::from_str(
+                r#"{"role":"acceptor","wallet_pubkey":"","change_script":null}"#,
+            )
+            .unwrap(),
+            transactions: BTreeMap::new(),
+            outbox: BTreeMap::from([(
+                "hash".to_string(),
+                MessageRecord {
+                    challenge_id: "challenge-1".to_string(),
+                    kind: MessageKind::Acceptance,
+                    content: r#"{"id":"challenge-1","proof":"abcd","vk":"ef"}"#.to_string(),
+                    delivered: true,
+                    recorded_at: None,
+                },
+            )]),
+            inbox: BTreeMap::new(),
+            settled_at: 1_760_000_000,
+            proofs_dropped: false,
+        };
+
+        archived.drop_proofs().unwrap();
+
+        let content: serde_json::Value =
+            serde_json::from_str(&archived.outbox["hash"].content).unwrap();
+        assert_eq!(
+            content,
+            serde_json::json!({"id": "challenge-1", "vk": "ef"})
+        );
+        assert!(archived.proofs_dropped);
+    }
+}
@@ -1,16 +1,290 @@
 use std::path::PathBuf;
 
-use bitcoin::{Network, PrivateKey};
-use color_eyre::eyre;
+use bitcoin::{Amount, FeeRate, Network, PrivateKey, ScriptBuf, WPubkeyHash, Weight, hashes::Hash};
+use color_eyre::eyre::{self, OptionExt, ensure};
+use op_rand_transaction_builder::{FeePolicy, PolicyProfile, RelayPolicy};
+use op_rand_types::derive_identity_key;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Clone, Serialize)]
 pub struct Config {
+    /// Wallet key, funding and signing for challenges.
     pub private_key: PrivateKey,
 
+    /// Protocol identity key, authenticating this party towards counterparties and relays. It
+    /// never signs for funds. Derived from `private_key` if unset.
+    pub identity_key: Option<PrivateKey>,
+
     pub esplora_url: String,
 
     pub network: Network,
+
+    /// Path to the local state store.
+    #[serde(default = "default_store_path")]
+    pub store_path: PathBuf,
+
+    /// Directory for cached Esplora responses.
+    #[serde(default = "default_cache_dir")]
+    pub cache_dir: PathBuf,
+
+    /// Verify deposit confirmations with SPV proofs instead of trusting the Esplora server.
+    #[serde(default)]
+    pub spv: bool,
+
+    /// What to do when a wallet key or change address is reused across challenges.
+    #[serde(default)]
+    pub address_reuse: AddressReusePolicy,
+
+    /// Socket of the prover daemon. Proofs are generated in-process when no daemon listens on it.
+    #[serde(default = "default_prover_socket")]
+    pub prover_socket: PathBuf,
+
+    /// HTTP relay of the drand randomness beacon.
+    #[serde(default = "default_drand_url")]
+    pub drand_url: String,
+
+    /// drand chain the beacon signatures are verified against.
+    #[serde(default)]
+    pub drand_chain: DrandChainConfig,
+
+    /// Abort proofs generated in-process that take longer than this many seconds.
+    pub proving_timeout: Option<u64>,
+
+    /// Overrides of the network's default fee policy.
+    #[serde(default)]
+    pub fees: FeeConfig,
+
+    /// Overrides of the network's default relay policy.
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
+    /// Standard stake amounts, so that games can't be told apart by their amount.
+    #[serde(default)]
+    pub denominations: DenominationConfig,
+
+    /// Opt-in reporting of protocol failures.
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Retention of settled challenges in the state store.
+    #[serde(default)]
+    pub archive: ArchiveConfig,
+}
+
+/// Fee policy settings. Unset values fall back to the defaults of the configured network.
+#[derive(Deserialize, Clone, Copy, Serialize, Default, Debug)]
+pub struct FeeConfig {
+    /// Fee rate in sat/vB.
+    pub fee_rate: Option<u64>,
+
+    /// Smallest change output in sats, smaller change is left to the miners.
+    pub min_change: Option<u64>,
+
+    /// Largest fee of a single transaction in sats.
+    pub max_fee: Option<u64>,
+}
+
+/// Relay policy settings. Unset values fall back to the profile, or to the defaults of the
+/// configured network without one.
+#[derive(Deserialize, Clone, Copy, Serialize, Default, Debug)]
+pub struct PolicyConfig {
+    /// Named relay policy of the nodes transactions are broadcast to.
+    pub profile: Option<PolicyProfileConfig>,
+
+    /// Relay fee floor in sat/kvB, as `minrelaytxfee` of Bitcoin Core.
+    pub min_relay_fee: Option<u64>,
+
+    /// Fee rate the dust limit is computed with in sat/kvB, as `dustrelayfee` of Bitcoin Core.
+    pub dust_relay_fee: Option<u64>,
+
+    /// Heaviest relayed transaction in weight units.
+    pub max_standard_weight: Option<u64>,
+}
+
+/// Relay policy profiles
+#[derive(Deserialize, Clone, Copy, Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum PolicyProfileConfig {
+    /// Bitcoin Core before 29.1
+    BitcoinCore,
+    /// Bitcoin Core 29.1 and later, and other nodes relaying fee rates below 1 sat/vB
+    LowFee,
+}
+
+impl From<PolicyProfileConfig> for PolicyProfile {
+    fn from(profile: PolicyProfileConfig) -> Self {
+        match profile {
+            PolicyProfileConfig::BitcoinCore => PolicyProfile::BitcoinCore,
+            PolicyProfileConfig::LowFee => PolicyProfile::LowFee,
+        }
+    }
+}
+
+/// Standard stake denominations
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct DenominationConfig {
+    /// Standard stake amounts in sats.
+    #[serde(default = "default_denominations")]
+    pub amounts: Vec<u64>,
+
+    /// What to do when a challenge amount is not one of the standard amounts.
+    #[serde(default)]
+    pub non_standard: DenominationPolicy,
+}
+
+impl Default for DenominationConfig {
+    fn default() -> Self {
+        Self {
+            amounts: default_denominations(),
+            non_standard: DenominationPolicy::default(),
+        }
+    }
+}
+
+impl DenominationConfig {
+    /// Returns whether `amount` is one of the standard amounts.
+    pub fn is_standard(&self, amount: Amount) -> bool {
+        self.amounts.contains(&amount.to_sat())
+    }
+}
+
+/// Policy applied to a challenge amount that is not a standard denomination
+#[derive(Deserialize, Clone, Copy, Serialize, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DenominationPolicy {
+    Allow,
+    #[default]
+    Warn,
+    /// Denominations-only mode
+    Refuse,
+}
+
+/// Parameters of a drand chain, from its `/info` endpoint. Defaults to the League of Entropy
+/// mainnet chain served at `/public`.
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct DrandChainConfig {
+    /// Public key of the chain, a compressed G1 point hex encoded.
+    pub public_key: String,
+
+    /// Unix time of the first round.
+    pub genesis_time: u64,
+
+    /// Seconds between rounds.
+    pub period: u64,
+
+    /// Whether each round signs the signature of the previous one.
+    pub chained: bool,
+}
+
+impl Default for DrandChainConfig {
+    fn default() -> Self {
+        Self {
+            public_key: "868f005eb8e6e4ca0a47c8a77ceaa5309a47978a7c71bc5cce96366b5d7a569937c529eeda66c7293784a9402801af31".to_string(),
+            genesis_time: 1_595_431_050,
+            period: 30,
+            chained: true,
+        }
+    }
+}
+
+impl DrandChainConfig {
+    /// Unix time `round` is published at.
+    pub fn round_time(&self, round: u64) -> u64 {
+        self.genesis_time + round.saturating_sub(1) * self.period
+    }
+}
+
+/// Failure reporting settings
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct TelemetryConfig {
+    /// Report failure categories. Disabled unless set.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// File the reports are appended to.
+    #[serde(default = "default_telemetry_file")]
+    pub file: PathBuf,
+
+    /// HTTP endpoint the reports are also posted to.
+    pub endpoint: Option<String>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: default_telemetry_file(),
+            endpoint: None,
+        }
+    }
+}
+
+/// Retention settings of settled challenges
+#[derive(Deserialize, Clone, Serialize, Debug)]
+pub struct ArchiveConfig {
+    /// Compressed file settled challenges are moved to by `archive`.
+    #[serde(default = "default_archive_path")]
+    pub path: PathBuf,
+
+    /// Archive challenges settled more than this many days ago.
+    #[serde(default = "default_archive_after_days")]
+    pub after_days: u64,
+
+    /// Drop the proofs of archived challenges settled more than this many days ago. Proofs are
+    /// kept if unset.
+    pub drop_proofs_after_days: Option<u64>,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            path: default_archive_path(),
+            after_days: default_archive_after_days(),
+            drop_proofs_after_days: None,
+        }
+    }
+}
+
+/// Policy applied when a wallet key or change address was already used in another challenge
+#[derive(Deserialize, Clone, Copy, Serialize, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressReusePolicy {
+    Allow,
+    #[default]
+    Warn,
+    Refuse,
+}
+
+fn default_store_path() -> PathBuf {
+    PathBuf::from("op_rand_store.json")
+}
+
+fn default_archive_path() -> PathBuf {
+    PathBuf::from("op_rand_archive.json.gz")
+}
+
+fn default_archive_after_days() -> u64 {
+    30
+}
+
+fn default_cache_dir() -> PathBuf {
+    PathBuf::from("op_rand_cache")
+}
+
+fn default_prover_socket() -> PathBuf {
+    PathBuf::from("op_rand_prover.sock")
+}
+
+fn default_telemetry_file() -> PathBuf {
+    PathBuf::from("op_rand_failures.jsonl")
+}
+
+fn default_denominations() -> Vec<u64> {
+    vec![10_000, 100_000, 1_000_000]
+}
+
+fn default_drand_url() -> String {
+    "https://api.drand.sh".to_string()
 }
 
 impl Config {
@@ -21,4 +295,73 @@ impl Config {
 
         Ok(config.try_deserialize()?)
     }
+
+    /// Returns the protocol identity key: the configured one, or the one derived from the
+    /// wallet key. It must never be the wallet key itself.
+    pub fn identity_key(&self) -> eyre::Result<PrivateKey> {
+        let identity_key = self.identity_key.unwrap_or_else(|| {
+            PrivateKey::new(derive_identity_key(&self.private_key.inner), self.network)
+        });
+        ensure!(
+            identity_key.inner != self.private_key.inner,
+            "The identity key must differ from the wallet key"
+        );
+
+        Ok(identity_key)
+    }
+
+    /// Returns the relay policy of the configured profile or network with the configured
+    /// overrides applied.
+    pub fn relay_policy(&self) -> RelayPolicy {
+        // Fee rates are configured per kvB, as by the nodes
+        let per_kvb = |rate: u64| FeeRate::from_sat_per_kwu(rate / 4);
+        let defaults = match self.policy.profile {
+            Some(profile) => RelayPolicy::profile(profile.into()),
+            None => RelayPolicy::for_network(self.network),
+        };
+
+        RelayPolicy {
+            min_relay_fee: self
+                .policy
+                .min_relay_fee
+                .map_or(defaults.min_relay_fee, per_kvb),
+            dust_relay_fee: self
+                .policy
+                .dust_relay_fee
+                .map_or(defaults.dust_relay_fee, per_kvb),
+            max_standard_weight: self
+                .policy
+                .max_standard_weight
+                .map_or(defaults.max_standard_weight, Weight::from_wu),
+        }
+    }
+
+    /// Returns the fee policy of the configured network with the configured overrides applied.
+    pub fn fee_policy(&self) -> eyre::Result<FeePolicy> {
+        let defaults = FeePolicy::for_network(self.network);
+        let policy = FeePolicy {
+            fee_rate: match self.fees.fee_rate {
+                Some(rate) => FeeRate::from_sat_per_vb(rate).ok_or_eyre("Fee rate overflow")?,
+                None => defaults.fee_rate,
+            },
+            min_change: self
+                .fees
+                .min_change
+                .map_or(defaults.min_change, Amount::from_sat),
+            max_fee: self.fees.max_fee.map_or(defaults.max_fee, Amount::from_sat),
+            relay: self.relay_policy(),
+        };
+
+        let dust_limit = policy
+            .relay
+            .dust_limit(&ScriptBuf::new_p2wpkh(&WPubkeyHash::all_zeros()));
+        ensure!(
+            policy.min_change >= dust_limit,
+            "Minimum change {} is below the dust limit of {}",
+            policy.min_change,
+            dust_limit
+        );
+
+        Ok(policy)
+    }
 }
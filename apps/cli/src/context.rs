@@ -1,11 +1,24 @@
+use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
-use crate::{config::Config, esplora::EsploraClient};
+use crate::{
+    archive::Archive,
+    config::Config,
+    esplora::EsploraClient,
+    platform::Dirs,
+    proving::{self, ProvingTimes},
+    render::{OutputFormat, Renderer},
+    store::Store,
+};
 use bitcoin::secp256k1::{All, Secp256k1};
 use color_eyre::{eyre, eyre::Context as _};
 use indicatif::{ProgressBar, ProgressStyle};
-use op_rand_transaction_builder::TransactionBuilder;
+use op_rand_prover::{
+    BarretenbergProver, Circuit, DaemonProver, OpRandProver, ProvingProgress, ProvingStage,
+};
+use op_rand_transaction_builder::{FeePolicy, TransactionBuilder};
 
 /// Context is a struct which holds all information that could be used globally, like info from
 /// configuration file. All the data taken from context is evaluated lazily, so it's not a problem
@@ -14,6 +27,9 @@ pub struct Context {
     /// Stored path to configuration file, ti lazy load it when needed.
     config_path: PathBuf,
 
+    /// Platform locations of the CLI files.
+    dirs: Dirs,
+
     /// Global secp256k1 context, used for signing and verifying signatures.
     secp_ctx: Secp256k1<All>,
 
@@ -25,18 +41,27 @@ pub struct Context {
 
     /// Transaction builder
     transaction_builder: Option<TransactionBuilder<All>>,
+
+    /// Format of the command output.
+    output_format: OutputFormat,
+
+    /// Progress bar the in-process prover reports proving progress to.
+    proving_bar: Arc<Mutex<Option<ProgressBar>>>,
 }
 
 impl Context {
-    pub fn new(config: PathBuf) -> Self {
+    pub fn new(config: PathBuf, dirs: Dirs, output_format: OutputFormat) -> Self {
         let secp_ctx = Secp256k1::new();
 
         Self {
             config_path: config,
+            dirs,
             secp_ctx,
             config: None,
             esplora_client: None,
             transaction_builder: None,
+            output_format,
+            proving_bar: Arc::default(),
         }
     }
 
@@ -45,13 +70,43 @@ impl Context {
             return Ok(config.clone());
         }
 
-        let cfg = Config::from_path(self.config_path.clone()).wrap_err("Failed to load config")?;
+        let mut cfg =
+            Config::from_path(self.config_path.clone()).wrap_err("Failed to load config")?;
+
+        // Relative paths are relative to the data directory, so that commands work from any
+        // working directory
+        cfg.store_path = self.dirs.resolve(&cfg.store_path);
+        cfg.archive.path = self.dirs.resolve(&cfg.archive.path);
+        cfg.cache_dir = self.dirs.resolve(&cfg.cache_dir);
+        cfg.prover_socket = self.dirs.resolve(&cfg.prover_socket);
+        cfg.telemetry.file = self.dirs.resolve(&cfg.telemetry.file);
+        fs::create_dir_all(self.dirs.data_dir()).wrap_err_with(|| {
+            format!(
+                "Failed to create data directory {}",
+                self.dirs.data_dir().display()
+            )
+        })?;
 
         self.config = Some(cfg.clone());
 
         Ok(cfg)
     }
 
+    /// Returns the platform locations of the CLI files.
+    pub fn dirs(&self) -> &Dirs {
+        &self.dirs
+    }
+
+    /// Returns the fee policy all transactions are built with.
+    pub fn fee_policy(&mut self) -> eyre::Result<FeePolicy> {
+        self.config()?.fee_policy()
+    }
+
+    /// Creates a renderer of the selected output format writing to stdout.
+    pub fn renderer(&self) -> Box<dyn Renderer> {
+        self.output_format.renderer(std::io::stdout())
+    }
+
     pub fn secp_ctx(&self) -> &Secp256k1<All> {
         &self.secp_ctx
     }
@@ -61,13 +116,77 @@ impl Context {
             return Ok(client.clone());
         }
 
-        let cfg = Config::from_path(self.config_path.clone()).wrap_err("Failed to load config")?;
-        let client = EsploraClient::new(cfg.esplora_url);
+        let cfg = self.config()?;
+        let client = EsploraClient::new(cfg.esplora_url).with_cache_dir(cfg.cache_dir);
         self.esplora_client = Some(client.clone());
 
         Ok(client)
     }
 
+    /// Loads the local state store. The store is not cached, so every call sees the latest
+    /// saved state.
+    pub fn store(&mut self) -> eyre::Result<Store> {
+        Store::load(self.config()?.store_path)
+    }
+
+    /// Loads the archive of settled challenges.
+    pub fn archive(&mut self) -> eyre::Result<Archive> {
+        Archive::load(self.config()?.archive.path)
+    }
+
+    /// Returns a prover delegating to the prover daemon if one is listening on the configured
+    /// socket, or an in-process prover otherwise.
+    pub fn prover(&mut self) -> eyre::Result<Arc<dyn OpRandProver + Send + Sync>> {
+        let socket = self.config()?.prover_socket;
+
+        Ok(match DaemonProver::connect(socket) {
+            Ok(daemon) => Arc::new(daemon),
+            Err(_) => Arc::new(self.in_process_prover()?),
+        })
+    }
+
+    /// Returns a prover running in this process, using the local SRS file if there is one.
+    /// It reports proving progress to the bar passed to [`Context::track_proving`] and records
+    /// the duration of every proof for the estimates of the next ones.
+    pub fn in_process_prover(&mut self) -> eyre::Result<BarretenbergProver> {
+        let proving_timeout = self.config()?.proving_timeout;
+        let srs_path = self.dirs.srs_path();
+        let times_path = self.dirs.proving_times_path();
+        let times = ProvingTimes::load(&times_path);
+        let proving_bar = self.proving_bar.clone();
+
+        let mut builder =
+            BarretenbergProver::builder().progress(Arc::new(move |progress: &ProvingProgress| {
+                if progress.stage == ProvingStage::Done {
+                    ProvingTimes::record(&times_path, progress.circuit, progress.elapsed);
+                }
+                if let Some(pb) = &*proving_bar.lock().unwrap_or_else(PoisonError::into_inner) {
+                    pb.set_message(proving::describe(progress));
+                }
+            }));
+        for circuit in [Circuit::Challenger, Circuit::Acceptor] {
+            if let Some(expected) = times.get(circuit) {
+                builder = builder.expected_proving_time(circuit, expected);
+            }
+        }
+        if srs_path.exists() {
+            builder = builder.srs_path(srs_path);
+        }
+        if let Some(timeout) = proving_timeout {
+            builder = builder.proving_timeout(Duration::from_secs(timeout));
+        }
+
+        Ok(builder.build()?)
+    }
+
+    /// Shows the progress of the next in-process proofs on `pb` instead of a plain spinner.
+    pub fn track_proving(&self, pb: &ProgressBar) {
+        *self
+            .proving_bar
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(pb.clone());
+    }
+
     pub fn transaction_builder(&mut self) -> eyre::Result<TransactionBuilder<All>> {
         if let Some(builder) = &self.transaction_builder {
             return Ok(builder.clone());
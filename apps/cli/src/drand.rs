@@ -0,0 +1,150 @@
+use bls12_381::{
+    G1Affine, G2Affine, G2Projective,
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    pairing,
+};
+use eyre::{Context as _, OptionExt, Result, ensure, eyre};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::DrandChainConfig;
+
+/// Domain separation tag of drand signatures on G2
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Round of the drand randomness beacon
+#[derive(Debug, Clone, Deserialize)]
+pub struct Beacon {
+    pub round: u64,
+
+    /// Beacon randomness, hex encoded. It is the SHA-256 of the signature.
+    pub randomness: String,
+
+    /// Signature of the round by the drand network, hex encoded.
+    pub signature: String,
+
+    /// Signature of the previous round, hex encoded. Only returned by chained networks, which
+    /// sign it along with the round.
+    #[serde(default)]
+    pub previous_signature: Option<String>,
+}
+
+impl Beacon {
+    /// Checks the round signature against the public key of `chain`, and that the randomness is
+    /// derived from the signature.
+    pub fn verify(&self, chain: &DrandChainConfig) -> Result<()> {
+        let public_key: [u8; 48] = hex::decode(&chain.public_key)?
+            .try_into()
+            .map_err(|_| eyre!("drand public key must be a compressed G1 point"))?;
+        let public_key = Option::<G1Affine>::from(G1Affine::from_compressed(&public_key))
+            .ok_or_eyre("Invalid drand public key")?;
+
+        let signature_bytes = hex::decode(&self.signature)?;
+        let signature: [u8; 96] = signature_bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| eyre!("drand signature must be a compressed G2 point"))?;
+        let signature = Option::<G2Affine>::from(G2Affine::from_compressed(&signature))
+            .ok_or_eyre("Invalid drand signature")?;
+
+        let mut message = Sha256::new();
+        if chain.chained {
+            let previous_signature = self
+                .previous_signature
+                .as_deref()
+                .ok_or_eyre("drand beacon of a chained network misses the previous signature")?;
+            message.update(hex::decode(previous_signature)?);
+        }
+        message.update(self.round.to_be_bytes());
+        let message = G2Affine::from(
+            <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
+                message.finalize(),
+                SIGNATURE_DST,
+            ),
+        );
+
+        ensure!(
+            pairing(&G1Affine::generator(), &signature) == pairing(&public_key, &message),
+            "drand round {} is not signed by the configured chain",
+            self.round
+        );
+        ensure!(
+            hex::decode(&self.randomness)? == Sha256::digest(&signature_bytes).as_slice(),
+            "drand round {} randomness is not derived from its signature",
+            self.round
+        );
+
+        Ok(())
+    }
+}
+
+/// Fetches `round` of the drand beacon from the HTTP relay at `base_url`, or the latest round if
+/// `None`.
+///
+/// The beacon is verified against the public key of `chain`, so that a relay can't forge the
+/// beacon that selects a commitment.
+pub async fn fetch_beacon(
+    base_url: &str,
+    chain: &DrandChainConfig,
+    round: Option<u64>,
+) -> Result<Beacon> {
+    let requested = round.map_or("latest".to_string(), |round| round.to_string());
+    let url = format!("{}/public/{}", base_url.trim_end_matches('/'), requested);
+
+    let beacon: Beacon = reqwest::get(&url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .wrap_err_with(|| format!("Failed to fetch drand round {}", requested))?
+        .json()
+        .await
+        .wrap_err("Failed to parse drand beacon")?;
+    if let Some(round) = round {
+        ensure!(
+            beacon.round == round,
+            "drand relay returned round {} instead of {}",
+            beacon.round,
+            round
+        );
+    }
+    beacon.verify(chain)?;
+
+    Ok(beacon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Round 72785 of the League of Entropy mainnet chain
+    fn beacon() -> Beacon {
+        Beacon {
+            round: 72785,
+            randomness: "8b676484b5fb1f37f9ec5c413d7d29883504e5b669f604a1ce68b3388e9ae3d9"
+                .to_string(),
+            signature: "82f5d3d2de4db19d40a6980e8aa37842a0e55d1df06bd68bddc8d60002e8e959eb9cfa368b3c1b77d18f02a54fe047b80f0989315f83b12a74fd8679c4f12aae86eaf6ab5690b34f1fddd50ee3cc6f6cdf59e95526d5a5d82aaa84fa6f181e42".to_string(),
+            previous_signature: Some("a609e19a03c2fcc559e8dae14900aaefe517cb55c840f6e69bc8e4f66c8d18e8a609685d9917efbfb0c37f058c2de88f13d297c7e19e0ab24813079efe57a182554ff054c7638153f9b26a60e7111f71a0ff63d9571704905d3ca6df0b031747".to_string()),
+        }
+    }
+
+    #[test]
+    fn verifies_signed_round() {
+        beacon().verify(&DrandChainConfig::default()).unwrap();
+    }
+
+    #[test]
+    fn rejects_forged_rounds() {
+        let chain = DrandChainConfig::default();
+
+        let mut other_round = beacon();
+        other_round.round += 1;
+        assert!(other_round.verify(&chain).is_err());
+
+        let mut other_randomness = beacon();
+        other_randomness.randomness = "00".repeat(32);
+        assert!(other_randomness.verify(&chain).is_err());
+
+        let mut unchained = beacon();
+        unchained.previous_signature = None;
+        assert!(unchained.verify(&chain).is_err());
+    }
+}
@@ -1,12 +1,49 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use bitcoin::{Amount, OutPoint, Transaction, Txid, block::Header, consensus};
 use eyre::{Result, eyre};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use futures_util::{Stream, stream};
+use op_rand_transaction_builder::{ChainBackend, OutpointEvent};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::sync::OnceCell;
+
+/// Interval a watched outpoint is polled at
+const WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// HTTP client shared by all `EsploraClient`s, so they reuse one connection pool.
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Requests in flight, keyed by path. Each request resolves to the response body or an error
+/// message.
+type InFlight = Arc<Mutex<HashMap<String, Arc<OnceCell<Result<String, String>>>>>>;
+
+/// Rejection of a broadcast transaction by the Esplora server
+#[derive(Debug, thiserror::Error)]
+#[error("Transaction broadcast failed with status {status}: {message}")]
+pub struct BroadcastRejected {
+    pub status: StatusCode,
+    pub message: String,
+}
 
 /// Esplora client for interacting with esplora-tapyrus API
 #[derive(Clone)]
 pub struct EsploraClient {
     client: Client,
     base_url: String,
+
+    /// Directory for cached immutable responses. Caching is disabled if `None`.
+    cache_dir: Option<PathBuf>,
+
+    /// Requests in flight, shared between clones to de-duplicate identical requests.
+    in_flight: InFlight,
 }
 
 /// UTXO information returned by the API
@@ -15,7 +52,15 @@ pub struct Utxo {
     pub txid: String,
     pub vout: u32,
     pub status: UtxoStatus,
-    pub value: u64,
+    #[serde(with = "bitcoin::amount::serde::as_sat")]
+    pub value: Amount,
+}
+
+impl Utxo {
+    /// Returns the outpoint of the UTXO
+    pub fn outpoint(&self) -> Result<OutPoint> {
+        Ok(OutPoint::new(Txid::from_str(&self.txid)?, self.vout))
+    }
 }
 
 /// Status information for a UTXO
@@ -27,15 +72,59 @@ pub struct UtxoStatus {
     pub block_time: Option<u64>,
 }
 
+/// Spending status of a transaction output
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutSpend {
+    pub spent: bool,
+    pub txid: Option<String>,
+    pub vin: Option<u32>,
+    pub status: Option<UtxoStatus>,
+}
+
+/// Merkle inclusion proof of a transaction, in Electrum format
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MerkleProof {
+    pub block_height: u64,
+    /// Sibling hashes from the leaf up to the root, as hex in display order.
+    pub merkle: Vec<String>,
+    /// Position of the transaction in the block.
+    pub pos: u32,
+}
+
+/// Block information returned by the API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BlockInfo {
+    pub id: String,
+    pub height: u64,
+    pub timestamp: u64,
+}
+
 impl EsploraClient {
     /// Create a new EsploraClient instance
     pub fn new(base_url: impl Into<String>) -> Self {
+        let client = HTTP_CLIENT
+            .get_or_init(|| {
+                Client::builder()
+                    .pool_idle_timeout(Duration::from_secs(90))
+                    .build()
+                    .unwrap_or_default()
+            })
+            .clone();
+
         Self {
-            client: Client::new(),
+            client,
             base_url: base_url.into(),
+            cache_dir: None,
+            in_flight: Arc::default(),
         }
     }
 
+    /// Caches immutable responses, such as confirmed transactions and blocks, in `cache_dir`
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+
     /// Get UTXOs for a specific address
     ///
     /// # Arguments
@@ -44,7 +133,85 @@ impl EsploraClient {
     /// # Returns
     /// A vector of UTXO information
     pub async fn get_utxos(&self, address: &str) -> Result<Vec<Utxo>> {
-        let url = format!("{}/address/{}/utxo", self.base_url, address);
+        self.get_json(&format!("address/{}/utxo", address), "UTXO")
+            .await
+    }
+
+    /// Broadcast a raw transaction to the network
+    ///
+    /// # Arguments
+    /// * `raw_tx_hex` - The raw transaction as a hex string
+    ///
+    /// # Returns
+    /// The transaction ID of the broadcasted transaction
+    pub async fn broadcast_transaction(&self, raw_tx_hex: &str) -> Result<String> {
+        let url = format!("{}/tx", self.base_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "text/plain")
+            .body(raw_tx_hex.to_string())
+            .send()
+            .await
+            .map_err(|e| eyre!("Failed to send broadcast request to {}: {}", url, e))?;
+
+        if !response.status().is_success() {
+            return Err(BroadcastRejected {
+                status: response.status(),
+                message: response.text().await.unwrap_or_default(),
+            }
+            .into());
+        }
+
+        // The API returns just the txid as a string
+        let txid = response
+            .text()
+            .await
+            .map_err(|e| eyre!("Failed to read txid response: {}", e))?
+            .trim()
+            .to_string();
+
+        Ok(txid)
+    }
+
+    /// Get a transaction by its id. Confirmed transactions are cached.
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction id
+    ///
+    /// # Returns
+    /// The decoded transaction
+    pub async fn get_transaction(&self, txid: &Txid) -> Result<Transaction> {
+        let cache_key = format!("tx-{}.hex", txid);
+
+        let tx_hex = match self.read_cache(&cache_key) {
+            Some(tx_hex) => tx_hex,
+            None => {
+                let status = self.get_tx_status(txid).await?;
+                let tx_hex = self.get_text(&format!("tx/{}/hex", txid)).await?;
+
+                if status.confirmed {
+                    self.write_cache(&cache_key, &tx_hex);
+                }
+
+                tx_hex
+            }
+        };
+
+        consensus::encode::deserialize_hex(&tx_hex)
+            .map_err(|e| eyre!("Failed to decode transaction {}: {}", txid, e))
+    }
+
+    /// Check whether the server knows a transaction, either in the mempool or in a block
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction id
+    ///
+    /// # Returns
+    /// `true` if the transaction is known
+    pub async fn has_transaction(&self, txid: &Txid) -> Result<bool> {
+        let url = format!("{}/tx/{}", self.base_url, txid);
 
         let response = self
             .client
@@ -53,57 +220,272 @@ impl EsploraClient {
             .await
             .map_err(|e| eyre!("Failed to send request to {}: {}", url, e))?;
 
-        if !response.status().is_success() {
-            return Err(eyre!(
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(eyre!(
                 "API request failed with status {}: {}",
-                response.status(),
+                status,
                 response.text().await.unwrap_or_default()
-            ));
+            )),
+        }
+    }
+
+    /// Get the confirmation status of a transaction
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction id
+    ///
+    /// # Returns
+    /// The confirmation status
+    pub async fn get_tx_status(&self, txid: &Txid) -> Result<UtxoStatus> {
+        self.get_json(&format!("tx/{}/status", txid), "transaction status")
+            .await
+    }
+
+    /// Get the spending status of a transaction output
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction id
+    /// * `vout` - The output index
+    ///
+    /// # Returns
+    /// The spending status of the output
+    pub async fn get_outspend(&self, txid: &Txid, vout: u32) -> Result<OutSpend> {
+        self.get_json(&format!("tx/{}/outspend/{}", txid, vout), "outspend")
+            .await
+    }
+
+    /// Returns the transaction spending an output with its confirmation height, or `None` if
+    /// the output is unspent
+    async fn get_spend(&self, outpoint: &OutPoint) -> Result<Option<(Txid, Option<u32>)>> {
+        let outspend = self.get_outspend(&outpoint.txid, outpoint.vout).await?;
+        if !outspend.spent {
+            return Ok(None);
         }
 
-        let utxos: Vec<Utxo> = response
-            .json()
+        let txid = outspend
+            .txid
+            .as_deref()
+            .ok_or_else(|| eyre!("Esplora didn't return the spending transaction"))?;
+        let block_height = outspend
+            .status
+            .and_then(|status| status.block_height)
+            .and_then(|height| u32::try_from(height).ok());
+
+        Ok(Some((Txid::from_str(txid)?, block_height)))
+    }
+
+    /// Get a merkle inclusion proof for a confirmed transaction
+    ///
+    /// # Arguments
+    /// * `txid` - The transaction id
+    ///
+    /// # Returns
+    /// The merkle proof
+    pub async fn get_merkle_proof(&self, txid: &Txid) -> Result<MerkleProof> {
+        self.get_json(&format!("tx/{}/merkle-proof", txid), "merkle proof")
             .await
-            .map_err(|e| eyre!("Failed to parse UTXO response: {}", e))?;
+    }
+
+    /// Get the height of the current chain tip
+    ///
+    /// # Returns
+    /// The tip height
+    pub async fn get_tip_height(&self) -> Result<u64> {
+        let height = self.get_text("blocks/tip/height").await?;
 
-        Ok(utxos)
+        height
+            .parse()
+            .map_err(|e| eyre!("Failed to parse tip height {}: {}", height, e))
     }
 
-    /// Broadcast a raw transaction to the network
+    /// Get the hash of the current chain tip
+    ///
+    /// # Returns
+    /// The tip block hash as a hex string
+    pub async fn get_tip_hash(&self) -> Result<String> {
+        self.get_text("blocks/tip/hash").await
+    }
+
+    /// Get the hash of the block at a specific height
     ///
     /// # Arguments
-    /// * `raw_tx_hex` - The raw transaction as a hex string
+    /// * `height` - The block height
     ///
     /// # Returns
-    /// The transaction ID of the broadcasted transaction
-    pub async fn broadcast_transaction(&self, raw_tx_hex: &str) -> Result<String> {
-        let url = format!("{}/tx", self.base_url);
+    /// The block hash as a hex string
+    pub async fn get_block_hash(&self, height: u64) -> Result<String> {
+        self.get_text(&format!("block-height/{}", height)).await
+    }
+
+    /// Get information about a block. Blocks are cached.
+    ///
+    /// # Arguments
+    /// * `block_hash` - The block hash as a hex string
+    ///
+    /// # Returns
+    /// The block information
+    pub async fn get_block(&self, block_hash: &str) -> Result<BlockInfo> {
+        let cache_key = format!("block-{}.json", block_hash);
+
+        let block_json = match self.read_cache(&cache_key) {
+            Some(block_json) => block_json,
+            None => {
+                let block_json = self.get_text(&format!("block/{}", block_hash)).await?;
+                self.write_cache(&cache_key, &block_json);
+                block_json
+            }
+        };
+
+        serde_json::from_str(&block_json)
+            .map_err(|e| eyre!("Failed to parse block response: {}", e))
+    }
+
+    /// Get a block header. Headers are cached.
+    ///
+    /// # Arguments
+    /// * `block_hash` - The block hash as a hex string
+    ///
+    /// # Returns
+    /// The decoded block header
+    pub async fn get_block_header(&self, block_hash: &str) -> Result<Header> {
+        let cache_key = format!("header-{}.hex", block_hash);
+
+        let header_hex = match self.read_cache(&cache_key) {
+            Some(header_hex) => header_hex,
+            None => {
+                let header_hex = self
+                    .get_text(&format!("block/{}/header", block_hash))
+                    .await?;
+                self.write_cache(&cache_key, &header_hex);
+                header_hex
+            }
+        };
+
+        consensus::encode::deserialize_hex(&header_hex)
+            .map_err(|e| eyre!("Failed to decode block header {}: {}", block_hash, e))
+    }
+
+    /// Sends a GET request to `path` and parses the JSON response
+    async fn get_json<T: DeserializeOwned>(&self, path: &str, what: &str) -> Result<T> {
+        let body = self.get_text(path).await?;
+
+        serde_json::from_str(&body).map_err(|e| eyre!("Failed to parse {} response: {}", what, e))
+    }
+
+    /// Sends a GET request to `path` and returns the trimmed response body. Concurrent calls for
+    /// the same path share a single request.
+    async fn get_text(&self, path: &str) -> Result<String> {
+        let cell = self
+            .in_flight
+            .lock()
+            .expect("in-flight requests lock poisoned")
+            .entry(path.to_string())
+            .or_default()
+            .clone();
+
+        let result = cell
+            .get_or_init(|| async { self.send_get(path).await.map_err(|e| format!("{:#}", e)) })
+            .await
+            .clone();
+
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .expect("in-flight requests lock poisoned");
+        if in_flight
+            .get(path)
+            .is_some_and(|entry| Arc::ptr_eq(entry, &cell))
+        {
+            in_flight.remove(path);
+        }
+
+        result.map_err(|e| eyre!(e))
+    }
+
+    /// Sends a GET request and fails on a non-success status
+    async fn send_get(&self, path: &str) -> Result<String> {
+        let url = format!("{}/{}", self.base_url, path);
 
         let response = self
             .client
-            .post(&url)
-            .header("Content-Type", "text/plain")
-            .body(raw_tx_hex.to_string())
+            .get(&url)
             .send()
             .await
-            .map_err(|e| eyre!("Failed to send broadcast request to {}: {}", url, e))?;
+            .map_err(|e| eyre!("Failed to send request to {}: {}", url, e))?;
 
         if !response.status().is_success() {
             return Err(eyre!(
-                "Transaction broadcast failed with status {}: {}",
+                "API request failed with status {}: {}",
                 response.status(),
                 response.text().await.unwrap_or_default()
             ));
         }
 
-        // The API returns just the txid as a string
-        let txid = response
+        let text = response
             .text()
             .await
-            .map_err(|e| eyre!("Failed to read txid response: {}", e))?
-            .trim()
-            .to_string();
+            .map_err(|e| eyre!("Failed to read response from {}: {}", url, e))?;
 
-        Ok(txid)
+        Ok(text.trim().to_string())
+    }
+
+    /// Reads a cached response, if caching is enabled and the entry exists
+    fn read_cache(&self, key: &str) -> Option<String> {
+        let cache_dir = self.cache_dir.as_ref()?;
+        fs::read_to_string(cache_dir.join(key)).ok()
+    }
+
+    /// Writes a response to the cache. Failures are ignored, the cache is best-effort.
+    fn write_cache(&self, key: &str, value: &str) {
+        if let Some(cache_dir) = &self.cache_dir {
+            let _ =
+                fs::create_dir_all(cache_dir).and_then(|_| fs::write(cache_dir.join(key), value));
+        }
+    }
+}
+
+impl ChainBackend for EsploraClient {
+    type Error = eyre::Report;
+
+    /// Polls the spend of `outpoint` every [`WATCH_INTERVAL`]. A failed poll is yielded as an
+    /// error and retried at the next interval.
+    fn watch_outpoint(
+        &self,
+        outpoint: OutPoint,
+    ) -> impl Stream<Item = Result<OutpointEvent>> + Send {
+        // The spend seen by the last poll, and whether the next poll has to wait
+        let state: (Option<(Txid, Option<u32>)>, bool) = (None, false);
+
+        stream::unfold(state, move |(seen, wait)| async move {
+            if wait {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+            }
+
+            loop {
+                let spend = match self.get_spend(&outpoint).await {
+                    Ok(spend) => spend,
+                    Err(err) => return Some((Err(err), (seen, true))),
+                };
+                if spend == seen {
+                    tokio::time::sleep(WATCH_INTERVAL).await;
+                    continue;
+                }
+
+                let event = match spend {
+                    Some((txid, block_height)) => match self.get_transaction(&txid).await {
+                        Ok(transaction) => OutpointEvent::Spent {
+                            transaction,
+                            block_height,
+                        },
+                        Err(err) => return Some((Err(err), (seen, true))),
+                    },
+                    None => OutpointEvent::Unspent,
+                };
+
+                return Some((Ok(event), (spend, true)));
+            }
+        })
     }
 }
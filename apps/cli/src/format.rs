@@ -0,0 +1,71 @@
+use bitcoin::{Address, Amount, address::NetworkUnchecked};
+use console::style;
+use op_rand_transaction_builder::PayoutShare;
+
+/// Formats an amount as satoshis followed by its BTC value.
+pub fn format_bitcoin_amount(amount: Amount) -> String {
+    format!(
+        "{} satoshis ({} BTC)",
+        style(amount.to_sat().to_string()).bright().green().bold(),
+        style(format!("{:.8}", amount.to_btc())).bright().green()
+    )
+}
+
+/// Parses a command line amount given in satoshis, optionally with a `k` (thousand) or `M`
+/// (million) suffix such as `100k`.
+pub fn parse_sats(s: &str) -> Result<Amount, String> {
+    let (digits, multiplier) = match (s.strip_suffix('k'), s.strip_suffix('M')) {
+        (Some(digits), _) => (digits, 1_000),
+        (_, Some(digits)) => (digits, 1_000_000),
+        _ => (s, 1),
+    };
+
+    digits
+        .parse::<u64>()
+        .map_err(|e| format!("{e}"))?
+        .checked_mul(multiplier)
+        .map(Amount::from_sat)
+        .ok_or_else(|| "amount is too large".to_string())
+}
+
+/// Parses a command line output given as `ADDRESS:SATS`.
+pub fn parse_output(s: &str) -> Result<(Address<NetworkUnchecked>, Amount), String> {
+    let (address, amount) = s.rsplit_once(':').ok_or("expected ADDRESS:SATS")?;
+
+    Ok((
+        address.parse().map_err(|e| format!("{e}"))?,
+        parse_sats(amount)?,
+    ))
+}
+
+/// Parses a command line payout given as `ADDRESS:SATS` or `ADDRESS:PERCENT%`, the percentage
+/// having up to two decimals.
+pub fn parse_payout(s: &str) -> Result<(Address<NetworkUnchecked>, PayoutShare), String> {
+    let (address, share) = s
+        .rsplit_once(':')
+        .ok_or("expected ADDRESS:SATS or ADDRESS:PERCENT%")?;
+    let address = address.parse().map_err(|e| format!("{e}"))?;
+
+    let share = match share.strip_suffix('%') {
+        Some(percent) => {
+            let (whole, fraction) = percent.split_once('.').unwrap_or((percent, ""));
+            if fraction.len() > 2 {
+                return Err("percentages have at most two decimals".to_string());
+            }
+            let whole: u16 = whole.parse().map_err(|e| format!("{e}"))?;
+            let fraction: u16 = format!("{fraction:0<2}")
+                .parse()
+                .map_err(|e| format!("{e}"))?;
+
+            whole
+                .checked_mul(100)
+                .and_then(|points| points.checked_add(fraction))
+                .filter(|points| *points <= 10_000)
+                .map(PayoutShare::BasisPoints)
+                .ok_or("percentage is above 100%")?
+        }
+        None => PayoutShare::Fixed(parse_sats(share)?),
+    };
+
+    Ok((address, share))
+}
@@ -0,0 +1,155 @@
+//! Size limits of the files a counterparty sends. They are enforced while reading, before any
+//! of the content can be validated, so that an oversized file can't exhaust memory.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use op_rand_types::MIN_PROTOCOL_VERSION;
+use serde::de::DeserializeOwned;
+
+/// Largest protocol message file, in bytes
+pub const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+
+/// Longest hex-encoded proof
+pub const MAX_PROOF_LEN: usize = 128 * 1024;
+
+/// Longest hex-encoded verification key
+pub const MAX_VK_LEN: usize = 32 * 1024;
+
+/// Longest base64-encoded PSBT
+pub const MAX_PSBT_LEN: usize = 256 * 1024;
+
+/// Longest hex-encoded witness script, twice the standardness limit of P2WSH scripts
+pub const MAX_WITNESS_SCRIPT_LEN: usize = 2 * 3600;
+
+/// Longest ID, key, hash or other short field
+pub const MAX_SHORT_FIELD_LEN: usize = 256;
+
+/// Reasons a counterparty file is rejected before parsing its content
+#[derive(Debug, thiserror::Error)]
+pub enum InputError {
+    #[error("{} is larger than {limit} bytes", path.display())]
+    FileTooLarge { path: PathBuf, limit: u64 },
+    #[error("Field `{field}` is {size} bytes long, at most {limit} bytes are allowed")]
+    FieldTooLarge {
+        field: &'static str,
+        size: usize,
+        limit: usize,
+    },
+    #[error(
+        "{} has no protocol version, it was written by a client older than protocol version {}",
+        path.display(),
+        MIN_PROTOCOL_VERSION
+    )]
+    Unversioned { path: PathBuf },
+    #[error("Failed to read {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// Protocol message whose fields have size limits
+pub trait BoundedMessage {
+    /// Fails on the first field over its limit.
+    fn check_sizes(&self) -> Result<(), InputError>;
+}
+
+/// Fails if `value` of `field` is longer than `limit` bytes.
+pub fn check_field(field: &'static str, value: &str, limit: usize) -> Result<(), InputError> {
+    if value.len() > limit {
+        return Err(InputError::FieldTooLarge {
+            field,
+            size: value.len(),
+            limit,
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads the file at `path`, failing as soon as more than `limit` bytes were read.
+pub fn read_bounded(path: impl AsRef<Path>, limit: u64) -> Result<String, InputError> {
+    let path = path.as_ref();
+    let io_error = |source| InputError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    let mut content = String::new();
+    File::open(path)
+        .map_err(io_error)?
+        .take(limit + 1)
+        .read_to_string(&mut content)
+        .map_err(io_error)?;
+
+    if content.len() as u64 > limit {
+        return Err(InputError::FileTooLarge {
+            path: path.to_path_buf(),
+            limit,
+        });
+    }
+
+    Ok(content)
+}
+
+/// Reads and parses a protocol message sent by a counterparty, enforcing the file and field
+/// size limits.
+pub fn load_message<T: DeserializeOwned + BoundedMessage>(
+    path: impl AsRef<Path>,
+) -> eyre::Result<T> {
+    let path = path.as_ref();
+    let content = read_bounded(path, MAX_MESSAGE_SIZE)?;
+    let message: T = match serde_json::from_str(&content) {
+        Ok(message) => message,
+        Err(_) if is_unversioned(&content) => {
+            return Err(InputError::Unversioned {
+                path: path.to_path_buf(),
+            }
+            .into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    message.check_sizes()?;
+
+    Ok(message)
+}
+
+/// Returns whether `content` is a JSON object without the protocol info every message of a
+/// versioned client has
+fn is_unversioned(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .is_ok_and(|value| value.is_object() && value.get("protocol").is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_unversioned_messages() {
+        assert!(is_unversioned(r#"{"id":"challenge-1"}"#));
+        assert!(!is_unversioned(
+            r#"{"id":"challenge-1","protocol":{"version":2,"capabilities":0}}"#
+        ));
+        assert!(!is_unversioned("not json"));
+        assert!(!is_unversioned("[]"));
+    }
+
+    #[test]
+    fn rejects_oversized_fields() {
+        assert!(check_field("vk", &"ab".repeat(8), 16).is_ok());
+        assert!(matches!(
+            check_field("vk", &"ab".repeat(9), 16),
+            Err(InputError::FieldTooLarge {
+                field: "vk",
+                size: 18,
+                limit: 16
+            })
+        ));
+    }
+}
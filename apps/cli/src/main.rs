@@ -1,9 +1,20 @@
 mod actions;
+mod archive;
 mod config;
 mod context;
+mod drand;
 mod esplora;
+mod format;
+mod limits;
+mod platform;
+mod proving;
+mod render;
+mod spv;
+mod store;
+mod telemetry;
 mod ui;
 mod util;
+mod verification;
 
 use clap::Parser;
 
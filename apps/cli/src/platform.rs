@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{self, OptionExt};
+use directories::ProjectDirs;
+
+/// Name of the configuration file
+const CONFIG_FILE: &str = "config.toml";
+
+/// Locations of the CLI files, following the conventions of the platform:
+///
+/// - Linux: `$XDG_CONFIG_HOME/op_rand` and `$XDG_DATA_HOME/op_rand`
+/// - macOS: `~/Library/Application Support/org.distributed-lab.op_rand`
+/// - Windows: `%APPDATA%\distributed-lab\op_rand\config` and `...\data`
+///
+/// With a data directory override all files live in that directory.
+#[derive(Debug, Clone)]
+pub struct Dirs {
+    config_dir: PathBuf,
+    data_dir: PathBuf,
+}
+
+impl Dirs {
+    pub fn new(data_dir: Option<PathBuf>) -> eyre::Result<Self> {
+        if let Some(data_dir) = data_dir {
+            return Ok(Self {
+                config_dir: data_dir.clone(),
+                data_dir,
+            });
+        }
+
+        let project = ProjectDirs::from("org", "distributed-lab", "op_rand")
+            .ok_or_eyre("Failed to find the home directory, pass --data-dir")?;
+
+        Ok(Self {
+            config_dir: project.config_dir().to_path_buf(),
+            data_dir: project.data_dir().to_path_buf(),
+        })
+    }
+
+    /// Directory holding the state store, the caches and the prover socket.
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Directory compiled circuits are looked up in before the source tree.
+    pub fn circuit_dir(&self) -> PathBuf {
+        self.data_dir.join("circuits")
+    }
+
+    /// Local SRS file, used instead of downloading the SRS if it exists.
+    pub fn srs_path(&self) -> PathBuf {
+        self.data_dir.join("srs.dat")
+    }
+
+    /// Durations of the last proofs, used to estimate proving progress.
+    pub fn proving_times_path(&self) -> PathBuf {
+        self.data_dir.join("proving_times.json")
+    }
+
+    /// Returns the configuration file to load: the explicitly given one, `config.toml` in the
+    /// working directory if there is one, or `config.toml` in the configuration directory.
+    pub fn config_path(&self, explicit: Option<PathBuf>) -> PathBuf {
+        if let Some(path) = explicit {
+            return path;
+        }
+
+        let local = PathBuf::from(CONFIG_FILE);
+        if local.exists() {
+            return local;
+        }
+
+        self.config_dir.join(CONFIG_FILE)
+    }
+
+    /// Resolves a path from the configuration file against the data directory.
+    pub fn resolve(&self, path: &Path) -> PathBuf {
+        self.data_dir.join(path)
+    }
+}
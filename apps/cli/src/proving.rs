@@ -0,0 +1,150 @@
+//! Progress of in-process proof generations. Proving takes minutes on slow machines, so the
+//! duration of the last proof of each circuit is recorded and used to estimate how far along
+//! the next one is.
+
+use std::{fs, path::Path, time::Duration};
+
+use color_eyre::eyre;
+use op_rand_prover::{Circuit, OpRandProof, ProofMetrics, ProvingProgress, ProvingStage};
+use serde::{Deserialize, Serialize};
+
+use crate::render::Renderer;
+
+/// Durations of the last proofs, in milliseconds
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProvingTimes {
+    pub challenger: Option<u64>,
+    pub acceptor: Option<u64>,
+}
+
+impl ProvingTimes {
+    /// Loads the recorded durations. A missing or corrupt file records none.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Returns the duration of the last proof of `circuit`
+    pub fn get(&self, circuit: Circuit) -> Option<Duration> {
+        match circuit {
+            Circuit::Challenger => self.challenger,
+            Circuit::Acceptor => self.acceptor,
+        }
+        .map(Duration::from_millis)
+    }
+
+    /// Records the duration of a proof of `circuit`. Failing to save only loses the estimate,
+    /// so errors are ignored.
+    pub fn record(path: &Path, circuit: Circuit, elapsed: Duration) {
+        let mut times = Self::load(path);
+        let millis = Some(elapsed.as_millis() as u64);
+        match circuit {
+            Circuit::Challenger => times.challenger = millis,
+            Circuit::Acceptor => times.acceptor = millis,
+        }
+
+        if let Ok(content) = serde_json::to_string(&times) {
+            let _ = fs::write(path, content);
+        }
+    }
+}
+
+/// Describes the progress of a proof generation for a progress bar
+pub fn describe(progress: &ProvingProgress) -> String {
+    let elapsed = progress.elapsed.as_secs();
+
+    if progress.stage == ProvingStage::Setup {
+        return format!(
+            "Setting up the {} circuit, {}s elapsed",
+            progress.circuit, elapsed
+        );
+    }
+
+    match (progress.percent(), progress.expected) {
+        (Some(percent), Some(expected)) => format!(
+            "Generating the {} proof: {}, {}% ({}s of about {}s)",
+            progress.circuit,
+            progress.stage,
+            percent,
+            elapsed,
+            expected.as_secs()
+        ),
+        _ => format!(
+            "Generating the {} proof: {}, {}s elapsed",
+            progress.circuit, progress.stage, elapsed
+        ),
+    }
+}
+
+/// Renders the timing and size metrics of a generated proof
+pub fn render_metrics(r: &mut dyn Renderer, metrics: &ProofMetrics) {
+    r.field("Setup ms", metrics.setup_ms);
+    r.field("Prove ms", metrics.prove_ms);
+    r.field("Proof bytes", metrics.proof_bytes);
+    r.field("VK bytes", metrics.vk_bytes);
+}
+
+/// Decodes the hex encoded proof of an envelope along with the circuit hash it declares
+pub fn envelope_proof(
+    proof: &str,
+    vk: &str,
+    circuit_hash: Option<&str>,
+) -> eyre::Result<OpRandProof> {
+    let mut proof = OpRandProof::new(hex::decode(proof)?, hex::decode(vk)?);
+    if let Some(circuit_hash) = circuit_hash {
+        proof = proof.with_circuit_hash(circuit_hash.parse()?);
+    }
+
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn describes_progress() {
+        let mut progress = ProvingProgress {
+            circuit: Circuit::Challenger,
+            stage: ProvingStage::Proof,
+            elapsed: Duration::from_secs(45),
+            expected: Some(Duration::from_secs(90)),
+        };
+        assert_eq!(
+            describe(&progress),
+            "Generating the challenger proof: solving the witness and proving, 50% (45s of about 90s)"
+        );
+
+        // A slow proof never reports completion before it is done
+        progress.elapsed = Duration::from_secs(120);
+        assert_eq!(progress.percent(), Some(99));
+
+        progress.expected = None;
+        assert_eq!(
+            describe(&progress),
+            "Generating the challenger proof: solving the witness and proving, 120s elapsed"
+        );
+
+        progress.stage = ProvingStage::Setup;
+        assert_eq!(progress.percent(), None);
+        assert_eq!(
+            describe(&progress),
+            "Setting up the challenger circuit, 120s elapsed"
+        );
+    }
+
+    #[test]
+    fn renders_metrics() {
+        let metrics = ProofMetrics {
+            setup_ms: 5400,
+            prove_ms: 41250,
+            proof_bytes: 15_936,
+            vk_bytes: 1_888,
+        };
+
+        insta::assert_snapshot!(render_all(|r| render_metrics(r, &metrics)));
+    }
+}
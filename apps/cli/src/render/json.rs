@@ -0,0 +1,115 @@
+use std::io::Write;
+
+use console::Emoji;
+use indicatif::ProgressBar;
+use serde_json::{Map, Value as JsonValue};
+
+use super::{Renderer, Value, to_key};
+
+/// Collects the command result and writes it as a single JSON document on finish. Progress
+/// messages are dropped so that the output stays machine readable.
+pub struct Json<W> {
+    out: W,
+    root: Map<String, JsonValue>,
+    section: Option<Section>,
+    warnings: Vec<JsonValue>,
+}
+
+/// Section being collected
+struct Section {
+    key: String,
+    fields: Map<String, JsonValue>,
+    entries: Vec<Map<String, JsonValue>>,
+}
+
+impl<W: Write> Json<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            root: Map::new(),
+            section: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Moves the section being collected into the document
+    fn close_section(&mut self) {
+        if let Some(section) = self.section.take() {
+            let value = if section.entries.is_empty() {
+                JsonValue::Object(section.fields)
+            } else {
+                JsonValue::Array(section.entries.into_iter().map(JsonValue::Object).collect())
+            };
+            self.root.insert(section.key, value);
+        }
+    }
+}
+
+impl<W: Write> Renderer for Json<W> {
+    fn header(&mut self, _icon: &str, _title: &str) {}
+
+    fn step(&mut self, _icon: Emoji<'_, '_>, _message: &str) {}
+
+    fn done(&mut self, _message: &str) {}
+
+    fn warning(&mut self, message: &str) {
+        self.warnings.push(message.into());
+    }
+
+    fn section(&mut self, title: &str) {
+        self.close_section();
+        self.section = Some(Section {
+            key: to_key(title),
+            fields: Map::new(),
+            entries: Vec::new(),
+        });
+    }
+
+    fn entry(&mut self, _title: &str) {
+        if let Some(section) = &mut self.section {
+            section.entries.push(Map::new());
+        }
+    }
+
+    fn value(&mut self, label: &str, value: Value<'_>) {
+        let value = match value {
+            Value::Text(text) => JsonValue::String(text.into_owned()),
+            Value::Amount(amount) => amount.to_sat().into(),
+            Value::Number(number) => number.into(),
+            Value::Bool(flag) => flag.into(),
+        };
+
+        let fields = match &mut self.section {
+            Some(section) => match section.entries.last_mut() {
+                Some(entry) => entry,
+                None => &mut section.fields,
+            },
+            None => &mut self.root,
+        };
+        fields.insert(to_key(label), value);
+    }
+
+    fn text(&mut self, _text: &str) {}
+
+    fn success(&mut self, message: &str) {
+        self.root.insert("message".to_string(), message.into());
+    }
+
+    fn progress(&mut self, _message: &str) -> ProgressBar {
+        ProgressBar::hidden()
+    }
+
+    fn finish(&mut self) {
+        self.close_section();
+        if !self.warnings.is_empty() {
+            self.root.insert(
+                "warnings".to_string(),
+                JsonValue::Array(std::mem::take(&mut self.warnings)),
+            );
+        }
+
+        let document = JsonValue::Object(std::mem::take(&mut self.root));
+        serde_json::to_writer_pretty(&mut self.out, &document).expect("Failed to write output");
+        writeln!(self.out).expect("Failed to write output");
+    }
+}
@@ -0,0 +1,209 @@
+//! Output layer of the CLI. Actions describe what they print through the [`Renderer`] trait and
+//! the selected [`OutputFormat`] decides how it looks.
+
+use std::{borrow::Cow, io::Write};
+
+use bitcoin::Amount;
+use clap::ValueEnum;
+use console::Emoji;
+use indicatif::ProgressBar;
+
+mod json;
+mod plain;
+mod pretty;
+
+pub use json::Json;
+pub use plain::Plain;
+pub use pretty::Pretty;
+
+/// Format of the command output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored output with emojis and progress spinners
+    #[default]
+    Pretty,
+    /// Uncolored `label: value` lines
+    Plain,
+    /// A single JSON document with the command result
+    Json,
+}
+
+impl OutputFormat {
+    /// Creates a renderer of this format writing to `out`
+    pub fn renderer<'a, W: Write + 'a>(self, out: W) -> Box<dyn Renderer + 'a> {
+        match self {
+            OutputFormat::Pretty => Box::new(Pretty::new(out)),
+            OutputFormat::Plain => Box::new(Plain::new(out)),
+            OutputFormat::Json => Box::new(Json::new(out)),
+        }
+    }
+}
+
+/// Value of a rendered field
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'a> {
+    Text(Cow<'a, str>),
+    Amount(Amount),
+    Number(u64),
+    Bool(bool),
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(value: &'a str) -> Self {
+        Value::Text(Cow::Borrowed(value))
+    }
+}
+
+impl<'a> From<&'a String> for Value<'a> {
+    fn from(value: &'a String) -> Self {
+        Value::Text(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(value: String) -> Self {
+        Value::Text(Cow::Owned(value))
+    }
+}
+
+impl From<Amount> for Value<'_> {
+    fn from(value: Amount) -> Self {
+        Value::Amount(value)
+    }
+}
+
+impl From<u64> for Value<'_> {
+    fn from(value: u64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<u32> for Value<'_> {
+    fn from(value: u32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<usize> for Value<'_> {
+    fn from(value: usize) -> Self {
+        Value::Number(value as u64)
+    }
+}
+
+impl From<bool> for Value<'_> {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+/// Sink for everything a command prints. Progress messages are informational only, while
+/// sections and fields make up the command result and are what the JSON format emits.
+pub trait Renderer {
+    /// Prints the command banner
+    fn header(&mut self, icon: &str, title: &str);
+
+    /// Announces a step the command is about to perform
+    fn step(&mut self, icon: Emoji<'_, '_>, message: &str);
+
+    /// Reports a completed step
+    fn done(&mut self, message: &str);
+
+    /// Reports a non-fatal problem
+    fn warning(&mut self, message: &str);
+
+    /// Starts a group of fields. Fields before the first section belong to the top level.
+    fn section(&mut self, title: &str);
+
+    /// Starts a new entry of a repeated group in the current section
+    fn entry(&mut self, title: &str);
+
+    /// Prints a labeled value
+    fn value(&mut self, label: &str, value: Value<'_>);
+
+    /// Prints preformatted text such as a diagram. Like progress messages, it is not part of the
+    /// command result.
+    fn text(&mut self, text: &str);
+
+    /// Prints the final message of a successful command
+    fn success(&mut self, message: &str);
+
+    /// Starts a spinner for a long-running operation
+    fn progress(&mut self, message: &str) -> ProgressBar;
+
+    /// Completes the output
+    fn finish(&mut self);
+}
+
+impl dyn Renderer + '_ {
+    /// Prints a labeled value
+    pub fn field<'v>(&mut self, label: &str, value: impl Into<Value<'v>>) {
+        self.value(label, value.into());
+    }
+}
+
+/// Converts a label or a section title into a JSON key
+fn to_key(label: &str) -> String {
+    label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// Renders the output of `render` in every deterministic format, for snapshot tests.
+#[cfg(test)]
+pub fn render_all(render: impl Fn(&mut dyn Renderer)) -> String {
+    [OutputFormat::Plain, OutputFormat::Json]
+        .into_iter()
+        .map(|format| {
+            let mut out = Vec::new();
+            {
+                let mut renderer = format.renderer(&mut out);
+                render(renderer.as_mut());
+                renderer.finish();
+            }
+            format!(
+                "--- {:?} ---\n{}",
+                format,
+                String::from_utf8(out).expect("Output is valid UTF-8")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_snake_case() {
+        assert_eq!(to_key("Challenge ID"), "challenge_id");
+        assert_eq!(to_key("┌─ UTXO DETAILS"), "utxo_details");
+        assert_eq!(
+            to_key("Public data (share with acceptor)"),
+            "public_data_share_with_acceptor"
+        );
+    }
+
+    #[test]
+    fn renders_nested_output() {
+        insta::assert_snapshot!(render_all(|r| {
+            r.header("🧪", "TEST");
+            r.step(crate::ui::GEAR, "Doing things...");
+            r.done("Things done");
+            r.field("Challenge ID", "id");
+            r.warning("Something looks off");
+            r.section("DETAILS");
+            r.field("Amount", Amount::from_sat(1_000));
+            r.field("Confirmed", true);
+            r.section("ITEMS");
+            r.entry("Item 1");
+            r.field("Height", 10u64);
+            r.entry("Item 2");
+            r.field("Height", 11u64);
+            r.success("Done!");
+        }));
+    }
+}
@@ -0,0 +1,88 @@
+use std::{fmt::Display, io::Write};
+
+use console::Emoji;
+use indicatif::ProgressBar;
+
+use super::{Renderer, Value};
+
+/// Uncolored `label: value` lines, meant for logs and scripts
+pub struct Plain<W> {
+    out: W,
+    in_section: bool,
+    in_entry: bool,
+}
+
+impl<W: Write> Plain<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            in_section: false,
+            in_entry: false,
+        }
+    }
+
+    fn line(&mut self, line: impl Display) {
+        writeln!(self.out, "{}", line).expect("Failed to write output");
+    }
+}
+
+impl<W: Write> Renderer for Plain<W> {
+    fn header(&mut self, _icon: &str, title: &str) {
+        self.line(title);
+    }
+
+    fn step(&mut self, _icon: Emoji<'_, '_>, message: &str) {
+        self.line(message);
+    }
+
+    fn done(&mut self, message: &str) {
+        self.line(message);
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.line(format!("warning: {}", message));
+    }
+
+    fn section(&mut self, title: &str) {
+        self.in_section = true;
+        self.in_entry = false;
+        self.line(format!("\n{}", title));
+    }
+
+    fn entry(&mut self, title: &str) {
+        self.in_entry = true;
+        self.line(format!("  {}", title));
+    }
+
+    fn value(&mut self, label: &str, value: Value<'_>) {
+        let value = match value {
+            Value::Text(text) => text.into_owned(),
+            Value::Amount(amount) => format!("{} sat", amount.to_sat()),
+            Value::Number(number) => number.to_string(),
+            Value::Bool(flag) => if flag { "yes" } else { "no" }.to_string(),
+        };
+        let indent = match (self.in_section, self.in_entry) {
+            (_, true) => "    ",
+            (true, false) => "  ",
+            (false, false) => "",
+        };
+        self.line(format!("{}{}: {}", indent, label, value));
+    }
+
+    fn text(&mut self, text: &str) {
+        self.line(format!("\n{}", text.trim_end()));
+    }
+
+    fn success(&mut self, message: &str) {
+        self.line(format!("\n{}", message));
+    }
+
+    fn progress(&mut self, message: &str) -> ProgressBar {
+        self.line(message);
+        ProgressBar::hidden()
+    }
+
+    fn finish(&mut self) {
+        self.out.flush().expect("Failed to write output");
+    }
+}
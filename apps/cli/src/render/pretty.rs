@@ -0,0 +1,103 @@
+use std::{fmt::Display, io::Write};
+
+use console::{Emoji, style};
+use indicatif::ProgressBar;
+
+use super::{Renderer, Value};
+use crate::{
+    context::setup_progress_bar,
+    format::format_bitcoin_amount,
+    ui::{self, CHECK, WARNING},
+};
+
+/// Colored output with emojis and progress spinners, meant for terminals
+pub struct Pretty<W> {
+    out: W,
+    in_section: bool,
+    entries: usize,
+}
+
+impl<W: Write> Pretty<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            in_section: false,
+            entries: 0,
+        }
+    }
+
+    fn line(&mut self, line: impl Display) {
+        writeln!(self.out, "{}", line).expect("Failed to write output");
+    }
+}
+
+impl<W: Write> Renderer for Pretty<W> {
+    fn header(&mut self, icon: &str, title: &str) {
+        let title = format!("{} {} {}", icon, title, icon);
+        self.line(ui::header(&format!("{:^78}", title)));
+    }
+
+    fn step(&mut self, icon: Emoji<'_, '_>, message: &str) {
+        self.line(format!("\n{} {}", icon, style(message).bold().blue()));
+    }
+
+    fn done(&mut self, message: &str) {
+        self.line(format!("{} {}", CHECK, style(message).bold().green()));
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.line(format!("{} {}", WARNING, style(message).bold().yellow()));
+    }
+
+    fn section(&mut self, title: &str) {
+        self.in_section = true;
+        self.entries = 0;
+        self.line(format!("\n{}\n│", ui::section_header(title)));
+    }
+
+    fn entry(&mut self, title: &str) {
+        if self.entries > 0 {
+            self.line("│");
+        }
+        self.entries += 1;
+        self.line(format!("│ {}", style(title).bold()));
+    }
+
+    fn value(&mut self, label: &str, value: Value<'_>) {
+        let value = match value {
+            Value::Text(text) => style(text).bright().white().to_string(),
+            Value::Amount(amount) => format_bitcoin_amount(amount),
+            Value::Number(number) => style(number).bright().cyan().to_string(),
+            Value::Bool(flag) => style(if flag { "yes" } else { "no" })
+                .bright()
+                .white()
+                .to_string(),
+        };
+        let label = format!("{}:", label);
+
+        let line = if self.entries > 0 {
+            format!("│   {} {}", style(label).dim(), value)
+        } else if self.in_section {
+            format!("│ {} {}", style(label).bold().yellow(), value)
+        } else {
+            format!("{} {}", style(label).bold().yellow(), value)
+        };
+        self.line(line);
+    }
+
+    fn text(&mut self, text: &str) {
+        self.line(format!("\n{}", text.trim_end()));
+    }
+
+    fn success(&mut self, message: &str) {
+        self.line(ui::success_footer(message));
+    }
+
+    fn progress(&mut self, message: &str) -> ProgressBar {
+        setup_progress_bar(message.to_string())
+    }
+
+    fn finish(&mut self) {
+        self.out.flush().expect("Failed to write output");
+    }
+}
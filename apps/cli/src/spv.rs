@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+use bitcoin::{
+    Network, Txid,
+    block::Header,
+    consensus::params::Params,
+    hashes::{Hash, sha256d},
+};
+use color_eyre::eyre::{self, ensure};
+
+use crate::esplora::EsploraClient;
+
+/// Verifies that `txid` is included in the chain served by `esplora_client` without trusting the
+/// server's word: the merkle proof is checked against the block header, and every header from
+/// that block up to the tip must carry valid proof of work and link to its predecessor.
+///
+/// Returns the number of confirmations counted over the validated headers.
+pub async fn verify_inclusion(
+    esplora_client: &EsploraClient,
+    network: Network,
+    txid: &Txid,
+) -> eyre::Result<u64> {
+    let proof = esplora_client.get_merkle_proof(txid).await?;
+
+    let block_hash = esplora_client.get_block_hash(proof.block_height).await?;
+    let header = fetch_validated_header(esplora_client, network, &block_hash).await?;
+
+    let merkle_root = merkle_root_from_branch(txid, &proof.merkle, proof.pos)?;
+    ensure!(
+        merkle_root == header.merkle_root.to_raw_hash(),
+        "Merkle proof for {} does not match the block header",
+        txid
+    );
+
+    let tip_height = esplora_client.get_tip_height().await?;
+    ensure!(
+        tip_height >= proof.block_height,
+        "Chain tip is below the block including {}",
+        txid
+    );
+
+    let mut prev_hash = header.block_hash();
+    for height in proof.block_height + 1..=tip_height {
+        let block_hash = esplora_client.get_block_hash(height).await?;
+        let header = fetch_validated_header(esplora_client, network, &block_hash).await?;
+
+        ensure!(
+            header.prev_blockhash == prev_hash,
+            "Header at height {} does not extend the previous header",
+            height
+        );
+
+        prev_hash = header.block_hash();
+    }
+
+    Ok(tip_height - proof.block_height + 1)
+}
+
+/// Fetches a header and checks that it hashes to `block_hash` and has valid proof of work within
+/// the network's limit.
+async fn fetch_validated_header(
+    esplora_client: &EsploraClient,
+    network: Network,
+    block_hash: &str,
+) -> eyre::Result<Header> {
+    let header = esplora_client.get_block_header(block_hash).await?;
+
+    ensure!(
+        header.block_hash().to_string() == block_hash,
+        "Header does not hash to {}",
+        block_hash
+    );
+
+    let target = header.target();
+    ensure!(
+        target <= Params::new(network).max_attainable_target,
+        "Header {} exceeds the network proof of work limit",
+        block_hash
+    );
+    header
+        .validate_pow(target)
+        .map_err(|e| eyre::eyre!("Invalid proof of work in header {}: {}", block_hash, e))?;
+
+    Ok(header)
+}
+
+/// Computes the merkle root from a transaction id and its Electrum-style merkle branch.
+fn merkle_root_from_branch(
+    txid: &Txid,
+    branch: &[String],
+    pos: u32,
+) -> eyre::Result<sha256d::Hash> {
+    let mut hash = txid.to_raw_hash();
+
+    for (level, sibling) in branch.iter().enumerate() {
+        let sibling = sha256d::Hash::from_str(sibling)?;
+
+        let mut concat = Vec::with_capacity(64);
+        if (pos >> level) & 1 == 1 {
+            concat.extend_from_slice(sibling.as_byte_array());
+            concat.extend_from_slice(hash.as_byte_array());
+        } else {
+            concat.extend_from_slice(hash.as_byte_array());
+            concat.extend_from_slice(sibling.as_byte_array());
+        }
+
+        hash = sha256d::Hash::hash(&concat);
+    }
+
+    Ok(hash)
+}
@@ -0,0 +1,528 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::{
+    OutPoint, Transaction, TxOut, Txid, consensus,
+    hashes::{Hash, sha256},
+};
+use color_eyre::{eyre, eyre::Context as _};
+use op_rand_types::Capabilities;
+use serde::{Deserialize, Serialize};
+
+/// Local state store persisted as a single JSON file. It keeps track of the challenges this
+/// wallet took part in, so that commands can reason about history across invocations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Store {
+    /// Path the store is loaded from and saved to.
+    #[serde(skip)]
+    path: PathBuf,
+
+    /// Challenges keyed by challenge ID.
+    #[serde(default)]
+    pub challenges: BTreeMap<String, ChallengeRecord>,
+
+    /// User metadata about wallet UTXOs.
+    #[serde(default)]
+    pub utxos: BTreeMap<OutPoint, UtxoRecord>,
+
+    /// Protocol transactions broadcast by this wallet, keyed by txid.
+    #[serde(default)]
+    pub transactions: BTreeMap<Txid, TransactionRecord>,
+
+    /// Protocol messages created by this wallet, keyed by message hash.
+    #[serde(default)]
+    pub outbox: BTreeMap<String, MessageRecord>,
+
+    /// Protocol messages received from counterparties, keyed by message hash.
+    #[serde(default)]
+    pub inbox: BTreeMap<String, MessageRecord>,
+
+    /// Challengers this wallet accepted challenges from, keyed by their wallet public key.
+    #[serde(default)]
+    pub contacts: BTreeMap<String, ContactRecord>,
+
+    /// Acceptances whose proof was not generated yet, keyed by challenge ID.
+    #[serde(default)]
+    pub pending_acceptances: BTreeMap<String, PendingAcceptance>,
+}
+
+/// Role this wallet played in a challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Challenger,
+    Acceptor,
+}
+
+/// Information recorded about a challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub role: Role,
+
+    /// Wallet public key used to fund and sign for the challenge.
+    pub wallet_pubkey: String,
+
+    /// Script pubkey receiving the change, if any.
+    pub change_script: Option<String>,
+
+    /// Wallet UTXOs funding the challenge. They stay reserved for this challenge.
+    #[serde(default)]
+    pub funding_outpoints: Vec<OutPoint>,
+
+    /// Deposit output spent by the challenge transaction.
+    #[serde(default)]
+    pub deposit_outpoint: Option<OutPoint>,
+
+    /// Challenge output witness script hex, known once the challenge is accepted.
+    #[serde(default)]
+    pub witness_script: Option<String>,
+
+    /// Block height from which an acceptance can no longer be completed. The funding UTXOs
+    /// are released then.
+    #[serde(default)]
+    pub completion_deadline: Option<u32>,
+
+    /// User notes about the challenge.
+    #[serde(default)]
+    pub metadata: ChallengeMetadata,
+
+    /// Challenge this one re-offers.
+    #[serde(default)]
+    pub reoffer_of: Option<String>,
+
+    /// Re-offer replacing this challenge. A superseded challenge can't be completed.
+    #[serde(default)]
+    pub superseded_by: Option<String>,
+
+    /// Wallet public key of the challenger, for acceptances. Acceptors don't reveal theirs.
+    #[serde(default)]
+    pub counterparty_pubkey: Option<String>,
+}
+
+/// User notes about a challenge, to tell many challenges apart
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChallengeMetadata {
+    pub label: Option<String>,
+
+    /// Name of the other party.
+    pub counterparty: Option<String>,
+
+    pub memo: Option<String>,
+
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+}
+
+/// What was verified about a challenger in earlier games. Later challenges from them are
+/// compared against it, so that a change of circuits or policy stands out.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContactRecord {
+    /// SHA-256 of the verification key sent with their last verified proof, hex encoded.
+    pub vk_hash: Option<String>,
+
+    /// Capabilities advertised in their last challenge.
+    #[serde(default)]
+    pub capabilities: Capabilities,
+
+    /// Acceptances of their challenges that expired with the funding UTXOs still unspent.
+    #[serde(default)]
+    pub disputes: u32,
+}
+
+/// An acceptance whose challenge transaction was built but whose proof was not generated yet.
+/// Accepting the challenge again resumes from the proving step with it, so that a failed proof
+/// doesn't lose the UTXO selection and the transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingAcceptance {
+    /// Hash of the challenge data the acceptance was built for, see [`message_hash`].
+    pub challenge_hash: String,
+
+    /// Index of the accepted commitment.
+    pub selected_commitment: usize,
+
+    /// Script pubkey receiving the change, if any.
+    pub change_script: Option<String>,
+
+    /// Wallet UTXOs funding the challenge. They stay reserved until the acceptance is done.
+    pub funding_outpoints: Vec<OutPoint>,
+
+    /// Signed challenge transaction PSBT, base64 encoded.
+    pub psbt: String,
+
+    /// Challenge output witness script hex.
+    pub witness_script: String,
+
+    /// `--change-pubkey` the transaction was built with.
+    #[serde(default)]
+    pub change_pubkey: Option<String>,
+
+    /// `--exclude-utxo` outpoints the transaction was built with.
+    #[serde(default)]
+    pub exclude_utxos: BTreeSet<OutPoint>,
+
+    /// Sponsor change output the transaction was built with, see `--sponsor-change`.
+    #[serde(default)]
+    pub sponsor_change: Option<TxOut>,
+}
+
+/// User metadata about a wallet UTXO
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UtxoRecord {
+    pub label: Option<String>,
+
+    /// Frozen UTXOs are never used to fund a challenge.
+    #[serde(default)]
+    pub frozen: bool,
+}
+
+/// A protocol transaction broadcast by this wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub challenge_id: String,
+
+    /// Consensus-encoded transaction hex.
+    pub raw_tx: String,
+
+    /// Unknown for transactions recorded by older versions.
+    #[serde(default)]
+    pub kind: Option<BroadcastKind>,
+
+    /// Unix time the transaction was first recorded at.
+    #[serde(default)]
+    pub recorded_at: Option<u64>,
+
+    /// Height of the block including the transaction, as last seen by `txlog`.
+    #[serde(default)]
+    pub block_height: Option<u64>,
+}
+
+/// Kind of a protocol transaction broadcast by this wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastKind {
+    /// Challenger's transaction anchoring a beacon selection, funding the deposit.
+    #[serde(rename = "beacon_anchor")]
+    BeaconAnchor,
+    Deposit,
+    Stake,
+    Challenge,
+    Sweep,
+    Refund,
+}
+
+impl BroadcastKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BroadcastKind::BeaconAnchor => "beacon_anchor",
+            BroadcastKind::Deposit => "deposit",
+            BroadcastKind::Stake => "stake",
+            BroadcastKind::Challenge => "challenge",
+            BroadcastKind::Sweep => "sweep",
+            BroadcastKind::Refund => "refund",
+        }
+    }
+}
+
+/// Kind of a protocol message exchanged between the parties
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageKind {
+    /// Public challenge data sent by the Challenger.
+    Challenge,
+    /// Acceptor data sent by the Acceptor.
+    Acceptance,
+    /// Funding UTXOs disclosed by the Acceptor before proving.
+    #[serde(rename = "pre_acceptance")]
+    PreAcceptance,
+    /// Challenger's acknowledgement or veto of the disclosed funding.
+    #[serde(rename = "pre_acceptance_ack")]
+    PreAcceptanceAck,
+}
+
+impl MessageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageKind::Challenge => "challenge",
+            MessageKind::Acceptance => "acceptance",
+            MessageKind::PreAcceptance => "pre_acceptance",
+            MessageKind::PreAcceptanceAck => "pre_acceptance_ack",
+        }
+    }
+}
+
+/// A protocol message queued for delivery or received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageRecord {
+    pub challenge_id: String,
+
+    pub kind: MessageKind,
+
+    /// JSON document of the message, exactly as written to or read from a file.
+    pub content: String,
+
+    /// Whether the message was delivered to a transport. Only used by outbox messages.
+    #[serde(default)]
+    pub delivered: bool,
+
+    /// Unix time the message was queued or received at. Unknown for messages recorded by older
+    /// versions.
+    #[serde(default)]
+    pub recorded_at: Option<u64>,
+}
+
+/// Current Unix time, if the system clock is after the epoch
+pub fn unix_time() -> Option<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|time| time.as_secs())
+}
+
+/// Returns the hash identifying a protocol message
+pub fn message_hash(content: &str) -> String {
+    sha256::Hash::hash(content.as_bytes()).to_string()
+}
+
+impl Store {
+    /// Loads the store from `path`, returning an empty store if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+
+        let mut store = if path.exists() {
+            let content = fs::read_to_string(path)
+                .wrap_err_with(|| format!("Failed to read store {}", path.display()))?;
+            serde_json::from_str::<Store>(&content)
+                .wrap_err_with(|| format!("Failed to parse store {}", path.display()))?
+        } else {
+            Store::default()
+        };
+
+        store.path = path.to_path_buf();
+
+        Ok(store)
+    }
+
+    /// Saves the store, replacing the file atomically.
+    pub fn save(&self) -> eyre::Result<()> {
+        let tmp_path = self.path.with_extension("tmp");
+
+        fs::write(&tmp_path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("Failed to write store {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path)
+            .wrap_err_with(|| format!("Failed to replace store {}", self.path.display()))?;
+
+        Ok(())
+    }
+
+    /// Returns the IDs of challenges that used the given wallet public key or change script.
+    pub fn challenges_reusing(
+        &self,
+        wallet_pubkey: &str,
+        change_script: Option<&str>,
+    ) -> Vec<&str> {
+        self.challenges
+            .iter()
+            .filter(|(_, record)| {
+                record.wallet_pubkey == wallet_pubkey
+                    || (change_script.is_some() && record.change_script.as_deref() == change_script)
+            })
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+
+    /// Returns the ID of the challenge that reserved the given UTXO, if any. UTXOs of pending
+    /// acceptances are reserved too.
+    pub fn reservation(&self, outpoint: &OutPoint) -> Option<&str> {
+        self.challenges
+            .iter()
+            .map(|(id, record)| (id, &record.funding_outpoints))
+            .chain(
+                self.pending_acceptances
+                    .iter()
+                    .map(|(id, pending)| (id, &pending.funding_outpoints)),
+            )
+            .find(|(_, funding_outpoints)| funding_outpoints.contains(outpoint))
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// Releases the UTXOs reserved by acceptances whose completion deadline passed at
+    /// `tip_height` and returns the IDs of those challenges with the released UTXOs.
+    pub fn release_expired(&mut self, tip_height: u64) -> Vec<(String, Vec<OutPoint>)> {
+        self.challenges
+            .iter_mut()
+            .filter(|(_, record)| {
+                !record.funding_outpoints.is_empty()
+                    && record
+                        .completion_deadline
+                        .is_some_and(|deadline| tip_height >= u64::from(deadline))
+            })
+            .map(|(id, record)| (id.clone(), std::mem::take(&mut record.funding_outpoints)))
+            .collect()
+    }
+
+    /// Releases the UTXOs reserved by the challenge and returns them.
+    pub fn release(&mut self, challenge_id: &str) -> Vec<OutPoint> {
+        self.challenges
+            .get_mut(challenge_id)
+            .map(|record| std::mem::take(&mut record.funding_outpoints))
+            .unwrap_or_default()
+    }
+
+    /// Returns whether any reservation has a completion deadline.
+    pub fn has_deadlines(&self) -> bool {
+        self.challenges.values().any(|record| {
+            !record.funding_outpoints.is_empty() && record.completion_deadline.is_some()
+        })
+    }
+
+    /// Records `record` as the re-offer `new_id` of the challenge `old_id`. The funding UTXOs
+    /// move to the re-offer, and messages of the old challenge that were not delivered yet are
+    /// dropped.
+    pub fn supersede(&mut self, old_id: &str, new_id: &str, mut record: ChallengeRecord) {
+        if let Some(old) = self.challenges.get_mut(old_id) {
+            record.funding_outpoints.append(&mut old.funding_outpoints);
+            old.superseded_by = Some(new_id.to_string());
+        }
+        record.reoffer_of = Some(old_id.to_string());
+        self.challenges.insert(new_id.to_string(), record);
+
+        self.outbox
+            .retain(|_, message| message.delivered || message.challenge_id != old_id);
+    }
+
+    /// Returns the number of challenges accepted from the challenger with wallet key `pubkey`.
+    pub fn games_with(&self, pubkey: &str) -> usize {
+        self.challenges
+            .values()
+            .filter(|record| record.counterparty_pubkey.as_deref() == Some(pubkey))
+            .count()
+    }
+
+    /// Counts a dispute against the challenger of the challenge, if known.
+    pub fn record_dispute(&mut self, challenge_id: &str) {
+        if let Some(pubkey) = self
+            .challenges
+            .get(challenge_id)
+            .and_then(|record| record.counterparty_pubkey.as_ref())
+        {
+            self.contacts.entry(pubkey.clone()).or_default().disputes += 1;
+        }
+    }
+
+    /// Returns whether an acceptance of the challenge was received.
+    pub fn is_accepted(&self, challenge_id: &str) -> bool {
+        self.inbox.values().any(|message| {
+            message.challenge_id == challenge_id && message.kind == MessageKind::Acceptance
+        })
+    }
+
+    /// Returns the message of the given kind created or received for the challenge, if any.
+    pub fn find_message(&self, challenge_id: &str, kind: MessageKind) -> Option<&MessageRecord> {
+        self.outbox
+            .values()
+            .chain(self.inbox.values())
+            .find(|message| message.challenge_id == challenge_id && message.kind == kind)
+    }
+
+    /// Returns whether the given UTXO is frozen.
+    pub fn is_frozen(&self, outpoint: &OutPoint) -> bool {
+        self.utxos.get(outpoint).is_some_and(|record| record.frozen)
+    }
+
+    /// Queues a protocol message for delivery and returns its hash. Queuing the same message
+    /// twice keeps a single copy.
+    pub fn queue_message(
+        &mut self,
+        challenge_id: &str,
+        kind: MessageKind,
+        content: &str,
+    ) -> String {
+        let hash = message_hash(content);
+
+        self.outbox
+            .entry(hash.clone())
+            .or_insert_with(|| MessageRecord {
+                challenge_id: challenge_id.to_string(),
+                kind,
+                content: content.to_string(),
+                delivered: false,
+                recorded_at: unix_time(),
+            });
+
+        hash
+    }
+
+    /// Records a received protocol message. Returns `false` if it was already received.
+    pub fn receive_message(
+        &mut self,
+        challenge_id: &str,
+        kind: MessageKind,
+        content: &str,
+    ) -> bool {
+        let hash = message_hash(content);
+        if self.inbox.contains_key(&hash) {
+            return false;
+        }
+
+        self.inbox.insert(
+            hash,
+            MessageRecord {
+                challenge_id: challenge_id.to_string(),
+                kind,
+                content: content.to_string(),
+                delivered: false,
+                recorded_at: unix_time(),
+            },
+        );
+
+        true
+    }
+
+    /// Records a protocol transaction about to be broadcast and returns its txid. A transaction
+    /// recorded again keeps the time it was first recorded at.
+    pub fn record_transaction(
+        &mut self,
+        challenge_id: &str,
+        kind: BroadcastKind,
+        raw_tx: &str,
+    ) -> eyre::Result<Txid> {
+        let tx: Transaction = consensus::encode::deserialize_hex(raw_tx)?;
+        let txid = tx.compute_txid();
+
+        self.transactions
+            .entry(txid)
+            .or_insert_with(|| TransactionRecord {
+                challenge_id: challenge_id.to_string(),
+                raw_tx: raw_tx.to_string(),
+                kind: Some(kind),
+                recorded_at: unix_time(),
+                block_height: None,
+            });
+
+        Ok(txid)
+    }
+
+    /// Moves a transaction recorded before the challenge it belongs to had an ID to the
+    /// challenge.
+    pub fn assign_transaction(&mut self, txid: &Txid, challenge_id: &str) {
+        if let Some(record) = self.transactions.get_mut(txid) {
+            record.challenge_id = challenge_id.to_string();
+        }
+    }
+
+    /// Transactions recorded for a challenge, in the order they were recorded
+    pub fn challenge_transactions(&self, challenge_id: &str) -> Vec<(&Txid, &TransactionRecord)> {
+        let mut transactions = self
+            .transactions
+            .iter()
+            .filter(|(_, record)| record.challenge_id == challenge_id)
+            .collect::<Vec<_>>();
+        transactions.sort_by_key(|(_, record)| record.recorded_at);
+
+        transactions
+    }
+}
@@ -0,0 +1,209 @@
+//! Opt-in reporting of protocol failures.
+//!
+//! Reports only carry the category of a failure, the CLI version and the network. Error
+//! messages are never reported, as they may contain keys, amounts, addresses or txids.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bitcoin::Network;
+use color_eyre::eyre;
+use op_rand_prover::ProverError;
+use op_rand_transaction_builder::TransactionError;
+use serde::Serialize;
+
+use crate::{context::Context, esplora::BroadcastRejected, util::InputConflict};
+
+/// Timeout of a report sent to the configured endpoint
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Rejection reasons of the node mempool policy and consensus checks. A rejection message is
+/// reported as the first reason it contains, so that nothing else of the message is leaked.
+const REJECTION_REASONS: &[&str] = &[
+    "min relay fee not met",
+    "mempool min fee not met",
+    "insufficient fee",
+    "max-fee-exceeded",
+    "dust",
+    "txn-mempool-conflict",
+    "txn-already-in-mempool",
+    "txn-already-known",
+    "bad-txns-inputs-missingorspent",
+    "bad-txns-in-belowout",
+    "non-BIP68-final",
+    "non-final",
+    "too-long-mempool-chain",
+    "tx-size",
+    "scriptpubkey",
+    "non-mandatory-script-verify-flag",
+    "mandatory-script-verify-flag-failed",
+];
+
+/// Category of a reported failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "category", rename_all = "kebab-case")]
+pub enum FailureCategory {
+    ProofVerification,
+    ProofGeneration,
+    CircuitSetup,
+    PsbtFinalization,
+    BroadcastRejected {
+        /// One of the known rejection reasons, or `other`.
+        reason: &'static str,
+    },
+    /// The counterparty double-spent an input they signed
+    CounterpartyFault,
+}
+
+/// Reported failure
+#[derive(Debug, Serialize)]
+pub struct FailureReport {
+    pub version: &'static str,
+    pub network: Network,
+    #[serde(flatten)]
+    pub category: FailureCategory,
+    /// Days since the Unix epoch. The time is coarse, so that a report can't be matched to the
+    /// transactions of a challenge.
+    pub day: Option<u64>,
+}
+
+/// Returns the category of a failure, or `None` if it is not a protocol failure.
+pub fn classify(err: &eyre::Report) -> Option<FailureCategory> {
+    err.chain().find_map(|cause| {
+        if let Some(err) = cause.downcast_ref::<ProverError>() {
+            return match err {
+                ProverError::ProofVerificationError(_)
+                | ProverError::InvalidProof
+                | ProverError::PublicInputsMismatch
+                | ProverError::InvalidPublicInputs(_)
+                | ProverError::InvalidNumberOfPublicSignals { .. }
+                | ProverError::InvalidNumberOfCommitments { .. }
+                | ProverError::CircuitMismatch { .. }
+                | ProverError::VerificationKeyMismatch(_) => {
+                    Some(FailureCategory::ProofVerification)
+                }
+                ProverError::UnsatisfiedWitness { .. }
+                | ProverError::BackendError { .. }
+                | ProverError::MalformedWitness { .. } => Some(FailureCategory::ProofGeneration),
+                ProverError::SetupError(_)
+                | ProverError::SrsError(_)
+                | ProverError::UnexpectedCircuit { .. }
+                | ProverError::CircuitNotPrepared(_)
+                | ProverError::CircuitLoadError(..) => Some(FailureCategory::CircuitSetup),
+                ProverError::DaemonError(_)
+                | ProverError::Cancelled
+                | ProverError::TimedOut { .. } => None,
+            };
+        }
+        if let Some(err) = cause.downcast_ref::<TransactionError>() {
+            return match err {
+                TransactionError::PsbtFinalizationFailed(_)
+                | TransactionError::ExtractTransactionFailed => {
+                    Some(FailureCategory::PsbtFinalization)
+                }
+                _ => None,
+            };
+        }
+        if cause.downcast_ref::<InputConflict>().is_some() {
+            return Some(FailureCategory::CounterpartyFault);
+        }
+        cause
+            .downcast_ref::<BroadcastRejected>()
+            .map(|err| FailureCategory::BroadcastRejected {
+                reason: rejection_reason(&err.message),
+            })
+    })
+}
+
+/// Returns the known rejection reason contained in a rejection message
+fn rejection_reason(message: &str) -> &'static str {
+    REJECTION_REASONS
+        .iter()
+        .find(|reason| message.contains(*reason))
+        .copied()
+        .unwrap_or("other")
+}
+
+/// Reports a failed command if telemetry is enabled. Reporting is best effort, its own failures
+/// are ignored so that they never hide the failure of the command.
+pub async fn report_failure(ctx: &mut Context, err: &eyre::Report) {
+    let Some(category) = classify(err) else {
+        return;
+    };
+    let Ok(cfg) = ctx.config() else {
+        return;
+    };
+    if !cfg.telemetry.enabled {
+        return;
+    }
+
+    let report = FailureReport {
+        version: env!("CARGO_PKG_VERSION"),
+        network: cfg.network,
+        category,
+        day: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|time| time.as_secs() / SECONDS_PER_DAY),
+    };
+    let Ok(line) = serde_json::to_string(&report) else {
+        return;
+    };
+
+    if let Some(parent) = cfg.telemetry.file.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&cfg.telemetry.file)
+    {
+        let _ = writeln!(file, "{line}");
+    }
+
+    if let Some(endpoint) = &cfg.telemetry.endpoint {
+        let _ = reqwest::Client::new()
+            .post(endpoint)
+            .timeout(ENDPOINT_TIMEOUT)
+            .json(&report)
+            .send()
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::WrapErr;
+    use reqwest::StatusCode;
+
+    use super::*;
+
+    #[test]
+    fn classifies_protocol_failures() {
+        let verification = Err::<(), _>(ProverError::InvalidProof)
+            .wrap_err("Failed to verify the challenger proof")
+            .unwrap_err();
+        assert_eq!(
+            classify(&verification),
+            Some(FailureCategory::ProofVerification)
+        );
+
+        let rejection = eyre::Report::new(BroadcastRejected {
+            status: StatusCode::BAD_REQUEST,
+            message: "sendrawtransaction RPC error: {\"code\":-26,\"message\":\"min relay fee not met, 110 < 141\"}".to_string(),
+        });
+        assert_eq!(
+            classify(&rejection),
+            Some(FailureCategory::BroadcastRejected {
+                reason: "min relay fee not met"
+            })
+        );
+
+        assert_eq!(classify(&eyre::eyre!("Challenge not found")), None);
+    }
+}
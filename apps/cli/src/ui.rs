@@ -7,8 +7,7 @@ pub static KEY: Emoji<'_, '_> = Emoji("�� ", "");
 pub static CHAIN: Emoji<'_, '_> = Emoji("⛓️ ", "");
 pub static RADIO: Emoji<'_, '_> = Emoji("📡 ", "");
 pub static SPARKLES: Emoji<'_, '_> = Emoji("✨ ", "");
-pub static CLOCK: Emoji<'_, '_> = Emoji("⏰ ", "");
-pub static TARGET: Emoji<'_, '_> = Emoji("🎯 ", "");
+pub static WARNING: Emoji<'_, '_> = Emoji("⚠️ ", "");
 
 pub fn header(text: &str) -> String {
     format!(
@@ -31,12 +30,3 @@ pub fn success_footer(text: &str) -> String {
         "═".repeat(80)
     )
 }
-
-pub fn format_bitcoin_amount(satoshis: u64) -> String {
-    let btc_amount = satoshis as f64 / 100_000_000.0;
-    format!(
-        "{} satoshis ({} BTC)",
-        style(satoshis.to_string()).bright().green().bold(),
-        style(format!("{:.8}", btc_amount)).bright().green()
-    )
-}
@@ -1,27 +1,341 @@
-use color_eyre::eyre::ensure;
+use std::str::FromStr;
 
-use crate::esplora::Utxo;
+use bitcoin::{
+    Address, Amount, Network, OutPoint, PublicKey, ScriptBuf, Txid, address::NetworkUnchecked,
+    amount::CheckedSum,
+};
+use color_eyre::eyre::{OptionExt, bail, ensure};
+use op_rand_transaction_builder::{FeePolicy, TransactionKind};
+use op_rand_types::BeaconSource;
 
-pub const FEES: u64 = 300;
-pub const MIN_CHANGE: u64 = 500;
+use crate::{
+    config::{AddressReusePolicy, DenominationConfig, DenominationPolicy, DrandChainConfig},
+    drand,
+    esplora::{EsploraClient, Utxo, UtxoStatus},
+    render::Renderer,
+    store::Store,
+};
 
-pub fn select_utxos(utxos: Vec<Utxo>, amount: u64) -> eyre::Result<Vec<Utxo>> {
+/// Selects UTXOs until they cover `amount` plus the fee of the transaction spending them, which
+/// is built by `kind` from the number of selected UTXOs.
+pub fn select_utxos(
+    utxos: Vec<Utxo>,
+    amount: Amount,
+    policy: &FeePolicy,
+    kind: impl Fn(usize) -> TransactionKind,
+) -> eyre::Result<Vec<Utxo>> {
     let mut selected_utxos = Vec::new();
-    let mut remaining_amount = amount;
+    let mut selected_amount = Amount::ZERO;
 
     for utxo in utxos {
-        if remaining_amount == 0 {
-            break;
+        selected_amount = selected_amount
+            .checked_add(utxo.value)
+            .ok_or_eyre("UTXO values overflow")?;
+        selected_utxos.push(utxo);
+
+        let needed = amount
+            .checked_add(policy.fee(kind(selected_utxos.len()))?)
+            .ok_or_eyre("Amount overflow")?;
+        if selected_amount >= needed {
+            return Ok(selected_utxos);
         }
+    }
 
-        remaining_amount = remaining_amount.saturating_sub(utxo.value);
-        selected_utxos.push(utxo);
+    bail!("Not enough UTXOs to cover the amount and fees")
+}
+
+/// Computes the change left after spending `amount` plus `fee` from the selected UTXOs.
+/// Change below the policy's minimum change is left to the miners.
+pub fn compute_change(
+    selected_utxos: &[Utxo],
+    amount: Amount,
+    fee: Amount,
+    policy: &FeePolicy,
+) -> eyre::Result<Option<Amount>> {
+    let inputs_sum = selected_utxos
+        .iter()
+        .map(|utxo| utxo.value)
+        .checked_sum()
+        .ok_or_eyre("UTXO values overflow")?;
+    let spent = amount.checked_add(fee).ok_or_eyre("Amount overflow")?;
+
+    Ok(policy.change(inputs_sum, spent)?)
+}
+
+/// Resolves the destination of a sweep or refund from a recipient address or public key,
+/// checking that the address belongs to `network`. Returns `None` if neither is given.
+pub fn recipient_script(
+    address: Option<Address<NetworkUnchecked>>,
+    pubkey: Option<&str>,
+    network: Network,
+) -> eyre::Result<Option<ScriptBuf>> {
+    if let Some(address) = address {
+        return Ok(Some(address.require_network(network)?.script_pubkey()));
+    }
+
+    pubkey
+        .map(|pubkey| {
+            Ok(ScriptBuf::new_p2wpkh(
+                &PublicKey::from_str(pubkey)?.wpubkey_hash()?,
+            ))
+        })
+        .transpose()
+}
+
+/// Applies coin control to the wallet UTXOs. If `include` is not empty, only those UTXOs are
+/// returned, in the given order. Otherwise excluded, frozen and reserved UTXOs are filtered out.
+pub fn apply_coin_control(
+    utxos: Vec<Utxo>,
+    include: &[OutPoint],
+    exclude: &[OutPoint],
+    store: &Store,
+) -> eyre::Result<Vec<Utxo>> {
+    let mut wallet_utxos = utxos
+        .into_iter()
+        .map(|utxo| Ok((utxo.outpoint()?, utxo)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    if include.is_empty() {
+        wallet_utxos.retain(|(outpoint, _)| {
+            !exclude.contains(outpoint)
+                && !store.is_frozen(outpoint)
+                && store.reservation(outpoint).is_none()
+        });
+
+        return Ok(wallet_utxos.into_iter().map(|(_, utxo)| utxo).collect());
+    }
+
+    include
+        .iter()
+        .map(|outpoint| {
+            ensure!(
+                !exclude.contains(outpoint),
+                "UTXO {} is both included and excluded",
+                outpoint
+            );
+
+            ensure!(!store.is_frozen(outpoint), "UTXO {} is frozen", outpoint);
+
+            if let Some(id) = store.reservation(outpoint) {
+                bail!("UTXO {} is reserved by challenge {}", outpoint, id);
+            }
+
+            let index = wallet_utxos
+                .iter()
+                .position(|(wallet_outpoint, _)| wallet_outpoint == outpoint)
+                .ok_or_else(|| eyre::eyre!("UTXO {} is not in the wallet UTXO set", outpoint))?;
+
+            Ok(wallet_utxos.swap_remove(index).1)
+        })
+        .collect()
+}
+
+/// Releases the UTXOs reserved by expired acceptances, so that coin selection can use them again.
+/// Spending them makes the expired acceptance impossible to complete.
+pub async fn release_expired_reservations(
+    r: &mut dyn Renderer,
+    esplora_client: &EsploraClient,
+    store: &mut Store,
+) -> eyre::Result<()> {
+    if !store.has_deadlines() {
+        return Ok(());
+    }
+
+    let tip_height = esplora_client.get_tip_height().await?;
+    for (id, outpoints) in store.release_expired(tip_height) {
+        r.warning(&format!(
+            "Acceptance of challenge {} expired, its UTXOs are released",
+            id
+        ));
+
+        // The challenge transaction spends the funding UTXOs, so unspent ones mean the
+        // challenger never completed the acceptance
+        if let Some(outpoint) = outpoints.first()
+            && !esplora_client
+                .get_outspend(&outpoint.txid, outpoint.vout)
+                .await?
+                .spent
+        {
+            store.record_dispute(&id);
+        }
+    }
+
+    Ok(())
+}
+
+/// An input the counterparty signed for a protocol transaction was spent by another transaction
+#[derive(Debug, thiserror::Error)]
+#[error("Input {outpoint} of the challenge transaction was double-spent by {spending_txid}")]
+pub struct InputConflict {
+    pub outpoint: OutPoint,
+    pub spending_txid: Txid,
+}
+
+/// Returns the first of `inputs` that is spent by a transaction other than the ones recorded
+/// for the challenge. Unconfirmed spends count, so a conflict is found before the protocol
+/// transaction is broadcast.
+pub async fn find_input_conflict(
+    esplora_client: &EsploraClient,
+    store: &Store,
+    challenge_id: &str,
+    inputs: impl IntoIterator<Item = OutPoint>,
+) -> eyre::Result<Option<InputConflict>> {
+    for outpoint in inputs {
+        let outspend = esplora_client
+            .get_outspend(&outpoint.txid, outpoint.vout)
+            .await?;
+        let Some(spending_txid) = outspend.txid.filter(|_| outspend.spent) else {
+            continue;
+        };
+        let spending_txid = Txid::from_str(&spending_txid)?;
+
+        if store
+            .transactions
+            .get(&spending_txid)
+            .is_some_and(|record| record.challenge_id == challenge_id)
+        {
+            continue;
+        }
+
+        return Ok(Some(InputConflict {
+            outpoint,
+            spending_txid,
+        }));
     }
 
-    ensure!(
-        remaining_amount == 0,
-        "Not enough UTXOs to cover the amount"
+    Ok(None)
+}
+
+/// Applies the address reuse policy to the wallet key and change script of a new challenge.
+pub fn check_address_reuse(
+    r: &mut dyn Renderer,
+    policy: AddressReusePolicy,
+    store: &Store,
+    wallet_pubkey: &str,
+    change_script: Option<&str>,
+) -> eyre::Result<()> {
+    let reused = store.challenges_reusing(wallet_pubkey, change_script);
+    if reused.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "Wallet key or change address already used in challenge(s): {}",
+        reused.join(", ")
     );
 
-    Ok(selected_utxos)
+    match policy {
+        AddressReusePolicy::Allow => {}
+        AddressReusePolicy::Warn => {
+            r.warning(&message);
+        }
+        AddressReusePolicy::Refuse => bail!(
+            "{}. Use a fresh key and change address, or set `address_reuse` to \"warn\"",
+            message
+        ),
+    }
+
+    Ok(())
+}
+
+/// Applies the denomination policy to a challenge amount. An amount outside the standard
+/// denominations makes the game stand out on chain and easy to link to its parties.
+pub fn check_denomination(
+    r: &mut dyn Renderer,
+    denominations: &DenominationConfig,
+    amount: Amount,
+) -> eyre::Result<()> {
+    if denominations.is_standard(amount) {
+        return Ok(());
+    }
+
+    let standard = denominations
+        .amounts
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let message = format!(
+        "Amount of {} sats is not a standard denomination ({} sats), which makes the game linkable",
+        amount.to_sat(),
+        standard
+    );
+
+    match denominations.non_standard {
+        DenominationPolicy::Allow => {}
+        DenominationPolicy::Warn => {
+            r.warning(&message);
+        }
+        DenominationPolicy::Refuse => bail!(
+            "{}. Use a standard amount, or set `denominations.non_standard` to \"warn\"",
+            message
+        ),
+    }
+
+    Ok(())
+}
+
+/// Fetches the value of a beacon, or `None` if it isn't published yet. A drand round yields its
+/// randomness, verified against `drand_chain`, and a block height the block hash, in display byte
+/// order.
+pub async fn fetch_beacon_value(
+    drand_url: &str,
+    drand_chain: &DrandChainConfig,
+    esplora_client: &EsploraClient,
+    source: BeaconSource,
+) -> eyre::Result<Option<Vec<u8>>> {
+    match source {
+        BeaconSource::Drand { round } => {
+            let latest = drand::fetch_beacon(drand_url, drand_chain, None).await?;
+            if latest.round < round {
+                return Ok(None);
+            }
+            let beacon = drand::fetch_beacon(drand_url, drand_chain, Some(round)).await?;
+            Ok(Some(hex::decode(beacon.randomness)?))
+        }
+        BeaconSource::Block { height } => {
+            if esplora_client.get_tip_height().await? < height {
+                return Ok(None);
+            }
+            Ok(Some(hex::decode(
+                esplora_client.get_block_hash(height).await?,
+            )?))
+        }
+    }
+}
+
+/// How far a block timestamp may lag the time the block was mined. Timestamps only have to be
+/// later than the median time past of the previous blocks.
+pub const BLOCK_TIME_TOLERANCE: u64 = 2 * 60 * 60;
+
+/// Returns whether a transaction confirmed with `status` was confirmed before `source` was
+/// published. A drand round must be published [`BLOCK_TIME_TOLERANCE`] after the block timestamp.
+pub fn confirmed_before_beacon(
+    status: &UtxoStatus,
+    drand_chain: &DrandChainConfig,
+    source: BeaconSource,
+) -> eyre::Result<bool> {
+    ensure!(status.confirmed, "Transaction is not confirmed");
+
+    match source {
+        BeaconSource::Drand { round } => {
+            let block_time = status
+                .block_time
+                .ok_or_eyre("Esplora didn't return the block time")?;
+            Ok(block_time + BLOCK_TIME_TOLERANCE <= drand_chain.round_time(round))
+        }
+        BeaconSource::Block { height } => {
+            let block_height = status
+                .block_height
+                .ok_or_eyre("Esplora didn't return the block height")?;
+            Ok(block_height < height)
+        }
+    }
+}
+
+/// Returns the block height a window of `window` blocks starting at `tip_height` closes at
+pub fn window_deadline(tip_height: u64, window: u32) -> eyre::Result<u32> {
+    u32::try_from(tip_height)?
+        .checked_add(window)
+        .ok_or_eyre("The window ends past the largest block height, use a shorter window")
 }
@@ -0,0 +1,430 @@
+//! Verification of challenge envelopes. Every check runs even when an earlier one fails, so
+//! that all the problems of an envelope are reported at once.
+
+use std::{fmt, sync::Arc};
+
+use bitcoin::{
+    Network, ScriptBuf, WPubkeyHash,
+    absolute::{Height, LockTime},
+    hashes::Hash,
+    secp256k1::PublicKey,
+};
+use color_eyre::eyre::{self, OptionExt, ensure};
+use op_rand_prover::{
+    ChallengerPublicInputs, CircuitAbi, OpRandProof, OpRandProver, OpRandProverAsync,
+    challenger_circuit_abi,
+};
+use op_rand_transaction_builder::challenge_descriptor;
+use op_rand_types::{ThirdRankCommitment, parse_public_key_strict};
+
+use crate::{
+    actions::create_challenge::PublicChallengerData, esplora::EsploraClient,
+    proving::envelope_proof, render::Renderer, spv,
+};
+
+/// Outcome of a single check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// A check performed on an envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+}
+
+/// Every check performed on an envelope, in the order they were performed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationReport {
+    pub checks: Vec<Check>,
+}
+
+impl VerificationReport {
+    fn record(&mut self, name: &'static str, result: eyre::Result<()>) {
+        let status = match result {
+            Ok(()) => CheckStatus::Passed,
+            Err(err) => CheckStatus::Failed(err.to_string()),
+        };
+        self.checks.push(Check { name, status });
+    }
+
+    fn skip(&mut self, name: &'static str, reason: &str) {
+        self.checks.push(Check {
+            name,
+            status: CheckStatus::Skipped(reason.to_string()),
+        });
+    }
+
+    /// Returns the checks that failed
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks
+            .iter()
+            .filter(|check| matches!(check.status, CheckStatus::Failed(_)))
+    }
+
+    /// Whether no check failed. Skipped checks don't count as failures.
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Fails with every failed check if the envelope didn't pass
+    pub fn ensure_passed(&self) -> eyre::Result<()> {
+        ensure!(self.passed(), "Challenge verification failed: {}", self);
+        Ok(())
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failures = self
+            .failures()
+            .map(|check| match &check.status {
+                CheckStatus::Failed(reason) => format!("{} ({})", check.name, reason),
+                _ => check.name.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            write!(f, "all {} checks passed", self.checks.len())
+        } else {
+            write!(f, "{}", failures.join(", "))
+        }
+    }
+}
+
+/// What the checks of an envelope may use besides the envelope itself
+pub struct VerificationContext<'a> {
+    pub prover: Arc<dyn OpRandProver + Send + Sync>,
+    /// Server for the chain checks. They are skipped without one.
+    pub esplora_client: Option<&'a EsploraClient>,
+    /// If set, the deposit confirmation is checked with an SPV proof instead of trusting the
+    /// server
+    pub spv_network: Option<Network>,
+    /// Wallet key the challenge output scripts are reconstructed with. The check is skipped
+    /// without one.
+    pub acceptor_pubkey: Option<PublicKey>,
+}
+
+/// Fields of the envelope the proof binds
+struct ProofParams {
+    commitments: [ThirdRankCommitment; 2],
+    challenger_pubkey: PublicKey,
+    challenger_pubkey_hash: [u8; 20],
+    proof: OpRandProof,
+}
+
+impl ProofParams {
+    fn parse(envelope: &PublicChallengerData) -> eyre::Result<Self> {
+        let commitments = envelope
+            .third_rank_commitments
+            .iter()
+            .map(|s| ThirdRankCommitment::from_str_strict(s))
+            .collect::<Result<Vec<_>, _>>()?
+            .try_into()
+            .map_err(|_| eyre::eyre!("Expected exactly 2 commitments"))?;
+        let challenger_pubkey_hash = hex::decode(&envelope.challenger_pubkey_hash)?
+            .try_into()
+            .map_err(|_| eyre::eyre!("Failed to convert challenger public key hash to array"))?;
+
+        Ok(Self {
+            commitments,
+            challenger_pubkey: parse_public_key_strict(
+                "challenger public key",
+                &envelope.challenger_pubkey,
+            )?,
+            challenger_pubkey_hash,
+            proof: envelope_proof(
+                &envelope.proof,
+                &envelope.vk,
+                envelope.circuit_hash.as_deref(),
+            )?,
+        })
+    }
+}
+
+/// Checks the public signals of `proof` against the serialized public inputs `expected`,
+/// naming the public inputs that differ
+fn check_public_signals(
+    abi: &CircuitAbi,
+    proof: &OpRandProof,
+    expected: &[u8],
+) -> eyre::Result<()> {
+    let mismatched = abi
+        .read(proof)?
+        .into_iter()
+        .filter(|(input, bytes)| {
+            expected.get(input.offset..input.offset + input.size) != Some(bytes.as_slice())
+        })
+        .map(|(input, _)| input.name)
+        .collect::<Vec<_>>();
+    ensure!(
+        mismatched.is_empty(),
+        "Proof public inputs do not match the challenge: {}",
+        mismatched.join(", ")
+    );
+
+    Ok(())
+}
+
+/// Runs every check on a challenge envelope: the public signals and the validity of the
+/// proof, the reconstruction of the challenge output scripts, the existence of the deposit,
+/// the acceptance window and the locktime policy.
+pub async fn verify_challenge(
+    envelope: &PublicChallengerData,
+    ctx: &VerificationContext<'_>,
+) -> VerificationReport {
+    let mut report = VerificationReport::default();
+
+    let params = ProofParams::parse(envelope);
+    let tip_height = match ctx.esplora_client {
+        Some(esplora_client) => Some(esplora_client.get_tip_height().await),
+        None => None,
+    };
+
+    // The proof is verified on the blocking pool while the chain is queried
+    let proof_check = async {
+        let params = params.as_ref().map_err(|e| eyre::eyre!("{}", e))?;
+        let circuit = ctx.prover.setup_challenger_circuit().await?;
+        ctx.prover
+            .verify_challenger_proof(
+                &circuit,
+                &params.commitments,
+                &params.challenger_pubkey,
+                params.challenger_pubkey_hash,
+                &envelope.deposit_outpoint,
+                &params.proof,
+            )
+            .await?;
+        eyre::Ok(())
+    };
+    let deposit_check = async {
+        let esplora_client = ctx.esplora_client?;
+        envelope.deposit_broadcast.then_some(())?;
+        Some(verify_deposit_on_chain(esplora_client, envelope, ctx.spv_network).await)
+    };
+    let (proof_result, deposit_result) = tokio::join!(proof_check, deposit_check);
+
+    match &params {
+        Ok(params) => report.record(
+            "public signals",
+            check_public_signals(
+                &challenger_circuit_abi(),
+                &params.proof,
+                &ChallengerPublicInputs {
+                    third_rank_commitments: params.commitments.clone(),
+                    challenger_public_key: params.challenger_pubkey,
+                    challenger_public_key_hash: params.challenger_pubkey_hash,
+                    deposit_outpoint: envelope.deposit_outpoint,
+                }
+                .to_bytes(),
+            ),
+        ),
+        Err(err) => report.record("public signals", Err(eyre::eyre!("{}", err))),
+    }
+
+    match &params {
+        Ok(_) => report.record("proof validity", proof_result),
+        Err(_) => report.skip("proof validity", "the proof parameters could not be parsed"),
+    }
+
+    match (&params, ctx.acceptor_pubkey) {
+        (Ok(params), Some(acceptor_pubkey)) => report.record(
+            "script reconstruction",
+            reconstruct_scripts(envelope, params, &acceptor_pubkey),
+        ),
+        (Err(_), _) => report.skip(
+            "script reconstruction",
+            "the proof parameters could not be parsed",
+        ),
+        (_, None) => report.skip("script reconstruction", "no wallet key to build them with"),
+    }
+
+    match deposit_result {
+        Some(result) => report.record("deposit existence", result),
+        None if !envelope.deposit_broadcast => report.skip(
+            "deposit existence",
+            "the deposit is broadcast at completion",
+        ),
+        None => report.skip("deposit existence", "no server to check the chain with"),
+    }
+
+    match (envelope.acceptance_deadline, &tip_height) {
+        (None, _) => report.skip("acceptance window", "the challenge sets no deadline"),
+        (Some(deadline), Some(Ok(tip_height))) => report.record(
+            "acceptance window",
+            check_acceptance_window(deadline, *tip_height),
+        ),
+        (Some(_), Some(Err(err))) => {
+            report.record("acceptance window", Err(eyre::eyre!("{}", err)))
+        }
+        (Some(_), None) => report.skip("acceptance window", "no server to check the chain with"),
+    }
+
+    let tip_height = match &tip_height {
+        Some(Ok(tip_height)) => Some(*tip_height),
+        _ => None,
+    };
+    report.record(
+        "locktime policy",
+        check_locktime(envelope.locktime, tip_height),
+    );
+
+    report
+}
+
+/// Renders every check with its status
+pub fn render_report(r: &mut dyn Renderer, report: &VerificationReport) {
+    r.section("CHECKS");
+    for check in &report.checks {
+        r.entry(check.name);
+        r.field("Check", check.name);
+        match &check.status {
+            CheckStatus::Passed => r.field("Status", "passed"),
+            CheckStatus::Failed(reason) => {
+                r.field("Status", "failed");
+                r.field("Reason", reason.as_str());
+            }
+            CheckStatus::Skipped(reason) => {
+                r.field("Status", "skipped");
+                r.field("Reason", reason.as_str());
+            }
+        }
+    }
+}
+
+/// Builds the challenge output descriptor of both commitments with the acceptor key, as the
+/// acceptor would after selecting either of them
+fn reconstruct_scripts(
+    envelope: &PublicChallengerData,
+    params: &ProofParams,
+    acceptor_pubkey: &PublicKey,
+) -> eyre::Result<()> {
+    let lock_time = LockTime::Blocks(Height::from_consensus(envelope.locktime)?);
+
+    for commitment in &params.commitments {
+        let tweaked_acceptor_pubkey = acceptor_pubkey.combine(&commitment.inner())?;
+        challenge_descriptor(
+            &params.challenger_pubkey.into(),
+            &tweaked_acceptor_pubkey.into(),
+            lock_time,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn check_acceptance_window(deadline: u32, tip_height: u64) -> eyre::Result<()> {
+    ensure!(
+        tip_height < u64::from(deadline),
+        "Acceptance window closed at block {}, the challenger may refund the deposit",
+        deadline
+    );
+
+    Ok(())
+}
+
+/// The challenger branch of the challenge output is locked until `locktime`, which must be a
+/// block height still ahead of the tip, or the challenger could sweep the output right away
+fn check_locktime(locktime: u32, tip_height: Option<u64>) -> eyre::Result<()> {
+    Height::from_consensus(locktime)
+        .map_err(|_| eyre::eyre!("Locktime {} is not a block height", locktime))?;
+
+    if let Some(tip_height) = tip_height {
+        ensure!(
+            u64::from(locktime) > tip_height,
+            "Locktime {} has already passed, the challenger can sweep the challenge output at once",
+            locktime
+        );
+    }
+
+    Ok(())
+}
+
+/// Verifies that an already broadcasted deposit is confirmed, unspent and pays the challenge
+/// amount to the challenger's committed public key hash. If `spv_network` is set, the
+/// confirmation is checked with an SPV proof instead of trusting the server.
+async fn verify_deposit_on_chain(
+    esplora_client: &EsploraClient,
+    challenge_data: &PublicChallengerData,
+    spv_network: Option<Network>,
+) -> eyre::Result<()> {
+    let outpoint = challenge_data.deposit_outpoint;
+
+    match spv_network {
+        Some(network) => {
+            spv::verify_inclusion(esplora_client, network, &outpoint.txid).await?;
+        }
+        None => {
+            let status = esplora_client.get_tx_status(&outpoint.txid).await?;
+            ensure!(
+                status.confirmed,
+                "Deposit transaction {} is not confirmed",
+                outpoint.txid
+            );
+        }
+    }
+
+    let deposit_tx = esplora_client.get_transaction(&outpoint.txid).await?;
+    ensure!(
+        deposit_tx.compute_txid() == outpoint.txid,
+        "Server returned a transaction that does not match the deposit txid"
+    );
+    let deposit_output = deposit_tx
+        .output
+        .get(outpoint.vout as usize)
+        .ok_or_eyre("Deposit output does not exist")?;
+
+    ensure!(
+        deposit_output.value == challenge_data.amount,
+        "Deposit output value does not match the challenge amount"
+    );
+
+    let pubkey_hash: [u8; 20] = hex::decode(&challenge_data.challenger_pubkey_hash)?
+        .try_into()
+        .map_err(|_| eyre::eyre!("Failed to convert challenger public key hash to array"))?;
+    ensure!(
+        deposit_output.script_pubkey
+            == ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array(pubkey_hash)),
+        "Deposit output does not pay to the challenger public key hash"
+    );
+
+    let outspend = esplora_client
+        .get_outspend(&outpoint.txid, outpoint.vout)
+        .await?;
+    ensure!(!outspend.spent, "Deposit output is already spent");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::render_all;
+
+    #[test]
+    fn checks_locktime() {
+        assert!(check_locktime(900_000, Some(899_999)).is_ok());
+        assert!(check_locktime(900_000, None).is_ok());
+        assert!(check_locktime(900_000, Some(900_000)).is_err());
+        assert!(check_locktime(1_700_000_000, None).is_err());
+    }
+
+    #[test]
+    fn renders_report() {
+        let mut report = VerificationReport::default();
+        report.record("public signals", Ok(()));
+        report.record("proof validity", Err(eyre::eyre!("Invalid proof")));
+        report.skip(
+            "deposit existence",
+            "the deposit is broadcast at completion",
+        );
+
+        assert!(!report.passed());
+        assert_eq!(report.to_string(), "proof validity (Invalid proof)");
+        insta::assert_snapshot!(render_all(|r| render_report(r, &report)));
+    }
+}
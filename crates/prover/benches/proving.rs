@@ -0,0 +1,51 @@
+//! Proof generation and verification times of both circuits with Barretenberg.
+//!
+//! Setups are cached for the whole process, so only the first one can be timed. The setup times
+//! are printed from a run of `op_rand_prover::bench` before the measurements.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use op_rand_prover::{BarretenbergProver, BenchInputs, OpRandProver, bench};
+
+fn proving(c: &mut Criterion) {
+    let prover = BarretenbergProver::default();
+    let inputs = BenchInputs::generate(&mut rand::thread_rng()).expect("valid inputs");
+
+    let report = bench(&prover, &inputs).expect("proving works");
+    for circuit in [report.challenger, report.acceptor] {
+        println!(
+            "{:?}: setup {:?}, proof of {} bytes",
+            circuit.circuit, circuit.setup, circuit.proof_bytes
+        );
+    }
+
+    let circuit = prover.setup_challenger_circuit().expect("setup works");
+    let proof = inputs
+        .prove_challenger(&prover, &circuit)
+        .expect("proving works");
+    let mut group = c.benchmark_group("challenger");
+    group.sample_size(10);
+    group.bench_function("prove", |b| {
+        b.iter(|| inputs.prove_challenger(&prover, &circuit))
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| inputs.verify_challenger(&prover, &circuit, &proof))
+    });
+    group.finish();
+
+    let circuit = prover.setup_acceptor_circuit().expect("setup works");
+    let proof = inputs
+        .prove_acceptor(&prover, &circuit)
+        .expect("proving works");
+    let mut group = c.benchmark_group("acceptor");
+    group.sample_size(10);
+    group.bench_function("prove", |b| {
+        b.iter(|| inputs.prove_acceptor(&prover, &circuit))
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| inputs.verify_acceptor(&prover, &circuit, &proof))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, proving);
+criterion_main!(benches);
@@ -1,191 +1,628 @@
-use bitcoin::secp256k1;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex, OnceLock, PoisonError,
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bitcoin::{
+    OutPoint,
+    hashes::{Hash, sha256},
+    secp256k1,
+};
 use noir_rs::barretenberg::srs::setup_srs;
-use noir_rs::barretenberg::{prove::prove_ultra_honk, verify::verify_ultra_honk};
+use noir_rs::barretenberg::{
+    prove::{prove_ultra_honk, prove_ultra_plonk},
+    verify::{
+        get_ultra_honk_verification_key, get_ultra_plonk_verification_key, verify_ultra_honk,
+        verify_ultra_plonk,
+    },
+};
 use noir_rs::witness::from_vec_str_to_witness_map;
 
 use crate::{
-    bytecode::{ACCEPTOR_CIRCUIT_BYTECODE, CHALLENGER_CIRCUIT_BYTECODE},
-    traits::{OpRandProof, OpRandProver},
+    backends::{limits::ProvingLimits, witness},
+    bytecode::{
+        ACCEPTOR_CIRCUIT_BYTECODE, CHALLENGER_CIRCUIT_BYTECODE, CircuitMetadata, CircuitSource,
+    },
+    traits::{
+        AcceptorPublicInputs, CancellationToken, ChallengerPublicInputs, Circuit, OpRandProof,
+        OpRandProver, PreparedCircuit, ProgressCallback, ProofSystem, ProvingProgress,
+        ProvingStage,
+    },
 };
 
 use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
 
-/// Barretenberg prover implementation
+/// Barretenberg prover implementation. Clones share the setup state of the prover, so a clone
+/// handed to another task reuses the circuits already set up.
 #[derive(Debug, Clone, Default)]
 pub struct BarretenbergProver {
     is_recursive: bool,
+    proof_system: ProofSystem,
+    /// Local SRS file. The SRS is downloaded when it's not set.
+    srs_path: Option<PathBuf>,
+    /// Challenger circuit bytecode. The default artifact is used when it's not set.
+    challenger_bytecode: Option<CircuitBytecode>,
+    /// Acceptor circuit bytecode. The default artifact is used when it's not set.
+    acceptor_bytecode: Option<CircuitBytecode>,
+    progress: ProgressReporter,
+    limits: ProvingLimits,
+    setups: Arc<ProverSetups>,
+}
+
+/// Interval of the progress reports while a proving stage runs
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Builder of a [`BarretenbergProver`] with explicit circuit artifacts
+#[derive(Debug, Clone, Default)]
+pub struct BarretenbergProverBuilder {
+    is_recursive: bool,
+    proof_system: ProofSystem,
+    srs_path: Option<PathBuf>,
+    challenger_circuit: Option<CircuitSource>,
+    acceptor_circuit: Option<CircuitSource>,
+    progress: ProgressReporter,
+    limits: ProvingLimits,
+}
+
+impl BarretenbergProverBuilder {
+    pub fn recursive(mut self, is_recursive: bool) -> Self {
+        self.is_recursive = is_recursive;
+        self
+    }
+
+    /// Generates and verifies proofs with `proof_system` instead of UltraHonk
+    pub fn proof_system(mut self, proof_system: ProofSystem) -> Self {
+        self.proof_system = proof_system;
+        self
+    }
+
+    /// Loads the SRS from a local file instead of downloading it
+    pub fn srs_path(mut self, srs_path: PathBuf) -> Self {
+        self.srs_path = Some(srs_path);
+        self
+    }
+
+    /// Loads the challenger circuit from `source` instead of the default artifact
+    pub fn challenger_circuit(mut self, source: CircuitSource) -> Self {
+        self.challenger_circuit = Some(source);
+        self
+    }
+
+    /// Loads the acceptor circuit from `source` instead of the default artifact
+    pub fn acceptor_circuit(mut self, source: CircuitSource) -> Self {
+        self.acceptor_circuit = Some(source);
+        self
+    }
+
+    /// Reports the progress of every proof generation to `callback`
+    pub fn progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress.callback = Some(callback);
+        self
+    }
+
+    /// Aborts proof generations with [`ProverError::Cancelled`](crate::ProverError::Cancelled)
+    /// when `token` is cancelled. Barretenberg can't be interrupted, so an aborted proof keeps
+    /// a thread busy until it would have finished.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.limits.cancellation = Some(token);
+        self
+    }
+
+    /// Aborts proof generations that take longer than `timeout` with
+    /// [`ProverError::TimedOut`](crate::ProverError::TimedOut), see
+    /// [`cancellation`](Self::cancellation)
+    pub fn proving_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long a proof of `circuit` is expected to take, e.g. as measured for a previous
+    /// proof, so that progress is reported as a percentage
+    pub fn expected_proving_time(mut self, circuit: Circuit, expected: Duration) -> Self {
+        match circuit {
+            Circuit::Challenger => self.progress.challenger_expected = Some(expected),
+            Circuit::Acceptor => self.progress.acceptor_expected = Some(expected),
+        }
+        self
+    }
+
+    /// Loads the configured circuits and builds the prover. Circuits without a source use the
+    /// default artifacts, see [`set_circuit_dir`](crate::set_circuit_dir).
+    pub fn build(self) -> Result<BarretenbergProver, crate::errors::ProverError> {
+        let load = |circuit, source: Option<CircuitSource>| {
+            source
+                .map(|source| CircuitBytecode::load(&source))
+                .transpose()
+                .map_err(|e| crate::errors::ProverError::CircuitLoadError(circuit, e.to_string()))
+        };
+
+        Ok(BarretenbergProver {
+            is_recursive: self.is_recursive,
+            proof_system: self.proof_system,
+            srs_path: self.srs_path,
+            challenger_bytecode: load(Circuit::Challenger, self.challenger_circuit)?,
+            acceptor_bytecode: load(Circuit::Acceptor, self.acceptor_circuit)?,
+            progress: self.progress,
+            limits: self.limits,
+            setups: Arc::default(),
+        })
+    }
 }
 
 impl BarretenbergProver {
     /// Creates a new `BarretenbergProver`
     pub fn new(is_recursive: bool) -> Self {
-        Self { is_recursive }
+        Self {
+            is_recursive,
+            ..Self::default()
+        }
+    }
+
+    /// Creates a new `BarretenbergProver` generating and verifying proofs with `proof_system`
+    pub fn with_proof_system(is_recursive: bool, proof_system: ProofSystem) -> Self {
+        Self {
+            is_recursive,
+            proof_system,
+            ..Self::default()
+        }
+    }
+
+    /// Returns a builder of a prover with explicit circuit artifacts
+    pub fn builder() -> BarretenbergProverBuilder {
+        BarretenbergProverBuilder::default()
+    }
+
+    /// Loads the SRS from a local file instead of downloading it
+    pub fn with_srs_path(mut self, srs_path: PathBuf) -> Self {
+        self.srs_path = Some(srs_path);
+        self
+    }
+
+    fn srs_path(&self) -> Option<&str> {
+        self.srs_path.as_deref().and_then(|path| path.to_str())
+    }
+
+    /// Returns the key of the setup of `circuit` with the settings of this prover. The bytecode
+    /// is hashed once per prover and its clones.
+    fn setup_key(&self, circuit: Circuit) -> SetupKey {
+        let circuit_hash = self
+            .setups
+            .get(circuit)
+            .circuit_hash
+            .get_or_init(|| sha256::Hash::hash(self.bytecode(circuit).as_bytes()));
+
+        (*circuit_hash, self.is_recursive, self.proof_system)
+    }
+
+    /// Returns the bytecode of `circuit`
+    fn bytecode(&self, circuit: Circuit) -> &str {
+        let configured = match circuit {
+            Circuit::Challenger => &self.challenger_bytecode,
+            Circuit::Acceptor => &self.acceptor_bytecode,
+        };
+
+        match configured {
+            Some(bytecode) => bytecode.as_str(),
+            None => match circuit {
+                Circuit::Challenger => &CHALLENGER_CIRCUIT_BYTECODE,
+                Circuit::Acceptor => &ACCEPTOR_CIRCUIT_BYTECODE,
+            },
+        }
+    }
+
+    /// Sets up `circuit`, or returns the cached setup if the loaded SRS still serves it
+    fn setup(&self, circuit: Circuit) -> Result<PreparedCircuit, crate::errors::ProverError> {
+        // The setup of this prover or a clone, reused without waiting for setups of other
+        // provers, which may be downloading an SRS
+        let local = self.setups.get(circuit);
+        let mut local_prepared = local
+            .prepared
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(prepared) = local_prepared.as_ref()
+            && prepared.srs_size() <= LOADED_SRS_SIZE.load(Ordering::SeqCst)
+        {
+            return Ok(prepared.clone());
+        }
+
+        let key = self.setup_key(circuit);
+        let mut setups = SETUPS.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(prepared) = setups.get(&key)
+            && prepared.srs_size() <= LOADED_SRS_SIZE.load(Ordering::SeqCst)
+        {
+            *local_prepared = Some(prepared.clone());
+            return Ok(prepared.clone());
+        }
+
+        let bytecode = self.bytecode(circuit);
+        let started = Instant::now();
+        let (srs_size, vk) = self
+            .progress
+            .during(circuit, ProvingStage::Setup, started, || {
+                let srs_size = setup_srs(bytecode, self.srs_path(), self.is_recursive)
+                    .map_err(|e| crate::errors::ProverError::SrsError(e.to_string()))?;
+                let vk = match self.proof_system {
+                    ProofSystem::UltraHonk => {
+                        get_ultra_honk_verification_key(bytecode, self.is_recursive)
+                    }
+                    ProofSystem::UltraPlonk => {
+                        get_ultra_plonk_verification_key(bytecode, self.is_recursive)
+                    }
+                }
+                .map_err(|e| crate::errors::ProverError::SetupError(e.to_string()))?;
+
+                Ok::<_, crate::errors::ProverError>((srs_size, vk))
+            })?;
+
+        let prepared =
+            PreparedCircuit::new(circuit, srs_size, vk, key.0).with_setup_time(started.elapsed());
+        LOADED_SRS_SIZE.store(srs_size, Ordering::SeqCst);
+        setups.insert(key, prepared.clone());
+        *local_prepared = Some(prepared.clone());
+
+        Ok(prepared)
+    }
+
+    /// Checks that `prepared` is a setup of `circuit` by this prover and returns the circuit
+    /// bytecode. The circuit is set up again if the loaded SRS no longer serves it, e.g. after
+    /// another circuit was set up with a smaller SRS.
+    fn prepared_bytecode(
+        &self,
+        prepared: &PreparedCircuit,
+        circuit: Circuit,
+    ) -> Result<&str, crate::errors::ProverError> {
+        if prepared.circuit() != circuit {
+            return Err(crate::errors::ProverError::UnexpectedCircuit {
+                expected: circuit,
+                got: prepared.circuit(),
+            });
+        }
+
+        if !self.setup(circuit)?.is_same_setup(prepared) {
+            return Err(crate::errors::ProverError::CircuitNotPrepared(circuit));
+        }
+
+        Ok(self.bytecode(circuit))
+    }
+
+    /// Verifies `proof` against the verification key of `circuit` with the proof system of
+    /// this prover
+    fn verify(
+        &self,
+        circuit: &PreparedCircuit,
+        proof: &OpRandProof,
+    ) -> Result<(), crate::errors::ProverError> {
+        let (proof, vk) = (proof.proof().to_vec(), circuit.vk().to_vec());
+        let verdict = match self.proof_system {
+            ProofSystem::UltraHonk => verify_ultra_honk(proof, vk),
+            ProofSystem::UltraPlonk => verify_ultra_plonk(proof, vk),
+        }
+        .map_err(|e| crate::errors::ProverError::ProofVerificationError(e.to_string()))?;
+
+        if !verdict {
+            return Err(crate::errors::ProverError::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+/// Loaded circuit bytecode, cheap to clone
+#[derive(Clone, PartialEq, Eq)]
+struct CircuitBytecode(Arc<str>);
+
+impl CircuitBytecode {
+    fn load(source: &CircuitSource) -> eyre::Result<Self> {
+        let bytecode = match source {
+            CircuitSource::Path(path) => CircuitMetadata::from_file(path)?.bytecode,
+            CircuitSource::Bytecode(bytecode) => bytecode.clone(),
+        };
+        eyre::ensure!(!bytecode.is_empty(), "Empty bytecode");
+
+        Ok(Self(bytecode.into()))
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for CircuitBytecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CircuitBytecode({})",
+            sha256::Hash::hash(self.0.as_bytes())
+        )
+    }
+}
+
+/// Reports proving progress to an optional callback
+#[derive(Clone, Default)]
+struct ProgressReporter {
+    callback: Option<ProgressCallback>,
+    challenger_expected: Option<Duration>,
+    acceptor_expected: Option<Duration>,
+}
+
+impl ProgressReporter {
+    fn report(&self, circuit: Circuit, stage: ProvingStage, started: Instant) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+
+        callback(&ProvingProgress {
+            circuit,
+            stage,
+            elapsed: started.elapsed(),
+            expected: match circuit {
+                Circuit::Challenger => self.challenger_expected,
+                Circuit::Acceptor => self.acceptor_expected,
+            },
+        });
+    }
+
+    /// Runs `f` as `stage` of a proof of `circuit`, reporting progress until it returns
+    fn during<T>(
+        &self,
+        circuit: Circuit,
+        stage: ProvingStage,
+        started: Instant,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        if self.callback.is_none() {
+            return f();
+        }
+
+        let (done, ticks) = mpsc::channel::<()>();
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                loop {
+                    self.report(circuit, stage, started);
+                    if !matches!(
+                        ticks.recv_timeout(PROGRESS_INTERVAL),
+                        Err(RecvTimeoutError::Timeout)
+                    ) {
+                        break;
+                    }
+                }
+            });
+
+            let result = f();
+            drop(done);
+            result
+        })
+    }
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("callback", &self.callback.is_some())
+            .field("challenger_expected", &self.challenger_expected)
+            .field("acceptor_expected", &self.acceptor_expected)
+            .finish()
+    }
+}
+
+/// Circuits set up in this process, keyed by bytecode hash, recursion flag and proof system.
+/// The SRS is global to the process, so the setups are too.
+static SETUPS: Mutex<BTreeMap<SetupKey, PreparedCircuit>> = Mutex::new(BTreeMap::new());
+
+/// Size of the loaded SRS, only written while [`SETUPS`] is locked
+static LOADED_SRS_SIZE: AtomicU32 = AtomicU32::new(0);
+
+/// Bytecode hash, recursion flag and proof system of a setup
+type SetupKey = (sha256::Hash, bool, ProofSystem);
+
+/// Setup state of a prover, shared by its clones
+#[derive(Debug, Default)]
+struct ProverSetups {
+    challenger: CircuitSetup,
+    acceptor: CircuitSetup,
+}
+
+impl ProverSetups {
+    fn get(&self, circuit: Circuit) -> &CircuitSetup {
+        match circuit {
+            Circuit::Challenger => &self.challenger,
+            Circuit::Acceptor => &self.acceptor,
+        }
     }
 }
 
+#[derive(Debug, Default)]
+struct CircuitSetup {
+    /// SHA-256 of the bytecode, computed on the first setup
+    circuit_hash: OnceLock<sha256::Hash>,
+    /// Last setup of the circuit, valid while the loaded SRS serves it
+    prepared: Mutex<Option<PreparedCircuit>>,
+}
+
 impl OpRandProver for BarretenbergProver {
-    fn setup_challenger_circuit(&self) -> Result<u32, crate::errors::ProverError> {
-        setup_srs(&CHALLENGER_CIRCUIT_BYTECODE, None, self.is_recursive)
-            .map_err(|e| crate::errors::ProverError::SetupError(e.to_string()))
+    fn setup_challenger_circuit(&self) -> Result<PreparedCircuit, crate::errors::ProverError> {
+        self.setup(Circuit::Challenger)
     }
 
-    fn setup_acceptor_circuit(&self) -> Result<u32, crate::errors::ProverError> {
-        setup_srs(&ACCEPTOR_CIRCUIT_BYTECODE, None, self.is_recursive)
-            .map_err(|e| crate::errors::ProverError::SetupError(e.to_string()))
+    fn setup_acceptor_circuit(&self) -> Result<PreparedCircuit, crate::errors::ProverError> {
+        self.setup(Circuit::Acceptor)
     }
 
     fn generate_challenger_proof(
         &self,
-        first_rank_commitments: [FirstRankCommitment; 2],
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
         challenger_public_key: &secp256k1::PublicKey,
         challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
     ) -> Result<OpRandProof, crate::errors::ProverError> {
-        // Extract the x and y coordinates from the challenger's public key
-        let pk_coords = challenger_public_key.serialize_uncompressed();
-        let pk_x = &pk_coords[1..33]; // Skip the first byte (0x04)
-        let pk_y = &pk_coords[33..65];
-
-        // Extract first rank commitments (a1, a2)
-        let (a1_secret, _) = first_rank_commitments[0].inner();
-        let (a2_secret, _) = first_rank_commitments[1].inner();
-        let a1_bytes = a1_secret.secret_bytes();
-        let a2_bytes = a2_secret.secret_bytes();
-
-        // Extract coordinates from third rank commitments
-        let h1_coords = third_rank_commitments[0].inner().serialize_uncompressed();
-        let h1_x = &h1_coords[1..33];
-        let h1_y = &h1_coords[33..65];
-
-        let h2_coords = third_rank_commitments[1].inner().serialize_uncompressed();
-        let h2_x = &h2_coords[1..33];
-        let h2_y = &h2_coords[33..65];
-
-        let mut witness_inputs: Vec<String> = Vec::new();
-
-        // Private inputs first (order matters - must match circuit main function)
-        witness_inputs.extend(bytes_to_string_array(&a1_bytes)); // a1 (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(&a2_bytes)); // a2 (32 bytes)
-
-        // Public inputs
-        witness_inputs.extend(bytes_to_string_array(h1_x)); // H1_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h1_y)); // H1_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h2_x)); // H2_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h2_y)); // H2_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(pk_x)); // PK_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(pk_y)); // PK_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(&challenger_public_key_hash)); // ADDR (20 bytes)
+        let bytecode = self.prepared_bytecode(circuit, Circuit::Challenger)?;
+        let first_rank_commitments = circuit.commitment_array(first_rank_commitments)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        let started = Instant::now();
+        self.progress
+            .report(Circuit::Challenger, ProvingStage::Witness, started);
+
+        let witness_inputs = witness::challenger_witness(
+            &first_rank_commitments,
+            third_rank_commitments,
+            challenger_public_key,
+            challenger_public_key_hash,
+            deposit_outpoint,
+        )?;
 
         let witness_input_refs = witness_inputs
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
 
-        let initial_witness = from_vec_str_to_witness_map(witness_input_refs)
-            .map_err(|e| crate::errors::ProverError::ProofGenerationError(e.to_string()))?;
-
-        let (proof, vk) = prove_ultra_honk(
-            &CHALLENGER_CIRCUIT_BYTECODE,
-            initial_witness,
-            self.is_recursive,
-        )
-        .map_err(|e| crate::errors::ProverError::ProofGenerationError(e.to_string()))?;
-
-        Ok(OpRandProof::new(proof, vk))
+        let initial_witness = from_vec_str_to_witness_map(witness_input_refs).map_err(|e| {
+            crate::errors::ProverError::MalformedWitness {
+                circuit: Circuit::Challenger,
+                field: "witness map".to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        let bytecode = bytecode.to_owned();
+        let is_recursive = self.is_recursive;
+        let proof_system = self.proof_system;
+        let (proof, vk) = self
+            .progress
+            .during(Circuit::Challenger, ProvingStage::Proof, started, || {
+                self.limits
+                    .run(Circuit::Challenger, started, move || match proof_system {
+                        ProofSystem::UltraHonk => {
+                            prove_ultra_honk(&bytecode, initial_witness, is_recursive)
+                        }
+                        ProofSystem::UltraPlonk => {
+                            prove_ultra_plonk(&bytecode, initial_witness, is_recursive)
+                        }
+                    })
+            })?
+            .map_err(|e| crate::errors::ProverError::BackendError {
+                circuit: Circuit::Challenger,
+                code: None,
+                message: e.to_string(),
+            })?;
+        self.progress
+            .report(Circuit::Challenger, ProvingStage::Done, started);
+
+        Ok(OpRandProof::new(proof, vk).measured(circuit, started.elapsed()))
     }
 
     fn verify_challenger_proof(
         &self,
-        _third_rank_commitments: [ThirdRankCommitment; 2],
-        _challenger_public_key: &secp256k1::PublicKey,
-        _challenger_public_key_hash: [u8; 20],
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &secp256k1::PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
         proof: &OpRandProof,
     ) -> Result<(), crate::errors::ProverError> {
-        // TODO: Add verification of public inputs
-        let verdict = verify_ultra_honk(proof.proof().to_vec(), proof.vk().to_vec())
-            .map_err(|e| crate::errors::ProverError::ProofVerificationError(e.to_string()))?;
-
-        if !verdict {
-            return Err(crate::errors::ProverError::InvalidProof);
+        self.prepared_bytecode(circuit, Circuit::Challenger)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        circuit.check_proof(proof)?;
+
+        ChallengerPublicInputs {
+            third_rank_commitments,
+            challenger_public_key: *challenger_public_key,
+            challenger_public_key_hash,
+            deposit_outpoint: *deposit_outpoint,
         }
+        .verify(proof)?;
 
-        Ok(())
+        self.verify(circuit, proof)
     }
 
     fn generate_acceptor_proof(
         &self,
+        circuit: &PreparedCircuit,
         acceptor_public_key: &secp256k1::PublicKey,
         acceptor_signature: &secp256k1::ecdsa::Signature,
         acceptor_public_key_hash: [u8; 20],
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        third_rank_commitments: &[ThirdRankCommitment],
     ) -> Result<OpRandProof, crate::errors::ProverError> {
-        // Extract the x and y coordinates from the acceptor's public key
-        let pk_coords = acceptor_public_key.serialize_uncompressed();
-        let pk_x = &pk_coords[1..33]; // Skip the first byte (0x04)
-        let pk_y = &pk_coords[33..65];
-
-        let signature = acceptor_signature.serialize_compact();
-
-        // Extract coordinates from third rank commitments
-        let h1_coords = third_rank_commitments[0].inner().serialize_uncompressed();
-        let h1_x = &h1_coords[1..33];
-        let h1_y = &h1_coords[33..65];
-
-        let h2_coords = third_rank_commitments[1].inner().serialize_uncompressed();
-        let h2_x = &h2_coords[1..33];
-        let h2_y = &h2_coords[33..65];
-
-        let mut witness_inputs: Vec<String> = Vec::new();
-
-        // Private inputs first (order matters - must match circuit main function)
-        witness_inputs.extend(bytes_to_string_array(pk_x)); // PK_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(pk_y)); // PK_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(&signature)); // S (64 bytes)
-
-        // Public inputs
-        witness_inputs.extend(bytes_to_string_array(h1_x)); // H1_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h1_y)); // H1_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h2_x)); // H2_x (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(h2_y)); // H2_y (32 bytes)
-        witness_inputs.extend(bytes_to_string_array(&acceptor_public_key_hash)); // ADDR (20 bytes)
+        let bytecode = self.prepared_bytecode(circuit, Circuit::Acceptor)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        let started = Instant::now();
+        self.progress
+            .report(Circuit::Acceptor, ProvingStage::Witness, started);
+
+        let witness_inputs = witness::acceptor_witness(
+            acceptor_public_key,
+            acceptor_signature,
+            acceptor_public_key_hash,
+            third_rank_commitments,
+        )?;
 
         let witness_input_refs = witness_inputs
             .iter()
             .map(|s| s.as_str())
             .collect::<Vec<_>>();
 
-        let initial_witness = from_vec_str_to_witness_map(witness_input_refs)
-            .map_err(|e| crate::errors::ProverError::ProofGenerationError(e.to_string()))?;
-
-        let (proof, vk) = prove_ultra_honk(
-            &ACCEPTOR_CIRCUIT_BYTECODE,
-            initial_witness,
-            self.is_recursive,
-        )
-        .map_err(|e| crate::errors::ProverError::ProofGenerationError(e.to_string()))?;
-
-        Ok(OpRandProof::new(proof, vk))
+        let initial_witness = from_vec_str_to_witness_map(witness_input_refs).map_err(|e| {
+            crate::errors::ProverError::MalformedWitness {
+                circuit: Circuit::Acceptor,
+                field: "witness map".to_string(),
+                reason: e.to_string(),
+            }
+        })?;
+
+        let bytecode = bytecode.to_owned();
+        let is_recursive = self.is_recursive;
+        let proof_system = self.proof_system;
+        let (proof, vk) = self
+            .progress
+            .during(Circuit::Acceptor, ProvingStage::Proof, started, || {
+                self.limits
+                    .run(Circuit::Acceptor, started, move || match proof_system {
+                        ProofSystem::UltraHonk => {
+                            prove_ultra_honk(&bytecode, initial_witness, is_recursive)
+                        }
+                        ProofSystem::UltraPlonk => {
+                            prove_ultra_plonk(&bytecode, initial_witness, is_recursive)
+                        }
+                    })
+            })?
+            .map_err(|e| crate::errors::ProverError::BackendError {
+                circuit: Circuit::Acceptor,
+                code: None,
+                message: e.to_string(),
+            })?;
+        self.progress
+            .report(Circuit::Acceptor, ProvingStage::Done, started);
+
+        Ok(OpRandProof::new(proof, vk).measured(circuit, started.elapsed()))
     }
 
     fn verify_acceptor_proof(
         &self,
-        _acceptor_public_key_hash: [u8; 20],
-        _third_rank_commitments: [ThirdRankCommitment; 2],
+        circuit: &PreparedCircuit,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
         op_rand_proof: &OpRandProof,
     ) -> Result<(), crate::errors::ProverError> {
-        // TODO: Add verification of public inputs
-        let verdict =
-            verify_ultra_honk(op_rand_proof.proof().to_vec(), op_rand_proof.vk().to_vec())
-                .map_err(|e| crate::errors::ProverError::ProofVerificationError(e.to_string()))?;
+        self.prepared_bytecode(circuit, Circuit::Acceptor)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        circuit.check_proof(op_rand_proof)?;
 
-        if !verdict {
-            return Err(crate::errors::ProverError::InvalidProof);
+        AcceptorPublicInputs {
+            third_rank_commitments,
+            acceptor_public_key_hash,
         }
+        .verify(op_rand_proof)?;
 
-        Ok(())
+        self.verify(circuit, op_rand_proof)
     }
 }
-
-fn bytes_to_string_array(bytes: &[u8]) -> Vec<String> {
-    bytes.iter().map(|b| b.to_string()).collect()
-}
@@ -0,0 +1,263 @@
+#[cfg(feature = "barretenberg")]
+mod server;
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+use bitcoin::{
+    OutPoint,
+    hashes::sha256,
+    secp256k1::{PublicKey, SecretKey, ecdsa},
+};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::ProverError,
+    traits::{OpRandProof, OpRandProver, PreparedCircuit, ProofMetrics},
+};
+
+#[cfg(feature = "barretenberg")]
+pub use server::ProverDaemon;
+
+/// Request sent to the prover daemon, one JSON document per line
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Ping,
+    SetupChallengerCircuit,
+    SetupAcceptorCircuit,
+    GenerateChallengerProof {
+        circuit: PreparedCircuit,
+        first_rank_commitments: Vec<SecretKey>,
+        third_rank_commitments: Vec<PublicKey>,
+        challenger_public_key: PublicKey,
+        challenger_public_key_hash: String,
+        deposit_outpoint: OutPoint,
+    },
+    VerifyChallengerProof {
+        circuit: PreparedCircuit,
+        third_rank_commitments: Vec<PublicKey>,
+        challenger_public_key: PublicKey,
+        challenger_public_key_hash: String,
+        deposit_outpoint: OutPoint,
+        proof: WireProof,
+    },
+    GenerateAcceptorProof {
+        circuit: PreparedCircuit,
+        acceptor_public_key: PublicKey,
+        acceptor_signature: ecdsa::Signature,
+        acceptor_public_key_hash: String,
+        third_rank_commitments: Vec<PublicKey>,
+    },
+    VerifyAcceptorProof {
+        circuit: PreparedCircuit,
+        acceptor_public_key_hash: String,
+        third_rank_commitments: Vec<PublicKey>,
+        proof: WireProof,
+    },
+}
+
+/// Response of the prover daemon
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+enum Response {
+    Pong,
+    Setup { circuit: PreparedCircuit },
+    Proof { proof: WireProof },
+    Verified,
+    Error { error: ProverError },
+}
+
+/// Hex encoded proof and verification key
+#[derive(Debug, Serialize, Deserialize)]
+struct WireProof {
+    proof: String,
+    vk: String,
+    #[serde(default)]
+    circuit_hash: Option<sha256::Hash>,
+    #[serde(default)]
+    metrics: Option<ProofMetrics>,
+}
+
+impl From<&OpRandProof> for WireProof {
+    fn from(proof: &OpRandProof) -> Self {
+        Self {
+            proof: hex::encode(proof.proof()),
+            vk: hex::encode(proof.vk()),
+            circuit_hash: proof.circuit_hash(),
+            metrics: proof.metrics().copied(),
+        }
+    }
+}
+
+impl TryFrom<WireProof> for OpRandProof {
+    type Error = ProverError;
+
+    fn try_from(wire: WireProof) -> Result<Self, Self::Error> {
+        let decode = |s: &str| hex::decode(s).map_err(|e| ProverError::DaemonError(e.to_string()));
+        let mut proof = OpRandProof::new(decode(&wire.proof)?, decode(&wire.vk)?);
+        if let Some(circuit_hash) = wire.circuit_hash {
+            proof = proof.with_circuit_hash(circuit_hash);
+        }
+        if let Some(metrics) = wire.metrics {
+            proof = proof.with_metrics(metrics);
+        }
+
+        Ok(proof)
+    }
+}
+
+/// Prover delegating to a prover daemon listening on a local unix socket
+#[derive(Debug, Clone)]
+pub struct DaemonProver {
+    socket_path: PathBuf,
+}
+
+impl DaemonProver {
+    /// Connects to the daemon listening on `socket_path`, failing if it is not running
+    pub fn connect(socket_path: impl Into<PathBuf>) -> Result<Self, ProverError> {
+        let prover = Self {
+            socket_path: socket_path.into(),
+        };
+
+        match prover.call(&Request::Ping)? {
+            Response::Pong => Ok(prover),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Returns the path of the daemon socket
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Sends a single request over a fresh connection and waits for the response
+    fn call(&self, request: &Request) -> Result<Response, ProverError> {
+        let daemon_err = |e: std::io::Error| ProverError::DaemonError(e.to_string());
+
+        let mut stream = UnixStream::connect(&self.socket_path).map_err(daemon_err)?;
+        let mut line =
+            serde_json::to_string(request).map_err(|e| ProverError::DaemonError(e.to_string()))?;
+        line.push('\n');
+        stream.write_all(line.as_bytes()).map_err(daemon_err)?;
+
+        let mut line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut line)
+            .map_err(daemon_err)?;
+
+        match serde_json::from_str(&line).map_err(|e| ProverError::DaemonError(e.to_string()))? {
+            Response::Error { error } => Err(error),
+            response => Ok(response),
+        }
+    }
+
+    fn call_setup(&self, request: &Request) -> Result<PreparedCircuit, ProverError> {
+        match self.call(request)? {
+            Response::Setup { circuit } => Ok(circuit),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn call_proof(&self, request: &Request) -> Result<OpRandProof, ProverError> {
+        match self.call(request)? {
+            Response::Proof { proof } => proof.try_into(),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn call_verify(&self, request: &Request) -> Result<(), ProverError> {
+        match self.call(request)? {
+            Response::Verified => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+impl OpRandProver for DaemonProver {
+    fn setup_challenger_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        self.call_setup(&Request::SetupChallengerCircuit)
+    }
+
+    fn setup_acceptor_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        self.call_setup(&Request::SetupAcceptorCircuit)
+    }
+
+    fn generate_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> Result<OpRandProof, ProverError> {
+        self.call_proof(&Request::GenerateChallengerProof {
+            circuit: circuit.clone(),
+            first_rank_commitments: first_rank_commitments.iter().map(|c| c.inner().0).collect(),
+            third_rank_commitments: third_rank_commitments.iter().map(|c| c.inner()).collect(),
+            challenger_public_key: *challenger_public_key,
+            challenger_public_key_hash: hex::encode(challenger_public_key_hash),
+            deposit_outpoint: *deposit_outpoint,
+        })
+    }
+
+    fn verify_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        self.call_verify(&Request::VerifyChallengerProof {
+            circuit: circuit.clone(),
+            third_rank_commitments: third_rank_commitments.iter().map(|c| c.inner()).collect(),
+            challenger_public_key: *challenger_public_key,
+            challenger_public_key_hash: hex::encode(challenger_public_key_hash),
+            deposit_outpoint: *deposit_outpoint,
+            proof: proof.into(),
+        })
+    }
+
+    fn generate_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> Result<OpRandProof, ProverError> {
+        self.call_proof(&Request::GenerateAcceptorProof {
+            circuit: circuit.clone(),
+            acceptor_public_key: *acceptor_public_key,
+            acceptor_signature: *acceptor_signature,
+            acceptor_public_key_hash: hex::encode(acceptor_public_key_hash),
+            third_rank_commitments: third_rank_commitments.iter().map(|c| c.inner()).collect(),
+        })
+    }
+
+    fn verify_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        self.call_verify(&Request::VerifyAcceptorProof {
+            circuit: circuit.clone(),
+            acceptor_public_key_hash: hex::encode(acceptor_public_key_hash),
+            third_rank_commitments: third_rank_commitments.iter().map(|c| c.inner()).collect(),
+            proof: proof.into(),
+        })
+    }
+}
+
+fn unexpected(response: Response) -> ProverError {
+    ProverError::DaemonError(format!("unexpected response {:?}", response))
+}
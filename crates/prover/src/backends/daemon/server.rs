@@ -0,0 +1,198 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    str::FromStr,
+};
+
+use bitcoin::secp256k1::{PublicKey, SecretKey};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+
+use super::{Request, Response};
+use crate::{
+    backends::BarretenbergProver,
+    errors::ProverError,
+    traits::{OpRandProver, PreparedCircuit},
+};
+
+/// Prover daemon keeping both circuits set up between requests
+pub struct ProverDaemon {
+    prover: BarretenbergProver,
+    challenger_circuit: PreparedCircuit,
+    acceptor_circuit: PreparedCircuit,
+}
+
+impl ProverDaemon {
+    /// Sets up both circuits. The SRS is a global shared by the circuits, so the circuit with the
+    /// larger SRS is set up last and the loaded SRS serves both of them.
+    pub fn new(prover: BarretenbergProver) -> Result<Self, ProverError> {
+        let mut challenger_circuit = prover.setup_challenger_circuit()?;
+        let acceptor_circuit = prover.setup_acceptor_circuit()?;
+        if challenger_circuit.srs_size() > acceptor_circuit.srs_size() {
+            challenger_circuit = prover.setup_challenger_circuit()?;
+        }
+
+        Ok(Self {
+            prover,
+            challenger_circuit,
+            acceptor_circuit,
+        })
+    }
+
+    /// Returns the circuits the daemon serves
+    pub fn circuits(&self) -> [&PreparedCircuit; 2] {
+        [&self.challenger_circuit, &self.acceptor_circuit]
+    }
+
+    /// Binds the socket at `socket_path`, readable by the current user only, and serves requests
+    /// one at a time until the listener fails.
+    pub fn serve(&self, socket_path: &Path) -> Result<(), ProverError> {
+        let daemon_err = |e: std::io::Error| ProverError::DaemonError(e.to_string());
+
+        // A socket left behind by a previous daemon would make the bind fail
+        if UnixStream::connect(socket_path).is_err() {
+            let _ = fs::remove_file(socket_path);
+        }
+
+        let listener = UnixListener::bind(socket_path).map_err(daemon_err)?;
+        fs::set_permissions(socket_path, fs::Permissions::from_mode(0o600)).map_err(daemon_err)?;
+
+        for stream in listener.incoming() {
+            // A misbehaving client must not bring the daemon down
+            let _ = stream.map_err(daemon_err).and_then(|s| self.handle(s));
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self, stream: UnixStream) -> Result<(), ProverError> {
+        let daemon_err = |e: std::io::Error| ProverError::DaemonError(e.to_string());
+
+        let mut reader = BufReader::new(stream.try_clone().map_err(daemon_err)?);
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(daemon_err)?;
+
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => self
+                .respond(request)
+                .unwrap_or_else(|error| Response::Error { error }),
+            Err(e) => Response::Error {
+                error: ProverError::DaemonError(e.to_string()),
+            },
+        };
+
+        let mut line = serde_json::to_string(&response)
+            .map_err(|e| ProverError::DaemonError(e.to_string()))?;
+        line.push('\n');
+        (&stream).write_all(line.as_bytes()).map_err(daemon_err)
+    }
+
+    fn respond(&self, request: Request) -> Result<Response, ProverError> {
+        let response = match request {
+            Request::Ping => Response::Pong,
+            Request::SetupChallengerCircuit => Response::Setup {
+                circuit: self.challenger_circuit.clone(),
+            },
+            Request::SetupAcceptorCircuit => Response::Setup {
+                circuit: self.acceptor_circuit.clone(),
+            },
+            Request::GenerateChallengerProof {
+                circuit,
+                first_rank_commitments,
+                third_rank_commitments,
+                challenger_public_key,
+                challenger_public_key_hash,
+                deposit_outpoint,
+            } => Response::Proof {
+                proof: (&self.prover.generate_challenger_proof(
+                    &circuit,
+                    &first_rank_from_wire(first_rank_commitments)?,
+                    &third_rank_from_wire(third_rank_commitments)?,
+                    &challenger_public_key,
+                    hash_from_wire(&challenger_public_key_hash)?,
+                    &deposit_outpoint,
+                )?)
+                    .into(),
+            },
+            Request::VerifyChallengerProof {
+                circuit,
+                third_rank_commitments,
+                challenger_public_key,
+                challenger_public_key_hash,
+                deposit_outpoint,
+                proof,
+            } => {
+                self.prover.verify_challenger_proof(
+                    &circuit,
+                    &third_rank_from_wire(third_rank_commitments)?,
+                    &challenger_public_key,
+                    hash_from_wire(&challenger_public_key_hash)?,
+                    &deposit_outpoint,
+                    &proof.try_into()?,
+                )?;
+                Response::Verified
+            }
+            Request::GenerateAcceptorProof {
+                circuit,
+                acceptor_public_key,
+                acceptor_signature,
+                acceptor_public_key_hash,
+                third_rank_commitments,
+            } => Response::Proof {
+                proof: (&self.prover.generate_acceptor_proof(
+                    &circuit,
+                    &acceptor_public_key,
+                    &acceptor_signature,
+                    hash_from_wire(&acceptor_public_key_hash)?,
+                    &third_rank_from_wire(third_rank_commitments)?,
+                )?)
+                    .into(),
+            },
+            Request::VerifyAcceptorProof {
+                circuit,
+                acceptor_public_key_hash,
+                third_rank_commitments,
+                proof,
+            } => {
+                self.prover.verify_acceptor_proof(
+                    &circuit,
+                    hash_from_wire(&acceptor_public_key_hash)?,
+                    &third_rank_from_wire(third_rank_commitments)?,
+                    &proof.try_into()?,
+                )?;
+                Response::Verified
+            }
+        };
+
+        Ok(response)
+    }
+}
+
+fn first_rank_from_wire(keys: Vec<SecretKey>) -> Result<Vec<FirstRankCommitment>, ProverError> {
+    keys.into_iter()
+        .map(|key| {
+            FirstRankCommitment::from_str(&key.display_secret().to_string())
+                .map_err(|e| ProverError::DaemonError(e.to_string()))
+        })
+        .collect()
+}
+
+fn third_rank_from_wire(keys: Vec<PublicKey>) -> Result<Vec<ThirdRankCommitment>, ProverError> {
+    keys.into_iter()
+        .map(|key| {
+            ThirdRankCommitment::from_str(&key.to_string())
+                .map_err(|e| ProverError::DaemonError(e.to_string()))
+        })
+        .collect()
+}
+
+fn hash_from_wire(hash: &str) -> Result<[u8; 20], ProverError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ProverError::DaemonError(format!("invalid public key hash {}", hash)))
+}
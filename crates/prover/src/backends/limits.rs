@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+#[cfg(feature = "barretenberg")]
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+};
+
+use crate::{
+    errors::ProverError,
+    traits::{CancellationToken, Circuit},
+};
+
+/// Interval at which a running proof generation checks whether it is aborted
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Cancellation token and timeout of the proof generations of a prover
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ProvingLimits {
+    pub(crate) cancellation: Option<CancellationToken>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ProvingLimits {
+    /// Returns whether proof generations can't be aborted
+    #[cfg(feature = "barretenberg")]
+    fn is_unlimited(&self) -> bool {
+        self.cancellation.is_none() && self.timeout.is_none()
+    }
+
+    /// Fails if the proof of `circuit` that started at `started` was cancelled or ran out of
+    /// time
+    pub(crate) fn check(&self, circuit: Circuit, started: Instant) -> Result<(), ProverError> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(ProverError::Cancelled);
+        }
+        if let Some(timeout) = self.timeout
+            && started.elapsed() >= timeout
+        {
+            return Err(ProverError::TimedOut { circuit, timeout });
+        }
+
+        Ok(())
+    }
+
+    /// Runs `f` for a proof of `circuit` and returns its result, unless the proof is aborted
+    /// first. Barretenberg can't be interrupted, so `f` runs on a thread of its own and an
+    /// aborted `f` still runs to completion in the background, its result discarded.
+    #[cfg(feature = "barretenberg")]
+    pub(crate) fn run<T: Send + 'static>(
+        &self,
+        circuit: Circuit,
+        started: Instant,
+        f: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, ProverError> {
+        self.check(circuit, started)?;
+        if self.is_unlimited() {
+            return Ok(f());
+        }
+
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            // The receiver is gone if the proof was aborted
+            let _ = sender.send(f());
+        });
+
+        loop {
+            match result.recv_timeout(POLL_INTERVAL) {
+                Ok(result) => return Ok(result),
+                Err(RecvTimeoutError::Timeout) => self.check(circuit, started)?,
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(ProverError::BackendError {
+                        circuit,
+                        code: None,
+                        message: "the proving thread panicked".to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
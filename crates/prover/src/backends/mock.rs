@@ -0,0 +1,233 @@
+use bitcoin::{
+    OutPoint,
+    hashes::{Hash, HashEngine, hash160, sha256},
+    secp256k1::{self, Message, PublicKey, Secp256k1, ecdsa},
+};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+
+use crate::{
+    errors::ProverError,
+    traits::{
+        AcceptorPublicInputs, ChallengerPublicInputs, Circuit, OpRandProof, OpRandProver,
+        PreparedCircuit, public_inputs::to_field_elements,
+    },
+};
+
+/// Domain separation tag of mock proofs and verification keys
+const MOCK_TAG: &[u8] = b"op_rand/mock-proof";
+
+/// Prover generating fake proofs, for tests that can't build Barretenberg.
+///
+/// It checks the same relations as the circuits before proving, so an invalid witness fails as
+/// it would with a real prover. A proof is its public inputs, laid out as in a real proof,
+/// followed by a hash binding them to the circuit. Anyone can forge one, so it must never
+/// verify proofs of a counterparty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockProver;
+
+impl MockProver {
+    fn setup(&self, circuit: Circuit) -> PreparedCircuit {
+        PreparedCircuit::new(circuit, 0, mock_vk(circuit), sha256::Hash::hash(MOCK_TAG))
+    }
+
+    /// Checks that `prepared` is a mock setup of `circuit`
+    fn check_prepared(
+        &self,
+        prepared: &PreparedCircuit,
+        circuit: Circuit,
+    ) -> Result<(), ProverError> {
+        if prepared.circuit() != circuit {
+            return Err(ProverError::UnexpectedCircuit {
+                expected: circuit,
+                got: prepared.circuit(),
+            });
+        }
+        if *prepared != self.setup(circuit) {
+            return Err(ProverError::CircuitNotPrepared(circuit));
+        }
+
+        Ok(())
+    }
+
+    fn prove(&self, circuit: Circuit, public_inputs: &[u8]) -> OpRandProof {
+        let mut proof = to_field_elements(public_inputs);
+        proof.extend_from_slice(proof_tag(circuit, public_inputs).as_byte_array());
+
+        OpRandProof::new(proof, mock_vk(circuit))
+    }
+
+    fn verify(
+        &self,
+        circuit: &PreparedCircuit,
+        public_inputs: &[u8],
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        let tag = proof.proof().get(public_inputs.len() * 32..);
+        if tag != Some(proof_tag(circuit.circuit(), public_inputs).as_byte_array()) {
+            return Err(ProverError::InvalidProof);
+        }
+
+        Ok(())
+    }
+}
+
+impl OpRandProver for MockProver {
+    fn setup_challenger_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        Ok(self.setup(Circuit::Challenger))
+    }
+
+    fn setup_acceptor_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        Ok(self.setup(Circuit::Acceptor))
+    }
+
+    fn generate_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> Result<OpRandProof, ProverError> {
+        self.check_prepared(circuit, Circuit::Challenger)?;
+        let first_rank_commitments: [_; 2] = circuit.commitment_array(first_rank_commitments)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        let unsatisfied = |constraint: &str| ProverError::UnsatisfiedWitness {
+            circuit: Circuit::Challenger,
+            reason: format!("unsatisfied constraint {}", constraint),
+        };
+
+        // H_i = sha256(A_i) * G, and ADDR is hash160(PK + A1) or hash160(PK + A2)
+        let secp = Secp256k1::new();
+        let mut addresses = Vec::with_capacity(2);
+        for (first, third) in first_rank_commitments.iter().zip(&third_rank_commitments) {
+            let (_, first_rank_public_key) = first.inner();
+            let derived =
+                ThirdRankCommitment::from_first_rank_public_key(&secp, &first_rank_public_key)
+                    .map_err(|_| unsatisfied("third rank commitments"))?;
+            if !derived.ct_eq(third) {
+                return Err(unsatisfied("third rank commitments"));
+            }
+
+            let combined = challenger_public_key
+                .combine(&first_rank_public_key)
+                .map_err(|_| unsatisfied("ADDR"))?;
+            addresses.push(hash160::Hash::hash(&combined.serialize()).to_byte_array());
+        }
+        if !addresses.contains(&challenger_public_key_hash) {
+            return Err(unsatisfied("ADDR"));
+        }
+
+        let public_inputs = ChallengerPublicInputs {
+            third_rank_commitments,
+            challenger_public_key: *challenger_public_key,
+            challenger_public_key_hash,
+            deposit_outpoint: *deposit_outpoint,
+        };
+
+        Ok(self.prove(Circuit::Challenger, &public_inputs.to_bytes()))
+    }
+
+    fn verify_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &secp256k1::PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        self.check_prepared(circuit, Circuit::Challenger)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+
+        let public_inputs = ChallengerPublicInputs {
+            third_rank_commitments,
+            challenger_public_key: *challenger_public_key,
+            challenger_public_key_hash,
+            deposit_outpoint: *deposit_outpoint,
+        };
+        public_inputs.verify(proof)?;
+
+        self.verify(circuit, &public_inputs.to_bytes(), proof)
+    }
+
+    fn generate_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> Result<OpRandProof, ProverError> {
+        self.check_prepared(circuit, Circuit::Acceptor)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+        let unsatisfied = |constraint: &str| ProverError::UnsatisfiedWitness {
+            circuit: Circuit::Acceptor,
+            reason: format!("unsatisfied constraint {}", constraint),
+        };
+
+        // S signs sha256(ADDR) with PK, and ADDR is hash160(PK + H1) or hash160(PK + H2)
+        let message =
+            Message::from_digest(sha256::Hash::hash(&acceptor_public_key_hash).to_byte_array());
+        Secp256k1::verification_only()
+            .verify_ecdsa(&message, acceptor_signature, acceptor_public_key)
+            .map_err(|_| unsatisfied("S"))?;
+
+        let addresses = third_rank_commitments
+            .iter()
+            .map(|commitment| {
+                let combined = acceptor_public_key
+                    .combine(&commitment.inner())
+                    .map_err(|_| unsatisfied("ADDR"))?;
+                Ok(hash160::Hash::hash(&combined.serialize()).to_byte_array())
+            })
+            .collect::<Result<Vec<_>, ProverError>>()?;
+        if !addresses.contains(&acceptor_public_key_hash) {
+            return Err(unsatisfied("ADDR"));
+        }
+
+        let public_inputs = AcceptorPublicInputs {
+            third_rank_commitments,
+            acceptor_public_key_hash,
+        };
+
+        Ok(self.prove(Circuit::Acceptor, &public_inputs.to_bytes()))
+    }
+
+    fn verify_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        self.check_prepared(circuit, Circuit::Acceptor)?;
+        let third_rank_commitments = circuit.commitment_array(third_rank_commitments)?;
+
+        let public_inputs = AcceptorPublicInputs {
+            third_rank_commitments,
+            acceptor_public_key_hash,
+        };
+        public_inputs.verify(proof)?;
+
+        self.verify(circuit, &public_inputs.to_bytes(), proof)
+    }
+}
+
+/// Verification key of a mock circuit
+fn mock_vk(circuit: Circuit) -> Vec<u8> {
+    let mut engine = sha256::Hash::engine();
+    engine.input(MOCK_TAG);
+    engine.input(circuit.to_string().as_bytes());
+
+    sha256::Hash::from_engine(engine).to_byte_array().to_vec()
+}
+
+/// Hash binding mock proof public inputs to the circuit
+fn proof_tag(circuit: Circuit, public_inputs: &[u8]) -> sha256::Hash {
+    let mut engine = sha256::Hash::engine();
+    engine.input(&mock_vk(circuit));
+    engine.input(public_inputs);
+
+    sha256::Hash::from_engine(engine)
+}
@@ -1,3 +1,17 @@
+#[cfg(feature = "barretenberg")]
 mod barretenberg;
+mod daemon;
+#[cfg(feature = "barretenberg")]
+mod limits;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "barretenberg")]
+mod witness;
 
-pub use barretenberg::BarretenbergProver;
+#[cfg(feature = "barretenberg")]
+pub use barretenberg::{BarretenbergProver, BarretenbergProverBuilder};
+pub use daemon::DaemonProver;
+#[cfg(feature = "barretenberg")]
+pub use daemon::ProverDaemon;
+#[cfg(feature = "mock")]
+pub use mock::MockProver;
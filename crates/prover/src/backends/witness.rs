@@ -0,0 +1,165 @@
+use bitcoin::{OutPoint, secp256k1};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+
+use crate::{
+    errors::ProverError,
+    traits::{AcceptorPublicInputs, ChallengerPublicInputs, Circuit},
+};
+
+/// Parameters of a circuit main function as name and size in bytes, in order. Every parameter
+/// is a byte array and every byte a separate field element of the witness.
+pub(crate) struct WitnessAbi {
+    circuit: Circuit,
+    private: &'static [(&'static str, usize)],
+    public: &'static [(&'static str, usize)],
+}
+
+impl WitnessAbi {
+    pub(crate) const CHALLENGER: Self = Self {
+        circuit: Circuit::Challenger,
+        private: &[("a1", 32), ("a2", 32)],
+        public: ChallengerPublicInputs::LAYOUT,
+    };
+
+    pub(crate) const ACCEPTOR: Self = Self {
+        circuit: Circuit::Acceptor,
+        private: &[("PK_x", 32), ("PK_y", 32), ("S", 64)],
+        public: AcceptorPublicInputs::LAYOUT,
+    };
+
+    fn parameters(&self) -> impl Iterator<Item = &(&'static str, usize)> {
+        self.private.iter().chain(self.public)
+    }
+}
+
+/// Assembles the initial witness of a circuit in the order of its ABI. Every parameter is
+/// checked against the ABI as it is added, so a malformed witness fails before proving.
+pub(crate) struct WitnessBuilder {
+    abi: &'static WitnessAbi,
+    /// Number of parameters added so far
+    added: usize,
+    inputs: Vec<String>,
+}
+
+impl WitnessBuilder {
+    pub(crate) fn new(abi: &'static WitnessAbi) -> Self {
+        Self {
+            abi,
+            added: 0,
+            inputs: Vec::with_capacity(abi.parameters().map(|(_, size)| size).sum()),
+        }
+    }
+
+    /// Adds the private parameter `name`, which must be the next one of the ABI
+    pub(crate) fn private(mut self, name: &str, bytes: &[u8]) -> Result<Self, ProverError> {
+        let Some(&(expected, size)) = self.abi.private.get(self.added) else {
+            return Err(self.malformed(name, "unexpected private parameter".to_string()));
+        };
+        if name != expected {
+            return Err(self.malformed(name, format!("expected {}", expected)));
+        }
+        if bytes.len() != size {
+            return Err(self.malformed(name, format!("{} bytes, expected {}", bytes.len(), size)));
+        }
+
+        self.push(bytes);
+
+        Ok(self)
+    }
+
+    /// Adds the public parameters, serialized as by `to_bytes` of the public inputs of the
+    /// circuit. They follow all private parameters.
+    pub(crate) fn public_inputs(mut self, bytes: &[u8]) -> Result<Self, ProverError> {
+        if let Some((missing, _)) = self.abi.private.get(self.added) {
+            return Err(self.malformed(missing, "private parameter is missing".to_string()));
+        }
+        let size = self.abi.public.iter().map(|(_, size)| size).sum::<usize>();
+        if bytes.len() != size {
+            return Err(self.malformed(
+                "public inputs",
+                format!("{} bytes, expected {}", bytes.len(), size),
+            ));
+        }
+
+        let mut rest = bytes;
+        for (_, size) in self.abi.public {
+            let (parameter, tail) = rest.split_at(*size);
+            self.push(parameter);
+            rest = tail;
+        }
+
+        Ok(self)
+    }
+
+    /// Returns the witness, one decimal field element per byte
+    pub(crate) fn build(self) -> Result<Vec<String>, ProverError> {
+        if let Some((missing, _)) = self.abi.parameters().nth(self.added) {
+            return Err(self.malformed(missing, "parameter is missing".to_string()));
+        }
+
+        Ok(self.inputs)
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.inputs.extend(bytes.iter().map(|b| b.to_string()));
+        self.added += 1;
+    }
+
+    fn malformed(&self, field: &str, reason: String) -> ProverError {
+        ProverError::MalformedWitness {
+            circuit: self.abi.circuit,
+            field: field.to_string(),
+            reason,
+        }
+    }
+}
+
+/// Returns the initial witness of the challenger circuit
+pub(crate) fn challenger_witness(
+    first_rank_commitments: &[FirstRankCommitment; 2],
+    third_rank_commitments: [ThirdRankCommitment; 2],
+    challenger_public_key: &secp256k1::PublicKey,
+    challenger_public_key_hash: [u8; 20],
+    deposit_outpoint: &OutPoint,
+) -> Result<Vec<String>, ProverError> {
+    let (a1, _) = first_rank_commitments[0].inner();
+    let (a2, _) = first_rank_commitments[1].inner();
+
+    WitnessBuilder::new(&WitnessAbi::CHALLENGER)
+        .private("a1", &a1.secret_bytes())?
+        .private("a2", &a2.secret_bytes())?
+        .public_inputs(
+            &ChallengerPublicInputs {
+                third_rank_commitments,
+                challenger_public_key: *challenger_public_key,
+                challenger_public_key_hash,
+                deposit_outpoint: *deposit_outpoint,
+            }
+            .to_bytes(),
+        )?
+        .build()
+}
+
+/// Returns the initial witness of the acceptor circuit
+pub(crate) fn acceptor_witness(
+    acceptor_public_key: &secp256k1::PublicKey,
+    acceptor_signature: &secp256k1::ecdsa::Signature,
+    acceptor_public_key_hash: [u8; 20],
+    third_rank_commitments: [ThirdRankCommitment; 2],
+) -> Result<Vec<String>, ProverError> {
+    // Skip the 0x04 prefix of the uncompressed key
+    let pk_coords = acceptor_public_key.serialize_uncompressed();
+
+    WitnessBuilder::new(&WitnessAbi::ACCEPTOR)
+        .private("PK_x", &pk_coords[1..33])?
+        .private("PK_y", &pk_coords[33..65])?
+        .private("S", &acceptor_signature.serialize_compact())?
+        .public_inputs(
+            &AcceptorPublicInputs {
+                third_rank_commitments,
+                acceptor_public_key_hash,
+            }
+            .to_bytes(),
+        )?
+        .build()
+}
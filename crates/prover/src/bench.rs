@@ -0,0 +1,201 @@
+//! Timing of the setup, proof generation and verification of both circuits, so that a change
+//! of the circuits or of the proving stack that slows proving down is noticed.
+
+use std::time::{Duration, Instant};
+
+use bitcoin::{
+    OutPoint, Txid,
+    hashes::{Hash, hash160, sha256},
+    secp256k1::{self, Message, PublicKey, Secp256k1, ecdsa, rand::Rng},
+};
+use op_rand_types::Commitments;
+
+use crate::{
+    errors::ProverError,
+    traits::{Circuit, OpRandProof, OpRandProver, PreparedCircuit},
+};
+
+/// Random inputs satisfying both circuits
+#[derive(Debug, Clone)]
+pub struct BenchInputs {
+    commitments: Commitments,
+    challenger_public_key: PublicKey,
+    challenger_public_key_hash: [u8; 20],
+    deposit_outpoint: OutPoint,
+    acceptor_public_key: PublicKey,
+    acceptor_signature: ecdsa::Signature,
+    acceptor_public_key_hash: [u8; 20],
+}
+
+impl BenchInputs {
+    /// Generates a challenge with random commitments and keys, accepted with a guess of the
+    /// first commitment
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R) -> Result<Self, secp256k1::Error> {
+        let secp = Secp256k1::new();
+        let commitments = Commitments::generate(&secp, rng)?;
+
+        // ADDR of the challenger is hash160(PK + A1)
+        let (_, challenger_public_key) = secp.generate_keypair(rng);
+        let (_, first_rank_public_key) = commitments.first_rank_commitments()[0].inner();
+        let challenger_public_key_hash = hash160::Hash::hash(
+            &challenger_public_key
+                .combine(&first_rank_public_key)?
+                .serialize(),
+        )
+        .to_byte_array();
+
+        // ADDR of the acceptor is hash160(PK + H1), and S signs sha256(ADDR)
+        let (acceptor_secret_key, acceptor_public_key) = secp.generate_keypair(rng);
+        let third_rank_public_key = commitments.third_rank_commitments()[0].inner();
+        let acceptor_public_key_hash = hash160::Hash::hash(
+            &acceptor_public_key
+                .combine(&third_rank_public_key)?
+                .serialize(),
+        )
+        .to_byte_array();
+        let message =
+            Message::from_digest(sha256::Hash::hash(&acceptor_public_key_hash).to_byte_array());
+
+        Ok(Self {
+            commitments,
+            challenger_public_key,
+            challenger_public_key_hash,
+            deposit_outpoint: OutPoint::new(Txid::from_byte_array(rng.r#gen()), 0),
+            acceptor_public_key,
+            acceptor_signature: secp.sign_ecdsa(&message, &acceptor_secret_key),
+            acceptor_public_key_hash,
+        })
+    }
+
+    /// Generates a proof of the challenger circuit
+    pub fn prove_challenger<P: OpRandProver + ?Sized>(
+        &self,
+        prover: &P,
+        circuit: &PreparedCircuit,
+    ) -> Result<OpRandProof, ProverError> {
+        prover.generate_challenger_proof(
+            circuit,
+            self.commitments.first_rank_commitments(),
+            self.commitments.third_rank_commitments(),
+            &self.challenger_public_key,
+            self.challenger_public_key_hash,
+            &self.deposit_outpoint,
+        )
+    }
+
+    /// Verifies a proof generated by [`prove_challenger`](Self::prove_challenger)
+    pub fn verify_challenger<P: OpRandProver + ?Sized>(
+        &self,
+        prover: &P,
+        circuit: &PreparedCircuit,
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        prover.verify_challenger_proof(
+            circuit,
+            self.commitments.third_rank_commitments(),
+            &self.challenger_public_key,
+            self.challenger_public_key_hash,
+            &self.deposit_outpoint,
+            proof,
+        )
+    }
+
+    /// Generates a proof of the acceptor circuit
+    pub fn prove_acceptor<P: OpRandProver + ?Sized>(
+        &self,
+        prover: &P,
+        circuit: &PreparedCircuit,
+    ) -> Result<OpRandProof, ProverError> {
+        prover.generate_acceptor_proof(
+            circuit,
+            &self.acceptor_public_key,
+            &self.acceptor_signature,
+            self.acceptor_public_key_hash,
+            self.commitments.third_rank_commitments(),
+        )
+    }
+
+    /// Verifies a proof generated by [`prove_acceptor`](Self::prove_acceptor)
+    pub fn verify_acceptor<P: OpRandProver + ?Sized>(
+        &self,
+        prover: &P,
+        circuit: &PreparedCircuit,
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        prover.verify_acceptor_proof(
+            circuit,
+            self.acceptor_public_key_hash,
+            self.commitments.third_rank_commitments(),
+            proof,
+        )
+    }
+}
+
+/// Timings of a circuit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBench {
+    pub circuit: Circuit,
+    /// Time the first setup of the circuit in this process took, see
+    /// [`PreparedCircuit::setup_ms`]
+    pub setup: Duration,
+    pub prove: Duration,
+    pub verify: Duration,
+    pub proof_bytes: usize,
+}
+
+/// Timings of both circuits, see [`bench`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchReport {
+    pub challenger: CircuitBench,
+    pub acceptor: CircuitBench,
+}
+
+/// Sets up both circuits, then generates and verifies a proof of each with `inputs`, timing
+/// every step. Setups are cached for the whole process, so the setup times are those of the
+/// first setups.
+pub fn bench<P: OpRandProver + ?Sized>(
+    prover: &P,
+    inputs: &BenchInputs,
+) -> Result<BenchReport, ProverError> {
+    let circuit = prover.setup_challenger_circuit()?;
+    let challenger = time(
+        &circuit,
+        || inputs.prove_challenger(prover, &circuit),
+        |proof| inputs.verify_challenger(prover, &circuit, proof),
+    )?;
+
+    let circuit = prover.setup_acceptor_circuit()?;
+    let acceptor = time(
+        &circuit,
+        || inputs.prove_acceptor(prover, &circuit),
+        |proof| inputs.verify_acceptor(prover, &circuit, proof),
+    )?;
+
+    Ok(BenchReport {
+        challenger,
+        acceptor,
+    })
+}
+
+/// Times `prove` and `verify` of the proof it generates
+fn time(
+    circuit: &PreparedCircuit,
+    prove: impl FnOnce() -> Result<OpRandProof, ProverError>,
+    verify: impl FnOnce(&OpRandProof) -> Result<(), ProverError>,
+) -> Result<CircuitBench, ProverError> {
+    let started = Instant::now();
+    let proof = prove()?;
+    let prove_time = started.elapsed();
+
+    let started = Instant::now();
+    verify(&proof)?;
+    let verify_time = started.elapsed();
+
+    Ok(CircuitBench {
+        circuit: circuit.circuit(),
+        setup: Duration::from_millis(circuit.setup_ms()),
+        prove: prove_time,
+        verify: verify_time,
+        proof_bytes: proof.proof().len(),
+    })
+}
@@ -1,7 +1,45 @@
+use bitcoin::hashes::{Hash, sha256};
 use eyre::OptionExt;
 use lazy_static::lazy_static;
 use serde_json::Value;
-use std::fs;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// Path to the compiled challenger circuit
+pub const CHALLENGER_CIRCUIT_PATH: &str =
+    "circuits/crates/challenger_circuit/target/challenger_circuit.json";
+
+/// Path to the compiled acceptor circuit
+pub const ACCEPTOR_CIRCUIT_PATH: &str =
+    "circuits/crates/acceptor_circuit/target/acceptor_circuit.json";
+
+/// Directory searched for compiled circuits before the source tree paths
+static CIRCUIT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Sets the directory searched for `challenger_circuit.json` and `acceptor_circuit.json` before
+/// the source tree paths. Only the first call before the circuits are loaded has an effect.
+pub fn set_circuit_dir(dir: PathBuf) {
+    let _ = CIRCUIT_DIR.set(dir);
+}
+
+/// Returns the path a circuit is loaded from
+fn circuit_path(file_name: &str, source_tree_path: &str) -> PathBuf {
+    CIRCUIT_DIR
+        .get()
+        .map(|dir| dir.join(file_name))
+        .filter(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(source_tree_path))
+}
+
+lazy_static! {
+    static ref CHALLENGER_CIRCUIT_FILE: PathBuf =
+        circuit_path("challenger_circuit.json", CHALLENGER_CIRCUIT_PATH);
+    static ref ACCEPTOR_CIRCUIT_FILE: PathBuf =
+        circuit_path("acceptor_circuit.json", ACCEPTOR_CIRCUIT_PATH);
+}
 
 /// Metadata for a circuit. Used to load the bytecode from a JSON file
 pub struct CircuitMetadata {
@@ -10,7 +48,7 @@ pub struct CircuitMetadata {
 
 impl CircuitMetadata {
     /// Creates a new `CircuitMetadata` from a JSON file
-    pub fn from_file(path: &str) -> eyre::Result<Self> {
+    pub fn from_file(path: &Path) -> eyre::Result<Self> {
         let content = fs::read_to_string(path)?;
         let json: Value = serde_json::from_str(&content)?;
 
@@ -28,19 +66,17 @@ impl CircuitMetadata {
 lazy_static! {
     /// Bytecode for the challenger circuit
     pub static ref CHALLENGER_CIRCUIT_BYTECODE: String = {
-        CircuitMetadata::from_file(
-            "circuits/crates/challenger_circuit/target/challenger_circuit.json",
-        )
-        .map(|metadata| metadata.bytecode)
-        .unwrap_or_else(|e| {
-            eprintln!("Failed to load challenger circuit bytecode: {}", e);
-            String::new()
-        })
+        CircuitMetadata::from_file(&CHALLENGER_CIRCUIT_FILE)
+            .map(|metadata| metadata.bytecode)
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load challenger circuit bytecode: {}", e);
+                String::new()
+            })
     };
 
     /// Bytecode for the acceptor circuit
     pub static ref ACCEPTOR_CIRCUIT_BYTECODE: String = {
-        CircuitMetadata::from_file("circuits/crates/acceptor_circuit/target/acceptor_circuit.json")
+        CircuitMetadata::from_file(&ACCEPTOR_CIRCUIT_FILE)
             .map(|metadata| metadata.bytecode)
             .unwrap_or_else(|e| {
                 eprintln!("Failed to load acceptor circuit bytecode: {}", e);
@@ -48,3 +84,45 @@ lazy_static! {
             })
     };
 }
+
+/// Where the bytecode of a circuit comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitSource {
+    /// Compiled circuit JSON file, as written by `nargo compile`
+    Path(PathBuf),
+    /// Bytecode, as found in the `bytecode` field of a compiled circuit
+    Bytecode(String),
+}
+
+/// Describes a compiled circuit artifact used by the prover
+#[derive(Debug, Clone)]
+pub struct CircuitArtifact {
+    /// Human-readable circuit name
+    pub name: &'static str,
+    /// Path the artifact is loaded from
+    pub path: PathBuf,
+    /// SHA-256 of the loaded bytecode, `None` if the artifact could not be loaded
+    pub bytecode_hash: Option<sha256::Hash>,
+}
+
+/// Returns the circuit artifacts used by the prover, loading them if needed
+pub fn circuit_artifacts() -> [CircuitArtifact; 2] {
+    let artifact = |name, path: &PathBuf, bytecode: &String| CircuitArtifact {
+        name,
+        path: path.clone(),
+        bytecode_hash: (!bytecode.is_empty()).then(|| sha256::Hash::hash(bytecode.as_bytes())),
+    };
+
+    [
+        artifact(
+            "challenger",
+            &CHALLENGER_CIRCUIT_FILE,
+            &CHALLENGER_CIRCUIT_BYTECODE,
+        ),
+        artifact(
+            "acceptor",
+            &ACCEPTOR_CIRCUIT_FILE,
+            &ACCEPTOR_CIRCUIT_BYTECODE,
+        ),
+    ]
+}
@@ -1,13 +1,86 @@
-#[derive(Debug, Clone, thiserror::Error)]
+use std::time::Duration;
+
+use bitcoin::hashes::sha256;
+use serde::{Deserialize, Serialize};
+
+use crate::traits::Circuit;
+
+#[derive(Debug, Clone, thiserror::Error, Serialize, Deserialize)]
 pub enum ProverError {
-    #[error("Proof generation error: {0}")]
-    ProofGenerationError(String),
+    #[error("The {circuit} witness doesn't satisfy the circuit: {reason}")]
+    UnsatisfiedWitness { circuit: Circuit, reason: String },
+    #[error(
+        "The proving backend failed on the {circuit} proof{}: {message}",
+        code.map(|code| format!(" with code {}", code)).unwrap_or_default()
+    )]
+    BackendError {
+        circuit: Circuit,
+        /// Exit code of the backend process, if it runs in one
+        code: Option<i32>,
+        message: String,
+    },
+    #[error("Failed to load the SRS: {0}")]
+    SrsError(String),
     #[error("Proof verification error: {0}")]
     ProofVerificationError(String),
     #[error("Setup error: {0}")]
     SetupError(String),
     #[error("Invalid number of public signals: expected {expected}, got {got}")]
     InvalidNumberOfPublicSignals { expected: usize, got: usize },
+    #[error("The {circuit} circuit takes {expected} commitments, got {got}")]
+    InvalidNumberOfCommitments {
+        circuit: Circuit,
+        expected: usize,
+        got: usize,
+    },
     #[error("Invalid proof")]
     InvalidProof,
+    #[error("Proof public inputs do not match the expected ones")]
+    PublicInputsMismatch,
+    #[error("Invalid public inputs: {0}")]
+    InvalidPublicInputs(String),
+    #[error("Expected the {expected} circuit, got the {got} circuit")]
+    UnexpectedCircuit { expected: Circuit, got: Circuit },
+    #[error("The {0} circuit was set up with another bytecode or by another prover")]
+    CircuitNotPrepared(Circuit),
+    #[error(
+        "The {circuit} proof was generated with circuit {remote}, this prover has circuit {local}. \
+         Both parties need the same circuit version"
+    )]
+    CircuitMismatch {
+        circuit: Circuit,
+        local: sha256::Hash,
+        remote: sha256::Hash,
+    },
+    #[error("The verification key sent with the {0} proof does not match the {0} circuit")]
+    VerificationKeyMismatch(Circuit),
+    #[error("Malformed {circuit} witness at `{field}`: {reason}")]
+    MalformedWitness {
+        circuit: Circuit,
+        field: String,
+        reason: String,
+    },
+    #[error("Failed to load the {0} circuit: {1}")]
+    CircuitLoadError(Circuit, String),
+    #[error("Prover daemon error: {0}")]
+    DaemonError(String),
+    #[error("Prover task was cancelled")]
+    Cancelled,
+    #[error("The {circuit} proof was not generated within {timeout:?}")]
+    TimedOut { circuit: Circuit, timeout: Duration },
+}
+
+impl ProverError {
+    /// Returns whether the prover or its backend failed, rather than the inputs, circuits or
+    /// proofs it was given being wrong. Such a failure is a bug or an environment issue, and
+    /// the same call may succeed on another machine.
+    pub fn is_backend_failure(&self) -> bool {
+        matches!(
+            self,
+            ProverError::BackendError { .. }
+                | ProverError::SrsError(_)
+                | ProverError::SetupError(_)
+                | ProverError::DaemonError(_)
+        )
+    }
 }
@@ -1,8 +1,24 @@
 mod backends;
+#[cfg(feature = "bench")]
+mod bench;
 mod bytecode;
 mod errors;
 mod traits;
 
-pub use backends::BarretenbergProver;
+pub use backends::DaemonProver;
+#[cfg(feature = "mock")]
+pub use backends::MockProver;
+#[cfg(feature = "barretenberg")]
+pub use backends::{BarretenbergProver, BarretenbergProverBuilder, ProverDaemon};
+#[cfg(feature = "bench")]
+pub use bench::{BenchInputs, BenchReport, CircuitBench, bench};
+pub use bytecode::{CircuitArtifact, CircuitSource, circuit_artifacts, set_circuit_dir};
 pub use errors::ProverError;
-pub use traits::{OpRandProof, OpRandProver};
+#[cfg(feature = "async")]
+pub use traits::OpRandProverAsync;
+pub use traits::{
+    AcceptorPublicInputs, BuildInfo, CancellationToken, ChallengerPublicInputs, Circuit,
+    CircuitAbi, CircuitFingerprint, OpRandProof, OpRandProver, OpRandProverSpawn, PreparedCircuit,
+    ProgressCallback, ProofMetrics, ProofSystem, ProvingHandle, ProvingProgress, ProvingStage,
+    PublicInput, acceptor_circuit_abi, challenger_circuit_abi,
+};
@@ -0,0 +1,77 @@
+use bitcoin::hashes::{Hash, sha256};
+use op_rand_types::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::ProverError,
+    traits::{Circuit, OpRandProver, PreparedCircuit},
+};
+
+/// Hashes identifying a circuit that was set up. Parties that set up circuits with the same
+/// hashes verify each other's proofs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitFingerprint {
+    pub circuit: Circuit,
+    /// SHA-256 of the bytecode that was set up
+    pub bytecode_hash: sha256::Hash,
+    /// SHA-256 of the verification key derived from the bytecode
+    pub vk_hash: sha256::Hash,
+}
+
+impl CircuitFingerprint {
+    /// Returns the fingerprint of a circuit that was set up
+    pub fn new(circuit: &PreparedCircuit) -> Self {
+        Self {
+            circuit: circuit.circuit(),
+            bytecode_hash: circuit.circuit_hash(),
+            vk_hash: sha256::Hash::hash(circuit.vk()),
+        }
+    }
+}
+
+/// Protocol versions and circuit fingerprints of a build, for counterparties to confirm they
+/// run compatible and untampered circuits before staking funds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Protocol version of this build
+    pub protocol_version: u32,
+    /// Oldest protocol version this build plays with
+    pub min_protocol_version: u32,
+    pub challenger: CircuitFingerprint,
+    pub acceptor: CircuitFingerprint,
+}
+
+impl BuildInfo {
+    /// Returns the build info of the given setups of the challenger and acceptor circuits
+    pub fn new(
+        challenger: &PreparedCircuit,
+        acceptor: &PreparedCircuit,
+    ) -> Result<Self, ProverError> {
+        for (prepared, expected) in [
+            (challenger, Circuit::Challenger),
+            (acceptor, Circuit::Acceptor),
+        ] {
+            if prepared.circuit() != expected {
+                return Err(ProverError::UnexpectedCircuit {
+                    expected,
+                    got: prepared.circuit(),
+                });
+            }
+        }
+
+        Ok(Self {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            challenger: CircuitFingerprint::new(challenger),
+            acceptor: CircuitFingerprint::new(acceptor),
+        })
+    }
+
+    /// Sets up both circuits of `prover` and returns the build info of the setups
+    pub fn of<P: OpRandProver + ?Sized>(prover: &P) -> Result<Self, ProverError> {
+        Self::new(
+            &prover.setup_challenger_circuit()?,
+            &prover.setup_acceptor_circuit()?,
+        )
+    }
+}
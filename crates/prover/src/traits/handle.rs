@@ -0,0 +1,269 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, PoisonError, mpsc},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use bitcoin::{OutPoint, secp256k1};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+use secp256k1::{PublicKey, ecdsa};
+
+use crate::{
+    errors::ProverError,
+    traits::{CancellationToken, Circuit, OpRandProof, OpRandProver, PreparedCircuit},
+};
+
+/// Number of proofs generated at once. Barretenberg parallelizes a proof itself, so more
+/// workers would only split the cores between proofs.
+const POOL_SIZE: usize = 2;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static POOL: OnceLock<mpsc::Sender<Job>> = OnceLock::new();
+
+/// Starts proof generations in the background on the proving thread pool of the crate, so
+/// that callers don't block on them nor manage threads of their own. Proofs are generated
+/// two at a time, later ones wait for a free worker.
+///
+/// It is implemented for every `Arc` of an [`OpRandProver`], as the proof outlives the call.
+pub trait OpRandProverSpawn {
+    /// Starts [`OpRandProver::generate_challenger_proof`] and returns a handle to its result
+    fn spawn_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> ProvingHandle;
+
+    /// Starts [`OpRandProver::generate_acceptor_proof`] and returns a handle to its result
+    fn spawn_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> ProvingHandle;
+}
+
+// The proving calls name `P` explicitly, as `Arc<P>` itself has methods of the same names.
+impl<P> OpRandProverSpawn for Arc<P>
+where
+    P: OpRandProver + Send + Sync + ?Sized + 'static,
+{
+    fn spawn_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> ProvingHandle {
+        let prover = self.clone();
+        let prepared = circuit.clone();
+        let first_rank_commitments = first_rank_commitments.to_vec();
+        let third_rank_commitments = third_rank_commitments.to_vec();
+        let challenger_public_key = *challenger_public_key;
+        let deposit_outpoint = *deposit_outpoint;
+
+        ProvingHandle::spawn(circuit.circuit(), move || {
+            P::generate_challenger_proof(
+                &prover,
+                &prepared,
+                &first_rank_commitments,
+                &third_rank_commitments,
+                &challenger_public_key,
+                challenger_public_key_hash,
+                &deposit_outpoint,
+            )
+        })
+    }
+
+    fn spawn_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> ProvingHandle {
+        let prover = self.clone();
+        let prepared = circuit.clone();
+        let acceptor_public_key = *acceptor_public_key;
+        let acceptor_signature = *acceptor_signature;
+        let third_rank_commitments = third_rank_commitments.to_vec();
+
+        ProvingHandle::spawn(circuit.circuit(), move || {
+            P::generate_acceptor_proof(
+                &prover,
+                &prepared,
+                &acceptor_public_key,
+                &acceptor_signature,
+                acceptor_public_key_hash,
+                &third_rank_commitments,
+            )
+        })
+    }
+}
+
+/// Proof generation running in the background, see [`OpRandProverSpawn`]. The result is
+/// polled with [`try_result`](Self::try_result), waited for with [`wait`](Self::wait) or
+/// awaited, as the handle is a future.
+///
+/// Dropping the handle doesn't stop the proof generation, its result is discarded.
+#[derive(Debug)]
+pub struct ProvingHandle {
+    circuit: Circuit,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    slot: Mutex<Slot>,
+    finished: Condvar,
+    cancellation: CancellationToken,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    result: Option<Result<OpRandProof, ProverError>>,
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    /// Stores the result of the proof generation, unless it already has one
+    fn finish(&self, result: Result<OpRandProof, ProverError>) {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        if slot.result.is_some() {
+            return;
+        }
+        slot.result = Some(result);
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+        self.finished.notify_all();
+    }
+}
+
+impl ProvingHandle {
+    fn spawn<F>(circuit: Circuit, f: F) -> Self
+    where
+        F: FnOnce() -> Result<OpRandProof, ProverError> + Send + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let worker_shared = shared.clone();
+        let job: Job = Box::new(move || {
+            // Cancelled while waiting for a free worker
+            if worker_shared.cancellation.is_cancelled() {
+                return;
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+                Err(ProverError::BackendError {
+                    circuit,
+                    code: None,
+                    message: "the proving thread panicked".to_string(),
+                })
+            });
+            worker_shared.finish(result);
+        });
+
+        if let Err(mpsc::SendError(job)) = pool().send(job) {
+            // The workers are gone, which only happens if a thread couldn't be started
+            thread::spawn(job);
+        }
+
+        Self { circuit, shared }
+    }
+
+    /// Returns which circuit the proof is generated for
+    pub fn circuit(&self) -> Circuit {
+        self.circuit
+    }
+
+    /// Returns whether the proof generation is over, successful or not
+    pub fn is_finished(&self) -> bool {
+        self.lock().result.is_some()
+    }
+
+    /// Returns the result of the proof generation if it is over, without blocking
+    pub fn try_result(&self) -> Option<Result<OpRandProof, ProverError>> {
+        self.lock().result.clone()
+    }
+
+    /// Blocks until the proof generation is over and returns its result
+    pub fn wait(self) -> Result<OpRandProof, ProverError> {
+        let mut slot = self.lock();
+        loop {
+            if let Some(result) = slot.result.take() {
+                return result;
+            }
+            slot = self
+                .shared
+                .finished
+                .wait(slot)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Resolves the handle with [`ProverError::Cancelled`]. A proof waiting for a worker is
+    /// never generated. A running one can't be interrupted and completes in the background,
+    /// unless the prover was built with a cancellation token that is cancelled too.
+    pub fn cancel(&self) {
+        self.shared.cancellation.cancel();
+        self.shared.finish(Err(ProverError::Cancelled));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Slot> {
+        self.shared
+            .slot
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Future for ProvingHandle {
+    type Output = Result<OpRandProof, ProverError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = self.lock();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Returns the job queue of the proving thread pool, starting its workers on first use
+fn pool() -> &'static mpsc::Sender<Job> {
+    POOL.get_or_init(|| {
+        let (sender, jobs) = mpsc::channel::<Job>();
+        let jobs = Arc::new(Mutex::new(jobs));
+        for i in 0..POOL_SIZE {
+            let jobs = jobs.clone();
+            // A pool without workers falls back to a thread per proof, see `spawn`
+            let _ = thread::Builder::new()
+                .name(format!("op-rand-prover-{}", i))
+                .spawn(move || {
+                    loop {
+                        let job = jobs.lock().unwrap_or_else(PoisonError::into_inner).recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    }
+                });
+        }
+
+        sender
+    })
+}
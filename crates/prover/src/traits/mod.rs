@@ -1,17 +1,258 @@
+mod build_info;
+mod cancellation;
+mod handle;
+mod progress;
 mod prover;
+#[cfg(feature = "async")]
+mod prover_async;
+pub(crate) mod public_inputs;
 
+use std::{fmt, time::Duration};
+
+use bitcoin::hashes::sha256;
+use serde::{Deserialize, Serialize};
+
+#[cfg(any(feature = "barretenberg", feature = "mock", test))]
+use crate::errors::ProverError;
+
+pub use build_info::{BuildInfo, CircuitFingerprint};
+pub use cancellation::CancellationToken;
+pub use handle::{OpRandProverSpawn, ProvingHandle};
+pub use progress::{ProgressCallback, ProvingProgress, ProvingStage};
 pub use prover::OpRandProver;
+#[cfg(feature = "async")]
+pub use prover_async::OpRandProverAsync;
+pub use public_inputs::{
+    AcceptorPublicInputs, ChallengerPublicInputs, CircuitAbi, PublicInput, acceptor_circuit_abi,
+    challenger_circuit_abi,
+};
+
+/// Circuit of the op_rand protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Circuit {
+    Challenger,
+    Acceptor,
+}
+
+impl Circuit {
+    /// Returns the number of commitments the circuit is built for
+    pub fn commitments(&self) -> usize {
+        match self {
+            Circuit::Challenger | Circuit::Acceptor => 2,
+        }
+    }
+}
+
+impl fmt::Display for Circuit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Circuit::Challenger => write!(f, "challenger"),
+            Circuit::Acceptor => write!(f, "acceptor"),
+        }
+    }
+}
+
+/// Proof system proofs are generated and verified with. Proofs of one system only verify with
+/// provers using the same system, so both parties of a challenge need the same one.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofSystem {
+    #[default]
+    UltraHonk,
+    /// For tooling that can't consume Honk proofs, such as older Solidity verifiers
+    UltraPlonk,
+}
+
+impl fmt::Display for ProofSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofSystem::UltraHonk => write!(f, "UltraHonk"),
+            ProofSystem::UltraPlonk => write!(f, "UltraPlonk"),
+        }
+    }
+}
+
+/// Handle to a circuit that was set up, required to prove and verify with it. Setting up a
+/// circuit loads a global SRS, which another setup may replace with a smaller one. Provers then
+/// set the circuit up again before proving or verifying with the handle, so a handle stays
+/// valid as long as the prover sets up the same bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreparedCircuit {
+    circuit: Circuit,
+    srs_size: u32,
+    vk: Vec<u8>,
+    circuit_hash: sha256::Hash,
+    /// Time the setup took, in milliseconds
+    #[serde(default)]
+    setup_ms: u64,
+}
+
+impl PreparedCircuit {
+    /// Creates a new `PreparedCircuit`
+    pub fn new(circuit: Circuit, srs_size: u32, vk: Vec<u8>, circuit_hash: sha256::Hash) -> Self {
+        Self {
+            circuit,
+            srs_size,
+            vk,
+            circuit_hash,
+            setup_ms: 0,
+        }
+    }
+
+    /// Records how long the setup took
+    pub fn with_setup_time(mut self, setup_time: Duration) -> Self {
+        self.setup_ms = duration_ms(setup_time);
+        self
+    }
+
+    /// Returns how long the setup took, in milliseconds. A setup is reused by later setups of
+    /// the same circuit, which report the time of the first one.
+    pub fn setup_ms(&self) -> u64 {
+        self.setup_ms
+    }
+
+    /// Returns which circuit was set up
+    pub fn circuit(&self) -> Circuit {
+        self.circuit
+    }
+
+    /// Returns the size of the SRS the circuit needs
+    pub fn srs_size(&self) -> u32 {
+        self.srs_size
+    }
+
+    /// Returns the verification key of the circuit. Proofs are verified against it rather than
+    /// against the key they come with.
+    pub fn vk(&self) -> &[u8] {
+        &self.vk
+    }
+
+    /// Returns the SHA-256 of the circuit bytecode
+    pub fn circuit_hash(&self) -> sha256::Hash {
+        self.circuit_hash
+    }
+
+    /// Returns whether `other` is a setup of the same circuit bytecode with the same
+    /// verification key, regardless of the SRS loaded when either was set up
+    #[cfg(feature = "barretenberg")]
+    pub(crate) fn is_same_setup(&self, other: &PreparedCircuit) -> bool {
+        self.circuit == other.circuit
+            && self.circuit_hash == other.circuit_hash
+            && self.vk == other.vk
+    }
+
+    /// Checks that as many commitments were passed as the circuit is built for, and returns
+    /// them as the array its witness and public inputs are built from
+    #[cfg(any(feature = "barretenberg", feature = "mock", test))]
+    pub(crate) fn commitment_array<T: Clone, const N: usize>(
+        &self,
+        commitments: &[T],
+    ) -> Result<[T; N], ProverError> {
+        let expected = self.circuit.commitments();
+        let invalid = || ProverError::InvalidNumberOfCommitments {
+            circuit: self.circuit,
+            expected,
+            got: commitments.len(),
+        };
+        if commitments.len() != expected {
+            return Err(invalid());
+        }
+
+        commitments.to_vec().try_into().map_err(|_| invalid())
+    }
+
+    /// Checks the circuit hash and the verification key a counterparty sent with a proof
+    /// against the ones of this circuit, so that a proof of another circuit never verifies
+    #[cfg(any(feature = "barretenberg", test))]
+    pub(crate) fn check_proof(&self, proof: &OpRandProof) -> Result<(), ProverError> {
+        if let Some(circuit_hash) = proof.circuit_hash()
+            && circuit_hash != self.circuit_hash
+        {
+            return Err(ProverError::CircuitMismatch {
+                circuit: self.circuit,
+                local: self.circuit_hash,
+                remote: circuit_hash,
+            });
+        }
+        if proof.vk() != self.vk {
+            return Err(ProverError::VerificationKeyMismatch(self.circuit));
+        }
+
+        Ok(())
+    }
+}
+
+/// Timing and size metrics of a generated proof
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetrics {
+    /// Time the setup of the circuit took, see [`PreparedCircuit::setup_ms`]
+    pub setup_ms: u64,
+    /// Time the witness generation and the proof took
+    pub prove_ms: u64,
+    pub proof_bytes: usize,
+    pub vk_bytes: usize,
+}
 
 /// op_rand proof containing either a challenger or acceptor proof
+#[derive(Debug, Clone)]
 pub struct OpRandProof {
     proof: Vec<u8>,
     vk: Vec<u8>,
+    circuit_hash: Option<sha256::Hash>,
+    metrics: Option<ProofMetrics>,
 }
 
 impl OpRandProof {
     /// Creates a new `OpRandProof`
     pub fn new(proof: Vec<u8>, vk: Vec<u8>) -> Self {
-        Self { proof, vk }
+        Self {
+            proof,
+            vk,
+            circuit_hash: None,
+            metrics: None,
+        }
+    }
+
+    /// Sets the SHA-256 of the bytecode of the circuit the proof was generated with
+    pub fn with_circuit_hash(mut self, circuit_hash: sha256::Hash) -> Self {
+        self.circuit_hash = Some(circuit_hash);
+        self
+    }
+
+    /// Returns the SHA-256 of the bytecode of the circuit the proof was generated with, if it
+    /// is known. Proofs of older versions don't declare it.
+    pub fn circuit_hash(&self) -> Option<sha256::Hash> {
+        self.circuit_hash
+    }
+
+    /// Attaches the metrics of its generation to the proof
+    pub fn with_metrics(mut self, metrics: ProofMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Attaches the circuit hash and the metrics of a proof of `circuit` that took
+    /// `prove_time`
+    #[cfg(feature = "barretenberg")]
+    pub(crate) fn measured(self, circuit: &PreparedCircuit, prove_time: Duration) -> Self {
+        let metrics = ProofMetrics {
+            setup_ms: circuit.setup_ms(),
+            prove_ms: duration_ms(prove_time),
+            proof_bytes: self.proof.len(),
+            vk_bytes: self.vk.len(),
+        };
+
+        self.with_circuit_hash(circuit.circuit_hash)
+            .with_metrics(metrics)
+    }
+
+    /// Returns the metrics of the proof generation. Only proofs generated by a prover have
+    /// them, not proofs received from a counterparty.
+    pub fn metrics(&self) -> Option<&ProofMetrics> {
+        self.metrics.as_ref()
     }
 
     /// Returns the proof
@@ -23,23 +264,78 @@ impl OpRandProof {
     pub fn vk(&self) -> &[u8] {
         &self.vk
     }
+}
 
-    /// Extracts the public signals from the proof
-    pub fn extract_public_signals(&self, n_signals: usize) -> Vec<&[u8]> {
-        let proof = self.proof();
-        let mut result = Vec::new();
+/// Whole milliseconds of `duration`, saturating at `u64::MAX`
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
 
-        // Extract n_signals number of 32-byte chunks as public signals
-        for i in 0..n_signals {
-            let start = i * 32;
-            let end = start + 32;
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
 
-            if end <= proof.len() {
-                let chunk = &proof[start..end];
-                result.push(chunk);
-            }
-        }
+    use super::*;
+
+    fn circuit() -> PreparedCircuit {
+        PreparedCircuit::new(
+            Circuit::Acceptor,
+            1 << 16,
+            vec![1, 2, 3],
+            sha256::Hash::hash(b"acceptor circuit"),
+        )
+    }
+
+    #[test]
+    fn takes_as_many_commitments_as_circuit() {
+        let commitments: [u8; 2] = circuit().commitment_array(&[1, 2]).unwrap();
+        assert_eq!(commitments, [1, 2]);
+
+        assert!(matches!(
+            circuit().commitment_array::<u8, 2>(&[1, 2, 3]),
+            Err(ProverError::InvalidNumberOfCommitments {
+                circuit: Circuit::Acceptor,
+                expected: 2,
+                got: 3,
+            })
+        ));
+    }
+
+    #[test]
+    fn accepts_proof_of_same_circuit() {
+        let circuit = circuit();
+        let proof = OpRandProof::new(vec![], vec![1, 2, 3])
+            .with_circuit_hash(sha256::Hash::hash(b"acceptor circuit"));
+
+        circuit.check_proof(&proof).unwrap();
+        // Proofs of older versions don't declare their circuit hash
+        circuit
+            .check_proof(&OpRandProof::new(vec![], vec![1, 2, 3]))
+            .unwrap();
+    }
+
+    #[test]
+    fn rejects_proof_of_other_circuit() {
+        let proof = OpRandProof::new(vec![], vec![1, 2, 3])
+            .with_circuit_hash(sha256::Hash::hash(b"other circuit"));
+
+        assert!(matches!(
+            circuit().check_proof(&proof),
+            Err(ProverError::CircuitMismatch {
+                circuit: Circuit::Acceptor,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_proof_with_other_verification_key() {
+        let proof = OpRandProof::new(vec![], vec![3, 2, 1])
+            .with_circuit_hash(sha256::Hash::hash(b"acceptor circuit"));
 
-        result
+        assert!(matches!(
+            circuit().check_proof(&proof),
+            Err(ProverError::VerificationKeyMismatch(Circuit::Acceptor))
+        ));
     }
 }
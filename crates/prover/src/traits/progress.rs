@@ -0,0 +1,61 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::traits::Circuit;
+
+/// Stage of a proof generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvingStage {
+    /// Setting up the circuit, which may download the SRS. Only reported when a setup is not
+    /// cached, and timed on its own rather than as part of the proof generation.
+    Setup,
+    /// Assembling the witness inputs of the circuit
+    Witness,
+    /// Solving the witness and proving. The backend runs both in a single call, so its rounds
+    /// are not reported separately.
+    Proof,
+    /// The proof is generated
+    Done,
+}
+
+impl fmt::Display for ProvingStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProvingStage::Setup => write!(f, "setting up the circuit"),
+            ProvingStage::Witness => write!(f, "preparing the witness"),
+            ProvingStage::Proof => write!(f, "solving the witness and proving"),
+            ProvingStage::Done => write!(f, "done"),
+        }
+    }
+}
+
+/// Progress of a proof generation, reported to a [`ProgressCallback`] when a stage starts and
+/// periodically while it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProvingProgress {
+    pub circuit: Circuit,
+    pub stage: ProvingStage,
+    /// Time since the proof generation, or the setup, started
+    pub elapsed: Duration,
+    /// Expected duration of the whole proof generation, if known
+    pub expected: Option<Duration>,
+}
+
+impl ProvingProgress {
+    /// Returns the estimated percentage of the proof generation that is done. It stays below
+    /// 100 until the proof is generated, even if it takes longer than expected. There is no
+    /// estimate for setups.
+    pub fn percent(&self) -> Option<u8> {
+        match self.stage {
+            ProvingStage::Done => return Some(100),
+            ProvingStage::Setup => return None,
+            ProvingStage::Witness | ProvingStage::Proof => {}
+        }
+
+        let expected = self.expected?.as_millis().max(1);
+        Some((self.elapsed.as_millis() * 100 / expected).min(99) as u8)
+    }
+}
+
+/// Receives the progress of proof generations. It is called from the proving thread, so it
+/// should return quickly.
+pub type ProgressCallback = Arc<dyn Fn(&ProvingProgress) + Send + Sync>;
@@ -1,44 +1,74 @@
-use bitcoin::secp256k1;
+use bitcoin::{OutPoint, secp256k1};
 use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
 use secp256k1::{PublicKey, ecdsa};
 
-use crate::{errors::ProverError, traits::OpRandProof};
+use crate::{
+    errors::ProverError,
+    traits::{OpRandProof, PreparedCircuit},
+};
 
-/// Prover trait for the OpRand protocol
+/// Prover trait for the OpRand protocol. A circuit is set up once and the returned handle is
+/// passed to every proof generated or verified with it.
+///
+/// Commitments are passed as slices, so that circuits with more commitments can be proven
+/// with the same API. A prover refuses a number of commitments the circuit is not built for,
+/// see [`Circuit::commitments`](crate::Circuit::commitments).
 pub trait OpRandProver {
-    fn setup_challenger_circuit(&self) -> Result<u32, ProverError>;
-    fn setup_acceptor_circuit(&self) -> Result<u32, ProverError>;
+    /// Sets up the challenger circuit, reusing a previous setup of the same circuit if it is
+    /// still valid
+    fn setup_challenger_circuit(&self) -> Result<PreparedCircuit, ProverError>;
+    /// Sets up the acceptor circuit, reusing a previous setup of the same circuit if it is
+    /// still valid
+    fn setup_acceptor_circuit(&self) -> Result<PreparedCircuit, ProverError>;
+
+    /// Computes the verification key of the challenger circuit without generating a proof, so
+    /// that it can be cached and distributed ahead of any challenge. It is the key every proof
+    /// of the circuit is verified against, see [`PreparedCircuit::vk`].
+    fn generate_challenger_vk(&self) -> Result<Vec<u8>, ProverError> {
+        Ok(self.setup_challenger_circuit()?.vk().to_vec())
+    }
+    /// Computes the verification key of the acceptor circuit without generating a proof, see
+    /// [`generate_challenger_vk`](Self::generate_challenger_vk)
+    fn generate_acceptor_vk(&self) -> Result<Vec<u8>, ProverError> {
+        Ok(self.setup_acceptor_circuit()?.vk().to_vec())
+    }
 
     /// Used by the challenger to generate a proof for the acceptor
     fn generate_challenger_proof(
         &self,
-        first_rank_commitments: [FirstRankCommitment; 2],
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
         challenger_public_key: &PublicKey,
         challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
     ) -> Result<OpRandProof, ProverError>;
     /// Used by the acceptor to verify the proof from the challenger
     fn verify_challenger_proof(
         &self,
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
         challenger_public_key: &secp256k1::PublicKey,
         challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
         proof: &OpRandProof,
     ) -> Result<(), ProverError>;
 
     /// Used by the acceptor to generate a proof for the challenger
     fn generate_acceptor_proof(
         &self,
+        circuit: &PreparedCircuit,
         acceptor_public_key: &PublicKey,
         acceptor_signature: &ecdsa::Signature,
         acceptor_public_key_hash: [u8; 20],
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        third_rank_commitments: &[ThirdRankCommitment],
     ) -> Result<OpRandProof, ProverError>;
     /// Used by the challenger to verify the proof from the acceptor
     fn verify_acceptor_proof(
         &self,
+        circuit: &PreparedCircuit,
         acceptor_public_key_hash: [u8; 20],
-        third_rank_commitments: [ThirdRankCommitment; 2],
+        third_rank_commitments: &[ThirdRankCommitment],
         proof: &OpRandProof,
     ) -> Result<(), ProverError>;
 }
@@ -0,0 +1,223 @@
+use std::{future::Future, sync::Arc};
+
+use bitcoin::{OutPoint, secp256k1};
+use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
+use secp256k1::{PublicKey, ecdsa};
+
+use crate::{
+    errors::ProverError,
+    traits::{OpRandProof, OpRandProver, PreparedCircuit},
+};
+
+/// Async counterpart of [`OpRandProver`]. Proving and verifying are CPU bound and circuit setups
+/// may download the SRS, so every call runs on the blocking thread pool of the Tokio runtime
+/// instead of the executor.
+///
+/// It is implemented for every `Arc` of an [`OpRandProver`], so a shared prover can be used
+/// from async code directly. Circuit setups replace the global SRS, so calls that set up a
+/// circuit and prove or verify with it should still be awaited one after another.
+pub trait OpRandProverAsync {
+    /// See [`OpRandProver::setup_challenger_circuit`]
+    fn setup_challenger_circuit(
+        &self,
+    ) -> impl Future<Output = Result<PreparedCircuit, ProverError>> + Send;
+    /// See [`OpRandProver::setup_acceptor_circuit`]
+    fn setup_acceptor_circuit(
+        &self,
+    ) -> impl Future<Output = Result<PreparedCircuit, ProverError>> + Send;
+
+    /// See [`OpRandProver::generate_challenger_vk`]
+    fn generate_challenger_vk(&self) -> impl Future<Output = Result<Vec<u8>, ProverError>> + Send;
+    /// See [`OpRandProver::generate_acceptor_vk`]
+    fn generate_acceptor_vk(&self) -> impl Future<Output = Result<Vec<u8>, ProverError>> + Send;
+
+    /// See [`OpRandProver::generate_challenger_proof`]
+    fn generate_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> impl Future<Output = Result<OpRandProof, ProverError>> + Send;
+    /// See [`OpRandProver::verify_challenger_proof`]
+    fn verify_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+        proof: &OpRandProof,
+    ) -> impl Future<Output = Result<(), ProverError>> + Send;
+
+    /// See [`OpRandProver::generate_acceptor_proof`]
+    fn generate_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> impl Future<Output = Result<OpRandProof, ProverError>> + Send;
+    /// See [`OpRandProver::verify_acceptor_proof`]
+    fn verify_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+        proof: &OpRandProof,
+    ) -> impl Future<Output = Result<(), ProverError>> + Send;
+}
+
+// The blocking calls name `P` explicitly, as `Arc<P>` itself has methods of the same names.
+impl<P> OpRandProverAsync for Arc<P>
+where
+    P: OpRandProver + Send + Sync + ?Sized + 'static,
+{
+    async fn setup_challenger_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        let prover = self.clone();
+        run_blocking(move || P::setup_challenger_circuit(&prover)).await
+    }
+
+    async fn setup_acceptor_circuit(&self) -> Result<PreparedCircuit, ProverError> {
+        let prover = self.clone();
+        run_blocking(move || P::setup_acceptor_circuit(&prover)).await
+    }
+
+    async fn generate_challenger_vk(&self) -> Result<Vec<u8>, ProverError> {
+        let prover = self.clone();
+        run_blocking(move || P::generate_challenger_vk(&prover)).await
+    }
+
+    async fn generate_acceptor_vk(&self) -> Result<Vec<u8>, ProverError> {
+        let prover = self.clone();
+        run_blocking(move || P::generate_acceptor_vk(&prover)).await
+    }
+
+    async fn generate_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        first_rank_commitments: &[FirstRankCommitment],
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+    ) -> Result<OpRandProof, ProverError> {
+        let prover = self.clone();
+        let circuit = circuit.clone();
+        let first_rank_commitments = first_rank_commitments.to_vec();
+        let third_rank_commitments = third_rank_commitments.to_vec();
+        let challenger_public_key = *challenger_public_key;
+        let deposit_outpoint = *deposit_outpoint;
+
+        run_blocking(move || {
+            P::generate_challenger_proof(
+                &prover,
+                &circuit,
+                &first_rank_commitments,
+                &third_rank_commitments,
+                &challenger_public_key,
+                challenger_public_key_hash,
+                &deposit_outpoint,
+            )
+        })
+        .await
+    }
+
+    async fn verify_challenger_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        third_rank_commitments: &[ThirdRankCommitment],
+        challenger_public_key: &PublicKey,
+        challenger_public_key_hash: [u8; 20],
+        deposit_outpoint: &OutPoint,
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        let prover = self.clone();
+        let circuit = circuit.clone();
+        let third_rank_commitments = third_rank_commitments.to_vec();
+        let challenger_public_key = *challenger_public_key;
+        let deposit_outpoint = *deposit_outpoint;
+        let proof = proof.clone();
+
+        run_blocking(move || {
+            P::verify_challenger_proof(
+                &prover,
+                &circuit,
+                &third_rank_commitments,
+                &challenger_public_key,
+                challenger_public_key_hash,
+                &deposit_outpoint,
+                &proof,
+            )
+        })
+        .await
+    }
+
+    async fn generate_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key: &PublicKey,
+        acceptor_signature: &ecdsa::Signature,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+    ) -> Result<OpRandProof, ProverError> {
+        let prover = self.clone();
+        let circuit = circuit.clone();
+        let acceptor_public_key = *acceptor_public_key;
+        let acceptor_signature = *acceptor_signature;
+        let third_rank_commitments = third_rank_commitments.to_vec();
+
+        run_blocking(move || {
+            P::generate_acceptor_proof(
+                &prover,
+                &circuit,
+                &acceptor_public_key,
+                &acceptor_signature,
+                acceptor_public_key_hash,
+                &third_rank_commitments,
+            )
+        })
+        .await
+    }
+
+    async fn verify_acceptor_proof(
+        &self,
+        circuit: &PreparedCircuit,
+        acceptor_public_key_hash: [u8; 20],
+        third_rank_commitments: &[ThirdRankCommitment],
+        proof: &OpRandProof,
+    ) -> Result<(), ProverError> {
+        let prover = self.clone();
+        let circuit = circuit.clone();
+        let third_rank_commitments = third_rank_commitments.to_vec();
+        let proof = proof.clone();
+
+        run_blocking(move || {
+            P::verify_acceptor_proof(
+                &prover,
+                &circuit,
+                acceptor_public_key_hash,
+                &third_rank_commitments,
+                &proof,
+            )
+        })
+        .await
+    }
+}
+
+/// Runs `f` on the blocking thread pool. A panic of `f` is resumed on the calling task, as if
+/// `f` was called directly.
+async fn run_blocking<T, F>(f: F) -> Result<T, ProverError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, ProverError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        Err(_) => Err(ProverError::Cancelled),
+    }
+}
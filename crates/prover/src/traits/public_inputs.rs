@@ -0,0 +1,442 @@
+use bitcoin::{OutPoint, consensus, secp256k1::PublicKey};
+use op_rand_types::ThirdRankCommitment;
+use serde::Serialize;
+
+use crate::{
+    errors::ProverError,
+    traits::{Circuit, OpRandProof},
+};
+
+/// Size of a field element in a proof, in bytes
+const FIELD_SIZE: usize = 32;
+
+/// Size of the coordinates of a public key, in bytes
+const COORDINATES_SIZE: usize = 64;
+
+/// Public inputs of the challenger circuit, in the order of [`ChallengerPublicInputs::LAYOUT`]
+///
+/// `DEPOSIT_OUTPOINT` binds the proof to the deposit of its challenge. Neither circuit takes
+/// the challenge ID, so a re-offer keeping the deposit and the commitments reuses the proof.
+#[derive(Debug, Clone)]
+pub struct ChallengerPublicInputs {
+    pub third_rank_commitments: [ThirdRankCommitment; 2],
+    pub challenger_public_key: PublicKey,
+    pub challenger_public_key_hash: [u8; 20],
+    pub deposit_outpoint: OutPoint,
+}
+
+impl ChallengerPublicInputs {
+    /// Name and size in bytes of the public parameters of the circuit main function, in order
+    pub const LAYOUT: &[(&str, usize)] = &[
+        ("H1_x", 32),
+        ("H1_y", 32),
+        ("H2_x", 32),
+        ("H2_y", 32),
+        ("PK_x", 32),
+        ("PK_y", 32),
+        ("ADDR", 20),
+        ("DEPOSIT_OUTPOINT", 36),
+    ];
+
+    /// Number of public inputs. Every byte is a separate input.
+    pub const LEN: usize = layout_len(Self::LAYOUT);
+
+    /// Serializes the public inputs, one byte per input
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        for commitment in &self.third_rank_commitments {
+            bytes.extend_from_slice(&coordinates(&commitment.inner()));
+        }
+        bytes.extend_from_slice(&coordinates(&self.challenger_public_key));
+        bytes.extend_from_slice(&self.challenger_public_key_hash);
+        bytes.extend_from_slice(&consensus::serialize(&self.deposit_outpoint));
+
+        bytes
+    }
+
+    /// Parses the public inputs a proof was generated for
+    pub fn from_proof(proof: &OpRandProof) -> Result<Self, ProverError> {
+        let bytes = public_input_bytes(proof, Self::LEN)?;
+        let (commitments, rest) = bytes.split_at(2 * COORDINATES_SIZE);
+        let (public_key, rest) = rest.split_at(COORDINATES_SIZE);
+        let (public_key_hash, deposit_outpoint) = rest.split_at(20);
+
+        Ok(Self {
+            third_rank_commitments: third_rank_commitments(commitments)?,
+            challenger_public_key: public_key_from_coordinates(public_key)?,
+            challenger_public_key_hash: public_key_hash
+                .try_into()
+                .expect("public key hash is 20 bytes"),
+            deposit_outpoint: consensus::deserialize(deposit_outpoint).map_err(|e| {
+                ProverError::InvalidPublicInputs(format!("deposit outpoint: {}", e))
+            })?,
+        })
+    }
+
+    /// Checks that `proof` was generated for these public inputs
+    pub fn verify(&self, proof: &OpRandProof) -> Result<(), ProverError> {
+        verify_bytes(proof, &self.to_bytes())
+    }
+}
+
+/// Public inputs of the acceptor circuit, in the order of [`AcceptorPublicInputs::LAYOUT`]
+///
+/// The proof is bound to the commitments and the acceptor address only, so it verifies in any
+/// challenge with the same commitments that the same acceptor accepts.
+#[derive(Debug, Clone)]
+pub struct AcceptorPublicInputs {
+    pub third_rank_commitments: [ThirdRankCommitment; 2],
+    pub acceptor_public_key_hash: [u8; 20],
+}
+
+impl AcceptorPublicInputs {
+    /// Name and size in bytes of the public parameters of the circuit main function, in order
+    pub const LAYOUT: &[(&str, usize)] = &[
+        ("H1_x", 32),
+        ("H1_y", 32),
+        ("H2_x", 32),
+        ("H2_y", 32),
+        ("ADDR", 20),
+    ];
+
+    /// Number of public inputs. Every byte is a separate input.
+    pub const LEN: usize = layout_len(Self::LAYOUT);
+
+    /// Serializes the public inputs, one byte per input
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        for commitment in &self.third_rank_commitments {
+            bytes.extend_from_slice(&coordinates(&commitment.inner()));
+        }
+        bytes.extend_from_slice(&self.acceptor_public_key_hash);
+
+        bytes
+    }
+
+    /// Parses the public inputs a proof was generated for
+    pub fn from_proof(proof: &OpRandProof) -> Result<Self, ProverError> {
+        let bytes = public_input_bytes(proof, Self::LEN)?;
+        let (commitments, public_key_hash) = bytes.split_at(2 * COORDINATES_SIZE);
+
+        Ok(Self {
+            third_rank_commitments: third_rank_commitments(commitments)?,
+            acceptor_public_key_hash: public_key_hash
+                .try_into()
+                .expect("public key hash is 20 bytes"),
+        })
+    }
+
+    /// Checks that `proof` was generated for these public inputs
+    pub fn verify(&self, proof: &OpRandProof) -> Result<(), ProverError> {
+        verify_bytes(proof, &self.to_bytes())
+    }
+}
+
+/// Public parameter of a circuit main function. Every byte of it is a separate public signal,
+/// `offset` being the index of its first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PublicInput {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Public input layout of a circuit, so that callers can render and check the public signals
+/// of a proof without knowing the circuit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitAbi {
+    circuit: Circuit,
+    inputs: Vec<PublicInput>,
+}
+
+impl CircuitAbi {
+    fn new(circuit: Circuit, layout: &[(&'static str, usize)]) -> Self {
+        let mut offset = 0;
+        let inputs = layout
+            .iter()
+            .map(|&(name, size)| {
+                let input = PublicInput { name, offset, size };
+                offset += size;
+                input
+            })
+            .collect();
+
+        Self { circuit, inputs }
+    }
+
+    /// Returns which circuit the layout is of
+    pub fn circuit(&self) -> Circuit {
+        self.circuit
+    }
+
+    /// Returns the public parameters, in the order their signals lead a proof
+    pub fn inputs(&self) -> &[PublicInput] {
+        &self.inputs
+    }
+
+    /// Returns the public parameter `name`
+    pub fn input(&self, name: &str) -> Option<&PublicInput> {
+        self.inputs.iter().find(|input| input.name == name)
+    }
+
+    /// Returns the number of public signals of a proof of the circuit
+    pub fn signal_count(&self) -> usize {
+        self.inputs.iter().map(|input| input.size).sum()
+    }
+
+    /// Reads the public signals of `proof` and returns the bytes of every public parameter
+    pub fn read<'a>(
+        &'a self,
+        proof: &OpRandProof,
+    ) -> Result<Vec<(&'a PublicInput, Vec<u8>)>, ProverError> {
+        let bytes = public_input_bytes(proof, self.signal_count())?;
+
+        Ok(self
+            .inputs
+            .iter()
+            .map(|input| {
+                (
+                    input,
+                    bytes[input.offset..input.offset + input.size].to_vec(),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Returns the public input layout of the challenger circuit
+pub fn challenger_circuit_abi() -> CircuitAbi {
+    CircuitAbi::new(Circuit::Challenger, ChallengerPublicInputs::LAYOUT)
+}
+
+/// Returns the public input layout of the acceptor circuit
+pub fn acceptor_circuit_abi() -> CircuitAbi {
+    CircuitAbi::new(Circuit::Acceptor, AcceptorPublicInputs::LAYOUT)
+}
+
+/// Returns the number of bytes of a layout
+const fn layout_len(layout: &[(&str, usize)]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < layout.len() {
+        len += layout[i].1;
+        i += 1;
+    }
+
+    len
+}
+
+/// Returns the x and y coordinates of a public key, skipping the 0x04 prefix
+fn coordinates(public_key: &PublicKey) -> [u8; COORDINATES_SIZE] {
+    public_key.serialize_uncompressed()[1..]
+        .try_into()
+        .expect("uncompressed public key is 65 bytes")
+}
+
+fn public_key_from_coordinates(coordinates: &[u8]) -> Result<PublicKey, ProverError> {
+    let mut uncompressed = [0x04; COORDINATES_SIZE + 1];
+    uncompressed[1..].copy_from_slice(coordinates);
+
+    PublicKey::from_slice(&uncompressed)
+        .map_err(|e| ProverError::InvalidPublicInputs(format!("public key: {}", e)))
+}
+
+fn third_rank_commitments(bytes: &[u8]) -> Result<[ThirdRankCommitment; 2], ProverError> {
+    let (first, second) = bytes.split_at(COORDINATES_SIZE);
+
+    Ok([
+        public_key_from_coordinates(first)?.into(),
+        public_key_from_coordinates(second)?.into(),
+    ])
+}
+
+/// Encodes public input bytes as they lead a proof, one field element per byte
+#[cfg(any(feature = "mock", test))]
+pub(crate) fn to_field_elements(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| {
+            let mut element = [0; FIELD_SIZE];
+            element[FIELD_SIZE - 1] = *byte;
+            element
+        })
+        .collect()
+}
+
+/// Reads the first `len` public inputs of a proof. Each one is a field element holding a
+/// single byte.
+fn public_input_bytes(proof: &OpRandProof, len: usize) -> Result<Vec<u8>, ProverError> {
+    let elements = proof.proof().chunks_exact(FIELD_SIZE).take(len);
+    if elements.len() != len {
+        return Err(ProverError::InvalidNumberOfPublicSignals {
+            expected: len,
+            got: elements.len(),
+        });
+    }
+
+    elements
+        .map(|element| match element.split_last() {
+            Some((byte, high)) if high.iter().all(|b| *b == 0) => Ok(*byte),
+            _ => Err(ProverError::InvalidPublicInputs(
+                "public input is not a byte".to_string(),
+            )),
+        })
+        .collect()
+}
+
+/// Checks that the public inputs of `proof` are `expected`
+fn verify_bytes(proof: &OpRandProof, expected: &[u8]) -> Result<(), ProverError> {
+    // An input that is not a byte can't match either
+    match public_input_bytes(proof, expected.len()) {
+        Ok(bytes) if bytes == expected => Ok(()),
+        Ok(_) | Err(ProverError::InvalidPublicInputs(_)) => Err(ProverError::PublicInputsMismatch),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        Txid,
+        hashes::Hash,
+        secp256k1::{Secp256k1, SecretKey},
+    };
+
+    use super::*;
+
+    fn key(byte: u8) -> PublicKey {
+        SecretKey::from_slice(&[byte; 32])
+            .unwrap()
+            .public_key(&Secp256k1::signing_only())
+    }
+
+    /// Proof whose public inputs are `bytes`, followed by the proof itself
+    fn proof_of(bytes: &[u8]) -> OpRandProof {
+        let mut proof = to_field_elements(bytes);
+        proof.extend_from_slice(&[0xab; 2 * FIELD_SIZE]);
+
+        OpRandProof::new(proof, vec![])
+    }
+
+    fn challenger_inputs() -> ChallengerPublicInputs {
+        ChallengerPublicInputs {
+            third_rank_commitments: [key(2).into(), key(3).into()],
+            challenger_public_key: key(1),
+            challenger_public_key_hash: [7; 20],
+            deposit_outpoint: OutPoint::new(Txid::from_byte_array([5; 32]), 1),
+        }
+    }
+
+    #[test]
+    fn reads_challenger_inputs_of_proof() {
+        let inputs = challenger_inputs();
+        let proof = proof_of(&inputs.to_bytes());
+
+        let parsed = ChallengerPublicInputs::from_proof(&proof).unwrap();
+        assert_eq!(parsed.challenger_public_key, key(1));
+        assert_eq!(parsed.challenger_public_key_hash, [7; 20]);
+        assert!(parsed.third_rank_commitments[1].ct_eq(&key(3).into()));
+        assert_eq!(parsed.deposit_outpoint, inputs.deposit_outpoint);
+        inputs.verify(&proof).unwrap();
+    }
+
+    #[test]
+    fn reads_acceptor_inputs_of_proof() {
+        let inputs = AcceptorPublicInputs {
+            third_rank_commitments: [key(2).into(), key(3).into()],
+            acceptor_public_key_hash: [9; 20],
+        };
+        let proof = proof_of(&inputs.to_bytes());
+
+        let parsed = AcceptorPublicInputs::from_proof(&proof).unwrap();
+        assert_eq!(parsed.acceptor_public_key_hash, [9; 20]);
+        assert!(parsed.third_rank_commitments[0].ct_eq(&key(2).into()));
+        inputs.verify(&proof).unwrap();
+    }
+
+    #[test]
+    fn rejects_proof_of_other_inputs() {
+        let mut other = challenger_inputs();
+        other.challenger_public_key_hash = [8; 20];
+        let proof = proof_of(&other.to_bytes());
+
+        assert!(matches!(
+            challenger_inputs().verify(&proof),
+            Err(ProverError::PublicInputsMismatch)
+        ));
+
+        let mut other_deposit = challenger_inputs();
+        other_deposit.deposit_outpoint.vout = 0;
+        let proof = proof_of(&other_deposit.to_bytes());
+
+        assert!(matches!(
+            challenger_inputs().verify(&proof),
+            Err(ProverError::PublicInputsMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_public_inputs_that_are_not_bytes() {
+        let inputs = challenger_inputs();
+        let mut proof = proof_of(&inputs.to_bytes()).proof().to_vec();
+        proof[0] = 1;
+        let proof = OpRandProof::new(proof, vec![]);
+
+        assert!(matches!(
+            ChallengerPublicInputs::from_proof(&proof),
+            Err(ProverError::InvalidPublicInputs(_))
+        ));
+        assert!(matches!(
+            inputs.verify(&proof),
+            Err(ProverError::PublicInputsMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_proof_with_too_few_signals() {
+        let proof = OpRandProof::new(vec![0; 10 * FIELD_SIZE], vec![]);
+
+        assert!(matches!(
+            AcceptorPublicInputs::from_proof(&proof),
+            Err(ProverError::InvalidNumberOfPublicSignals {
+                expected: AcceptorPublicInputs::LEN,
+                got: 10,
+            })
+        ));
+    }
+
+    #[test]
+    fn lays_out_public_inputs() {
+        let abi = challenger_circuit_abi();
+        assert_eq!(abi.signal_count(), ChallengerPublicInputs::LEN);
+        assert_eq!(
+            abi.input("ADDR"),
+            Some(&PublicInput {
+                name: "ADDR",
+                offset: 192,
+                size: 20,
+            })
+        );
+        assert_eq!(
+            abi.input("DEPOSIT_OUTPOINT"),
+            Some(&PublicInput {
+                name: "DEPOSIT_OUTPOINT",
+                offset: 212,
+                size: 36,
+            })
+        );
+
+        let abi = acceptor_circuit_abi();
+        assert_eq!(abi.signal_count(), AcceptorPublicInputs::LEN);
+        assert_eq!(abi.input("PK_x"), None);
+
+        let inputs = AcceptorPublicInputs {
+            third_rank_commitments: [key(2).into(), key(3).into()],
+            acceptor_public_key_hash: [9; 20],
+        };
+        let read = abi.read(&proof_of(&inputs.to_bytes())).unwrap();
+        let (addr, bytes) = read.last().unwrap();
+        assert_eq!(addr.name, "ADDR");
+        assert_eq!(bytes, &[9; 20]);
+    }
+}
@@ -0,0 +1,537 @@
+//! Challenge lifecycle as seen by the Challenger, encoded with typestates.
+//!
+//! A challenge goes through the following phases:
+//!
+//! 1. [`Created`]: the deposit transaction is built, the challenge is waiting for an Acceptor.
+//! 2. [`Accepted`]: the Acceptor's partially signed challenge transaction was received.
+//! 3. [`Completed`]: the challenge transaction is fully signed and can be broadcast.
+//! 4. [`Confirmed`]: the challenge transaction is confirmed, its output can be swept.
+//!
+//! Every transition consumes the challenge, so an operation can only be called in the phase
+//! it belongs to. For example, sweeping an unconfirmed challenge doesn't compile:
+//!
+//! ```compile_fail
+//! # use bitcoin::{Amount, secp256k1::All};
+//! # use op_rand_transaction_builder::{Challenge, Completed, TransactionBuilder};
+//! fn sweep(challenge: Challenge<Completed>, builder: &TransactionBuilder<All>) {
+//!     challenge.sweep_challenger(builder, None, Amount::from_sat(300));
+//! }
+//! ```
+//!
+//! Recovery flows that restart from persisted data can enter a phase directly with
+//! [`Challenge::<Completed>::recover`] and [`Challenge::<Confirmed>::recover`].
+
+use bitcoin::{
+    Amount, OutPoint, Psbt, PublicKey, ScriptBuf, Transaction, absolute::LockTime,
+    key::Verification, secp256k1::Signing,
+};
+use op_rand_types::FirstRankCommitment;
+
+use crate::{
+    SignatureProvider, SpendingBranch, SpendingInstructions, TransactionBuilder,
+    errors::TransactionError,
+    scripts::{ChallengeScript, parse_challenge_script},
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Phase of a [`Challenge`]
+pub trait Phase: sealed::Sealed {}
+
+/// The deposit transaction is built, the challenge is waiting for an Acceptor.
+#[derive(Debug, Clone)]
+pub struct Created {
+    deposit_transaction: Transaction,
+    challenger_pubkey: PublicKey,
+    lock_time: LockTime,
+}
+
+/// The Acceptor's partially signed challenge transaction was received and checked against the
+/// deposit.
+#[derive(Debug, Clone)]
+pub struct Accepted {
+    deposit_transaction: Transaction,
+    psbt: Psbt,
+    deposit_input_index: usize,
+    witness_script: ScriptBuf,
+}
+
+/// The challenge transaction is fully signed and can be broadcast.
+#[derive(Debug, Clone)]
+pub struct Completed {
+    challenge_transaction: Transaction,
+    witness_script: ScriptBuf,
+    script: ChallengeScript,
+}
+
+/// The challenge transaction is confirmed, its output can be swept.
+#[derive(Debug, Clone)]
+pub struct Confirmed {
+    challenge_transaction: Transaction,
+    witness_script: ScriptBuf,
+    script: ChallengeScript,
+    block_height: u32,
+}
+
+impl sealed::Sealed for Created {}
+impl sealed::Sealed for Accepted {}
+impl sealed::Sealed for Completed {}
+impl sealed::Sealed for Confirmed {}
+impl Phase for Created {}
+impl Phase for Accepted {}
+impl Phase for Completed {}
+impl Phase for Confirmed {}
+
+/// A challenge in phase `P`
+#[derive(Debug, Clone)]
+pub struct Challenge<P: Phase> {
+    phase: P,
+}
+
+impl Challenge<Created> {
+    /// Starts a challenge from its deposit transaction, the Challenger's public key and the
+    /// lock time of the Challenger's branch. The deposit is output 0, and its value is the
+    /// amount each party stakes.
+    pub fn new(
+        deposit_transaction: Transaction,
+        challenger_pubkey: PublicKey,
+        lock_time: LockTime,
+    ) -> Self {
+        Self {
+            phase: Created {
+                deposit_transaction,
+                challenger_pubkey,
+                lock_time,
+            },
+        }
+    }
+
+    pub fn deposit_transaction(&self) -> &Transaction {
+        &self.phase.deposit_transaction
+    }
+
+    /// Takes the Acceptor's partially signed challenge transaction. Fails if it doesn't spend
+    /// the deposit, if its first output doesn't pay to a challenge script `witness_script`
+    /// with the Challenger's key and lock time, or if that output isn't twice the deposit.
+    pub fn accept(
+        self,
+        psbt: Psbt,
+        witness_script: ScriptBuf,
+    ) -> Result<Challenge<Accepted>, TransactionError> {
+        let deposit_outpoint = OutPoint::new(self.phase.deposit_transaction.compute_txid(), 0);
+        let deposit_input_index = psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .position(|input| input.previous_output == deposit_outpoint)
+            .ok_or(TransactionError::TransactionTypeMismatch)?;
+
+        let script = check_challenge_output(&psbt.unsigned_tx, &witness_script)?;
+        if script.challenger_pubkey != self.phase.challenger_pubkey
+            || script.lock_time != self.phase.lock_time
+        {
+            return Err(TransactionError::ChallengeScriptMismatch);
+        }
+
+        let deposit_amount = self
+            .phase
+            .deposit_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?
+            .value;
+        let expected = deposit_amount * 2;
+        let challenge_amount = psbt.unsigned_tx.output[0].value;
+        if challenge_amount != expected {
+            return Err(TransactionError::ChallengeAmountMismatch(
+                expected,
+                challenge_amount,
+            ));
+        }
+
+        Ok(Challenge {
+            phase: Accepted {
+                deposit_transaction: self.phase.deposit_transaction,
+                psbt,
+                deposit_input_index,
+                witness_script,
+            },
+        })
+    }
+}
+
+impl Challenge<Accepted> {
+    pub fn deposit_transaction(&self) -> &Transaction {
+        &self.phase.deposit_transaction
+    }
+
+    pub fn psbt(&self) -> &Psbt {
+        &self.phase.psbt
+    }
+
+    pub fn witness_script(&self) -> &ScriptBuf {
+        &self.phase.witness_script
+    }
+
+    /// Signs the deposit input with the key tweaked by the chosen first rank commitment, adding
+    /// and signing `fee_inputs` first. See [`TransactionBuilder::complete_challenge_tx`].
+    pub fn complete<C: Signing + Verification, S: SignatureProvider>(
+        self,
+        builder: &TransactionBuilder<C, S>,
+        first_rank_commitment: FirstRankCommitment,
+        fee_inputs: Vec<(OutPoint, Amount)>,
+    ) -> Result<Challenge<Completed>, TransactionError> {
+        let Accepted {
+            deposit_transaction,
+            psbt,
+            deposit_input_index,
+            witness_script,
+        } = self.phase;
+
+        let deposit_amount = deposit_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?
+            .value;
+        let challenge_transaction = builder.complete_challenge_tx(
+            psbt,
+            deposit_amount,
+            deposit_input_index,
+            first_rank_commitment,
+            fee_inputs,
+        )?;
+
+        Challenge::<Completed>::recover(challenge_transaction, witness_script)
+    }
+
+    /// Signs the deposit input for a fee sponsor instead of completing the challenge. See
+    /// [`TransactionBuilder::sign_challenge_for_sponsor`].
+    pub fn sign_for_sponsor<C: Signing + Verification, S: SignatureProvider>(
+        &self,
+        builder: &TransactionBuilder<C, S>,
+        first_rank_commitment: FirstRankCommitment,
+    ) -> Result<Psbt, TransactionError> {
+        let deposit_amount = self
+            .phase
+            .deposit_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?
+            .value;
+
+        builder.sign_challenge_for_sponsor(
+            self.phase.psbt.clone(),
+            deposit_amount,
+            self.phase.deposit_input_index,
+            first_rank_commitment,
+        )
+    }
+}
+
+impl Challenge<Completed> {
+    /// Enters the completed phase directly from a signed challenge transaction, e.g. one loaded
+    /// from a store. Fails if its first output doesn't pay to a challenge script
+    /// `witness_script`.
+    pub fn recover(
+        challenge_transaction: Transaction,
+        witness_script: ScriptBuf,
+    ) -> Result<Self, TransactionError> {
+        let script = check_challenge_output(&challenge_transaction, &witness_script)?;
+
+        Ok(Self {
+            phase: Completed {
+                challenge_transaction,
+                witness_script,
+                script,
+            },
+        })
+    }
+
+    pub fn challenge_transaction(&self) -> &Transaction {
+        &self.phase.challenge_transaction
+    }
+
+    pub fn witness_script(&self) -> &ScriptBuf {
+        &self.phase.witness_script
+    }
+
+    /// Moves to the confirmed phase. The caller is responsible for checking that the challenge
+    /// transaction was confirmed at `block_height`.
+    pub fn confirmed(self, block_height: u32) -> Challenge<Confirmed> {
+        let Completed {
+            challenge_transaction,
+            witness_script,
+            script,
+        } = self.phase;
+
+        Challenge {
+            phase: Confirmed {
+                challenge_transaction,
+                witness_script,
+                script,
+                block_height,
+            },
+        }
+    }
+}
+
+impl Challenge<Confirmed> {
+    /// Enters the confirmed phase directly, skipping the earlier phases. The caller is
+    /// responsible for checking that the challenge transaction was confirmed at `block_height`.
+    pub fn recover(
+        challenge_transaction: Transaction,
+        witness_script: ScriptBuf,
+        block_height: u32,
+    ) -> Result<Self, TransactionError> {
+        Ok(
+            Challenge::<Completed>::recover(challenge_transaction, witness_script)?
+                .confirmed(block_height),
+        )
+    }
+
+    pub fn challenge_transaction(&self) -> &Transaction {
+        &self.phase.challenge_transaction
+    }
+
+    pub fn witness_script(&self) -> &ScriptBuf {
+        &self.phase.witness_script
+    }
+
+    /// Block height the challenge transaction was confirmed at
+    pub fn block_height(&self) -> u32 {
+        self.phase.block_height
+    }
+
+    /// Builds the instructions to spend the challenge output with an external wallet
+    pub fn spending_instructions(
+        &self,
+        branch: SpendingBranch,
+    ) -> Result<SpendingInstructions, TransactionError> {
+        SpendingInstructions::new(
+            &self.phase.challenge_transaction,
+            &self.phase.witness_script,
+            branch,
+        )
+    }
+
+    /// Sweeps the challenge output with the Challenger's branch, valid once the lock time
+    /// expired, paying to `recipient` or to the signer's key. See
+    /// [`TransactionBuilder::sweep_challenge_output_challenger`].
+    pub fn sweep_challenger<C: Signing + Verification, S: SignatureProvider>(
+        &self,
+        builder: &TransactionBuilder<C, S>,
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Transaction, TransactionError> {
+        builder.sweep_challenge_output_challenger(
+            &self.phase.challenge_transaction,
+            &self.phase.witness_script,
+            self.phase.script.lock_time,
+            recipient,
+            fee,
+        )
+    }
+
+    /// Sweeps the challenge output with the Acceptor's branch, valid only if the Acceptor
+    /// guessed right, paying to `recipient` or to the signer's key. See
+    /// [`TransactionBuilder::sweep_challenge_output_acceptor`].
+    pub fn sweep_acceptor<C: Signing + Verification, S: SignatureProvider>(
+        &self,
+        builder: &TransactionBuilder<C, S>,
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Transaction, TransactionError> {
+        builder.sweep_challenge_output_acceptor(
+            &self.phase.challenge_transaction,
+            &self.phase.script.challenger_pubkey,
+            &self.phase.witness_script,
+            recipient,
+            fee,
+        )
+    }
+}
+
+/// Checks that the first output of `transaction` pays to `witness_script`, which must be a
+/// challenge script
+fn check_challenge_output(
+    transaction: &Transaction,
+    witness_script: &ScriptBuf,
+) -> Result<ChallengeScript, TransactionError> {
+    let challenge_output = transaction
+        .output
+        .first()
+        .ok_or(TransactionError::TransactionTypeMismatch)?;
+    if challenge_output.script_pubkey != ScriptBuf::new_p2wsh(&witness_script.wscript_hash()) {
+        return Err(TransactionError::WitnessScriptMismatch);
+    }
+
+    parse_challenge_script(witness_script)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        TxIn, TxOut,
+        absolute::Height,
+        secp256k1::{Secp256k1, SecretKey},
+    };
+
+    use super::*;
+    use crate::{scripts::create_challenge_p2wsh_script, transaction_builder::create_tx};
+
+    const AMOUNT: Amount = Amount::from_sat(50_000);
+
+    fn key(byte: u8) -> PublicKey {
+        let secp = Secp256k1::signing_only();
+        PublicKey::new(
+            SecretKey::from_slice(&[byte; 32])
+                .unwrap()
+                .public_key(&secp),
+        )
+    }
+
+    fn lock_time(height: u32) -> LockTime {
+        LockTime::Blocks(Height::from_consensus(height).unwrap())
+    }
+
+    fn created() -> Challenge<Created> {
+        let deposit = create_tx(
+            vec![TxIn::default()],
+            vec![TxOut {
+                value: AMOUNT,
+                script_pubkey: ScriptBuf::new(),
+            }],
+            None,
+        );
+
+        Challenge::new(deposit, key(1), lock_time(840_144))
+    }
+
+    /// Builds the Acceptor's challenge PSBT spending `input` to a challenge output of
+    /// `challenger_pubkey` and `lock_time`
+    fn challenge_psbt(
+        input: OutPoint,
+        challenger_pubkey: &PublicKey,
+        lock_time: LockTime,
+        value: Amount,
+    ) -> (Psbt, ScriptBuf) {
+        let witness_script =
+            create_challenge_p2wsh_script(challenger_pubkey, &key(2), lock_time).unwrap();
+        let transaction = create_tx(
+            vec![TxIn {
+                previous_output: input,
+                ..Default::default()
+            }],
+            vec![TxOut {
+                value,
+                script_pubkey: ScriptBuf::new_p2wsh(&witness_script.wscript_hash()),
+            }],
+            None,
+        );
+
+        (Psbt::from_unsigned_tx(transaction).unwrap(), witness_script)
+    }
+
+    fn deposit_outpoint(challenge: &Challenge<Created>) -> OutPoint {
+        OutPoint::new(challenge.deposit_transaction().compute_txid(), 0)
+    }
+
+    #[test]
+    fn accepts_matching_challenge() {
+        let challenge = created();
+        let (psbt, witness_script) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(1),
+            lock_time(840_144),
+            AMOUNT * 2,
+        );
+
+        let accepted = challenge.accept(psbt, witness_script.clone()).unwrap();
+        assert_eq!(accepted.witness_script(), &witness_script);
+    }
+
+    #[test]
+    fn rejects_other_challenger_key() {
+        let challenge = created();
+        let (psbt, witness_script) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(3),
+            lock_time(840_144),
+            AMOUNT * 2,
+        );
+
+        assert!(matches!(
+            challenge.accept(psbt, witness_script),
+            Err(TransactionError::ChallengeScriptMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_lock_time() {
+        let challenge = created();
+        let (psbt, witness_script) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(1),
+            lock_time(840_145),
+            AMOUNT * 2,
+        );
+
+        assert!(matches!(
+            challenge.accept(psbt, witness_script),
+            Err(TransactionError::ChallengeScriptMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_other_amount() {
+        let challenge = created();
+        let (psbt, witness_script) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(1),
+            lock_time(840_144),
+            AMOUNT,
+        );
+
+        assert!(matches!(
+            challenge.accept(psbt, witness_script),
+            Err(TransactionError::ChallengeAmountMismatch(expected, got))
+                if expected == AMOUNT * 2 && got == AMOUNT
+        ));
+    }
+
+    #[test]
+    fn rejects_transaction_not_spending_deposit() {
+        let challenge = created();
+        let (psbt, witness_script) =
+            challenge_psbt(OutPoint::null(), &key(1), lock_time(840_144), AMOUNT * 2);
+
+        assert!(matches!(
+            challenge.accept(psbt, witness_script),
+            Err(TransactionError::TransactionTypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn rejects_witness_script_of_other_output() {
+        let challenge = created();
+        let (psbt, _) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(1),
+            lock_time(840_144),
+            AMOUNT * 2,
+        );
+        let (_, other_witness_script) = challenge_psbt(
+            deposit_outpoint(&challenge),
+            &key(3),
+            lock_time(840_144),
+            AMOUNT * 2,
+        );
+
+        assert!(matches!(
+            challenge.accept(psbt, other_witness_script),
+            Err(TransactionError::WitnessScriptMismatch)
+        ));
+    }
+}
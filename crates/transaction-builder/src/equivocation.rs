@@ -0,0 +1,202 @@
+use bitcoin::{
+    PublicKey, Transaction, Txid,
+    key::{Secp256k1, Verification},
+    secp256k1::{self, Signing},
+};
+use op_rand_types::ThirdRankCommitment;
+
+use crate::{
+    errors::TransactionError,
+    spending::{revealed_second_rank_commitment, second_rank_commitment_hash},
+};
+
+/// Evidence that a Challenger revealed two different second rank commitments for the same
+/// set of third rank commitments, i.e. played the same game twice with different outcomes.
+///
+/// Note: the deposit output is a plain P2WPKH, so the evidence doesn't expose any secret key
+/// and no penalty transaction can claim the deposit. A penalty path needs a deposit script
+/// that commits to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equivocation {
+    pub challenge_txids: [Txid; 2],
+    /// Second rank commitments `A_i` and `A_j` revealed in the challenge transactions.
+    pub second_rank_commitments: [secp256k1::PublicKey; 2],
+    /// Key tweak relationship `A_i - A_j` between the two revealed deposit keys.
+    pub tweak_difference: secp256k1::PublicKey,
+}
+
+/// Looks for Challenger equivocation among challenge transactions built on the same third rank
+/// commitments. Transactions whose revealed second rank commitment doesn't match any of the
+/// commitments are ignored.
+pub fn detect_equivocation<C: Signing + Verification>(
+    ctx: &Secp256k1<C>,
+    challenger_pubkey: &PublicKey,
+    third_rank_commitments: &[ThirdRankCommitment],
+    challenge_transactions: &[Transaction],
+) -> Result<Option<Equivocation>, TransactionError> {
+    let mut revealed: Vec<(Txid, secp256k1::PublicKey)> = Vec::new();
+
+    for challenge_transaction in challenge_transactions {
+        let Ok(second_rank_commitment) =
+            revealed_second_rank_commitment(ctx, challenge_transaction, challenger_pubkey)
+        else {
+            continue;
+        };
+
+        // The revealed commitment must open one of the third rank commitments: H = sha256(A) * G
+        let hash = second_rank_commitment_hash(&second_rank_commitment)?.public_key(ctx);
+        if !third_rank_commitments
+            .iter()
+            .any(|commitment| commitment.inner() == hash)
+        {
+            continue;
+        }
+
+        let txid = challenge_transaction.compute_txid();
+
+        if let Some((other_txid, other_commitment)) = revealed
+            .iter()
+            .find(|(_, commitment)| *commitment != second_rank_commitment)
+        {
+            let tweak_difference = second_rank_commitment.combine(&other_commitment.negate(ctx))?;
+
+            return Ok(Some(Equivocation {
+                challenge_txids: [txid, *other_txid],
+                second_rank_commitments: [second_rank_commitment, *other_commitment],
+                tweak_difference,
+            }));
+        }
+
+        revealed.push((txid, second_rank_commitment));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        Amount, OutPoint, ScriptBuf, TxIn, TxOut, Witness,
+        secp256k1::{All, SecretKey},
+    };
+
+    use super::*;
+    use crate::transaction_builder::create_tx;
+
+    fn key(ctx: &Secp256k1<All>, byte: u8) -> secp256k1::PublicKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap().public_key(ctx)
+    }
+
+    /// Third rank commitments of the second rank commitments `key(3)` and `key(4)`
+    fn third_rank_commitments(ctx: &Secp256k1<All>) -> Vec<ThirdRankCommitment> {
+        [3, 4]
+            .map(|byte| {
+                ThirdRankCommitment::from_first_rank_public_key(ctx, &key(ctx, byte)).unwrap()
+            })
+            .to_vec()
+    }
+
+    /// Builds a challenge transaction whose deposit input reveals `challenger_pubkey +
+    /// commitment`. `value` tells transactions revealing the same commitment apart.
+    fn challenge_transaction(
+        challenger_pubkey: &PublicKey,
+        commitment: &secp256k1::PublicKey,
+        value: u64,
+    ) -> Transaction {
+        let witness_pubkey = challenger_pubkey.inner.combine(commitment).unwrap();
+
+        create_tx(
+            vec![TxIn {
+                previous_output: OutPoint::null(),
+                witness: Witness::from_slice(&[vec![0; 71], witness_pubkey.serialize().to_vec()]),
+                ..Default::default()
+            }],
+            vec![TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey: ScriptBuf::new(),
+            }],
+            None,
+        )
+    }
+
+    #[test]
+    fn detects_two_revealed_commitments() {
+        let ctx = Secp256k1::new();
+        let challenger_pubkey = PublicKey::new(key(&ctx, 1));
+        let transactions = [
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 3), 1_000),
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 4), 1_000),
+        ];
+
+        let equivocation = detect_equivocation(
+            &ctx,
+            &challenger_pubkey,
+            &third_rank_commitments(&ctx),
+            &transactions,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            equivocation.challenge_txids,
+            [
+                transactions[1].compute_txid(),
+                transactions[0].compute_txid()
+            ]
+        );
+        assert_eq!(
+            equivocation.second_rank_commitments,
+            [key(&ctx, 4), key(&ctx, 3)]
+        );
+        assert_eq!(
+            equivocation.tweak_difference,
+            key(&ctx, 4).combine(&key(&ctx, 3).negate(&ctx)).unwrap()
+        );
+    }
+
+    #[test]
+    fn ignores_same_commitment_revealed_twice() {
+        let ctx = Secp256k1::new();
+        let challenger_pubkey = PublicKey::new(key(&ctx, 1));
+        let transactions = [
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 3), 1_000),
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 3), 2_000),
+        ];
+
+        assert_eq!(
+            detect_equivocation(
+                &ctx,
+                &challenger_pubkey,
+                &third_rank_commitments(&ctx),
+                &transactions,
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_transactions_of_other_games() {
+        let ctx = Secp256k1::new();
+        let challenger_pubkey = PublicKey::new(key(&ctx, 1));
+        let mut unsigned = challenge_transaction(&challenger_pubkey, &key(&ctx, 4), 1_000);
+        unsigned.input[0].witness = Witness::new();
+        let transactions = [
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 3), 1_000),
+            // Reveals a commitment that opens none of the third rank commitments
+            challenge_transaction(&challenger_pubkey, &key(&ctx, 5), 1_000),
+            unsigned,
+        ];
+
+        assert_eq!(
+            detect_equivocation(
+                &ctx,
+                &challenger_pubkey,
+                &third_rank_commitments(&ctx),
+                &transactions,
+            )
+            .unwrap(),
+            None
+        );
+    }
+}
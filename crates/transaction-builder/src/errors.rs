@@ -1,8 +1,9 @@
 use bitcoin::{
-    key::UncompressedPublicKeyError, psbt::Error as PsbtError, secp256k1::Error as Secp256k1Error,
-    sighash::P2wpkhError,
+    EcdsaSighashType, key::UncompressedPublicKeyError, psbt::Error as PsbtError,
+    secp256k1::Error as Secp256k1Error, sighash::P2wpkhError,
 };
 use miniscript::psbt::SighashError;
+use op_rand_types::StrictParseError;
 
 /// Transaction builder errors.
 #[derive(Debug, thiserror::Error)]
@@ -33,6 +34,52 @@ pub enum TransactionError {
     NoDepositTxStored,
     #[error("Failed to sign p2wsh input.")]
     FailedToSignP2wshInput,
+    #[error("Miniscript error: {0}")]
+    Miniscript(miniscript::Error),
+    #[error("Script is not a challenge output script.")]
+    InvalidChallengeScript,
+    #[error("Challenge output script of protocol version 1 has no output descriptor.")]
+    NoChallengeDescriptor,
+    #[error("Invalid counterparty input: {0}")]
+    StrictParse(StrictParseError),
+    #[error("Witness script does not match the challenge output.")]
+    WitnessScriptMismatch,
+    #[error("Challenge output script does not use the challenger's key and lock time.")]
+    ChallengeScriptMismatch,
+    #[error("Challenge output pays {1}, expected {0}.")]
+    ChallengeAmountMismatch(bitcoin::Amount, bitcoin::Amount),
+    #[error("Private key does not match the signing key.")]
+    SigningKeyMismatch,
+    #[error("Sighash type {0} is not allowed for this input.")]
+    UnsupportedSighashType(EcdsaSighashType),
+    #[error("Signature sighash type does not match the PSBT input sighash type.")]
+    SighashTypeMismatch,
+    #[error("Signer error: {0}")]
+    Signer(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Signer returned a {0} byte signature, signatures must be low-R.")]
+    HighRSignature(usize),
+    #[error("Fee of the {0:?} transaction exceeds the maximum fee of {1}.")]
+    FeeTooHigh(crate::fees::TransactionKind, bitcoin::Amount),
+    #[error("Transaction weight {0:#} exceeds the standard weight of {1:#}.")]
+    NonStandardWeight(bitcoin::Weight, bitcoin::Weight),
+    #[error("Fee of {0} is below the relay fee floor of {1}.")]
+    FeeBelowRelayFloor(bitcoin::Amount, bitcoin::FeeRate),
+    #[error("Output {0} is below the dust limit of the relay policy.")]
+    DustOutput(usize),
+    #[error("Inputs don't cover the amount and fees.")]
+    InsufficientFunds,
+    #[error("Payout {0} is more than 100% of the swept amount.")]
+    InvalidPayoutShare(usize),
+    #[error("Payout {0} is below the dust limit.")]
+    DustPayout(usize),
+    #[error("Payouts exceed the swept amount of {0}.")]
+    PayoutsExceedAmount(bitcoin::Amount),
+    #[error("Input {0} is not signed.")]
+    UnsignedInput(usize),
+    #[error("The sponsor's change output is missing from the challenge transaction.")]
+    SponsorOutputMissing,
+    #[error("The sponsor's input is already in the challenge transaction.")]
+    SponsorInputPresent,
 }
 
 impl From<UncompressedPublicKeyError> for TransactionError {
@@ -70,3 +117,15 @@ impl From<SighashError> for TransactionError {
         TransactionError::Sighash(err)
     }
 }
+
+impl From<StrictParseError> for TransactionError {
+    fn from(err: StrictParseError) -> Self {
+        TransactionError::StrictParse(err)
+    }
+}
+
+impl From<miniscript::Error> for TransactionError {
+    fn from(err: miniscript::Error) -> Self {
+        TransactionError::Miniscript(err)
+    }
+}
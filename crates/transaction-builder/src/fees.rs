@@ -0,0 +1,220 @@
+//! Fee policy, relay policy and size estimates of the protocol transactions.
+//!
+//! Transactions are signed after their outputs are fixed, so fees are computed from an upper
+//! bound of the transaction virtual size rather than from the signed transaction. The signed
+//! transaction is then checked against the relay policy of the network it is broadcast to.
+//!
+//! Every signature is low-R (see [`SignatureProvider`](crate::SignatureProvider)), so the
+//! estimates below are exact up to the rounding of weight units into virtual bytes.
+
+use bitcoin::{Amount, FeeRate, Network, Script, Transaction, Weight};
+
+use crate::errors::TransactionError;
+
+/// Transaction overhead: version, lock time, input and output counts and the segwit marker
+const OVERHEAD_VSIZE: u64 = 11;
+/// P2WPKH input with a low-R signature
+const P2WPKH_INPUT_VSIZE: u64 = 68;
+/// Challenge output input with a low-R signature, spent through either branch of the
+/// challenge script
+const CHALLENGE_INPUT_VSIZE: u64 = 82;
+const P2WPKH_OUTPUT_VSIZE: u64 = 31;
+const P2WSH_OUTPUT_VSIZE: u64 = 43;
+/// `OP_RETURN` output of a 32-byte hash
+const ANCHOR_OUTPUT_VSIZE: u64 = 43;
+/// Output to an arbitrary address, sized for the largest standard scripts (P2WSH and P2TR)
+const RECIPIENT_OUTPUT_VSIZE: u64 = 43;
+
+/// Protocol transaction kinds, with the number of wallet inputs they spend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionKind {
+    /// Challenger's deposit transaction
+    Deposit { inputs: usize },
+    /// Acceptor's stake consolidation transaction
+    Stake { inputs: usize },
+    /// Challenger's beacon anchor transaction, funding the deposit
+    BeaconAnchor { inputs: usize },
+    /// Challenge transaction, spending the deposit and the Acceptor's inputs
+    Challenge { inputs: usize },
+    /// Refund of an unaccepted deposit
+    Refund,
+    /// Sweep of the challenge output by either party, paying `outputs` outputs
+    Sweep { outputs: usize },
+}
+
+impl TransactionKind {
+    /// Upper bound of the transaction virtual size, counting a change output where the
+    /// transaction may have one
+    pub fn estimated_vsize(&self) -> u64 {
+        let wallet_inputs = |inputs: usize| inputs as u64 * P2WPKH_INPUT_VSIZE;
+
+        OVERHEAD_VSIZE
+            + match *self {
+                TransactionKind::Deposit { inputs } | TransactionKind::Stake { inputs } => {
+                    wallet_inputs(inputs) + 2 * P2WPKH_OUTPUT_VSIZE
+                }
+                TransactionKind::BeaconAnchor { inputs } => {
+                    wallet_inputs(inputs) + 2 * P2WPKH_OUTPUT_VSIZE + ANCHOR_OUTPUT_VSIZE
+                }
+                TransactionKind::Challenge { inputs } => {
+                    P2WPKH_INPUT_VSIZE
+                        + wallet_inputs(inputs)
+                        + P2WSH_OUTPUT_VSIZE
+                        + P2WPKH_OUTPUT_VSIZE
+                }
+                TransactionKind::Refund => P2WPKH_INPUT_VSIZE + RECIPIENT_OUTPUT_VSIZE,
+                TransactionKind::Sweep { outputs } => {
+                    CHALLENGE_INPUT_VSIZE + outputs as u64 * RECIPIENT_OUTPUT_VSIZE
+                }
+            }
+    }
+}
+
+/// Named sets of relay policy values, for the nodes and networks that don't use the defaults
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyProfile {
+    /// Bitcoin Core before 29.1: a relay fee floor of 1 sat/vB
+    BitcoinCore,
+    /// Bitcoin Core 29.1 and later, and nodes configured alike: a relay fee floor of
+    /// 0.1 sat/vB
+    LowFee,
+}
+
+/// Relay policy of the nodes a transaction is broadcast to. A transaction breaking it is valid,
+/// but is not relayed and never reaches the miners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayPolicy {
+    /// Lowest fee rate of a relayed transaction
+    pub min_relay_fee: FeeRate,
+    /// Fee rate outputs are weighed against: an output costing more to spend at this rate than
+    /// it is worth is dust
+    pub dust_relay_fee: FeeRate,
+    /// Heaviest relayed transaction
+    pub max_standard_weight: Weight,
+}
+
+impl RelayPolicy {
+    /// Policy of `profile`
+    pub fn profile(profile: PolicyProfile) -> Self {
+        let min_relay_fee = match profile {
+            PolicyProfile::BitcoinCore => FeeRate::from_sat_per_vb_u32(1),
+            PolicyProfile::LowFee => FeeRate::from_sat_per_kwu(25),
+        };
+
+        Self {
+            min_relay_fee,
+            dust_relay_fee: FeeRate::from_sat_per_vb_u32(3),
+            max_standard_weight: Weight::from_wu(400_000),
+        }
+    }
+
+    /// Default policy of `network`
+    pub fn for_network(network: Network) -> Self {
+        match network {
+            // Regtest nodes are local, their policy is left to the defaults of the node
+            Network::Regtest => Self::profile(PolicyProfile::LowFee),
+            _ => Self::profile(PolicyProfile::BitcoinCore),
+        }
+    }
+
+    /// Smallest value of an output paying to `script_pubkey` that is not dust
+    pub fn dust_limit(&self, script_pubkey: &Script) -> Amount {
+        script_pubkey.minimal_non_dust_custom(self.dust_relay_fee)
+    }
+
+    /// Fails if a signed transaction paying `fee` would not be relayed: if it is too heavy, pays
+    /// less than the relay fee floor or has a dust output. `OP_RETURN` outputs are exempt from
+    /// the dust limit.
+    pub fn check_standard(&self, tx: &Transaction, fee: Amount) -> Result<(), TransactionError> {
+        let weight = tx.weight();
+        if weight > self.max_standard_weight {
+            return Err(TransactionError::NonStandardWeight(
+                weight,
+                self.max_standard_weight,
+            ));
+        }
+
+        let min_fee = self.min_relay_fee.fee_wu(weight).unwrap_or(Amount::MAX);
+        if fee < min_fee {
+            return Err(TransactionError::FeeBelowRelayFloor(fee, min_fee));
+        }
+
+        for (index, output) in tx.output.iter().enumerate() {
+            if !output.script_pubkey.is_op_return()
+                && output.value < self.dust_limit(&output.script_pubkey)
+            {
+                return Err(TransactionError::DustOutput(index));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Fee and change policy applied to the transactions a wallet builds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePolicy {
+    /// Fee rate paid by every transaction. Raised to the relay fee floor if below it.
+    pub fee_rate: FeeRate,
+    /// Change below this amount is not worth an output and is left to the miners. Must be at
+    /// least the P2WPKH dust limit of the relay policy.
+    pub min_change: Amount,
+    /// Upper bound on the fee of a single transaction
+    pub max_fee: Amount,
+    /// Relay policy of the network the transactions are broadcast to
+    pub relay: RelayPolicy,
+}
+
+impl FeePolicy {
+    /// Default policy of `network`
+    pub fn for_network(network: Network) -> Self {
+        let relay = RelayPolicy::for_network(network);
+        match network {
+            Network::Bitcoin => Self {
+                fee_rate: FeeRate::from_sat_per_vb_u32(2),
+                min_change: Amount::from_sat(1_000),
+                max_fee: Amount::from_sat(100_000),
+                relay,
+            },
+            _ => Self {
+                fee_rate: FeeRate::from_sat_per_vb_u32(1),
+                min_change: Amount::from_sat(500),
+                max_fee: Amount::from_sat(10_000),
+                relay,
+            },
+        }
+    }
+
+    /// Fee rate transactions are built with: [`FeePolicy::fee_rate`], but never below the relay
+    /// fee floor
+    pub fn effective_fee_rate(&self) -> FeeRate {
+        self.fee_rate.max(self.relay.min_relay_fee)
+    }
+
+    /// Fee of a transaction of `kind`. Fails if it exceeds [`FeePolicy::max_fee`].
+    pub fn fee(&self, kind: TransactionKind) -> Result<Amount, TransactionError> {
+        let fee = self
+            .effective_fee_rate()
+            .fee_vb(kind.estimated_vsize())
+            .ok_or(TransactionError::FeeTooHigh(kind, self.max_fee))?;
+        if fee > self.max_fee {
+            return Err(TransactionError::FeeTooHigh(kind, self.max_fee));
+        }
+
+        Ok(fee)
+    }
+
+    /// Change left from `inputs` after spending `spent`, or `None` if it is below
+    /// [`FeePolicy::min_change`]. Fails if the inputs don't cover `spent`.
+    pub fn change(
+        &self,
+        inputs: Amount,
+        spent: Amount,
+    ) -> Result<Option<Amount>, TransactionError> {
+        let change = inputs
+            .checked_sub(spent)
+            .ok_or(TransactionError::InsufficientFunds)?;
+
+        Ok((change >= self.min_change).then_some(change))
+    }
+}
@@ -1,5 +1,29 @@
+mod challenge;
+mod equivocation;
 mod errors;
+mod fees;
+mod outcome;
+mod payout;
 mod scripts;
+mod signer;
+mod spending;
+mod sponsor;
 mod transaction_builder;
+mod watch;
 
+pub use challenge::{Accepted, Challenge, Completed, Confirmed, Created, Phase};
+pub use equivocation::{Equivocation, detect_equivocation};
+pub use errors::TransactionError;
+pub use fees::{FeePolicy, PolicyProfile, RelayPolicy, TransactionKind};
+pub use outcome::Outcome;
+pub use payout::{Payout, PayoutShare};
+pub use scripts::{
+    ChallengeScript, ChallengeScriptEncoding, ChallengeScriptTemplate, MAX_CHALLENGE_SCRIPT_LEN,
+    beacon_anchor_script, challenge_descriptor, challenge_script_template,
+    parse_challenge_descriptor, parse_challenge_witness_script,
+};
+pub use signer::{MAX_SIGNATURE_DER_LEN, SignatureProvider};
+pub use spending::{SpendingBranch, SpendingInstructions};
+pub use sponsor::{Sponsorship, validate_sponsored_psbt};
 pub use transaction_builder::TransactionBuilder;
+pub use watch::{ChainBackend, ChallengeSpend, OutpointEvent};
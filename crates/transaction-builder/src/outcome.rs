@@ -0,0 +1,120 @@
+use std::fmt;
+
+use bitcoin::{Amount, PublicKey, ScriptBuf, Transaction, Txid, absolute::LockTime};
+
+use crate::{errors::TransactionError, scripts::parse_challenge_script, spending::SpendingBranch};
+
+/// Outcome of a settled challenge, reconstructed from chain data only.
+///
+/// The `Display` implementation renders a short human-readable certificate.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// Branch the challenge output was spent with, i.e. the winner.
+    pub winner: SpendingBranch,
+    pub challenge_txid: Txid,
+    pub settlement_txid: Txid,
+    pub amount: Amount,
+    pub challenger_pubkey: PublicKey,
+    /// Deposit key `P_c + A_i` revealed by the Challenger in the challenge transaction.
+    pub revealed_deposit_pubkey: PublicKey,
+    /// Key `P_a + H_j` the Acceptor committed to in the challenge output.
+    pub tweaked_acceptor_pubkey: PublicKey,
+    pub lock_time: LockTime,
+}
+
+impl Outcome {
+    /// Reconstructs the outcome from the challenge transaction and the transaction that spent
+    /// its output. The witness script is taken from the settlement witness.
+    pub fn from_settlement(
+        challenge_transaction: &Transaction,
+        settlement_transaction: &Transaction,
+    ) -> Result<Self, TransactionError> {
+        let challenge_txid = challenge_transaction.compute_txid();
+
+        let settlement_input = settlement_transaction
+            .input
+            .iter()
+            .find(|input| {
+                input.previous_output.txid == challenge_txid && input.previous_output.vout == 0
+            })
+            .ok_or(TransactionError::TransactionTypeMismatch)?;
+
+        // Witness is either <signature> <1> <witness_script> or <signature> <> <witness_script>
+        let witness = &settlement_input.witness;
+        let (Some(branch_selector), Some(witness_script)) = (witness.nth(1), witness.nth(2)) else {
+            return Err(TransactionError::InvalidChallengeScript);
+        };
+        let witness_script = ScriptBuf::from_bytes(witness_script.to_vec());
+
+        let challenge_output = challenge_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?;
+        if challenge_output.script_pubkey != ScriptBuf::new_p2wsh(&witness_script.wscript_hash()) {
+            return Err(TransactionError::WitnessScriptMismatch);
+        }
+
+        let script = parse_challenge_script(&witness_script)?;
+
+        let revealed_deposit_pubkey = challenge_transaction
+            .input
+            .first()
+            .and_then(|input| input.witness.nth(1))
+            .and_then(|pubkey| PublicKey::from_slice(pubkey).ok())
+            .ok_or(TransactionError::InvalidChallengeScript)?;
+
+        let winner = if branch_selector == [1] {
+            SpendingBranch::Acceptor
+        } else {
+            SpendingBranch::Challenger
+        };
+
+        Ok(Self {
+            winner,
+            challenge_txid,
+            settlement_txid: settlement_transaction.compute_txid(),
+            amount: challenge_output.value,
+            challenger_pubkey: script.challenger_pubkey,
+            revealed_deposit_pubkey,
+            tweaked_acceptor_pubkey: script.tweaked_acceptor_pubkey,
+            lock_time: script.lock_time,
+        })
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let winner = match self.winner {
+            SpendingBranch::Acceptor => "Acceptor",
+            SpendingBranch::Challenger => "Challenger",
+        };
+
+        writeln!(f, "OP_RAND outcome certificate")?;
+        writeln!(f)?;
+        writeln!(f, "Winner:            {}", winner)?;
+        writeln!(f, "Amount:            {}", self.amount)?;
+        writeln!(f, "Challenge TXID:    {}", self.challenge_txid)?;
+        writeln!(f, "Settlement TXID:   {}", self.settlement_txid)?;
+        writeln!(f)?;
+        writeln!(f, "Challenger key:    {}", self.challenger_pubkey)?;
+        writeln!(f, "Revealed deposit:  {}", self.revealed_deposit_pubkey)?;
+        writeln!(f, "Acceptor key:      {}", self.tweaked_acceptor_pubkey)?;
+        writeln!(f, "Lock time:         {}", self.lock_time)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "The challenger revealed the deposit key P_c + A_i when signing the challenge transaction."
+        )?;
+
+        match self.winner {
+            SpendingBranch::Acceptor => writeln!(
+                f,
+                "The acceptor's key P_a + H_j matched it, so they swept the output right away."
+            ),
+            SpendingBranch::Challenger => writeln!(
+                f,
+                "The output was swept by the challenger after the lock time, the acceptor didn't claim it."
+            ),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Splitting of a swept amount between several outputs.
+
+use bitcoin::{Amount, ScriptBuf, TxOut};
+
+use crate::errors::TransactionError;
+
+/// Basis points in a whole
+const BASIS_POINTS: u64 = 10_000;
+
+/// Amount paid to a [`Payout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutShare {
+    /// Fixed amount
+    Fixed(Amount),
+    /// Share of the swept amount after fees, in basis points (hundredths of a percent)
+    BasisPoints(u16),
+}
+
+/// Output paying a share of a sweep
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payout {
+    pub script_pubkey: ScriptBuf,
+    pub share: PayoutShare,
+}
+
+impl Payout {
+    pub fn new(script_pubkey: ScriptBuf, share: PayoutShare) -> Self {
+        Self {
+            script_pubkey,
+            share,
+        }
+    }
+}
+
+/// Splits `amount` between `payouts`. What's left is paid to `remainder_script`, or left to the
+/// miners if it is dust. Fails if the payouts exceed `amount` or any of them is dust.
+pub(crate) fn payout_outputs(
+    amount: Amount,
+    payouts: &[Payout],
+    remainder_script: ScriptBuf,
+) -> Result<Vec<TxOut>, TransactionError> {
+    let mut outputs = Vec::with_capacity(payouts.len() + 1);
+    let mut remainder = amount;
+
+    for (index, payout) in payouts.iter().enumerate() {
+        let value = match payout.share {
+            PayoutShare::Fixed(value) => value,
+            PayoutShare::BasisPoints(points) => {
+                if u64::from(points) > BASIS_POINTS {
+                    return Err(TransactionError::InvalidPayoutShare(index));
+                }
+                // Widened so that the product can't overflow
+                let sats =
+                    u128::from(amount.to_sat()) * u128::from(points) / u128::from(BASIS_POINTS);
+                Amount::from_sat(sats as u64)
+            }
+        };
+
+        if value < payout.script_pubkey.minimal_non_dust() {
+            return Err(TransactionError::DustPayout(index));
+        }
+        remainder = remainder
+            .checked_sub(value)
+            .ok_or(TransactionError::PayoutsExceedAmount(amount))?;
+
+        outputs.push(TxOut {
+            value,
+            script_pubkey: payout.script_pubkey.clone(),
+        });
+    }
+
+    if remainder >= remainder_script.minimal_non_dust() {
+        outputs.push(TxOut {
+            value: remainder,
+            script_pubkey: remainder_script,
+        });
+    }
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{WPubkeyHash, hashes::Hash};
+
+    use super::*;
+
+    fn script(byte: u8) -> ScriptBuf {
+        ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([byte; 20]))
+    }
+
+    #[test]
+    fn splits_amount_and_pays_remainder() {
+        let payouts = [
+            Payout::new(script(1), PayoutShare::BasisPoints(2_500)),
+            Payout::new(script(2), PayoutShare::Fixed(Amount::from_sat(10_000))),
+        ];
+
+        let outputs = payout_outputs(Amount::from_sat(100_000), &payouts, script(3)).unwrap();
+
+        assert_eq!(
+            outputs,
+            [
+                TxOut {
+                    value: Amount::from_sat(25_000),
+                    script_pubkey: script(1),
+                },
+                TxOut {
+                    value: Amount::from_sat(10_000),
+                    script_pubkey: script(2),
+                },
+                TxOut {
+                    value: Amount::from_sat(65_000),
+                    script_pubkey: script(3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_dust_remainder_to_miners() {
+        let payouts = [Payout::new(
+            script(1),
+            PayoutShare::Fixed(Amount::from_sat(99_900)),
+        )];
+
+        let outputs = payout_outputs(Amount::from_sat(100_000), &payouts, script(3)).unwrap();
+
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(outputs[0].script_pubkey, script(1));
+    }
+
+    #[test]
+    fn rejects_share_above_whole() {
+        let payouts = [
+            Payout::new(script(1), PayoutShare::BasisPoints(5_000)),
+            Payout::new(script(2), PayoutShare::BasisPoints(10_001)),
+        ];
+
+        assert!(matches!(
+            payout_outputs(Amount::from_sat(100_000), &payouts, script(3)),
+            Err(TransactionError::InvalidPayoutShare(1))
+        ));
+    }
+
+    #[test]
+    fn rejects_dust_payout() {
+        let payouts = [Payout::new(script(1), PayoutShare::BasisPoints(1))];
+
+        assert!(matches!(
+            payout_outputs(Amount::from_sat(100_000), &payouts, script(3)),
+            Err(TransactionError::DustPayout(0))
+        ));
+    }
+
+    #[test]
+    fn rejects_payouts_exceeding_amount() {
+        let payouts = [
+            Payout::new(script(1), PayoutShare::BasisPoints(6_000)),
+            Payout::new(script(2), PayoutShare::BasisPoints(6_000)),
+        ];
+
+        assert!(matches!(
+            payout_outputs(Amount::from_sat(100_000), &payouts, script(3)),
+            Err(TransactionError::PayoutsExceedAmount(amount)) if amount == Amount::from_sat(100_000)
+        ));
+    }
+}
@@ -1,12 +1,21 @@
+use std::str::FromStr;
+
 use bitcoin::{
     absolute::LockTime,
-    key::PublicKey,
+    hashes::{Hash, sha256},
+    key::{PublicKey, Secp256k1},
     opcodes,
-    script::{self, ScriptBuf},
+    script::{self, Instruction, Script, ScriptBuf},
+    secp256k1::SecretKey,
 };
+use miniscript::{Descriptor, Miniscript, Segwitv0, Terminal, descriptor::DescriptorType};
+use op_rand_types::parse_hex_strict;
 
 use crate::errors::TransactionError;
 
+/// Largest challenge output script of either encoding, with a lock time of 5 bytes
+pub const MAX_CHALLENGE_SCRIPT_LEN: usize = 81;
+
 /// Creates a P2WPKH script from a public key.
 pub(crate) fn create_p2wpkh_script(public_key: &PublicKey) -> Result<ScriptBuf, TransactionError> {
     let witness_pubkey_hash = public_key.wpubkey_hash()?;
@@ -14,22 +23,50 @@ pub(crate) fn create_p2wpkh_script(public_key: &PublicKey) -> Result<ScriptBuf,
     Ok(ScriptBuf::new_p2wpkh(&witness_pubkey_hash))
 }
 
+/// Creates the `OP_RETURN` script of a beacon anchor transaction, see
+/// [`beacon_anchor`](op_rand_types::beacon_anchor).
+pub fn beacon_anchor_script(anchor: &sha256::Hash) -> ScriptBuf {
+    ScriptBuf::new_op_return(anchor.to_byte_array())
+}
+
 /// Creates a custom script for challenge transaction output:  
 /// ```_
 /// OP_IF
 ///     <P_a + H> OP_CHECKSIG
 /// OP_ELSE  
-///     <LT> OP_CHECKLOCKTIMEVERIFY OP_DROP  
-///     <P_c> OP_CHECKSIG  
+///     <P_c> OP_CHECKSIGVERIFY
+///     <LT> OP_CHECKLOCKTIMEVERIFY
 /// OP_ENDIF
+/// ```
+///
+/// The script is the encoding of the [`challenge_miniscript`].
 pub(crate) fn create_challenge_p2wsh_script(
     challenger_pubkey: &PublicKey,
     tweaked_acceptor_pubkey: &PublicKey,
     lock_time: LockTime,
+) -> Result<ScriptBuf, TransactionError> {
+    Ok(challenge_miniscript(challenger_pubkey, tweaked_acceptor_pubkey, lock_time)?.encode())
+}
+
+/// Creates the challenge output script built by protocol version 1 clients:
+/// ```_
+/// OP_IF
+///     <P_a + H> OP_CHECKSIG
+/// OP_ELSE
+///     <LT> OP_CHECKLOCKTIMEVERIFY OP_DROP
+///     <P_c> OP_CHECKSIG
+/// OP_ENDIF
+/// ```
+///
+/// It's not a miniscript, so new challenges don't use it, but outputs created with it must
+/// stay spendable.
+pub(crate) fn create_legacy_challenge_p2wsh_script(
+    challenger_pubkey: &PublicKey,
+    tweaked_acceptor_pubkey: &PublicKey,
+    lock_time: LockTime,
 ) -> ScriptBuf {
     script::Builder::new()
         .push_opcode(opcodes::all::OP_IF)
-        // TODO: it should be a hash of the public key
         .push_key(tweaked_acceptor_pubkey)
         .push_opcode(opcodes::all::OP_CHECKSIG)
         .push_opcode(opcodes::all::OP_ELSE)
@@ -41,3 +78,359 @@ pub(crate) fn create_challenge_p2wsh_script(
         .push_opcode(opcodes::all::OP_ENDIF)
         .into_script()
 }
+
+/// Creates the miniscript of the challenge output:
+/// `or_i(pk(P_a + H),and_v(v:pk(P_c),after(LT)))`
+pub(crate) fn challenge_miniscript(
+    challenger_pubkey: &PublicKey,
+    tweaked_acceptor_pubkey: &PublicKey,
+    lock_time: LockTime,
+) -> Result<Miniscript<PublicKey, Segwitv0>, TransactionError> {
+    // TODO: it should be a hash of the public key
+    let miniscript = format!(
+        "or_i(pk({}),and_v(v:pk({}),after({})))",
+        tweaked_acceptor_pubkey,
+        challenger_pubkey,
+        lock_time.to_consensus_u32()
+    );
+
+    Ok(Miniscript::from_str(&miniscript)?)
+}
+
+/// Placeholders of the challenge output script parameters in [`challenge_script_template`]
+const TWEAKED_ACCEPTOR_PUBKEY_PLACEHOLDER: &str = "P_a + H";
+const CHALLENGER_PUBKEY_PLACEHOLDER: &str = "P_c";
+const LOCK_TIME_PLACEHOLDER: &str = "LT";
+
+/// Template of the challenge output script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChallengeScriptTemplate {
+    /// Miniscript of the output, e.g. `or_i(pk(P_a + H),and_v(v:pk(P_c),after(LT)))`
+    pub miniscript: String,
+    /// Witness script opcodes, with pushed parameters written as `<P_a + H>`, `<P_c>` and `<LT>`
+    pub asm: String,
+}
+
+/// Renders the challenge output script with its parameters replaced by placeholders. It is
+/// built by [`create_challenge_p2wsh_script`] itself, so it can't drift from the scripts
+/// challenges use.
+pub fn challenge_script_template() -> Result<ChallengeScriptTemplate, TransactionError> {
+    let secp = Secp256k1::signing_only();
+    let tweaked_acceptor_pubkey =
+        PublicKey::new(SecretKey::from_slice(&[1; 32])?.public_key(&secp));
+    let challenger_pubkey = PublicKey::new(SecretKey::from_slice(&[2; 32])?.public_key(&secp));
+    let lock_time = LockTime::from_consensus(840_000);
+
+    let miniscript = challenge_miniscript(&challenger_pubkey, &tweaked_acceptor_pubkey, lock_time)?
+        .to_string()
+        .replace(
+            &tweaked_acceptor_pubkey.to_string(),
+            TWEAKED_ACCEPTOR_PUBKEY_PLACEHOLDER,
+        )
+        .replace(
+            &challenger_pubkey.to_string(),
+            CHALLENGER_PUBKEY_PLACEHOLDER,
+        )
+        .replace(
+            &lock_time.to_consensus_u32().to_string(),
+            LOCK_TIME_PLACEHOLDER,
+        );
+
+    let script =
+        create_challenge_p2wsh_script(&challenger_pubkey, &tweaked_acceptor_pubkey, lock_time)?;
+    let asm = script
+        .instructions()
+        .map(|instruction| {
+            let instruction = instruction.map_err(|_| TransactionError::InvalidChallengeScript)?;
+            Ok(match instruction {
+                Instruction::Op(opcode) => opcode.to_string(),
+                Instruction::PushBytes(bytes)
+                    if bytes.as_bytes() == tweaked_acceptor_pubkey.to_bytes() =>
+                {
+                    format!("<{}>", TWEAKED_ACCEPTOR_PUBKEY_PLACEHOLDER)
+                }
+                Instruction::PushBytes(bytes)
+                    if bytes.as_bytes() == challenger_pubkey.to_bytes() =>
+                {
+                    format!("<{}>", CHALLENGER_PUBKEY_PLACEHOLDER)
+                }
+                _ if instruction.script_num() == Some(i64::from(lock_time.to_consensus_u32())) => {
+                    format!("<{}>", LOCK_TIME_PLACEHOLDER)
+                }
+                Instruction::PushBytes(_) => return Err(TransactionError::InvalidChallengeScript),
+            })
+        })
+        .collect::<Result<Vec<_>, TransactionError>>()?
+        .join(" ");
+
+    Ok(ChallengeScriptTemplate { miniscript, asm })
+}
+
+/// Creates the `wsh` output descriptor of the challenge output:
+/// `wsh(or_i(pk(P_a + H),and_v(v:pk(P_c),after(LT))))`
+pub fn challenge_descriptor(
+    challenger_pubkey: &PublicKey,
+    tweaked_acceptor_pubkey: &PublicKey,
+    lock_time: LockTime,
+) -> Result<Descriptor<PublicKey>, TransactionError> {
+    let miniscript = challenge_miniscript(challenger_pubkey, tweaked_acceptor_pubkey, lock_time)?;
+
+    Ok(Descriptor::new_wsh(miniscript)?)
+}
+
+/// Parses a challenge output descriptor created by [`challenge_descriptor`].
+pub fn parse_challenge_descriptor(
+    descriptor: &Descriptor<PublicKey>,
+) -> Result<ChallengeScript, TransactionError> {
+    if descriptor.desc_type() != DescriptorType::Wsh {
+        return Err(TransactionError::InvalidChallengeScript);
+    }
+
+    parse_challenge_script(&descriptor.explicit_script()?)
+}
+
+/// Encoding of a challenge output script. Both encodings have the same branches, spent with
+/// the same witness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeScriptEncoding {
+    /// Built by protocol version 1, see [`create_legacy_challenge_p2wsh_script`]
+    Legacy,
+    /// Built since protocol version 2, the encoding of the [`challenge_miniscript`]
+    Miniscript,
+}
+
+/// Parameters of a challenge output script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChallengeScript {
+    pub tweaked_acceptor_pubkey: PublicKey,
+    pub challenger_pubkey: PublicKey,
+    pub lock_time: LockTime,
+    pub encoding: ChallengeScriptEncoding,
+}
+
+impl ChallengeScript {
+    /// `wsh` output descriptor of the script. Legacy scripts are not miniscripts and have none.
+    pub fn descriptor(&self) -> Result<Option<Descriptor<PublicKey>>, TransactionError> {
+        match self.encoding {
+            ChallengeScriptEncoding::Legacy => Ok(None),
+            ChallengeScriptEncoding::Miniscript => challenge_descriptor(
+                &self.challenger_pubkey,
+                &self.tweaked_acceptor_pubkey,
+                self.lock_time,
+            )
+            .map(Some),
+        }
+    }
+}
+
+/// Parses the hex of a challenge output witness script sent by a counterparty. Only the
+/// lowercase hex of a script of the challenge template is accepted.
+pub fn parse_challenge_witness_script(hex: &str) -> Result<ScriptBuf, TransactionError> {
+    let script = ScriptBuf::from_bytes(parse_hex_strict(
+        "challenge output witness script",
+        hex,
+        MAX_CHALLENGE_SCRIPT_LEN,
+    )?);
+    parse_challenge_script(&script)?;
+
+    Ok(script)
+}
+
+/// Parses a challenge output script created by [`create_challenge_p2wsh_script`], or by
+/// [`create_legacy_challenge_p2wsh_script`] for challenges of protocol version 1.
+pub(crate) fn parse_challenge_script(script: &Script) -> Result<ChallengeScript, TransactionError> {
+    match parse_legacy_challenge_script(script) {
+        Some(parsed) => Ok(parsed),
+        None => parse_miniscript_challenge_script(script),
+    }
+}
+
+/// Parses a script created by [`create_legacy_challenge_p2wsh_script`], returns `None` for any
+/// other script
+fn parse_legacy_challenge_script(script: &Script) -> Option<ChallengeScript> {
+    let instructions = script
+        .instructions_minimal()
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let [
+        _,
+        tweaked_acceptor_pubkey,
+        _,
+        _,
+        lock_time,
+        _,
+        _,
+        challenger_pubkey,
+        _,
+        _,
+    ] = instructions.as_slice()
+    else {
+        return None;
+    };
+
+    let tweaked_acceptor_pubkey =
+        PublicKey::from_slice(tweaked_acceptor_pubkey.push_bytes()?.as_bytes()).ok()?;
+    let challenger_pubkey =
+        PublicKey::from_slice(challenger_pubkey.push_bytes()?.as_bytes()).ok()?;
+    let lock_time = LockTime::from_consensus(u32::try_from(lock_time.script_num()?).ok()?);
+
+    // Rebuilding the script checks the opcodes and the encoding of every push
+    let expected = create_legacy_challenge_p2wsh_script(
+        &challenger_pubkey,
+        &tweaked_acceptor_pubkey,
+        lock_time,
+    );
+    if expected.as_script() != script {
+        return None;
+    }
+
+    Some(ChallengeScript {
+        tweaked_acceptor_pubkey,
+        challenger_pubkey,
+        lock_time,
+        encoding: ChallengeScriptEncoding::Legacy,
+    })
+}
+
+fn parse_miniscript_challenge_script(script: &Script) -> Result<ChallengeScript, TransactionError> {
+    let miniscript = Miniscript::<PublicKey, Segwitv0>::parse(script)?;
+
+    let mut keys = miniscript.iter_pk();
+    let (Some(tweaked_acceptor_pubkey), Some(challenger_pubkey), None) =
+        (keys.next(), keys.next(), keys.next())
+    else {
+        return Err(TransactionError::InvalidChallengeScript);
+    };
+
+    let lock_time = miniscript
+        .iter()
+        .find_map(|node| match node.node {
+            Terminal::After(lock_time) => Some(LockTime::from(lock_time)),
+            _ => None,
+        })
+        .ok_or(TransactionError::InvalidChallengeScript)?;
+
+    // Make sure the script follows the challenge template and not just any script with two keys
+    let expected =
+        create_challenge_p2wsh_script(&challenger_pubkey, &tweaked_acceptor_pubkey, lock_time)?;
+    if expected.as_script() != script {
+        return Err(TransactionError::InvalidChallengeScript);
+    }
+
+    Ok(ChallengeScript {
+        tweaked_acceptor_pubkey,
+        challenger_pubkey,
+        lock_time,
+        encoding: ChallengeScriptEncoding::Miniscript,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::Height;
+
+    use super::*;
+
+    fn key(byte: u8) -> PublicKey {
+        PublicKey::new(
+            SecretKey::from_slice(&[byte; 32])
+                .unwrap()
+                .public_key(&Secp256k1::signing_only()),
+        )
+    }
+
+    fn lock_time() -> LockTime {
+        LockTime::Blocks(Height::from_consensus(840_144).unwrap())
+    }
+
+    #[test]
+    fn parses_miniscript_challenge_script() {
+        let script = create_challenge_p2wsh_script(&key(1), &key(2), lock_time()).unwrap();
+
+        let parsed = parse_challenge_script(&script).unwrap();
+        assert_eq!(
+            parsed,
+            ChallengeScript {
+                tweaked_acceptor_pubkey: key(2),
+                challenger_pubkey: key(1),
+                lock_time: lock_time(),
+                encoding: ChallengeScriptEncoding::Miniscript,
+            }
+        );
+
+        let descriptor = parsed.descriptor().unwrap().unwrap();
+        assert_eq!(descriptor.explicit_script().unwrap(), script);
+        assert_eq!(parse_challenge_descriptor(&descriptor).unwrap(), parsed);
+    }
+
+    #[test]
+    fn parses_legacy_challenge_script() {
+        let script = create_legacy_challenge_p2wsh_script(&key(1), &key(2), lock_time());
+
+        let parsed = parse_challenge_script(&script).unwrap();
+        assert_eq!(
+            parsed,
+            ChallengeScript {
+                tweaked_acceptor_pubkey: key(2),
+                challenger_pubkey: key(1),
+                lock_time: lock_time(),
+                encoding: ChallengeScriptEncoding::Legacy,
+            }
+        );
+        assert_eq!(parsed.descriptor().unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_other_scripts() {
+        let p2wpkh = create_p2wpkh_script(&key(1)).unwrap();
+        assert!(parse_challenge_script(&p2wpkh).is_err());
+
+        // Two keys and a lock time, but not the challenge template
+        let other = Miniscript::<PublicKey, Segwitv0>::from_str(&format!(
+            "or_i(and_v(v:pk({}),after(840144)),pk({}))",
+            key(1),
+            key(2)
+        ))
+        .unwrap()
+        .encode();
+        assert!(matches!(
+            parse_challenge_script(&other),
+            Err(TransactionError::InvalidChallengeScript)
+        ));
+
+        let mut trailing = create_legacy_challenge_p2wsh_script(&key(1), &key(2), lock_time());
+        trailing.push_opcode(opcodes::OP_TRUE);
+        assert!(parse_challenge_script(&trailing).is_err());
+    }
+
+    #[test]
+    fn fits_largest_lock_time() {
+        // Miniscript lock times are below 2^31
+        let lock_time = LockTime::from_consensus(0x7fff_ffff);
+
+        for script in [
+            create_challenge_p2wsh_script(&key(1), &key(2), lock_time).unwrap(),
+            create_legacy_challenge_p2wsh_script(&key(1), &key(2), lock_time),
+        ] {
+            assert!(script.len() <= MAX_CHALLENGE_SCRIPT_LEN);
+            parse_challenge_witness_script(&script.to_hex_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_non_canonical_witness_script_hex() {
+        let hex = create_challenge_p2wsh_script(&key(1), &key(2), lock_time())
+            .unwrap()
+            .to_hex_string();
+
+        assert!(parse_challenge_witness_script(&hex).is_ok());
+        assert!(matches!(
+            parse_challenge_witness_script(&hex.to_uppercase()),
+            Err(TransactionError::StrictParse(_))
+        ));
+        assert!(matches!(
+            parse_challenge_witness_script(&"00".repeat(MAX_CHALLENGE_SCRIPT_LEN + 1)),
+            Err(TransactionError::StrictParse(_))
+        ));
+    }
+}
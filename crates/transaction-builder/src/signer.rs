@@ -0,0 +1,66 @@
+use bitcoin::{
+    key::Secp256k1,
+    secp256k1::{Message, PublicKey, Scalar, SecretKey, ecdsa::Signature},
+};
+
+use crate::errors::TransactionError;
+
+/// Largest size of a low-R ECDSA signature in DER, without the sighash type byte
+pub const MAX_SIGNATURE_DER_LEN: usize = 71;
+
+/// Source of the ECDSA signatures used by the protocol.
+///
+/// New signer types (hardware wallets, remote signers, ...) only need to implement this trait
+/// to be used by [`TransactionBuilder`](crate::TransactionBuilder). The trait is object safe,
+/// so a signer chosen at runtime can be used as a `Box<dyn SignatureProvider>`. Signers report
+/// their own failures with [`TransactionError::Signer`].
+pub trait SignatureProvider {
+    /// Returns the public key of the signer.
+    fn public_key(&self) -> Result<PublicKey, TransactionError>;
+
+    /// Signs the message with the signer key. If `tweak` is provided, the message is signed
+    /// with the signer key tweaked by adding `tweak` to it.
+    ///
+    /// The signature must be low-R, i.e. at most [`MAX_SIGNATURE_DER_LEN`] bytes in DER, as
+    /// the fee estimates assume it. Deterministic signers get it by grinding the RFC6979 nonce.
+    fn sign_ecdsa(
+        &self,
+        message: &Message,
+        tweak: Option<&SecretKey>,
+    ) -> Result<Signature, TransactionError>;
+}
+
+/// Software signer holding the secret key in memory
+impl SignatureProvider for SecretKey {
+    fn public_key(&self) -> Result<PublicKey, TransactionError> {
+        Ok(SecretKey::public_key(self, &Secp256k1::signing_only()))
+    }
+
+    fn sign_ecdsa(
+        &self,
+        message: &Message,
+        tweak: Option<&SecretKey>,
+    ) -> Result<Signature, TransactionError> {
+        let secret_key = match tweak {
+            Some(tweak) => self.add_tweak(&Scalar::from(*tweak))?,
+            None => *self,
+        };
+
+        // RFC6979 nonces, ground until R fits in 32 bytes
+        Ok(Secp256k1::signing_only().sign_ecdsa_low_r(message, &secret_key))
+    }
+}
+
+impl<S: SignatureProvider + ?Sized> SignatureProvider for Box<S> {
+    fn public_key(&self) -> Result<PublicKey, TransactionError> {
+        (**self).public_key()
+    }
+
+    fn sign_ecdsa(
+        &self,
+        message: &Message,
+        tweak: Option<&SecretKey>,
+    ) -> Result<Signature, TransactionError> {
+        (**self).sign_ecdsa(message, tweak)
+    }
+}
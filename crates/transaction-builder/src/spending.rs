@@ -0,0 +1,288 @@
+use std::fmt;
+
+use bitcoin::{
+    Amount, EcdsaSighashType, OutPoint, PrivateKey, Psbt, PublicKey, ScriptBuf, Sequence,
+    Transaction, TxIn, TxOut,
+    absolute::LockTime,
+    hashes::{Hash, sha256},
+    key::{Secp256k1, Verification},
+    secp256k1::{self, SecretKey, Signing},
+};
+use miniscript::{Descriptor, DescriptorPublicKey};
+use op_rand_types::ct_eq;
+
+use crate::{
+    errors::TransactionError, scripts::parse_challenge_script, transaction_builder::create_tx,
+};
+
+/// Branch of the challenge output script used to spend it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendingBranch {
+    /// `OP_IF` branch, spendable right away by the Acceptor if they guessed correctly.
+    Acceptor,
+    /// `OP_ELSE` branch, spendable by the Challenger once the lock time has expired.
+    Challenger,
+}
+
+impl SpendingBranch {
+    /// Selector pushed on the witness stack to take the branch
+    pub fn selector(&self) -> Vec<u8> {
+        match self {
+            SpendingBranch::Acceptor => vec![1],
+            SpendingBranch::Challenger => vec![],
+        }
+    }
+
+    /// Witness stack spending the challenge output with the branch, bottom first
+    pub fn witness_stack_template(&self) -> [&'static str; 3] {
+        match self {
+            SpendingBranch::Acceptor => [
+                "<signature of P_a + H, SIGHASH_ALL>",
+                "<1>",
+                "<witness script>",
+            ],
+            SpendingBranch::Challenger => {
+                ["<signature of P_c, SIGHASH_ALL>", "<>", "<witness script>"]
+            }
+        }
+    }
+}
+
+/// Everything an external wallet needs to spend a challenge output.
+///
+/// The `Display` implementation renders a human-readable instruction document.
+#[derive(Debug, Clone)]
+pub struct SpendingInstructions {
+    pub branch: SpendingBranch,
+    pub outpoint: OutPoint,
+    pub amount: Amount,
+    pub witness_script: ScriptBuf,
+    /// `wsh` output descriptor of the challenge output. Outputs of protocol version 1 have
+    /// none, see [`ChallengeScriptEncoding`](crate::ChallengeScriptEncoding).
+    pub descriptor: Option<Descriptor<PublicKey>>,
+    /// Public key the spending transaction must be signed with.
+    pub signing_pubkey: PublicKey,
+    /// Tweak to add to the Acceptor's wallet secret key to get the signing key.
+    /// It's revealed by the Challenger's signature in the challenge transaction.
+    pub tweak: Option<SecretKey>,
+    /// Lock time the spending transaction must set.
+    pub lock_time: Option<LockTime>,
+}
+
+impl SpendingInstructions {
+    /// Builds spending instructions for output 0 of a completed challenge transaction.
+    ///
+    /// Note: Acceptor's instructions lead to a valid spend only if the Acceptor chose the
+    /// correct third rank commitment, which can be checked by adding the tweak to the wallet
+    /// public key and comparing it with `signing_pubkey`.
+    pub fn new(
+        challenge_transaction: &Transaction,
+        witness_script: &ScriptBuf,
+        branch: SpendingBranch,
+    ) -> Result<Self, TransactionError> {
+        let challenge_output = challenge_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?;
+
+        if challenge_output.script_pubkey != ScriptBuf::new_p2wsh(&witness_script.wscript_hash()) {
+            return Err(TransactionError::WitnessScriptMismatch);
+        }
+
+        let script = parse_challenge_script(witness_script)?;
+        let descriptor = script.descriptor()?;
+
+        let (signing_pubkey, tweak, lock_time) = match branch {
+            SpendingBranch::Acceptor => {
+                let tweak = second_rank_commitment_tweak(
+                    &Secp256k1::verification_only(),
+                    challenge_transaction,
+                    &script.challenger_pubkey,
+                )?;
+
+                (script.tweaked_acceptor_pubkey, Some(tweak), None)
+            }
+            SpendingBranch::Challenger => (script.challenger_pubkey, None, Some(script.lock_time)),
+        };
+
+        Ok(Self {
+            branch,
+            outpoint: OutPoint::new(challenge_transaction.compute_txid(), 0),
+            amount: challenge_output.value,
+            witness_script: witness_script.clone(),
+            descriptor,
+            signing_pubkey,
+            tweak,
+            lock_time,
+        })
+    }
+}
+
+impl SpendingInstructions {
+    /// Creates an unsigned PSBT spending the challenge output to `recipient`, pre-filled with
+    /// the witness UTXO, the witness script and the lock time, so that any wallet knowing the
+    /// signing key can sign it.
+    ///
+    /// Note: fees must be handled by the caller, a fee above the output amount fails with
+    /// [`TransactionError::InsufficientFunds`]
+    pub fn to_psbt(&self, recipient: ScriptBuf, fee: Amount) -> Result<Psbt, TransactionError> {
+        let sequence = match self.lock_time {
+            Some(_) => Sequence::ENABLE_LOCKTIME_NO_RBF,
+            None => Sequence::MAX,
+        };
+
+        let inputs = vec![TxIn {
+            previous_output: self.outpoint,
+            sequence,
+            ..Default::default()
+        }];
+
+        let outputs = vec![TxOut {
+            value: self
+                .amount
+                .checked_sub(fee)
+                .ok_or(TransactionError::InsufficientFunds)?,
+            script_pubkey: recipient,
+        }];
+
+        let mut psbt = Psbt::from_unsigned_tx(create_tx(inputs, outputs, self.lock_time))?;
+
+        let psbt_input = &mut psbt.inputs[0];
+        psbt_input.witness_utxo = Some(TxOut {
+            value: self.amount,
+            script_pubkey: ScriptBuf::new_p2wsh(&self.witness_script.wscript_hash()),
+        });
+        psbt_input.witness_script = Some(self.witness_script.clone());
+        psbt_input.sighash_type = Some(EcdsaSighashType::All.into());
+
+        Ok(psbt)
+    }
+
+    /// Renders the descriptor with the signing public key replaced by `signing_key`, so that
+    /// wallets importing it can sign for the branch on their own.
+    pub fn descriptor_with_secret<C: Signing>(
+        &self,
+        ctx: &Secp256k1<C>,
+        signing_key: &PrivateKey,
+    ) -> Result<String, TransactionError> {
+        if !ct_eq(
+            &signing_key.public_key(ctx).to_bytes(),
+            &self.signing_pubkey.to_bytes(),
+        ) {
+            return Err(TransactionError::SigningKeyMismatch);
+        }
+        let descriptor = self
+            .descriptor
+            .as_ref()
+            .ok_or(TransactionError::NoChallengeDescriptor)?;
+
+        let descriptor = format!("{:#}", descriptor)
+            .replace(&self.signing_pubkey.to_string(), &signing_key.to_wif());
+        let (descriptor, key_map) =
+            Descriptor::<DescriptorPublicKey>::parse_descriptor(ctx, &descriptor)?;
+
+        Ok(descriptor.to_string_with_secret(&key_map))
+    }
+}
+
+impl fmt::Display for SpendingInstructions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Challenge output spending instructions")?;
+        writeln!(f)?;
+        writeln!(f, "Outpoint:       {}", self.outpoint)?;
+        writeln!(f, "Amount:         {}", self.amount)?;
+        if let Some(descriptor) = &self.descriptor {
+            writeln!(f, "Descriptor:     {}", descriptor)?;
+        }
+        writeln!(f, "Witness script: {}", self.witness_script.to_hex_string())?;
+        writeln!(f)?;
+
+        match self.branch {
+            SpendingBranch::Acceptor => {
+                writeln!(f, "Branch:         OP_IF (Acceptor, no time lock)")?;
+                writeln!(f, "Signing key:    {}", self.signing_pubkey)?;
+                if let Some(tweak) = self.tweak {
+                    writeln!(f, "Key tweak:      {}", tweak.display_secret())?;
+                }
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "1. Add the key tweak to your wallet secret key (mod n) to get the signing key."
+                )?;
+                writeln!(
+                    f,
+                    "2. Spend the outpoint with a P2WSH input signed with SIGHASH_ALL."
+                )?;
+                writeln!(f, "3. Set the witness to: <signature> 01 <witness script>")?;
+            }
+            SpendingBranch::Challenger => {
+                writeln!(f, "Branch:         OP_ELSE (Challenger, time locked)")?;
+                writeln!(f, "Signing key:    {}", self.signing_pubkey)?;
+                if let Some(lock_time) = self.lock_time {
+                    writeln!(f, "Lock time:      {}", lock_time)?;
+                }
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "1. Set the transaction nLockTime to the lock time and the input nSequence below 0xffffffff."
+                )?;
+                writeln!(
+                    f,
+                    "2. Spend the outpoint with a P2WSH input signed with SIGHASH_ALL by your wallet key."
+                )?;
+                writeln!(
+                    f,
+                    "3. Set the witness to: <signature> <empty> <witness script>"
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Recovers the second rank commitment `A_i` revealed by the Challenger.
+///
+/// The Challenger's deposit input reveals `P_c + A_i`, so subtracting `P_c` yields `A_i`.
+pub(crate) fn revealed_second_rank_commitment<C: Verification>(
+    ctx: &Secp256k1<C>,
+    challenge_transaction: &Transaction,
+    challenger_pubkey: &PublicKey,
+) -> Result<secp256k1::PublicKey, TransactionError> {
+    // Extract the witness pubkey from the deposit input witness stack
+    let witness_pubkey = challenge_transaction
+        .input
+        .first()
+        .and_then(|input| input.witness.nth(1))
+        .and_then(|pubkey| PublicKey::from_slice(pubkey).ok())
+        .ok_or(TransactionError::Secp256k1(
+            secp256k1::Error::InvalidPublicKey,
+        ))?;
+
+    // Extract the second rank commitment by subtracting challenger_pubkey from witness_pubkey
+    let negated_challenger_pubkey = challenger_pubkey.inner.negate(ctx);
+
+    Ok(witness_pubkey.inner.combine(&negated_challenger_pubkey)?)
+}
+
+/// Recovers the tweak the Acceptor adds to their secret key to spend the challenge output,
+/// which is the hash of the revealed second rank commitment.
+pub(crate) fn second_rank_commitment_tweak<C: Verification>(
+    ctx: &Secp256k1<C>,
+    challenge_transaction: &Transaction,
+    challenger_pubkey: &PublicKey,
+) -> Result<SecretKey, TransactionError> {
+    let second_rank_commitment =
+        revealed_second_rank_commitment(ctx, challenge_transaction, challenger_pubkey)?;
+
+    Ok(second_rank_commitment_hash(&second_rank_commitment)?)
+}
+
+/// Hashes a second rank commitment into a scalar: `H_i = sha256(A_i) * G`
+pub(crate) fn second_rank_commitment_hash(
+    second_rank_commitment: &secp256k1::PublicKey,
+) -> Result<SecretKey, secp256k1::Error> {
+    let hash = sha256::Hash::hash(&second_rank_commitment.serialize());
+
+    SecretKey::from_slice(hash.as_byte_array())
+}
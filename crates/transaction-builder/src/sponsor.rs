@@ -0,0 +1,238 @@
+//! Fee sponsorship of a challenge transaction by a third party.
+//!
+//! A sponsor pays fees of the challenge transaction with an input of its own and gets the rest
+//! of that input back with a change output:
+//!
+//! 1. The Acceptor adds the sponsor's change output to the challenge transaction and signs their
+//!    inputs with `SIGHASH_ALL|ANYONECANPAY`, see [`TransactionBuilder::build_challenge_tx`].
+//! 2. The Challenger signs the deposit input with `SIGHASH_ALL|ANYONECANPAY` as well, see
+//!    [`TransactionBuilder::sign_challenge_for_sponsor`].
+//! 3. The sponsor checks the PSBT, then adds, signs and finalizes its input, see
+//!    [`TransactionBuilder::sponsor_challenge_tx`].
+//!
+//! The participants' signatures commit to all outputs, so the sponsor's change output must be
+//! known before they sign. Only the sponsor's input is added afterwards.
+//!
+//! [`TransactionBuilder::build_challenge_tx`]: crate::TransactionBuilder::build_challenge_tx
+//! [`TransactionBuilder::sign_challenge_for_sponsor`]: crate::TransactionBuilder::sign_challenge_for_sponsor
+//! [`TransactionBuilder::sponsor_challenge_tx`]: crate::TransactionBuilder::sponsor_challenge_tx
+
+use bitcoin::{Amount, EcdsaSighashType, OutPoint, Psbt, TxOut};
+
+use crate::errors::TransactionError;
+
+/// Input and change output a sponsor adds to a challenge transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sponsorship {
+    /// Sponsor's UTXO, spent with the sponsor's key
+    pub input: OutPoint,
+    pub input_value: Amount,
+    /// Output paying the rest of the input back to the sponsor
+    pub change: TxOut,
+}
+
+impl Sponsorship {
+    pub fn new(input: OutPoint, input_value: Amount, change: TxOut) -> Self {
+        Self {
+            input,
+            input_value,
+            change,
+        }
+    }
+
+    /// Fee paid by the sponsor
+    pub fn fee(&self) -> Result<Amount, TransactionError> {
+        self.input_value
+            .checked_sub(self.change.value)
+            .ok_or(TransactionError::InsufficientFunds)
+    }
+}
+
+/// Checks that a challenge PSBT is ready to be sponsored with `sponsorship`: every participant
+/// input is signed with `SIGHASH_ALL|ANYONECANPAY` only, the sponsor's change output is there
+/// and the sponsor's input is not.
+pub fn validate_sponsored_psbt(
+    psbt: &Psbt,
+    sponsorship: &Sponsorship,
+) -> Result<(), TransactionError> {
+    sponsorship.fee()?;
+
+    if psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .any(|input| input.previous_output == sponsorship.input)
+    {
+        return Err(TransactionError::SponsorInputPresent);
+    }
+    if !psbt.unsigned_tx.output.contains(&sponsorship.change) {
+        return Err(TransactionError::SponsorOutputMissing);
+    }
+
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        if input.partial_sigs.is_empty() {
+            return Err(TransactionError::UnsignedInput(index));
+        }
+        for signature in input.partial_sigs.values() {
+            if signature.sighash_type != EcdsaSighashType::AllPlusAnyoneCanPay {
+                return Err(TransactionError::UnsupportedSighashType(
+                    signature.sighash_type,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        PublicKey, ScriptBuf, TxIn, Txid, ecdsa,
+        hashes::Hash,
+        secp256k1::{Message, Secp256k1, SecretKey},
+    };
+
+    use super::*;
+    use crate::transaction_builder::create_tx;
+
+    fn outpoint(byte: u8) -> OutPoint {
+        OutPoint::new(Txid::from_byte_array([byte; 32]), 0)
+    }
+
+    fn sponsorship() -> Sponsorship {
+        Sponsorship::new(
+            outpoint(9),
+            Amount::from_sat(10_000),
+            TxOut {
+                value: Amount::from_sat(8_000),
+                script_pubkey: ScriptBuf::new(),
+            },
+        )
+    }
+
+    /// Builds a challenge PSBT spending `inputs`, with `outputs`, whose inputs are all signed
+    /// with `sighash_type`
+    fn psbt(inputs: &[OutPoint], outputs: Vec<TxOut>, sighash_type: EcdsaSighashType) -> Psbt {
+        let transaction = create_tx(
+            inputs
+                .iter()
+                .map(|input| TxIn {
+                    previous_output: *input,
+                    ..Default::default()
+                })
+                .collect(),
+            outputs,
+            None,
+        );
+        let mut psbt = Psbt::from_unsigned_tx(transaction).unwrap();
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let signature = ecdsa::Signature {
+            signature: secp.sign_ecdsa(&Message::from_digest([2; 32]), &secret_key),
+            sighash_type,
+        };
+        for input in &mut psbt.inputs {
+            input
+                .partial_sigs
+                .insert(PublicKey::new(secret_key.public_key(&secp)), signature);
+        }
+
+        psbt
+    }
+
+    #[test]
+    fn accepts_psbt_ready_for_sponsor() {
+        let sponsorship = sponsorship();
+        let psbt = psbt(
+            &[outpoint(1), outpoint(2)],
+            vec![sponsorship.change.clone()],
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        );
+
+        validate_sponsored_psbt(&psbt, &sponsorship).unwrap();
+        assert_eq!(sponsorship.fee().unwrap(), Amount::from_sat(2_000));
+    }
+
+    #[test]
+    fn rejects_change_above_sponsor_input() {
+        let mut sponsorship = sponsorship();
+        sponsorship.change.value = Amount::from_sat(10_001);
+        let psbt = psbt(
+            &[outpoint(1)],
+            vec![sponsorship.change.clone()],
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        );
+
+        assert!(matches!(
+            validate_sponsored_psbt(&psbt, &sponsorship),
+            Err(TransactionError::InsufficientFunds)
+        ));
+    }
+
+    #[test]
+    fn rejects_psbt_spending_sponsor_input() {
+        let sponsorship = sponsorship();
+        let psbt = psbt(
+            &[outpoint(1), sponsorship.input],
+            vec![sponsorship.change.clone()],
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        );
+
+        assert!(matches!(
+            validate_sponsored_psbt(&psbt, &sponsorship),
+            Err(TransactionError::SponsorInputPresent)
+        ));
+    }
+
+    #[test]
+    fn rejects_psbt_without_sponsor_change() {
+        let sponsorship = sponsorship();
+        let mut change = sponsorship.change.clone();
+        change.value = Amount::from_sat(7_000);
+        let psbt = psbt(
+            &[outpoint(1)],
+            vec![change],
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        );
+
+        assert!(matches!(
+            validate_sponsored_psbt(&psbt, &sponsorship),
+            Err(TransactionError::SponsorOutputMissing)
+        ));
+    }
+
+    #[test]
+    fn rejects_unsigned_participant_input() {
+        let sponsorship = sponsorship();
+        let mut psbt = psbt(
+            &[outpoint(1), outpoint(2)],
+            vec![sponsorship.change.clone()],
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        );
+        psbt.inputs[1].partial_sigs.clear();
+
+        assert!(matches!(
+            validate_sponsored_psbt(&psbt, &sponsorship),
+            Err(TransactionError::UnsignedInput(1))
+        ));
+    }
+
+    #[test]
+    fn rejects_signature_committing_to_all_inputs() {
+        let sponsorship = sponsorship();
+        let psbt = psbt(
+            &[outpoint(1)],
+            vec![sponsorship.change.clone()],
+            EcdsaSighashType::All,
+        );
+
+        assert!(matches!(
+            validate_sponsored_psbt(&psbt, &sponsorship),
+            Err(TransactionError::UnsupportedSighashType(
+                EcdsaSighashType::All
+            ))
+        ));
+    }
+}
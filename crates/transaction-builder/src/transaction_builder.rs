@@ -2,10 +2,10 @@ use bitcoin::{
     Amount, EcdsaSighashType, OutPoint, Psbt, PublicKey, ScriptBuf, Sequence, Transaction, TxIn,
     TxOut,
     absolute::LockTime,
-    hashes::{Hash, sha256},
+    hashes::sha256,
     key::{Secp256k1, Verification},
     psbt::PsbtSighashType,
-    secp256k1::{self, All, Context, Message, SecretKey, Signing},
+    secp256k1::{self, All, Context, Message, Scalar, SecretKey, Signing},
     sighash::SighashCache,
     transaction::Version,
 };
@@ -14,20 +14,28 @@ use op_rand_types::{FirstRankCommitment, ThirdRankCommitment};
 
 use crate::{
     errors::TransactionError,
-    scripts::{create_challenge_p2wsh_script, create_p2wpkh_script},
+    payout::{Payout, payout_outputs},
+    scripts::{beacon_anchor_script, create_challenge_p2wsh_script, create_p2wpkh_script},
+    signer::{MAX_SIGNATURE_DER_LEN, SignatureProvider},
+    spending::{SpendingBranch, second_rank_commitment_tweak},
+    sponsor::{Sponsorship, validate_sponsored_psbt},
 };
 
 /// `TransactionBuilder` is used by both parties to build deposit and challenge transactions.
+/// Signatures are requested from the [`SignatureProvider`], a software key by default.
 #[derive(Debug, Clone)]
-pub struct TransactionBuilder<C: Context> {
-    secret_key: SecretKey,
+pub struct TransactionBuilder<C: Context, S: SignatureProvider = SecretKey> {
+    signer: S,
     ctx: Secp256k1<C>,
 }
 
 impl From<SecretKey> for TransactionBuilder<All> {
     fn from(secret_key: SecretKey) -> Self {
         let ctx = Secp256k1::new();
-        TransactionBuilder { secret_key, ctx }
+        TransactionBuilder {
+            signer: secret_key,
+            ctx,
+        }
     }
 }
 
@@ -35,16 +43,21 @@ impl From<&SecretKey> for TransactionBuilder<All> {
     fn from(secret_key: &SecretKey) -> Self {
         let ctx = Secp256k1::new();
         TransactionBuilder {
-            secret_key: *secret_key,
+            signer: *secret_key,
             ctx,
         }
     }
 }
 
-impl<C: Signing + Verification> TransactionBuilder<C> {
-    /// Creates a new `TransactionBuilder` with the given secret key and context.
-    pub fn new(secret_key: SecretKey, ctx: Secp256k1<C>) -> Self {
-        TransactionBuilder { secret_key, ctx }
+impl<C: Signing + Verification, S: SignatureProvider> TransactionBuilder<C, S> {
+    /// Creates a new `TransactionBuilder` with the given signer and context.
+    pub fn new(signer: S, ctx: Secp256k1<C>) -> Self {
+        TransactionBuilder { signer, ctx }
+    }
+
+    /// Returns the signer used by the builder.
+    pub fn signer(&self) -> &S {
+        &self.signer
     }
 
     /// This method should be used by the Challenger to build a deposit transaction.
@@ -59,8 +72,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         change_amount: Option<Amount>,
         change_pubkey: Option<PublicKey>,
     ) -> Result<Transaction, TransactionError> {
-        let secp_public_key = self.secret_key.public_key(&self.ctx);
-        let public_key = PublicKey::new(secp_public_key);
+        let public_key = PublicKey::new(self.signer.public_key()?);
 
         // Combine the chosen first rank commitment with the public key to get the challenge public key
         let challenge_pubkey = first_rank_commitment.combine(&public_key.inner)?;
@@ -95,12 +107,112 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         Ok(deposit_tx)
     }
 
+    /// This method should be used by the Acceptor to consolidate wallet UTXOs into a single
+    /// stake output of exactly the amount the challenge transaction will spend, paid to the
+    /// signer's own key. Funding the challenge from it leaves the challenge transaction with one
+    /// Acceptor input and no change output.
+    ///
+    /// Note: fees must be handled by the caller
+    pub fn build_stake_transaction(
+        &self,
+        previous_outputs: Vec<(OutPoint, Amount)>,
+        stake_amount: Amount,
+        change_amount: Option<Amount>,
+        change_pubkey: Option<PublicKey>,
+    ) -> Result<Transaction, TransactionError> {
+        let public_key = PublicKey::new(self.signer.public_key()?);
+
+        let mut outputs = vec![TxOut {
+            value: stake_amount,
+            script_pubkey: create_p2wpkh_script(&public_key)?,
+        }];
+
+        if let Some(change_amount) = change_amount {
+            outputs.push(TxOut {
+                value: change_amount,
+                script_pubkey: create_p2wpkh_script(&change_pubkey.unwrap_or(public_key))?,
+            });
+        }
+
+        let inputs = previous_outputs
+            .iter()
+            .map(|(outpoint, _)| TxIn {
+                previous_output: *outpoint,
+                ..Default::default()
+            })
+            .collect();
+
+        let amounts = previous_outputs.iter().map(|(_, amount)| *amount).collect();
+
+        let mut stake_tx = create_tx(inputs, outputs, None);
+        self.sign_transaction(&mut stake_tx, amounts)?;
+
+        Ok(stake_tx)
+    }
+
+    /// This method should be used by the Challenger to anchor the commitments and the beacon salt
+    /// commitment on chain before the selection beacon. The anchor pays `funding_amount` to the
+    /// signer's own key, which the deposit then spends as its only input, and commits to
+    /// `anchor` in an `OP_RETURN` output.
+    ///
+    /// Note: fees must be handled by the caller
+    pub fn build_beacon_anchor_transaction(
+        &self,
+        previous_outputs: Vec<(OutPoint, Amount)>,
+        funding_amount: Amount,
+        anchor: &sha256::Hash,
+        change_amount: Option<Amount>,
+        change_pubkey: Option<PublicKey>,
+    ) -> Result<Transaction, TransactionError> {
+        let public_key = PublicKey::new(self.signer.public_key()?);
+
+        let mut outputs = vec![
+            TxOut {
+                value: funding_amount,
+                script_pubkey: create_p2wpkh_script(&public_key)?,
+            },
+            TxOut {
+                value: Amount::ZERO,
+                script_pubkey: beacon_anchor_script(anchor),
+            },
+        ];
+
+        if let Some(change_amount) = change_amount {
+            outputs.push(TxOut {
+                value: change_amount,
+                script_pubkey: create_p2wpkh_script(&change_pubkey.unwrap_or(public_key))?,
+            });
+        }
+
+        let inputs = previous_outputs
+            .iter()
+            .map(|(outpoint, _)| TxIn {
+                previous_output: *outpoint,
+                ..Default::default()
+            })
+            .collect();
+
+        let amounts = previous_outputs.iter().map(|(_, amount)| *amount).collect();
+
+        let mut anchor_tx = create_tx(inputs, outputs, None);
+        self.sign_transaction(&mut anchor_tx, amounts)?;
+
+        Ok(anchor_tx)
+    }
+
     /// This method should be used by the Acceptor to build a challenge transaction.
     /// Needs a third rank commitment to combine with Acceptor's public key
     ///
     /// At this point, a PSBT is created and signed only by the Acceptor.
     /// The PSBT is then returned to the Challenger to complete the transaction.
     ///
+    /// Acceptor's inputs are signed with `sighash_type`, which must be either `SIGHASH_ALL` or
+    /// `SIGHASH_ALL|ANYONECANPAY`. The latter lets the Challenger add fee inputs at completion.
+    ///
+    /// `sponsor_change` is the change output of a fee sponsor, added before signing so that the
+    /// sponsor can add its input later. It requires `SIGHASH_ALL|ANYONECANPAY`, see
+    /// [`Sponsorship`].
+    ///
     /// Note: fees must be handled by the caller
     #[allow(clippy::too_many_arguments)]
     pub fn build_challenge_tx(
@@ -113,8 +225,15 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         previous_outputs: Vec<(OutPoint, Amount)>,
         change_amount: Option<Amount>,
         change_pubkey: Option<PublicKey>,
+        sponsor_change: Option<TxOut>,
+        sighash_type: EcdsaSighashType,
     ) -> Result<(ScriptBuf, Psbt), TransactionError> {
-        let acceptor_public_key = self.secret_key.public_key(&self.ctx);
+        check_funding_sighash_type(sighash_type)?;
+        if sponsor_change.is_some() && sighash_type != EcdsaSighashType::AllPlusAnyoneCanPay {
+            return Err(TransactionError::UnsupportedSighashType(sighash_type));
+        }
+
+        let acceptor_public_key = self.signer.public_key()?;
 
         // Combine the chosen third rank commitment with the acceptor's public key to get the challenge public key
         let tweaked_acceptor_pubkey = third_rank_commitment.combine(&acceptor_public_key)?;
@@ -123,7 +242,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             challenger_pubkey,
             &PublicKey::new(tweaked_acceptor_pubkey),
             lock_time,
-        );
+        )?;
 
         let mut outputs = vec![TxOut {
             value: amount * 2,
@@ -139,6 +258,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
                 script_pubkey: change_script,
             });
         }
+        outputs.extend(sponsor_change);
 
         let mut inputs = vec![TxIn {
             previous_output: deposit_outpoint,
@@ -160,28 +280,47 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
 
         for (input_index, (_, amount)) in previous_outputs.iter().enumerate() {
             // Increment input index by 1 to skip the deposit input
-            self.sign_psbt_input(&mut psbt, input_index + 1, *amount, None)?;
+            self.sign_psbt_input(&mut psbt, input_index + 1, *amount, None, sighash_type)?;
         }
 
         Ok((challenge_script, psbt))
     }
 
     /// This method should be used by the Challenger to complete the challenge transaction.
-    /// It signs the deposit input and finalizes the PSBT.
+    /// It adds `fee_inputs` spent from the signer's key, signs the deposit input and finalizes
+    /// the PSBT. The whole value of the fee inputs goes to the miners.
+    ///
+    /// Fee inputs can only be added if the Acceptor signed all their inputs with
+    /// `SIGHASH_ALL|ANYONECANPAY`, otherwise they would invalidate the Acceptor's signatures.
     pub fn complete_challenge_tx(
         &self,
         mut psbt: Psbt,
         deposit_amount: Amount,
         deposit_input_index: usize,
         first_rank_commitment: FirstRankCommitment,
+        fee_inputs: Vec<(OutPoint, Amount)>,
     ) -> Result<Transaction, TransactionError> {
-        // Sign the deposit transaction output using the chosen first rank commitment
-        let deposit_signing_key = first_rank_commitment.add_tweak(&self.secret_key)?;
+        validate_acceptor_signatures(&psbt, deposit_input_index, !fee_inputs.is_empty())?;
+
+        for (outpoint, amount) in fee_inputs {
+            psbt.unsigned_tx.input.push(TxIn {
+                previous_output: outpoint,
+                ..Default::default()
+            });
+            psbt.inputs.push(Default::default());
+
+            let input_index = psbt.inputs.len() - 1;
+            self.sign_psbt_input(&mut psbt, input_index, amount, None, EcdsaSighashType::All)?;
+        }
+
+        // Sign the deposit transaction output with the key tweaked by the chosen first rank commitment
+        let (first_rank_secret_key, _) = first_rank_commitment.inner();
         self.sign_psbt_input(
             &mut psbt,
             deposit_input_index,
             deposit_amount,
-            Some(deposit_signing_key),
+            Some(first_rank_secret_key),
+            EcdsaSighashType::All,
         )?;
 
         psbt.finalize_mut(&self.ctx)?;
@@ -190,15 +329,129 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             .map_err(|_e| TransactionError::ExtractTransactionFailed)
     }
 
+    /// This method should be used by the Challenger to complete a sponsored challenge
+    /// transaction. It signs the deposit input with `SIGHASH_ALL|ANYONECANPAY` and returns the
+    /// PSBT for the sponsor to add its input to, see [`TransactionBuilder::sponsor_challenge_tx`].
+    pub fn sign_challenge_for_sponsor(
+        &self,
+        mut psbt: Psbt,
+        deposit_amount: Amount,
+        deposit_input_index: usize,
+        first_rank_commitment: FirstRankCommitment,
+    ) -> Result<Psbt, TransactionError> {
+        validate_acceptor_signatures(&psbt, deposit_input_index, true)?;
+
+        let (first_rank_secret_key, _) = first_rank_commitment.inner();
+        self.sign_psbt_input(
+            &mut psbt,
+            deposit_input_index,
+            deposit_amount,
+            Some(first_rank_secret_key),
+            EcdsaSighashType::AllPlusAnyoneCanPay,
+        )?;
+
+        Ok(psbt)
+    }
+
+    /// This method should be used by a fee sponsor. It checks the PSBT signed by both parties
+    /// with [`validate_sponsored_psbt`], adds and signs the sponsor's input and finalizes the
+    /// challenge transaction.
+    pub fn sponsor_challenge_tx(
+        &self,
+        mut psbt: Psbt,
+        sponsorship: &Sponsorship,
+    ) -> Result<Transaction, TransactionError> {
+        validate_sponsored_psbt(&psbt, sponsorship)?;
+
+        psbt.unsigned_tx.input.push(TxIn {
+            previous_output: sponsorship.input,
+            ..Default::default()
+        });
+        psbt.inputs.push(Default::default());
+
+        let input_index = psbt.inputs.len() - 1;
+        self.sign_psbt_input(
+            &mut psbt,
+            input_index,
+            sponsorship.input_value,
+            None,
+            EcdsaSighashType::All,
+        )?;
+
+        psbt.finalize_mut(&self.ctx)?;
+
+        psbt.extract_tx()
+            .map_err(|_e| TransactionError::ExtractTransactionFailed)
+    }
+
+    /// This method should be used by the Challenger to reclaim the deposit when the Acceptor
+    /// didn't cooperate within the acceptance window and the challenge was never completed.
+    /// The deposit is signed with the key tweaked by the chosen first rank commitment and paid to
+    /// `recipient`, or to the signer's key if `None`.
+    pub fn build_deposit_refund_tx(
+        &self,
+        deposit_transaction: &Transaction,
+        first_rank_commitment: FirstRankCommitment,
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Transaction, TransactionError> {
+        let deposit_amount = deposit_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::NoDepositTxStored)?
+            .value;
+
+        let inputs = vec![TxIn {
+            previous_output: OutPoint::new(deposit_transaction.compute_txid(), 0),
+            ..Default::default()
+        }];
+
+        let outputs = vec![TxOut {
+            value: deposit_amount
+                .checked_sub(fee)
+                .ok_or(TransactionError::InsufficientFunds)?,
+            script_pubkey: self.recipient_script(recipient)?,
+        }];
+
+        let (first_rank_secret_key, _) = first_rank_commitment.inner();
+
+        let mut tx = create_tx(inputs, outputs, None);
+        self.sign_single_input(&mut tx, 0, deposit_amount, Some(&first_rank_secret_key))?;
+
+        Ok(tx)
+    }
+
     /// This method should be used by the Acceptor to sweep the challenge output.
     /// It will result in a correct transaction only if the acceptor chose the correct
-    /// third rank commitment.
+    /// third rank commitment. The output is paid to `recipient`, or to the signer's key if `None`.
     pub fn sweep_challenge_output_acceptor(
         &self,
         challenge_transaction: &Transaction,
         challenger_pubkey: &PublicKey,
         witness_script: &ScriptBuf,
-        recipient_pubkey: Option<PublicKey>,
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Transaction, TransactionError> {
+        self.sweep_challenge_output_acceptor_with_payouts(
+            challenge_transaction,
+            challenger_pubkey,
+            witness_script,
+            &[],
+            recipient,
+            fee,
+        )
+    }
+
+    /// Same as [`TransactionBuilder::sweep_challenge_output_acceptor`], but splits the swept
+    /// amount after fees between `payouts`. The remainder is paid to `recipient`, or to the
+    /// signer's key if `None`.
+    pub fn sweep_challenge_output_acceptor_with_payouts(
+        &self,
+        challenge_transaction: &Transaction,
+        challenger_pubkey: &PublicKey,
+        witness_script: &ScriptBuf,
+        payouts: &[Payout],
+        recipient: Option<ScriptBuf>,
         fee: Amount,
     ) -> Result<Transaction, TransactionError> {
         let inputs = vec![TxIn {
@@ -206,31 +459,10 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             ..Default::default()
         }];
 
-        let outputs = vec![TxOut {
-            value: challenge_transaction.output[0].value - fee,
-            script_pubkey: create_p2wpkh_script(
-                &recipient_pubkey.unwrap_or(self.secret_key.public_key(&self.ctx).into()),
-            )?,
-        }];
-
-        // Extract the witness stack from the deposit input
-        let deposit_input_witness_stack = &challenge_transaction.input[0].witness;
+        let outputs = self.sweep_outputs(challenge_transaction, payouts, recipient, fee)?;
 
-        // Extract the witness pubkey from the witness stack
-        let witness_pubkey = PublicKey::from_slice(&deposit_input_witness_stack[1])
-            .map_err(|_e| TransactionError::Secp256k1(secp256k1::Error::InvalidPublicKey))?;
-
-        // Extract the second rank commitment by subtracting challenger_pubkey from witness_pubkey
-        let negated_challenger_pubkey = challenger_pubkey.inner.negate(&self.ctx);
-        let second_rank_commitment = witness_pubkey.inner.combine(&negated_challenger_pubkey)?;
-        let second_rank_commitment_hash = sha256::Hash::hash(&second_rank_commitment.serialize());
         let second_rank_commitment_sk =
-            SecretKey::from_slice(second_rank_commitment_hash.as_byte_array())?;
-
-        // Add the second rank commitment to the acceptor's secret key to get the tweaked secret key
-        let tweaked_acceptor_sk = self
-            .secret_key
-            .add_tweak(&second_rank_commitment_sk.into())?;
+            second_rank_commitment_tweak(&self.ctx, challenge_transaction, challenger_pubkey)?;
 
         let mut tx = create_tx(inputs, outputs, None);
 
@@ -239,7 +471,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             0,
             challenge_transaction.output[0].value,
             witness_script,
-            tweaked_acceptor_sk,
+            &second_rank_commitment_sk,
         )?;
 
         Ok(tx)
@@ -252,15 +484,16 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         input_index: usize,
         amount: Amount,
         witness_script: &ScriptBuf,
-        tweaked_secret_key: SecretKey,
+        tweak: &SecretKey,
     ) -> Result<(), TransactionError> {
         let mut sighash_cache = SighashCache::new(&*tx);
         let sighash = sighash_cache
             .p2wsh_signature_hash(input_index, witness_script, amount, EcdsaSighashType::All)
             .map_err(|_e| TransactionError::FailedToSignP2wshInput)?;
 
+        // Sign with the acceptor's key tweaked by the second rank commitment
         let message = Message::from_digest_slice(sighash.as_ref())?;
-        let signature = self.ctx.sign_ecdsa(&message, &tweaked_secret_key);
+        let signature = self.sign_message(&message, Some(tweak))?;
 
         let mut final_signature = signature.serialize_der().to_vec();
         final_signature.push(EcdsaSighashType::All as u8);
@@ -273,7 +506,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         // Build witness for OP_IF branch: <signature> <1> <witness_script>
         tx_input.witness.clear();
         tx_input.witness.push(final_signature); // Acceptor's signature with tweaked key
-        tx_input.witness.push(vec![1]); // Push 1 to take OP_IF branch
+        tx_input.witness.push(SpendingBranch::Acceptor.selector()); // Take the OP_IF branch
         tx_input.witness.push(witness_script.to_bytes()); // The witness script
 
         Ok(())
@@ -281,29 +514,45 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
 
     /// This method should be used by the Challenger to sweep the challenge output.
     /// It will result in a correct transaction only after the time lock has expired and
-    /// the acceptor has not swept the challenge output.
+    /// the acceptor has not swept the challenge output. The output is paid to `recipient`, or to
+    /// the signer's key if `None`.
     pub fn sweep_challenge_output_challenger(
         &self,
         challenge_transaction: &Transaction,
         witness_script: &ScriptBuf,
         lock_time: LockTime,
-        recipient_pubkey: Option<PublicKey>,
+        recipient: Option<ScriptBuf>,
         fee: Amount,
     ) -> Result<Transaction, TransactionError> {
-        let challenger_pubkey = self.secret_key.public_key(&self.ctx);
+        self.sweep_challenge_output_challenger_with_payouts(
+            challenge_transaction,
+            witness_script,
+            lock_time,
+            &[],
+            recipient,
+            fee,
+        )
+    }
 
+    /// Same as [`TransactionBuilder::sweep_challenge_output_challenger`], but splits the swept
+    /// amount after fees between `payouts`. The remainder is paid to `recipient`, or to the
+    /// signer's key if `None`.
+    pub fn sweep_challenge_output_challenger_with_payouts(
+        &self,
+        challenge_transaction: &Transaction,
+        witness_script: &ScriptBuf,
+        lock_time: LockTime,
+        payouts: &[Payout],
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Transaction, TransactionError> {
         let inputs = vec![TxIn {
             previous_output: OutPoint::new(challenge_transaction.compute_txid(), 0),
             sequence: Sequence::ENABLE_LOCKTIME_NO_RBF,
             ..Default::default()
         }];
 
-        let outputs = vec![TxOut {
-            value: challenge_transaction.output[0].value - fee,
-            script_pubkey: create_p2wpkh_script(
-                &recipient_pubkey.unwrap_or(PublicKey::new(challenger_pubkey)),
-            )?,
-        }];
+        let outputs = self.sweep_outputs(challenge_transaction, payouts, recipient, fee)?;
 
         let mut tx = create_tx(inputs, outputs, Some(lock_time));
 
@@ -332,7 +581,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             .map_err(|_e| TransactionError::FailedToSignP2wshInput)?;
 
         let message = Message::from_digest_slice(sighash.as_ref())?;
-        let signature = self.ctx.sign_ecdsa(&message, &self.secret_key);
+        let signature = self.sign_message(&message, None)?;
 
         let mut final_signature = signature.serialize_der().to_vec();
         final_signature.push(EcdsaSighashType::All as u8);
@@ -345,20 +594,22 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         // Build witness for OP_ELSE branch: <signature> <0> <witness_script>
         tx_input.witness.clear();
         tx_input.witness.push(final_signature); // Challenger's signature
-        tx_input.witness.push(vec![]); // Push 0 to take OP_ELSE branch
+        tx_input.witness.push(SpendingBranch::Challenger.selector()); // Take the OP_ELSE branch
         tx_input.witness.push(witness_script.to_bytes()); // The witness script
 
         Ok(())
     }
 
     /// Signs a single input inside `Transaction` by its index
+    /// If the tweak is provided, the input is signed with the signer key tweaked by it
     fn sign_single_input(
         &self,
         tx: &mut Transaction,
         input_index: usize,
         amount: Amount,
+        tweak: Option<&SecretKey>,
     ) -> Result<(), TransactionError> {
-        let public_key = self.secret_key.public_key(&self.ctx);
+        let public_key = self.tweaked_public_key(tweak)?;
         let script_code = ScriptBuf::new_p2wpkh(&PublicKey::new(public_key).wpubkey_hash()?);
 
         let mut sighash_cache = SighashCache::new(&*tx);
@@ -375,7 +626,7 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
             .ok_or(TransactionError::InputIndexOutOfBounds)?;
 
         let message = Message::from_digest_slice(sighash.as_ref())?;
-        let signature = self.ctx.sign_ecdsa(&message, &self.secret_key);
+        let signature = self.sign_message(&message, tweak)?;
 
         let mut final_signature = signature.serialize_der().to_vec();
         final_signature.push(EcdsaSighashType::All as u8);
@@ -387,38 +638,34 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         Ok(())
     }
 
-    /// Signs a single input inside `Psbt` by its index
-    /// If the secret key is not provided, the original secret key will be used
+    /// Signs a single input inside `Psbt` by its index with the given sighash type
+    /// If the tweak is provided, the input is signed with the signer key tweaked by it
     fn sign_psbt_input(
         &self,
         psbt: &mut Psbt,
         input_index: usize,
         amount: Amount,
-        secret_key: Option<SecretKey>,
+        tweak: Option<SecretKey>,
+        sighash_type: EcdsaSighashType,
     ) -> Result<(), TransactionError> {
         let psbt_input = psbt
             .inputs
             .get_mut(input_index)
             .ok_or(TransactionError::InputIndexOutOfBounds)?;
 
-        let secret_key = secret_key.unwrap_or(self.secret_key);
-        let public_key = secret_key.public_key(&self.ctx);
+        let public_key = self.tweaked_public_key(tweak.as_ref())?;
         let script_pubkey = create_p2wpkh_script(&public_key.into())?;
 
         let mut sighasher = SighashCache::new(&psbt.unsigned_tx);
-        let sighash = sighasher.p2wpkh_signature_hash(
-            input_index,
-            &script_pubkey,
-            amount,
-            EcdsaSighashType::All,
-        )?;
+        let sighash =
+            sighasher.p2wpkh_signature_hash(input_index, &script_pubkey, amount, sighash_type)?;
 
         let message = Message::from_digest_slice(sighash.as_ref())?;
-        let signature = self.ctx.sign_ecdsa(&message, &secret_key);
+        let signature = self.sign_message(&message, tweak.as_ref())?;
 
         let final_signature = bitcoin::ecdsa::Signature {
             signature,
-            sighash_type: EcdsaSighashType::All,
+            sighash_type,
         };
 
         psbt_input
@@ -431,14 +678,71 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         };
         psbt_input.witness_utxo = Some(witness_utxo);
 
-        let psbt_sighash_type = PsbtSighashType::from(EcdsaSighashType::All);
-        if psbt_input.sighash_type.is_none() {
-            psbt_input.sighash_type = Some(psbt_sighash_type);
-        }
+        psbt_input.sighash_type = Some(PsbtSighashType::from(sighash_type));
 
         Ok(())
     }
 
+    /// Signs `message` with the signer key, tweaked by adding `tweak` if provided. Fails if
+    /// the signer returns a high-R signature, which would outgrow the fee estimates.
+    fn sign_message(
+        &self,
+        message: &Message,
+        tweak: Option<&SecretKey>,
+    ) -> Result<secp256k1::ecdsa::Signature, TransactionError> {
+        let signature = self.signer.sign_ecdsa(message, tweak)?;
+        let len = signature.serialize_der().len();
+        if len > MAX_SIGNATURE_DER_LEN {
+            return Err(TransactionError::HighRSignature(len));
+        }
+
+        Ok(signature)
+    }
+
+    /// Returns the signer public key, tweaked by adding `tweak` if provided
+    fn tweaked_public_key(
+        &self,
+        tweak: Option<&SecretKey>,
+    ) -> Result<secp256k1::PublicKey, TransactionError> {
+        let public_key = self.signer.public_key()?;
+
+        match tweak {
+            Some(tweak) => Ok(public_key.add_exp_tweak(&self.ctx, &Scalar::from(*tweak))?),
+            None => Ok(public_key),
+        }
+    }
+
+    /// Returns `recipient`, or a P2WPKH script paying to the signer's key if `None`
+    fn recipient_script(
+        &self,
+        recipient: Option<ScriptBuf>,
+    ) -> Result<ScriptBuf, TransactionError> {
+        match recipient {
+            Some(script) => Ok(script),
+            None => create_p2wpkh_script(&self.signer.public_key()?.into()),
+        }
+    }
+
+    /// Builds the outputs of a sweep of the challenge output, splitting its value after `fee`
+    /// between `payouts` and `recipient`
+    fn sweep_outputs(
+        &self,
+        challenge_transaction: &Transaction,
+        payouts: &[Payout],
+        recipient: Option<ScriptBuf>,
+        fee: Amount,
+    ) -> Result<Vec<TxOut>, TransactionError> {
+        let swept_amount = challenge_transaction
+            .output
+            .first()
+            .ok_or(TransactionError::TransactionTypeMismatch)?
+            .value
+            .checked_sub(fee)
+            .ok_or(TransactionError::InsufficientFunds)?;
+
+        payout_outputs(swept_amount, payouts, self.recipient_script(recipient)?)
+    }
+
     /// Signs all transaction inputs with the same secret key
     fn sign_transaction(
         &self,
@@ -450,15 +754,59 @@ impl<C: Signing + Verification> TransactionBuilder<C> {
         }
 
         for (input_index, amount) in amounts.iter().enumerate() {
-            self.sign_single_input(tx, input_index, *amount)?;
+            self.sign_single_input(tx, input_index, *amount, None)?;
         }
 
         Ok(())
     }
 }
 
+/// Checks that `sighash_type` commits to all outputs, as required for the Acceptor's inputs
+fn check_funding_sighash_type(sighash_type: EcdsaSighashType) -> Result<(), TransactionError> {
+    match sighash_type {
+        EcdsaSighashType::All | EcdsaSighashType::AllPlusAnyoneCanPay => Ok(()),
+        other => Err(TransactionError::UnsupportedSighashType(other)),
+    }
+}
+
+/// Checks the sighash types of the Acceptor's signatures in the challenge PSBT. Every signature
+/// must commit to all outputs, and to all inputs unless `anyone_can_pay` is required.
+fn validate_acceptor_signatures(
+    psbt: &Psbt,
+    deposit_input_index: usize,
+    anyone_can_pay: bool,
+) -> Result<(), TransactionError> {
+    let acceptor_inputs = psbt
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(input_index, _)| *input_index != deposit_input_index);
+
+    for (_, input) in acceptor_inputs {
+        for signature in input.partial_sigs.values() {
+            check_funding_sighash_type(signature.sighash_type)?;
+
+            if input.sighash_type != Some(PsbtSighashType::from(signature.sighash_type)) {
+                return Err(TransactionError::SighashTypeMismatch);
+            }
+
+            if anyone_can_pay && signature.sighash_type != EcdsaSighashType::AllPlusAnyoneCanPay {
+                return Err(TransactionError::UnsupportedSighashType(
+                    signature.sighash_type,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Creates a new `Transaction` with the given inputs, outputs and lock time
-fn create_tx(input: Vec<TxIn>, output: Vec<TxOut>, lock_time: Option<LockTime>) -> Transaction {
+pub(crate) fn create_tx(
+    input: Vec<TxIn>,
+    output: Vec<TxOut>,
+    lock_time: Option<LockTime>,
+) -> Transaction {
     Transaction {
         version: Version::ONE,
         lock_time: lock_time.unwrap_or(LockTime::ZERO),
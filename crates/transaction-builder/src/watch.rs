@@ -0,0 +1,86 @@
+//! Detection of the spend of a challenge output, for wallets that watch the chain themselves.
+
+use bitcoin::{OutPoint, Transaction, key::Secp256k1, secp256k1};
+use futures_core::Stream;
+
+use crate::{
+    errors::TransactionError,
+    outcome::Outcome,
+    spending::{SpendingBranch, revealed_second_rank_commitment},
+};
+
+/// Change of the spending status of a watched outpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutpointEvent {
+    /// The outpoint was spent by `transaction`, confirmed at `block_height` if set and in the
+    /// mempool otherwise. Yielded again when the spend confirms or is replaced.
+    Spent {
+        transaction: Transaction,
+        block_height: Option<u32>,
+    },
+    /// The spend yielded before left the mempool or was reorged out
+    Unspent,
+}
+
+/// Source of chain data, implemented by wallets for the backend they watch the chain with
+pub trait ChainBackend {
+    type Error;
+
+    /// Watches `outpoint`, yielding an event whenever its spending status changes. An outpoint
+    /// that is already spent yields its spend first. The stream doesn't end on its own, it is
+    /// dropped to stop watching.
+    fn watch_outpoint(
+        &self,
+        outpoint: OutPoint,
+    ) -> impl Stream<Item = Result<OutpointEvent, Self::Error>> + Send;
+}
+
+/// Spend of a challenge output, classified by the branch of the challenge script it took. The
+/// script has no cooperative branch, so every spend takes one of these.
+#[derive(Debug, Clone)]
+pub enum ChallengeSpend {
+    /// The Acceptor swept the output, as the commitment they selected is the one the
+    /// Challenger revealed
+    Acceptor {
+        outcome: Outcome,
+        /// Second rank commitment `A_i` revealed by the Challenger in the challenge transaction
+        revealed_commitment: secp256k1::PublicKey,
+    },
+    /// The Challenger swept the output once the lock time expired
+    ChallengerTimeout { outcome: Outcome },
+}
+
+impl ChallengeSpend {
+    /// Classifies `spending_transaction`, which spends output 0 of `challenge_transaction`,
+    /// e.g. as yielded by [`ChainBackend::watch_outpoint`]
+    pub fn classify(
+        challenge_transaction: &Transaction,
+        spending_transaction: &Transaction,
+    ) -> Result<Self, TransactionError> {
+        let outcome = Outcome::from_settlement(challenge_transaction, spending_transaction)?;
+
+        Ok(match outcome.winner {
+            SpendingBranch::Acceptor => {
+                let revealed_commitment = revealed_second_rank_commitment(
+                    &Secp256k1::verification_only(),
+                    challenge_transaction,
+                    &outcome.challenger_pubkey,
+                )?;
+
+                ChallengeSpend::Acceptor {
+                    outcome,
+                    revealed_commitment,
+                }
+            }
+            SpendingBranch::Challenger => ChallengeSpend::ChallengerTimeout { outcome },
+        })
+    }
+
+    /// Returns the outcome of the challenge
+    pub fn outcome(&self) -> &Outcome {
+        match self {
+            ChallengeSpend::Acceptor { outcome, .. }
+            | ChallengeSpend::ChallengerTimeout { outcome } => outcome,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+
+use bitcoin::{
+    Txid,
+    hashes::{Hash, HashEngine, sha256},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{COMMITMENTS_COUNT, ThirdRankCommitment};
+
+/// Domain separation tag of the beacon salt commitment
+const SALT_TAG: &[u8] = b"op_rand/beacon-salt";
+
+/// Domain separation tag of the beacon selection
+const SELECTION_TAG: &[u8] = b"op_rand/beacon-selection";
+
+/// Domain separation tag of the beacon anchor
+const ANCHOR_TAG: &[u8] = b"op_rand/beacon-anchor";
+
+/// How the first-rank commitment is selected from a beacon, recorded in challenges so that the
+/// selection can be audited.
+pub const BEACON_SELECTION: &str = "index = le_u64(sha256(tag || salt || beacon)[..8]) mod count, \
+     salt_commitment = sha256(tag || salt)";
+
+/// Public randomness beacon that selects the Challenger's first-rank commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BeaconSource {
+    /// Randomness of a drand round
+    Drand { round: u64 },
+    /// Hash of the Bitcoin block at a height, in display byte order
+    Block { height: u64 },
+}
+
+impl fmt::Display for BeaconSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Drand { round } => write!(f, "drand round {}", round),
+            Self::Block { height } => write!(f, "block {}", height),
+        }
+    }
+}
+
+/// Beacon parameters declared in a challenge. The salt is kept secret until the challenge is
+/// settled, so that the acceptor can't compute the selection from the beacon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconParams {
+    pub source: BeaconSource,
+
+    /// Commitment to the Challenger's salt, hex encoded. See [`beacon_salt_commitment`].
+    pub salt_commitment: String,
+
+    /// Selection rule, see [`BEACON_SELECTION`].
+    pub selection: String,
+
+    /// Transaction anchoring the commitments and the salt commitment before the beacon, see
+    /// [`beacon_anchor`]. It funds the deposit. Unknown for challenges created by older versions.
+    #[serde(default)]
+    pub anchor_txid: Option<Txid>,
+}
+
+impl BeaconParams {
+    pub fn new(source: BeaconSource, salt: &[u8; 32]) -> Self {
+        Self {
+            source,
+            salt_commitment: beacon_salt_commitment(salt).to_string(),
+            selection: BEACON_SELECTION.to_string(),
+            anchor_txid: None,
+        }
+    }
+}
+
+/// Commitment to the salt mixed into the beacon selection.
+pub fn beacon_salt_commitment(salt: &[u8; 32]) -> sha256::Hash {
+    let mut engine = sha256::Hash::engine();
+    engine.input(SALT_TAG);
+    engine.input(salt);
+    sha256::Hash::from_engine(engine)
+}
+
+/// Hash committed to in the `OP_RETURN` output of the anchor transaction. Once the anchor is
+/// confirmed before the beacon, the Challenger can't grind the commitments or the salt against
+/// the beacon value.
+pub fn beacon_anchor(
+    source: &BeaconSource,
+    salt_commitment: &sha256::Hash,
+    third_rank_commitments: &[ThirdRankCommitment; COMMITMENTS_COUNT],
+) -> sha256::Hash {
+    let mut engine = sha256::Hash::engine();
+    engine.input(ANCHOR_TAG);
+    match source {
+        BeaconSource::Drand { round } => {
+            engine.input(&[0]);
+            engine.input(&round.to_be_bytes());
+        }
+        BeaconSource::Block { height } => {
+            engine.input(&[1]);
+            engine.input(&height.to_be_bytes());
+        }
+    }
+    engine.input(salt_commitment.as_byte_array());
+    for commitment in third_rank_commitments {
+        engine.input(&commitment.inner().serialize());
+    }
+    sha256::Hash::from_engine(engine)
+}
+
+/// Index of the first-rank commitment selected by `beacon` and the Challenger's `salt`.
+pub fn beacon_selection(salt: &[u8; 32], beacon: &[u8]) -> usize {
+    let mut engine = sha256::Hash::engine();
+    engine.input(SELECTION_TAG);
+    engine.input(salt);
+    engine.input(beacon);
+    let hash = sha256::Hash::from_engine(engine).to_byte_array();
+
+    let value = u64::from_le_bytes(hash[..8].try_into().expect("hash is 32 bytes long"));
+    (value % COMMITMENTS_COUNT as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::mock::StepRng;
+
+    use super::*;
+    use crate::Commitments;
+
+    #[test]
+    fn selects_commitment_from_salt_and_beacon() {
+        let selections = (0u8..32)
+            .map(|beacon| beacon_selection(&[1; 32], &[beacon]))
+            .collect::<Vec<_>>();
+
+        assert!(selections.iter().all(|index| *index < COMMITMENTS_COUNT));
+        assert!((0..COMMITMENTS_COUNT).all(|index| selections.contains(&index)));
+        assert_eq!(beacon_selection(&[1; 32], &[0]), selections[0]);
+    }
+
+    #[test]
+    fn commits_to_salt() {
+        let params = BeaconParams::new(BeaconSource::Block { height: 840_000 }, &[1; 32]);
+
+        assert_eq!(
+            params.salt_commitment,
+            beacon_salt_commitment(&[1; 32]).to_string()
+        );
+        assert_ne!(
+            beacon_salt_commitment(&[1; 32]),
+            beacon_salt_commitment(&[2; 32])
+        );
+        assert_ne!(
+            beacon_salt_commitment(&[1; 32]),
+            sha256::Hash::hash(&[1; 32])
+        );
+    }
+
+    #[test]
+    fn anchors_source_salt_and_commitments() {
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let commitments = Commitments::generate(&secp, &mut StepRng::new(1, 1)).unwrap();
+        let other = Commitments::generate(&secp, &mut StepRng::new(1_000, 1)).unwrap();
+        let source = BeaconSource::Drand { round: 1_000 };
+        let salt_commitment = beacon_salt_commitment(&[1; 32]);
+
+        let anchor = beacon_anchor(
+            &source,
+            &salt_commitment,
+            commitments.third_rank_commitments(),
+        );
+
+        assert_eq!(
+            anchor,
+            beacon_anchor(
+                &source,
+                &salt_commitment,
+                commitments.third_rank_commitments()
+            )
+        );
+        assert_ne!(
+            anchor,
+            beacon_anchor(
+                &BeaconSource::Block { height: 1_000 },
+                &salt_commitment,
+                commitments.third_rank_commitments()
+            )
+        );
+        assert_ne!(
+            anchor,
+            beacon_anchor(
+                &source,
+                &beacon_salt_commitment(&[2; 32]),
+                commitments.third_rank_commitments()
+            )
+        );
+        assert_ne!(
+            anchor,
+            beacon_anchor(&source, &salt_commitment, other.third_rank_commitments())
+        );
+    }
+}
@@ -11,6 +11,8 @@ use bitcoin::{
 
 use rand::seq::IteratorRandom;
 
+use crate::ct_eq;
+
 /// Number of commitments to create.
 /// Currently only 2 commitments are supported.
 pub const COMMITMENTS_COUNT: usize = 2;
@@ -53,6 +55,14 @@ impl FirstRankCommitment {
     pub fn combine(&self, tweak: &PublicKey) -> Result<PublicKey, secp256k1::Error> {
         self.public_key.combine(tweak)
     }
+
+    /// Compares two commitments in constant time.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        ct_eq(
+            &self.secret_key.secret_bytes(),
+            &other.secret_key.secret_bytes(),
+        )
+    }
 }
 
 /// Third rank commitment.
@@ -72,6 +82,25 @@ impl ThirdRankCommitment {
     pub fn combine(&self, tweak: &PublicKey) -> Result<PublicKey, secp256k1::Error> {
         self.public_key.combine(tweak)
     }
+
+    /// Compares two commitments in constant time.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        ct_eq(&self.public_key.serialize(), &other.public_key.serialize())
+    }
+
+    /// Derives the third rank commitment of a first rank commitment public key.
+    pub fn from_first_rank_public_key<C: Signing>(
+        ctx: &Secp256k1<C>,
+        first_rank_pk: &PublicKey,
+    ) -> Result<Self, secp256k1::Error> {
+        let second_rank_commitment_hash = sha256::Hash::hash(&first_rank_pk.serialize());
+        let second_rank_commitment =
+            SecretKey::from_slice(second_rank_commitment_hash.as_byte_array())?;
+
+        Ok(ThirdRankCommitment {
+            public_key: second_rank_commitment.public_key(ctx),
+        })
+    }
 }
 
 impl FromStr for ThirdRankCommitment {
@@ -83,6 +112,12 @@ impl FromStr for ThirdRankCommitment {
     }
 }
 
+impl From<PublicKey> for ThirdRankCommitment {
+    fn from(public_key: PublicKey) -> Self {
+        ThirdRankCommitment { public_key }
+    }
+}
+
 /// Commitments are used to create the challenge transaction.
 /// They are generated from the first rank commitments.
 /// Note that the second rank commitments are not stored, they are only used to generate the
@@ -179,3 +214,62 @@ impl Commitments {
         &self.third_rank_commitments
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::rand::rngs::mock::StepRng;
+
+    use super::*;
+
+    #[test]
+    fn derives_third_rank_commitments_from_first_rank_ones() {
+        let ctx = Secp256k1::new();
+        let commitments = Commitments::generate(&ctx, &mut StepRng::new(1, 1)).unwrap();
+
+        for (first, third) in commitments
+            .first_rank_commitments()
+            .iter()
+            .zip(commitments.third_rank_commitments())
+        {
+            let (_, first_rank_pk) = first.inner();
+            let derived =
+                ThirdRankCommitment::from_first_rank_public_key(&ctx, &first_rank_pk).unwrap();
+
+            assert!(derived.ct_eq(third));
+        }
+        assert!(
+            !commitments.first_rank_commitments()[0]
+                .ct_eq(&commitments.first_rank_commitments()[1])
+        );
+    }
+
+    #[test]
+    fn tweaks_secret_and_public_keys_alike() {
+        let ctx = Secp256k1::new();
+        let commitment = FirstRankCommitment::from_str(&"11".repeat(32)).unwrap();
+        let tweak = SecretKey::from_slice(&[2; 32]).unwrap();
+
+        assert_eq!(
+            commitment.add_tweak(&tweak).unwrap().public_key(&ctx),
+            commitment.combine(&tweak.public_key(&ctx)).unwrap()
+        );
+    }
+
+    #[test]
+    fn picks_commitments_by_index() {
+        let commitments =
+            Commitments::generate(&Secp256k1::new(), &mut StepRng::new(1, 1)).unwrap();
+
+        assert!(commitments.pick_first_rank_commitment(1).is_some());
+        assert!(
+            commitments
+                .pick_first_rank_commitment(COMMITMENTS_COUNT)
+                .is_none()
+        );
+        assert!(
+            commitments
+                .pick_third_rank_commitment(COMMITMENTS_COUNT)
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,7 @@
+use subtle::ConstantTimeEq;
+
+/// Compares two byte strings in constant time, for data derived from secrets such as
+/// commitments, tweaked keys and their hashes. Only the lengths may leak.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
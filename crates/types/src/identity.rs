@@ -0,0 +1,30 @@
+use bitcoin::{
+    hashes::{Hash, HashEngine, sha256},
+    secp256k1::SecretKey,
+};
+
+/// Domain separation tag of the protocol identity key
+const IDENTITY_TAG: &[u8] = b"op_rand/identity-key";
+
+/// How the protocol identity key is derived from the wallet key, so that the derivation can be
+/// reproduced by other implementations.
+pub const IDENTITY_DERIVATION: &str = "identity = sha256(tag || wallet_key || counter) for the first counter = 0, 1, ... \
+     that is a valid secret key";
+
+/// Derives the protocol identity key from the wallet key.
+///
+/// The identity key authenticates a party towards counterparties and relays, and never signs
+/// for funds. The derivation is one-way, so a leaked identity key doesn't expose the wallet key,
+/// while the wallet key alone is enough to recover the identity.
+pub fn derive_identity_key(wallet_key: &SecretKey) -> SecretKey {
+    (0u8..=u8::MAX)
+        .find_map(|counter| {
+            let mut engine = sha256::Hash::engine();
+            engine.input(IDENTITY_TAG);
+            engine.input(&wallet_key.secret_bytes());
+            engine.input(&[counter]);
+
+            SecretKey::from_slice(sha256::Hash::from_engine(engine).as_byte_array()).ok()
+        })
+        .expect("a valid secret key within 256 hashes")
+}
@@ -1,3 +1,15 @@
+mod beacon;
 mod commitment;
+mod ct;
+mod identity;
+mod protocol;
+mod randomness;
+mod strict;
 
+pub use beacon::*;
 pub use commitment::*;
+pub use ct::*;
+pub use identity::*;
+pub use protocol::*;
+pub use randomness::*;
+pub use strict::*;
@@ -0,0 +1,232 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the protocol messages exchanged between the parties.
+///
+/// Version 2 changed the challenge output script to the encoding of its miniscript, and derives
+/// challenge IDs from the challenge parameters.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol version this implementation can still talk to. Version 1 builds the
+/// challenge output script that version 2 doesn't accept.
+pub const MIN_PROTOCOL_VERSION: u32 = 2;
+
+/// Set of optional protocol features, encoded as a bitset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Taproot challenge outputs.
+    pub const TAPROOT: Self = Self(1 << 0);
+    /// Relative (CSV) time locks on the Challenger's branch.
+    pub const CSV: Self = Self(1 << 1);
+    /// Different stakes for the Challenger and the Acceptor.
+    pub const ASYMMETRIC_STAKES: Self = Self(1 << 2);
+    /// More than [`COMMITMENTS_COUNT`](crate::COMMITMENTS_COUNT) commitments.
+    pub const N_COMMITMENTS: Self = Self(1 << 3);
+    /// Acceptor's funding inputs signed with `SIGHASH_ALL|ANYONECANPAY`, so the Challenger can
+    /// add fee inputs when completing the challenge transaction.
+    pub const ANYONECANPAY: Self = Self(1 << 4);
+    /// Challenge IDs derived from the challenge parameters instead of generated at random.
+    pub const DETERMINISTIC_IDS: Self = Self(1 << 5);
+    /// Optional pre-acceptance phase, in which the Acceptor discloses the funding UTXOs before
+    /// proving and the Challenger acknowledges or vetoes them.
+    pub const FUNDING_PRECHECK: Self = Self(1 << 6);
+
+    /// Capabilities supported by this implementation.
+    pub const SUPPORTED: Self = Self::ANYONECANPAY
+        .union(Self::DETERMINISTIC_IDS)
+        .union(Self::FUNDING_PRECHECK);
+
+    /// Names of the known capabilities
+    pub const NAMES: [(Self, &'static str); 7] = [
+        (Self::TAPROOT, "taproot"),
+        (Self::CSV, "csv"),
+        (Self::ASYMMETRIC_STAKES, "asymmetric stakes"),
+        (Self::N_COMMITMENTS, "n commitments"),
+        (Self::ANYONECANPAY, "anyonecanpay"),
+        (Self::DETERMINISTIC_IDS, "deterministic ids"),
+        (Self::FUNDING_PRECHECK, "funding precheck"),
+    ];
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether all capabilities of `other` are in `self`.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Returns capabilities in `self` that are not in `other`.
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+
+        let mut names = Self::NAMES
+            .iter()
+            .filter(|(capability, _)| self.contains(*capability))
+            .map(|(_, name)| name.to_string())
+            .collect::<Vec<_>>();
+
+        let unknown = self.difference(
+            Self::NAMES
+                .iter()
+                .fold(Self::empty(), |all, (capability, _)| all.union(*capability)),
+        );
+        if !unknown.is_empty() {
+            names.push(format!("unknown ({:#x})", unknown.0));
+        }
+
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// Protocol version and capabilities declared in every protocol message. Messages without it
+/// predate versioning and are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolInfo {
+    pub version: u32,
+    pub capabilities: Capabilities,
+}
+
+/// Reasons two parties can't run the protocol together.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IncompatibilityError {
+    #[error(
+        "Counterparty speaks protocol version {remote}, this wallet supports versions {min}..={max}"
+    )]
+    Version { remote: u32, min: u32, max: u32 },
+    #[error("Capabilities required but not supported by both parties: {0}")]
+    MissingCapabilities(Capabilities),
+}
+
+impl ProtocolInfo {
+    /// Protocol info of this implementation.
+    pub fn local() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: Capabilities::SUPPORTED,
+        }
+    }
+
+    /// Negotiates with the counterparty and returns the capabilities both parties support.
+    /// Fails if the counterparty's version is not supported, or if some of the `required`
+    /// capabilities are not supported by either party.
+    pub fn negotiate(
+        &self,
+        remote: &ProtocolInfo,
+        required: Capabilities,
+    ) -> Result<Capabilities, IncompatibilityError> {
+        if !(MIN_PROTOCOL_VERSION..=self.version).contains(&remote.version) {
+            return Err(IncompatibilityError::Version {
+                remote: remote.version,
+                min: MIN_PROTOCOL_VERSION,
+                max: self.version,
+            });
+        }
+
+        let common = self.capabilities.intersection(remote.capabilities);
+        let missing = required.difference(common);
+        if !missing.is_empty() {
+            return Err(IncompatibilityError::MissingCapabilities(missing));
+        }
+
+        Ok(common)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(version: u32, capabilities: Capabilities) -> ProtocolInfo {
+        ProtocolInfo {
+            version,
+            capabilities,
+        }
+    }
+
+    #[test]
+    fn negotiates_common_capabilities() {
+        let remote = remote(
+            PROTOCOL_VERSION,
+            Capabilities::DETERMINISTIC_IDS.union(Capabilities::TAPROOT),
+        );
+
+        assert_eq!(
+            ProtocolInfo::local().negotiate(&remote, Capabilities::DETERMINISTIC_IDS),
+            Ok(Capabilities::DETERMINISTIC_IDS)
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        for version in [MIN_PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            assert_eq!(
+                ProtocolInfo::local().negotiate(
+                    &remote(version, Capabilities::SUPPORTED),
+                    Capabilities::empty()
+                ),
+                Err(IncompatibilityError::Version {
+                    remote: version,
+                    min: MIN_PROTOCOL_VERSION,
+                    max: PROTOCOL_VERSION,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_missing_required_capabilities() {
+        let remote = remote(PROTOCOL_VERSION, Capabilities::ANYONECANPAY);
+        let required = Capabilities::ANYONECANPAY.union(Capabilities::DETERMINISTIC_IDS);
+
+        assert_eq!(
+            ProtocolInfo::local().negotiate(&remote, required),
+            Err(IncompatibilityError::MissingCapabilities(
+                Capabilities::DETERMINISTIC_IDS
+            ))
+        );
+    }
+
+    #[test]
+    fn displays_capability_names() {
+        assert_eq!(Capabilities::empty().to_string(), "none");
+        assert_eq!(
+            Capabilities::ANYONECANPAY
+                .union(Capabilities::from_bits(1 << 31))
+                .to_string(),
+            "anyonecanpay, unknown (0x80000000)"
+        );
+    }
+}
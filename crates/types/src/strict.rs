@@ -0,0 +1,188 @@
+//! Strict parsing of the keys and byte strings a counterparty sends.
+//!
+//! The `FromStr` implementations of the key types accept more than the protocol produces, such
+//! as uncompressed keys or mixed-case hex, so the same key has several encodings. Counterparty
+//! input is parsed with the functions below instead, which accept exactly one encoding.
+
+use bitcoin::secp256k1::{self, PublicKey};
+
+use crate::ThirdRankCommitment;
+
+/// Size of a compressed public key
+pub const COMPRESSED_KEY_LEN: usize = 33;
+
+/// Reasons counterparty input is rejected by the strict parsers
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StrictParseError {
+    #[error("{what} is {len} bytes long, at most {max} bytes are allowed")]
+    TooLong {
+        what: &'static str,
+        len: usize,
+        max: usize,
+    },
+    #[error("{what} is not lowercase hex")]
+    InvalidHex { what: &'static str },
+    #[error("{what} is {len} bytes long, a compressed public key is 33 bytes")]
+    KeyLength { what: &'static str, len: usize },
+    #[error("{what} has prefix {prefix:#04x}, a compressed public key has 0x02 or 0x03")]
+    KeyPrefix { what: &'static str, prefix: u8 },
+    #[error("{what} is not a point of the curve: {source}")]
+    NotOnCurve {
+        what: &'static str,
+        #[source]
+        source: secp256k1::Error,
+    },
+}
+
+/// Decodes `hex` of `what` into at most `max` bytes. Only lowercase hex of an even length is
+/// accepted, without prefix or whitespace.
+pub fn parse_hex_strict(
+    what: &'static str,
+    hex: &str,
+    max: usize,
+) -> Result<Vec<u8>, StrictParseError> {
+    // Checked before decoding, so that oversized input isn't decoded at all
+    if hex.len() / 2 > max {
+        return Err(StrictParseError::TooLong {
+            what,
+            len: hex.len() / 2,
+            max,
+        });
+    }
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        return Err(StrictParseError::InvalidHex { what });
+    }
+
+    Ok(hex
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| (nibble(pair[0]) << 4) | nibble(pair[1]))
+        .collect())
+}
+
+/// Parses the hex of a compressed public key: 33 bytes, prefixed by 0x02 or 0x03 for the parity
+/// of its y coordinate, of a point of the curve
+pub fn parse_public_key_strict(
+    what: &'static str,
+    hex: &str,
+) -> Result<PublicKey, StrictParseError> {
+    let bytes = parse_hex_strict(what, hex, COMPRESSED_KEY_LEN)?;
+    if bytes.len() != COMPRESSED_KEY_LEN {
+        return Err(StrictParseError::KeyLength {
+            what,
+            len: bytes.len(),
+        });
+    }
+    if !matches!(bytes[0], 0x02 | 0x03) {
+        return Err(StrictParseError::KeyPrefix {
+            what,
+            prefix: bytes[0],
+        });
+    }
+
+    PublicKey::from_slice(&bytes).map_err(|source| StrictParseError::NotOnCurve { what, source })
+}
+
+impl ThirdRankCommitment {
+    /// Parses a commitment sent by a counterparty, see [`parse_public_key_strict`]
+    pub fn from_str_strict(s: &str) -> Result<Self, StrictParseError> {
+        Ok(parse_public_key_strict("third rank commitment", s)?.into())
+    }
+}
+
+/// Value of a lowercase hex digit, already validated by [`parse_hex_strict`]
+fn nibble(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        _ => digit - b'a' + 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+
+    use super::*;
+
+    fn public_key() -> PublicKey {
+        SecretKey::from_slice(&[1; 32])
+            .unwrap()
+            .public_key(&Secp256k1::signing_only())
+    }
+
+    #[test]
+    fn parses_lowercase_hex() {
+        assert_eq!(
+            parse_hex_strict("field", "00ff7a", 3),
+            Ok(vec![0x00, 0xff, 0x7a])
+        );
+        assert_eq!(
+            parse_hex_strict("field", "00FF", 3),
+            Err(StrictParseError::InvalidHex { what: "field" })
+        );
+        assert_eq!(
+            parse_hex_strict("field", "0ff", 3),
+            Err(StrictParseError::InvalidHex { what: "field" })
+        );
+        assert_eq!(
+            parse_hex_strict("field", "0x00", 3),
+            Err(StrictParseError::InvalidHex { what: "field" })
+        );
+        assert_eq!(
+            parse_hex_strict("field", "00000000", 3),
+            Err(StrictParseError::TooLong {
+                what: "field",
+                len: 4,
+                max: 3
+            })
+        );
+    }
+
+    #[test]
+    fn parses_compressed_keys_only() {
+        let key = public_key();
+        assert_eq!(parse_public_key_strict("key", &key.to_string()), Ok(key));
+
+        let uncompressed = hex_of(&key.serialize_uncompressed());
+        assert!(matches!(
+            parse_public_key_strict("key", &uncompressed),
+            Err(StrictParseError::TooLong { len: 65, .. })
+        ));
+        assert_eq!(
+            parse_public_key_strict("key", &key.to_string()[..64]),
+            Err(StrictParseError::KeyLength {
+                what: "key",
+                len: 32
+            })
+        );
+        assert_eq!(
+            parse_public_key_strict("key", &format!("04{}", &key.to_string()[2..])),
+            Err(StrictParseError::KeyPrefix {
+                what: "key",
+                prefix: 0x04
+            })
+        );
+        assert!(matches!(
+            parse_public_key_strict("key", &format!("02{}", "ff".repeat(32))),
+            Err(StrictParseError::NotOnCurve { what: "key", .. })
+        ));
+    }
+
+    #[test]
+    fn parses_commitments_strictly() {
+        let key = public_key();
+
+        assert_eq!(
+            ThirdRankCommitment::from_str_strict(&key.to_string())
+                .unwrap()
+                .inner(),
+            key
+        );
+        assert!(ThirdRankCommitment::from_str_strict(&key.to_string().to_uppercase()).is_err());
+    }
+
+    fn hex_of(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+}